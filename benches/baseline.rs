@@ -0,0 +1,149 @@
+//! Compares a large baseline file against many candidate files, parsing the baseline for every comparison
+//! or only once through the document cache, like directory mode does with a single baseline file.
+//! Also checks deeply nested documents for key differences, where every level adds its keys to the paths.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::fs;
+
+use dtfterminal::{
+    compare_json_files, compare_json_values,
+    dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext, WorkingContext},
+    DocumentCache,
+};
+use libdtf::core::diff_types::WorkingFile;
+use serde_json::{json, Value};
+
+const CANDIDATES: usize = 20;
+
+/// Just under the deepest nesting the values may have
+const NESTED_DEPTH: usize = 120;
+const NESTED_WIDTH: usize = 40;
+
+fn document(version: usize) -> Value {
+    let services: Vec<Value> = (0..2_000)
+        .map(|i| {
+            json!({
+                "name": format!("service-{}", i),
+                "replicas": i % 5,
+                "image": format!("registry.example.com/service-{}:{}", i, version),
+                "env": {"LOG_LEVEL": "info", "REGION": "eu-west-1"},
+            })
+        })
+        .collect();
+    json!({ "version": version, "services": services })
+}
+
+/// A document nested `NESTED_DEPTH` levels deep with `NESTED_WIDTH` keys at each level.
+/// The candidate renames one key at every tenth level.
+fn nested_document(candidate: bool) -> Value {
+    (0..NESTED_DEPTH).rev().fold(json!({}), |child, level| {
+        let mut object = serde_json::Map::new();
+        for i in 0..NESTED_WIDTH {
+            let renamed = candidate && level % 10 == 0 && i == 0;
+            let key = if renamed {
+                format!("renamed-{}", i)
+            } else {
+                format!("key-{}", i)
+            };
+            object.insert(key, json!(i));
+        }
+        object.insert("child".to_owned(), child);
+        Value::Object(object)
+    })
+}
+
+fn working_context() -> WorkingContext {
+    WorkingContext::new(
+        LibWorkingContext::new(
+            WorkingFile::new("baseline.json".to_owned()),
+            WorkingFile::new("candidate.json".to_owned()),
+            LibConfig::new(true),
+        ),
+        ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .check_for_type_diffs(true)
+            .check_for_value_diffs(true)
+            .build(),
+    )
+}
+
+fn bench_baseline(c: &mut Criterion) {
+    let context = working_context();
+    let dir = std::env::temp_dir().join(format!("dtf_bench_baseline_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let write = |name: String, version: usize| {
+        let path = dir.join(name).to_string_lossy().into_owned();
+        fs::write(&path, document(version).to_string()).unwrap();
+        path
+    };
+    let baseline = write("baseline.json".to_owned(), 0);
+    let candidates: Vec<String> = (1..=CANDIDATES)
+        .map(|v| write(format!("candidate-{}.json", v), v))
+        .collect();
+
+    let mut group = c.benchmark_group("baseline");
+    group.sample_size(10);
+    group.bench_function("re-parsed", |b| {
+        b.iter(|| {
+            for candidate in &candidates {
+                let mut cache = DocumentCache::default();
+                compare_json_files(&baseline, candidate, &context, &mut cache).unwrap();
+            }
+        })
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            let mut cache = DocumentCache::new(baseline.clone());
+            for candidate in &candidates {
+                compare_json_files(&baseline, candidate, &context, &mut cache).unwrap();
+            }
+        })
+    });
+    group.finish();
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+fn bench_nested_keys(c: &mut Criterion) {
+    let context = |config: ConfigBuilder| {
+        WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("baseline.json".to_owned()),
+                WorkingFile::new("candidate.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config.build(),
+        )
+    };
+    let key_diffs = context(ConfigBuilder::new().check_for_key_diffs(true));
+    let keys_only = context(
+        ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .compare_keys_only(true),
+    );
+    let all_categories = context(
+        ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .check_for_type_diffs(true)
+            .check_for_value_diffs(true)
+            .check_for_array_diffs(true),
+    );
+    let baseline = nested_document(false);
+    let candidate = nested_document(true);
+
+    let mut group = c.benchmark_group("nested_keys");
+    group.sample_size(10);
+    group.bench_function("key differences", |b| {
+        b.iter(|| compare_json_values(&baseline, &candidate, &key_diffs).unwrap())
+    });
+    group.bench_function("keys only walk", |b| {
+        b.iter(|| compare_json_values(&baseline, &candidate, &keys_only).unwrap())
+    });
+    group.bench_function("all categories", |b| {
+        b.iter(|| compare_json_values(&baseline, &candidate, &all_categories).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_baseline, bench_nested_keys);
+criterion_main!(benches);
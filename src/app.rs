@@ -1,93 +1,217 @@
-use std::path;
-use std::{error::Error, fs::File, io::Write};
+use std::{fs, path};
+use std::{
+    fs::File,
+    io::{self, Write},
+};
 
 use colored::Colorize;
-use html_builder::Buffer;
 
+use crate::badge::render_badge;
+use crate::compare::DataFormat;
+use crate::explain::explain_diffs;
 use crate::html_renderer::HtmlRenderer;
-use crate::utils::{create_working_context, is_yaml_file, CHECKMARK};
+#[cfg(feature = "parquet")]
+use crate::parquet_app::ParquetApp;
+use crate::utils::{
+    apply_severities, check_diff_limit, count_diffs, create_working_context, diff_paths,
+    highest_severity, is_parquet_file, is_toml_file, render_header, ASCII_CHECKMARK, CHECKMARK,
+};
 use crate::{
-    array_table::ArrayTable,
+    array_indexes::{is_in_first_file, ArrayIndexes, IndexCursor},
+    diff_of_diffs::{diff_entries, subtract_known_diffs, DiffEntry},
+    document_cache::DocumentCache,
     dtfterminal_types::{
-        Config, ConfigBuilder, DiffCollection, DtfError, ParsedArgs, TermTable, WorkingContext,
+        ArrayMatchStrategy, CategoryOutput, Config, ConfigBuilder, DiffCategory, DiffCollection,
+        DiffStats, DtfError, Outcome, OutputFormat, ParsedArgs, Severity, TermTable, Tolerance,
+        WorkingContext,
     },
     file_handler::FileHandler,
+    file_pairs::{has_glob_metacharacters, pair_files, FilePairs},
+    hash::FileHashes,
     json_app::JsonApp,
-    key_table::KeyTable,
-    type_table::TypeTable,
-    value_table::ValueTable,
+    key_order::KeyOrderDiff,
+    key_order_table::KeyOrderTable,
+    largest::render_largest_value_diffs,
+    line_diff::LineDiffKeys,
+    path_matcher::PathMatcher,
+    plan::render_plan,
+    positions::{
+        find_json_positions, find_yaml_positions, relative_to, KeyPositions, SourcePositions,
+    },
+    redact::{redact, redact_diffs, RedactedTexts},
+    render::{diff_tables, diffs_json, render_diffs},
+    sample::{sampling_note, SampledArray},
+    side::KeySides,
+    stream::{diff_records, write_stream},
+    value_types::ValueTypes,
     yaml_app::YamlApp,
     Arguments,
 };
 
-use ::clap::Parser;
 use spinners::Spinner;
 
+/// The environment variable setting the default of `-o`, so teams can treat arrays as ordered by default.
+/// `-o` and `--array-match` still win.
+const ARRAY_SAME_ORDER_VAR: &str = "DATADIFF_ARRAY_SAME_ORDER";
+
 /// Responsible for the main functionality of the app. Makes sure everything runs in the correct order.
 pub struct App {
     diffs: DiffCollection,
+    key_order_diffs: Vec<KeyOrderDiff>,
     context: WorkingContext,
     file_handler: FileHandler,
     json_app: Option<JsonApp>,
     yaml_app: Option<YamlApp>,
+    #[cfg(feature = "parquet")]
+    parquet_app: Option<ParquetApp>,
 }
 
 impl App {
     /// Creates a new App instance
     /// 1. Parses the command line arguments
     /// 2. Checks for differences and stores them
-    pub fn new() -> App {
-        let (path1, path2, config) = App::parse_args();
+    pub fn new(args: Arguments) -> Result<App, DtfError> {
+        App::new_cached(args, &mut DocumentCache::default())
+    }
+
+    /// Creates a new App instance, reading the files through the cache.
+    /// Used when one baseline file is compared against many, so it's only parsed once.
+    pub fn new_cached(args: Arguments, cache: &mut DocumentCache) -> Result<App, DtfError> {
+        let (path1, path2, config) = App::parse_args(args)?;
         let mut file_handler = FileHandler::new(config.clone(), None);
         let (diffs, context) = if config.read_from_file.is_empty() {
-            ((None, None, None, None), create_working_context(&config))
+            ((None, None, None, None), create_working_context(&config)?)
         } else {
-            file_handler
-                .load_saved_results()
-                .expect("Could not load saved file!")
+            file_handler.load_saved_results()?
         };
 
-        let json_app = match (&path1, &path2) {
-            (Some(p1), Some(p2)) if p1.ends_with(".json") && p2.ends_with(".json") => {
-                Some(JsonApp::new(p1.clone(), p2.clone(), context.clone()))
+        let format = match (&path1, &path2) {
+            (Some(p1), Some(p2)) => {
+                match (
+                    FileHandler::detect_format(p1, config.max_size),
+                    FileHandler::detect_format(p2, config.max_size),
+                ) {
+                    (Some(format1), Some(format2)) if format1 == format2 && !config.schema => {
+                        Some(format1)
+                    }
+                    // Files validated against a schema are normalized into JSON values
+                    (Some(_), Some(_)) if config.schema => Some(DataFormat::Json),
+                    // JSON and YAML files are not compared with each other
+                    (Some(_), Some(_)) => None,
+                    (Some(format1), None) => return Err(App::undetected_format(p2, p1, format1)),
+                    (None, Some(format2)) => return Err(App::undetected_format(p1, p2, format2)),
+                    (None, None) => None,
+                }
             }
             _ => None,
         };
 
-        let yaml_app = match (&path1, &path2) {
-            (Some(p1), Some(p2)) if is_yaml_file(p1) && is_yaml_file(p2) => {
-                Some(YamlApp::new(p1.clone(), p2.clone(), context.clone()))
-            }
+        let json_app = match (format, &path1, &path2) {
+            (Some(DataFormat::Json), Some(p1), Some(p2)) => Some(JsonApp::new_cached(
+                p1.clone(),
+                p2.clone(),
+                context.clone(),
+                cache,
+            )?),
+            _ => None,
+        };
+
+        let yaml_app = match (format, &path1, &path2) {
+            (Some(DataFormat::Yaml), Some(p1), Some(p2)) => Some(YamlApp::new_cached(
+                p1.clone(),
+                p2.clone(),
+                context.clone(),
+                cache,
+            )?),
             _ => None,
         };
 
-        if App::are_diffs_empty(&diffs) && json_app.is_none() && yaml_app.is_none() {
-            panic!("No valid files to check!");
+        let is_parquet = matches!((&path1, &path2),
+            (Some(p1), Some(p2)) if is_parquet_file(p1) && is_parquet_file(p2));
+        #[cfg(feature = "parquet")]
+        let parquet_app = match (is_parquet, &path1, &path2) {
+            (true, Some(p1), Some(p2)) => Some(ParquetApp::new(p1, p2, context.clone())?),
+            _ => None,
+        };
+        #[cfg(not(feature = "parquet"))]
+        if is_parquet {
+            return Err(DtfError::DiffError(
+                "Comparing Parquet files requires the parquet feature".to_owned(),
+            ));
+        }
+
+        if App::are_diffs_empty(&diffs) && json_app.is_none() && yaml_app.is_none() && !is_parquet {
+            return Err(DtfError::MissingArgument(
+                "two JSON or TOML files or two YAML files to check".to_owned(),
+            ));
         }
 
         let mut app = App {
             diffs,
+            key_order_diffs: vec![],
             context,
             file_handler,
             json_app,
             yaml_app,
+            #[cfg(feature = "parquet")]
+            parquet_app,
         };
 
-        app.collect_data(&config);
+        app.collect_data(&config)?;
 
-        app
+        Ok(app)
+    }
+
+    /// The differences found as comparable entries, the files referred to as A and B
+    pub fn diff_entries(&self) -> Vec<DiffEntry> {
+        diff_entries(&self.diffs, &self.context)
+    }
+
+    /// Describes what would be compared with the arguments, without reading the files
+    pub fn plan(args: Arguments) -> Result<String, DtfError> {
+        let (path1, path2, config) = App::parse_args(args)?;
+        let file_pairs = match (path1, path2) {
+            (Some(path1), Some(path2))
+                if has_glob_metacharacters(&path1) || has_glob_metacharacters(&path2) =>
+            {
+                pair_files(&path1, &path2)?
+            }
+            (Some(path1), Some(path2)) => FilePairs {
+                pairs: vec![(path1, path2)],
+                unmatched: vec![],
+            },
+            _ => FilePairs::default(),
+        };
+        Ok(render_plan(&file_pairs, &config))
     }
 
     /// Handles the output into file or to the terminal
-    pub fn execute(&self) -> Result<(), DtfError> {
-        let mut spinner = Spinner::new(
-            spinners::Spinners::Monkey,
-            "Checking for differences...\n".into(),
-        );
+    pub fn execute(&self) -> Result<Outcome, DtfError> {
+        if self.context.config.count {
+            print!("{}", self.render_count());
+            return Ok(self.outcome());
+        }
+
+        if self.context.config.emit_header {
+            println!("{}", render_header(&self.diffs, &self.context.config));
+        }
+
+        if let Some(badge) = &self.context.config.badge {
+            fs::write(badge, render_badge(&DiffStats::from_diffs(&self.diffs)))
+                .map_err(DtfError::IoError)?;
+        }
+
+        // The spinner would get mixed into data written to the terminal
+        let mut spinner = (self.context.config.output_format == OutputFormat::Table).then(|| {
+            Spinner::new(
+                spinners::Spinners::Monkey,
+                "Checking for differences...\n".into(),
+            )
+        });
 
         if self.context.config.write_to_file.is_some() {
             self.file_handler
-                .write_to_file(self.diffs.clone())
+                .write_to_file(self.diffs.clone(), &self.context)
                 .map_err(|e| DtfError::GeneralError(Box::new(e)))?;
         } else if let Some(browser_view) = &self.context.config.browser_view {
             self.render_html()
@@ -98,194 +222,1175 @@ impl App {
                     .map_err(|e| DtfError::DiffError(e.to_string()))?;
             }
         } else {
-            self.render_tables()
-                .map_err(|e| DtfError::DiffError(e.to_string()))?;
+            match self.context.config.output_format {
+                OutputFormat::Table => self
+                    .render_tables()
+                    .map_err(|e| DtfError::DiffError(e.to_string()))?,
+                OutputFormat::Json => println!("{}", self.render_json()?),
+                OutputFormat::Paths => {
+                    for path in diff_paths(&self.diffs, &self.context.config) {
+                        println!("{}", path);
+                    }
+                }
+                OutputFormat::Ndjson => write_stream(
+                    diff_records(&self.diffs, &self.context),
+                    &self.context,
+                    &mut io::stdout().lock(),
+                )
+                .map_err(DtfError::IoError)?,
+                format @ (OutputFormat::Markdown | OutputFormat::Html) => {
+                    println!("{}", render_diffs(&self.diffs, &self.context, format)?)
+                }
+            }
         }
 
-        spinner.stop_with_message(format!("{} {}", CHECKMARK.green(), "Done!".green()));
-        Ok(())
+        self.write_category_outputs()?;
+
+        if let Some(spinner) = spinner.as_mut() {
+            let checkmark = if self.context.config.ascii {
+                ASCII_CHECKMARK
+            } else {
+                CHECKMARK
+            };
+            spinner.stop_with_message(format!("{} {}", checkmark.green(), "Done!".green()));
+        }
+
+        Ok(self.outcome())
+    }
+
+    /// Decides if the differences written fail the run
+    fn outcome(&self) -> Outcome {
+        let outcome = match &self.context.config.baseline_diffs {
+            Some(baseline_diffs) => self.check_new_diffs(baseline_diffs),
+            None => self.check_fail_fast(),
+        };
+        match (outcome, self.context.config.fail_on_severity) {
+            (Outcome::Passed, Some(fail_on_severity)) => self.check_severity(fail_on_severity),
+            (outcome, _) => outcome,
+        }
+    }
+
+    /// Fails if a difference has at least the severity of `--fail-on-severity`
+    fn check_severity(&self, fail_on_severity: Severity) -> Outcome {
+        match highest_severity(&self.diffs, &self.context.config, &self.context) {
+            Some(severity) if severity >= fail_on_severity => {
+                Outcome::Failed(format!("Differences with {} severity were found", severity))
+            }
+            _ => Outcome::Passed,
+        }
+    }
+
+    /// Fails if `--fail-fast` found a difference, so it can gate a build
+    fn check_fail_fast(&self) -> Outcome {
+        match count_diffs(&self.diffs) {
+            found if found > 0 && self.context.config.fail_fast => {
+                Outcome::Failed("Stopped at the first difference".to_owned())
+            }
+            _ => Outcome::Passed,
+        }
+    }
+
+    /// Fails if there are differences not in the baseline, so only new ones break the build
+    fn check_new_diffs(&self, baseline_diffs: &str) -> Outcome {
+        match count_diffs(&self.diffs) {
+            0 => Outcome::Passed,
+            new_diffs => Outcome::Failed(format!(
+                "{} differences are not in {}",
+                new_diffs, baseline_diffs
+            )),
+        }
     }
 
     /// Parses the command line arguments
-    fn parse_args() -> ParsedArgs {
-        let args = Arguments::parse();
+    /// If the roles of the files are inverted, they are swapped before anything else happens
+    fn parse_args(args: Arguments) -> Result<ParsedArgs, DtfError> {
+        App::parse_args_with_env(args, |name| std::env::var(name).ok())
+    }
+
+    /// Parses the command line arguments, with the defaults set by the environment variables looked up by `env`
+    fn parse_args_with_env(
+        mut args: Arguments,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<ParsedArgs, DtfError> {
+        if !args.array_same_order {
+            args.array_same_order = match env(ARRAY_SAME_ORDER_VAR) {
+                Some(value) => parse_env_flag(ARRAY_SAME_ORDER_VAR, &value)?,
+                None => false,
+            };
+        }
+
+        if args.invert {
+            args.check_files.reverse();
+            std::mem::swap(&mut args.unwrap_a, &mut args.unwrap_b);
+        }
 
         let (path1, path2) = if args.read_from_file.is_empty() {
-            (
-                Some(args.check_files[0].clone()),
-                Some(args.check_files[1].clone()),
-            )
+            match args.check_files.as_slice() {
+                [path1, path2] => (Some(path1.clone()), Some(path2.clone())),
+                _ => {
+                    return Err(DtfError::MissingArgument(
+                        "two files to check or a saved file to read".to_owned(),
+                    ))
+                }
+            }
         } else {
             (None, None)
         };
 
+        let (epsilon, path_epsilons) = Tolerance::split(args.epsilon);
+        // --array-key on its own only lines up the objects for display, pairing by similarity has to be chosen
+        let array_match_strategy = args.array_match.unwrap_or(if args.array_same_order {
+            ArrayMatchStrategy::Positional
+        } else {
+            ArrayMatchStrategy::Set
+        });
+        if array_match_strategy == ArrayMatchStrategy::Key && args.array_key.is_none() {
+            return Err(DtfError::MissingArgument(
+                "--array-key is required by --array-match key".to_owned(),
+            ));
+        }
+        if let Some(threshold) = args.string_similarity {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(DtfError::MissingArgument(format!(
+                    "a --string-similarity threshold from 0 to 1, got: {}",
+                    threshold
+                )));
+            }
+        }
+        // The first and the last items are always kept, so a smaller sample couldn't be taken
+        if let Some(size @ (0 | 1)) = args.sample {
+            return Err(DtfError::MissingArgument(format!(
+                "a --sample of at least 2 items, got: {}",
+                size
+            )));
+        }
+
+        let category_outputs: Vec<CategoryOutput> = [
+            (DiffCategory::Key, args.key_out),
+            (DiffCategory::Type, args.type_out),
+            (DiffCategory::Value, args.value_out),
+            (DiffCategory::Array, args.array_out),
+        ]
+        .into_iter()
+        .filter_map(|(category, path)| Some(CategoryOutput::new(category, path?)))
+        .collect();
+        let (key_diffs, type_diffs, value_diffs, array_diffs) = if args.arrays_only {
+            // Only the checker of the array differences runs, whatever else was asked for
+            (false, false, false, true)
+        } else {
+            (
+                args.key_diffs || args.compare_keys_only,
+                args.type_diffs,
+                args.value_diffs,
+                args.array_diffs,
+            )
+        };
+        // The categories written to a file of their own are checked, even if they aren't shown
+        let has_output = |category: DiffCategory| {
+            category_outputs
+                .iter()
+                .any(|output| output.category == category)
+        };
         let config = ConfigBuilder::new()
-            .check_for_key_diffs(args.key_diffs)
-            .check_for_type_diffs(args.type_diffs)
-            .check_for_value_diffs(args.value_diffs)
-            .check_for_array_diffs(args.array_diffs)
-            .render_key_diffs(args.key_diffs)
-            .render_type_diffs(args.type_diffs)
-            .render_value_diffs(args.value_diffs)
-            .render_array_diffs(args.array_diffs)
+            .check_for_key_diffs(key_diffs || has_output(DiffCategory::Key))
+            .check_for_type_diffs(type_diffs || has_output(DiffCategory::Type))
+            .check_for_value_diffs(value_diffs || has_output(DiffCategory::Value))
+            .check_for_array_diffs(array_diffs || has_output(DiffCategory::Array))
+            .render_key_diffs(key_diffs)
+            .render_type_diffs(type_diffs)
+            .render_value_diffs(value_diffs)
+            .render_array_diffs(array_diffs)
             .read_from_file(args.read_from_file)
             .write_to_file(args.write_to_file)
             .file_a(path1.clone())
             .file_b(path2.clone())
-            .array_same_order(args.array_same_order)
+            .array_same_order(array_match_strategy == ArrayMatchStrategy::Positional)
+            .ordered_array_paths(args.ordered_array)
+            .severity_rules(args.severity)
+            .min_severity(args.min_severity)
+            .fail_on_severity(args.fail_on_severity)
+            .max_size(args.max_size)
+            .max_diffs(args.max_diffs_hard)
+            .array_key(args.array_key)
+            .parse_dates(args.parse_dates)
+            .emit_header(args.emit_header)
+            .intersection(args.intersection)
+            .defaults(args.defaults)
+            .multiline_string_diff(args.multiline_string_diff)
+            .baseline(args.baseline)
+            .output_format(args.format)
+            .value_truncate_len(args.value_truncate_len)
+            .max_array_diffs_per_key(args.max_array_diffs_per_key)
+            .explain(args.explain)
+            .check_key_order(args.check_key_order && !args.arrays_only)
+            .compare_keys_only(args.compare_keys_only)
+            .schema(args.schema)
+            .show_types_in_values(args.show_types_in_values)
+            .only_paths(args.only_path)
+            .ignore_paths(args.ignore_path)
+            .badge(args.badge)
+            .max_value_len(args.max_value_len)
+            .embed_data(args.embed_data)
+            .epsilon(epsilon)
+            .path_epsilons(path_epsilons)
+            .tag_paths(args.tag_paths)
+            .normalize_unicode(args.normalize_unicode)
+            .unwrap_a(args.unwrap_a)
+            .unwrap_b(args.unwrap_b)
+            .sort_array_values(args.sort_array_values)
+            .canonicalize(args.canonicalize)
+            .trim_strings(args.trim_strings)
+            .side_by_side(args.side_by_side)
+            .array_match_strategy(array_match_strategy)
+            .ascii(args.ascii)
+            .string_similarity_threshold(args.string_similarity)
+            .flatten(args.flatten)
+            .flatten_arrays(args.flatten_arrays)
+            .common(args.common)
+            .append(args.append)
+            .show_empty_tables(args.show_empty_tables && !args.no_empty_tables)
+            .report_title(args.report_title)
+            .report_description(args.report_description)
+            .ignore_array_tail(args.ignore_array_tail)
+            .redact_patterns(args.redact)
+            .baseline_diffs(args.baseline_diffs)
+            .positions(args.positions)
+            .empty_equivalence(args.empty_equivalence)
+            .ignore_yaml_tags(args.ignore_yaml_tags)
+            .count(args.count)
+            .category_outputs(category_outputs)
+            .number_format(args.number_format)
+            .ignore_chomping(args.ignore_chomping)
+            .top(args.top)
+            .jsonc(args.jsonc)
+            .set_array_diffs(args.set_array_diffs)
+            .coerce_numeric_strings(args.coerce_numeric_strings)
+            .fail_fast(args.fail_fast)
+            .primary_key(args.primary_key)
+            .identical_message(args.identical_message)
+            .sample(args.sample)
+            .sample_seed(args.sample_seed)
+            .type_naming(args.type_naming)
+            .hash(args.hash)
+            .matrices(args.matrices)
+            .collapse_ranges(args.collapse_ranges)
             .browser_view(args.browser_view)
             .printer_friendly(args.printer_friendly)
             .no_browser_show(args.no_browser_show)
             .build();
 
-        (path1, path2, config)
+        Ok((path1, path2, config))
     }
 
     /// Collects the data from the files
-    /// If the user has specified a file to read from, it will load the saved results
+    /// If the user has specified a file to read from, the saved results loaded by `new` are used
     /// Otherwise it will perform a new check
-    fn collect_data(&mut self, user_config: &Config) {
+    fn collect_data(&mut self, user_config: &Config) -> Result<(), DtfError> {
         if user_config.read_from_file.is_empty() {
-            self.diffs = self.check_for_diffs().expect("Data check failed!")
-        } else {
-            self.diffs = self
-                .file_handler
-                .load_saved_results()
-                .expect("Could not load saved file!")
-                .0;
+            if user_config.check_key_order {
+                self.key_order_diffs = self.find_key_order_diffs()?;
+            }
+            let (diffs, key_sides) = self.check_for_diffs()?;
+            if user_config.sample.is_some() {
+                self.context.sampled_arrays = self.find_sampled_arrays();
+            }
+            if user_config.hash {
+                let (file_a, file_b) = self.context.get_file_names();
+                self.context.file_hashes = Some(FileHashes::of_files(file_a, file_b)?);
+            }
+            if user_config.show_types_in_values {
+                self.context.value_types = self.find_value_types(&diffs);
+            }
+            self.context.key_sides = key_sides;
+            if user_config.multiline_string_diff {
+                self.context.line_diff_keys = self.find_line_diff_keys(&diffs);
+            }
+            if user_config.check_for_array_diffs {
+                self.context.array_indexes = self.find_array_indexes(&diffs);
+            }
+            if let Some(common) = &user_config.common {
+                self.write_common(common)?;
+            }
+            if user_config.positions {
+                self.context.key_positions = App::find_key_positions(user_config)?;
+            }
+            self.diffs = diffs;
+        }
+        if let Some(baseline_diffs) = &user_config.baseline_diffs {
+            let known = FileHandler::read_saved_context(baseline_diffs)?;
+            self.diffs =
+                subtract_known_diffs(std::mem::take(&mut self.diffs), &known, &self.context);
+        }
+        self.diffs = apply_severities(std::mem::take(&mut self.diffs), user_config, &self.context);
+        if !user_config.redact_patterns.is_empty() {
+            self.redact();
+        }
+        Ok(())
+    }
+
+    /// Redacts everything that gets shown of the differences, after they are found by the real values
+    /// The data looked up for the differences stays by their original keys, as different keys can be redacted the same.
+    fn redact(&mut self) {
+        let patterns = &self.context.config.redact_patterns;
+        self.context.redacted = RedactedTexts::of_diffs(&self.diffs, patterns);
+        let diffs = std::mem::take(&mut self.diffs);
+        self.diffs = redact_diffs(diffs, patterns);
+        for key_order_diff in &mut self.key_order_diffs {
+            key_order_diff.key = redact(&key_order_diff.key, patterns);
+            for key in key_order_diff
+                .order1
+                .iter_mut()
+                .chain(key_order_diff.order2.iter_mut())
+            {
+                *key = redact(key, patterns);
+            }
         }
     }
 
-    /// Checks for differences in the files
+    /// Checks for differences in the files, with the file having the key of each key difference
     /// Handles both JSON and YAML files
     /// Returns an error if no file is found
-    fn check_for_diffs(&self) -> Result<DiffCollection, Box<dyn Error>> {
+    fn check_for_diffs(&self) -> Result<(DiffCollection, KeySides), DtfError> {
+        let (diffs, key_sides) = if self.context.config.schema {
+            (self.validate_against_schema()?, KeySides::new())
+        } else if let Some(diffs) = self.check_parquet_files() {
+            (diffs?, KeySides::new())
+        } else if self.context.config.fail_fast {
+            self.find_first_diff()?
+        } else if let Some(json_app) = &self.json_app {
+            json_app.perform_sided_check()?
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.perform_sided_check()?
+        } else {
+            return Err(DtfError::DiffError("No file to check".to_string()));
+        };
+
+        check_diff_limit(&diffs, &self.context.config)?;
+        Ok((diffs, key_sides))
+    }
+
+    /// Finds only the first difference in the files
+    fn find_first_diff(&self) -> Result<(DiffCollection, KeySides), DtfError> {
         if let Some(json_app) = &self.json_app {
-            Ok(json_app.perform_new_check())
+            Ok(json_app.with_key_sides(json_app.find_first_diff()?))
         } else if let Some(yaml_app) = &self.yaml_app {
-            Ok(yaml_app.perform_new_check())
+            Ok(yaml_app.with_key_sides(yaml_app.find_first_diff()?))
         } else {
-            Err(Box::new(DtfError::DiffError(
-                "No file to check".to_string(),
-            )))
+            Err(DtfError::DiffError("No file to check".to_string()))
         }
     }
 
-    /// Renders the tables to the terminal
-    fn render_tables(&self) -> Result<(), DtfError> {
-        let (key_diff, type_diff, value_diff, array_diff) = &self.diffs;
+    /// Checks the rows of the Parquet files, if they are compared
+    #[cfg(feature = "parquet")]
+    fn check_parquet_files(&self) -> Option<Result<DiffCollection, DtfError>> {
+        self.parquet_app.as_ref().map(ParquetApp::perform_new_check)
+    }
 
-        let mut rendered_tables = vec![];
-        if self.context.config.render_key_diffs {
-            if let Some(diffs) = key_diff.as_ref().filter(|kd| !kd.is_empty()) {
-                let table = KeyTable::new(diffs, &self.context);
-                rendered_tables.push(table.render());
-            }
+    #[cfg(not(feature = "parquet"))]
+    fn check_parquet_files(&self) -> Option<Result<DiffCollection, DtfError>> {
+        None
+    }
+
+    /// Validates the first file against the JSON Schema in the second one
+    #[cfg(feature = "schema")]
+    fn validate_against_schema(&self) -> Result<DiffCollection, DtfError> {
+        match &self.json_app {
+            Some(json_app) => json_app.validate_against_schema(),
+            None => Err(DtfError::DiffError("No file to check".to_string())),
         }
+    }
 
-        if self.context.config.render_type_diffs {
-            if let Some(diffs) = type_diff.as_ref().filter(|td| !td.is_empty()) {
-                let table = TypeTable::new(diffs, &self.context);
-                rendered_tables.push(table.render());
-            }
+    #[cfg(not(feature = "schema"))]
+    fn validate_against_schema(&self) -> Result<DiffCollection, DtfError> {
+        Err(DtfError::DiffError(
+            "Validating against a JSON Schema requires the schema feature".to_owned(),
+        ))
+    }
+
+    /// Looks up the types of the differing values in the files
+    fn find_value_types(&self, diffs: &DiffCollection) -> ValueTypes {
+        let value_diffs = diffs.2.as_deref().unwrap_or_default();
+        if let Some(json_app) = &self.json_app {
+            json_app.find_value_types(value_diffs)
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.find_value_types(value_diffs)
+        } else {
+            ValueTypes::new()
         }
+    }
 
-        if self.context.config.render_value_diffs {
-            if let Some(diffs) = value_diff.as_ref().filter(|vd| !vd.is_empty()) {
-                let table = ValueTable::new(diffs, &self.context);
-                rendered_tables.push(table.render());
-            }
+    /// Finds the value differences of multi-line strings, which are shown as the lines that differ
+    fn find_line_diff_keys(&self, diffs: &DiffCollection) -> LineDiffKeys {
+        let value_diffs = diffs.2.as_deref().unwrap_or_default();
+        if let Some(json_app) = &self.json_app {
+            json_app.find_line_diff_keys(value_diffs)
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.find_line_diff_keys(value_diffs)
+        } else {
+            LineDiffKeys::new()
+        }
+    }
+
+    /// Finds the arrays of which only a sample of the items was compared
+    fn find_sampled_arrays(&self) -> Vec<SampledArray> {
+        if let Some(json_app) = &self.json_app {
+            json_app.sampled_arrays()
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.sampled_arrays()
+        } else {
+            vec![]
         }
+    }
+
+    /// Looks up where the items of the array differences are in the files.
+    /// Only the items of the arrays compared in order get indexes, the ones compared as sets have no position.
+    fn find_array_indexes(&self, diffs: &DiffCollection) -> ArrayIndexes {
+        let array_diffs = diffs.3.as_deref().unwrap_or_default();
+        let mut array_indexes = if let Some(json_app) = &self.json_app {
+            json_app.find_array_indexes(array_diffs)
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.find_array_indexes(array_diffs)
+        } else {
+            ArrayIndexes::new()
+        };
+        let config = &self.context.config;
+        let all_ordered = self.context.lib_working_context.config.array_same_order;
+        let ordered_paths = PathMatcher::compile_all(&config.ordered_array_paths);
+        array_indexes.retain(|(key, _, _), _| {
+            !config.set_array_diffs
+                && (all_ordered || ordered_paths.iter().any(|matcher| matcher.matches(key)))
+        });
+        array_indexes
+    }
 
-        if self.context.config.render_array_diffs {
-            if let Some(diffs) = array_diff.as_ref().filter(|ad| !ad.is_empty()) {
-                let table = ArrayTable::new(diffs, &self.context);
-                rendered_tables.push(table.render());
+    /// Looks up where the keys are in the files, reading them again as text
+    fn find_key_positions(config: &Config) -> Result<KeyPositions, DtfError> {
+        let find = |path: &Option<String>, unwrap: &Option<String>| {
+            let path = path.as_deref().unwrap_or_default();
+            // Only the keys of JSON and YAML files are located
+            if is_toml_file(path) {
+                return Ok(SourcePositions::new());
+            }
+            let source = FileHandler::read_text(path, config.max_size)?;
+            let positions = match FileHandler::detect_format(path, config.max_size) {
+                Some(DataFormat::Yaml) => find_yaml_positions(&source),
+                _ => find_json_positions(&source),
+            };
+            Ok::<_, DtfError>(match unwrap {
+                Some(unwrap) => relative_to(positions, unwrap),
+                None => positions,
+            })
+        };
+        Ok(KeyPositions {
+            a: find(&config.file_a, &config.unwrap_a)?,
+            b: find(&config.file_b, &config.unwrap_b)?,
+        })
+    }
+
+    /// Writes the structure the files agree on to a file, in their format
+    fn write_common(&self, path: &str) -> Result<(), DtfError> {
+        let common = if let Some(json_app) = &self.json_app {
+            json_app.common()?
+        } else if let Some(yaml_app) = &self.yaml_app {
+            yaml_app.common()?
+        } else {
+            return Err(DtfError::DiffError("No file to check".to_string()));
+        };
+        fs::write(path, common).map_err(DtfError::IoError)
+    }
+
+    /// Finds the objects having the same keys in a different order in the files
+    fn find_key_order_diffs(&self) -> Result<Vec<KeyOrderDiff>, DtfError> {
+        if let Some(json_app) = &self.json_app {
+            if !cfg!(feature = "key-order") {
+                return Err(DtfError::DiffError(
+                    "Checking the key order of JSON files requires the key-order feature"
+                        .to_owned(),
+                ));
             }
+            Ok(json_app.find_key_order_diffs())
+        } else if let Some(yaml_app) = &self.yaml_app {
+            Ok(yaml_app.find_key_order_diffs())
+        } else {
+            Err(DtfError::DiffError("No file to check".to_string()))
         }
+    }
 
+    /// Renders the tables to the terminal
+    fn render_tables(&self) -> Result<(), DtfError> {
+        let rendered_tables = self.rendered_tables();
         if rendered_tables.is_empty() {
-            println!("The data is identical!");
+            println!("{}", self.context.config.identical_message());
+            self.print_notes();
             return Ok(());
         }
 
         for table in rendered_tables {
             println!("{}", table);
         }
+        self.print_notes();
+
+        let config = &self.context.config;
+        if config.explain {
+            let mut cursor = IndexCursor::default();
+            let sentences = explain_diffs(&self.diffs, &self.context, |ad| {
+                let in_a = is_in_first_file(&ad.descriptor);
+                self.context
+                    .next_array_index(&mut cursor, &ad.key, in_a, &ad.value)
+            });
+            for sentence in sentences {
+                println!("{}", sentence);
+            }
+        }
+
+        if let Some(top) = config.top.filter(|_| config.render_value_diffs) {
+            let value_diffs = self.diffs.2.as_deref().unwrap_or_default();
+            for line in render_largest_value_diffs(value_diffs, top) {
+                println!("{}", line);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints what the reader should know about the comparison besides the differences
+    fn print_notes(&self) {
+        if let Some(note) = sampling_note(&self.context.sampled_arrays) {
+            println!("{}", note);
+        }
+        if let Some(file_hashes) = &self.context.file_hashes {
+            println!(
+                "{}",
+                file_hashes.render(self.context.get_file_names(), count_diffs(&self.diffs) == 0)
+            );
+        }
+    }
+
+    /// Renders the tables of the categories to be shown
+    fn rendered_tables(&self) -> Vec<String> {
+        let mut rendered_tables = diff_tables(&self.diffs, &self.context);
+        if !self.key_order_diffs.is_empty() {
+            let table = KeyOrderTable::new(&self.key_order_diffs, &self.context);
+            rendered_tables.push(table.render());
+        }
+
+        rendered_tables
+    }
+
+    /// Renders the total number of differences as a line of its own
+    fn render_count(&self) -> String {
+        format!("{}\n", count_diffs(&self.diffs))
+    }
+
+    /// Renders the differences as JSON, without the configuration saved with `-w`
+    fn render_json(&self) -> Result<String, DtfError> {
+        diffs_json(&self.diffs, &self.context)
+    }
 
+    /// Writes the differences of each category given a file of its own into it, in the format of its extension
+    fn write_category_outputs(&self) -> Result<(), DtfError> {
+        for output in &self.context.config.category_outputs {
+            let context = output.working_context(&self.context);
+            let diffs = output.diffs(&self.diffs);
+            let rendered = render_diffs(&diffs, &context, output.format())?;
+            fs::write(&output.path, rendered).map_err(DtfError::IoError)?;
+        }
         Ok(())
     }
 
     /// Renders the HTML output
     fn render_html(&self) -> Result<(), DtfError> {
-        let mut buf = Buffer::new();
         let mut html_renderer = HtmlRenderer::new(&self.context);
-        let render_key_diffs = self.context.config.render_key_diffs
-            && self.diffs.0.as_ref().filter(|kd| !kd.is_empty()).is_some();
-        let key_diffs = if render_key_diffs {
-            self.diffs.0.as_ref()
-        } else {
-            None
+        let document = html_renderer.render_document(&self.diffs)?;
+
+        let path = self.context.config.browser_view.as_ref().ok_or_else(|| {
+            DtfError::MissingArgument("the file to write the HTML output to".to_owned())
+        })?;
+        let mut file = File::create(path)
+            .map_err(|e| DtfError::DiffError(format!("Could not create file: {}", e)))?;
+
+        write!(file, "{}", document).map_err(|e| DtfError::DiffError(format!("{}", e)))
+    }
+
+    /// The error of a file whose format could not be detected, while the other file's could
+    fn undetected_format(path: &str, other_path: &str, other_format: DataFormat) -> DtfError {
+        DtfError::DiffError(format!(
+            "{} is {}, but the format of {} could not be detected. \
+             Give it the .json, .toml, .yaml or .yml extension matching its contents.",
+            other_path, other_format, path
+        ))
+    }
+
+    fn are_diffs_empty(diffs: &DiffCollection) -> bool {
+        diffs.0.is_none() && diffs.1.is_none() && diffs.2.is_none() && diffs.3.is_none()
+    }
+}
+
+/// Parses a boolean environment variable: `true`, `1` or `yes` and `false`, `0`, `no` or empty, in any case
+fn parse_env_flag(name: &str, value: &str) -> Result<bool, DtfError> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" | "" => Ok(false),
+        _ => Err(DtfError::MissingArgument(format!(
+            "true or false as {}, got: {}",
+            name, value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_invert() {
+        let original = App::new(arguments("person1", "person3", false)).unwrap();
+        let inverted = App::new(arguments("person1", "person3", true)).unwrap();
+        let inverted_twice = App::new(arguments("person3", "person1", true)).unwrap();
+
+        assert_eq!(
+            inverted.context.config.file_a,
+            original.context.config.file_b
+        );
+        assert_ne!(serialize(&inverted.diffs), serialize(&original.diffs));
+        assert_eq!(serialize(&inverted_twice.diffs), serialize(&original.diffs));
+
+        let original_values = original.diffs.2.unwrap();
+        let inverted_values = inverted.diffs.2.unwrap();
+        assert_eq!(original_values.len(), inverted_values.len());
+        for (original_value, inverted_value) in original_values.iter().zip(inverted_values.iter()) {
+            assert_eq!(original_value.value1, inverted_value.value2);
+            assert_eq!(original_value.value2, inverted_value.value1);
+        }
+    }
+
+    fn arguments(file_a: &str, file_b: &str, invert: bool) -> Arguments {
+        Arguments {
+            check_files: vec![
+                format!("test_data/json/{}.json", file_a),
+                format!("test_data/json/{}.json", file_b),
+            ],
+            key_diffs: true,
+            type_diffs: true,
+            value_diffs: true,
+            array_diffs: true,
+            invert,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_files_to_check() {
+        let result = App::parse_args(Arguments::default());
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+
+        let mut args = arguments("person1", "person3", false);
+        args.check_files = vec![
+            "test_data/person1.txt".to_owned(),
+            "test_data/person2.txt".to_owned(),
+        ];
+        let result = App::new(args);
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+
+        let mut args = arguments("person1", "person3", false);
+        args.check_files[1] = "test_data/yaml/person1.yaml".to_owned();
+        let result = App::new(args);
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+    }
+
+    #[test]
+    fn test_undetected_format() {
+        let mut args = arguments("person1", "person3", false);
+        args.check_files[1] = "test_data/person1.txt".to_owned();
+
+        let error = App::new(args).err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Diff error: test_data/json/person1.json is JSON, but the format of \
+             test_data/person1.txt could not be detected. Give it the .json, .toml, .yaml or .yml \
+             extension matching its contents."
+        );
+    }
+
+    #[test]
+    fn test_plan_directory() {
+        let dir = std::env::temp_dir().join(format!("dtf_plan_{}", std::process::id()));
+        for (sub_dir, file) in [("a", "x.json"), ("a", "y.json"), ("b", "x.json")] {
+            fs::create_dir_all(dir.join(sub_dir)).unwrap();
+            fs::write(dir.join(sub_dir).join(file), "{}").unwrap();
+        }
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        let args = Arguments {
+            check_files: vec![path("a/*.json"), path("b/*.json")],
+            key_diffs: true,
+            value_diffs: true,
+            ignore_path: vec!["metadata".to_owned()],
+            ordered_array: vec!["steps".to_owned()],
+            plan: true,
+            ..Default::default()
         };
 
-        let render_type_diffs = self.context.config.render_type_diffs
-            && self.diffs.1.as_ref().filter(|td| !td.is_empty()).is_some();
-        let type_diffs = if render_type_diffs {
-            self.diffs.1.as_ref()
-        } else {
-            None
+        let plan = App::plan(args).unwrap();
+
+        assert_eq!(
+            plan,
+            [
+                "Compare:".to_owned(),
+                format!("  {} against {}", path("a/x.json"), path("b/x.json")),
+                "Skip without a pair:".to_owned(),
+                format!("  {}", path("a/y.json")),
+                "Check for: key, value".to_owned(),
+                "Ordered arrays: steps".to_owned(),
+                "Only paths: all".to_owned(),
+                "Ignored paths: metadata".to_owned(),
+                "Output: tables".to_owned(),
+            ]
+            .join("\n")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_compare_json_against_toml() {
+        let mut args = arguments("config", "config", false);
+        args.check_files[1] = "test_data/toml/config.toml".to_owned();
+
+        let (key_diff, type_diff, value_diff, array_diff) = App::new(args).unwrap().diffs;
+
+        assert!(key_diff.unwrap().is_empty());
+        assert!(type_diff.unwrap().is_empty());
+        assert!(value_diff.unwrap().is_empty());
+        assert!(array_diff.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_missing_file() {
+        let result = App::new(arguments("person1", "missing", false));
+        assert!(
+            matches!(result, Err(DtfError::FileNotFound(path)) if path == "test_data/json/missing.json")
+        );
+    }
+
+    #[test]
+    fn test_check_missing_file_json_error() {
+        let args = Arguments {
+            format: OutputFormat::Json,
+            ..arguments("person1", "missing", false)
         };
+        assert!(args.format.is_machine_readable());
 
-        let render_value_diffs = self.context.config.render_value_diffs
-            && self.diffs.2.as_ref().filter(|vd| !vd.is_empty()).is_some();
-        let value_diffs = if render_value_diffs {
-            self.diffs.2.as_ref()
-        } else {
-            None
+        let error = App::new(args).err().unwrap();
+
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "error": {
+                    "kind": "fileNotFound",
+                    "message": "File not found: test_data/json/missing.json"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let dir = std::env::temp_dir().join(format!("dtf_redact_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        fs::write(path("a.json"), r#"{"token": "sk-abc", "key": "sk-same"}"#).unwrap();
+        fs::write(path("b.json"), r#"{"token": "sk-abd", "key": "sk-same"}"#).unwrap();
+        let args = Arguments {
+            check_files: vec![path("a.json"), path("b.json")],
+            value_diffs: true,
+            format: OutputFormat::Json,
+            redact: vec![Regex::new(r"sk-\w+").unwrap()],
+            ..Default::default()
         };
 
-        let render_array_diffs = self.context.config.render_array_diffs
-            && self.diffs.3.as_ref().filter(|ad| !ad.is_empty()).is_some();
-        let array_diffs = if render_array_diffs {
-            self.diffs.3.as_ref()
-        } else {
-            None
+        let app = App::new(args).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The real values still decide what differs
+        let value_diffs = app.diffs.2.as_ref().unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "token");
+        let json = app.render_json().unwrap();
+        assert!(json.contains("***"));
+        assert!(!json.contains("sk-"));
+    }
+
+    #[test]
+    fn test_redact_common_and_colliding_keys() {
+        let dir = std::env::temp_dir().join(format!("dtf_redact_common_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        fs::write(
+            path("a.json"),
+            r#"{"sk-abc": 1, "sk-abd": "x", "id": 1, "shared": {"sk-key": "sk-value"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            path("b.json"),
+            r#"{"sk-abc": 2, "sk-abd": "y", "id": 2, "shared": {"sk-key": "sk-value"}}"#,
+        )
+        .unwrap();
+        let args = Arguments {
+            check_files: vec![path("a.json"), path("b.json")],
+            value_diffs: true,
+            show_types_in_values: true,
+            common: Some(path("common.json")),
+            redact: vec![Regex::new(r"sk-\w+").unwrap()],
+            ..Default::default()
         };
 
-        html_renderer.init_document(
-            &mut buf,
-            (
-                render_key_diffs,
-                render_type_diffs,
-                render_value_diffs,
-                render_array_diffs,
-            ),
-        )?;
+        let app = App::new(args).unwrap();
+        let common = fs::read_to_string(path("common.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
 
-        if render_key_diffs {
-            html_renderer.render_key_diff_table(&mut buf, key_diffs.unwrap())?;
-        }
+        assert!(common.contains("***"));
+        assert!(!common.contains("sk-"));
+        // Both secrets are redacted to the same key, so neither gets the types of the other
+        assert_eq!(app.context.value_types_of("***"), None);
+        assert_eq!(
+            app.context.value_types_of("id"),
+            Some(&("number".to_owned(), "number".to_owned()))
+        );
+    }
 
-        if render_type_diffs {
-            html_renderer.render_type_diff_table(&mut buf, type_diffs.unwrap())?;
-        }
+    #[test]
+    fn test_saved_file_read_once() {
+        let path = std::env::temp_dir().join(format!("dtf_saved_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let write_args = Arguments {
+            write_to_file: Some(path.clone()),
+            ..arguments("person1", "person2", false)
+        };
+        let app = App::new(write_args).unwrap();
+        app.file_handler
+            .write_to_file(app.diffs.clone(), &app.context)
+            .unwrap();
+        let read_args = Arguments {
+            read_from_file: path.clone(),
+            value_diffs: true,
+            ..Default::default()
+        };
+        let (_, _, config) = App::parse_args(read_args.clone()).unwrap();
+        let mut app = App::new(read_args).unwrap();
+        let loaded = serialize(&app.diffs);
+
+        // Collecting the data again must not need the file anymore
+        fs::remove_file(&path).unwrap();
+        app.collect_data(&config).unwrap();
+
+        assert_eq!(serialize(&app.diffs), loaded);
+        assert!(!app.diffs.2.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_array_same_order_env() {
+        let env = |value: &'static str| {
+            move |name: &str| (name == ARRAY_SAME_ORDER_VAR).then(|| value.to_owned())
+        };
+        let args = || arguments("person1", "person2", false);
+
+        let (_, _, config) = App::parse_args_with_env(args(), env("true")).unwrap();
+        assert_eq!(config.array_same_order, true);
+        assert_eq!(config.array_match_strategy, ArrayMatchStrategy::Positional);
+
+        let (_, _, config) = App::parse_args_with_env(args(), env("0")).unwrap();
+        assert_eq!(config.array_same_order, false);
 
-        if render_value_diffs {
-            html_renderer.render_value_diff_table(&mut buf, value_diffs.unwrap())?;
+        // The flags win over the environment
+        let ordered_args = Arguments {
+            array_same_order: true,
+            ..args()
+        };
+        let (_, _, config) = App::parse_args_with_env(ordered_args, env("false")).unwrap();
+        assert_eq!(config.array_same_order, true);
+        let set_args = Arguments {
+            array_match: Some(ArrayMatchStrategy::Set),
+            ..args()
+        };
+        let (_, _, config) = App::parse_args_with_env(set_args, env("true")).unwrap();
+        assert_eq!(config.array_same_order, false);
+
+        let result = App::parse_args_with_env(args(), env("sometimes"));
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+        let (_, _, config) = App::parse_args_with_env(args(), |_| None).unwrap();
+        assert_eq!(config.array_same_order, false);
+    }
+
+    #[test]
+    fn test_array_key_keeps_set_matching() {
+        let args = Arguments {
+            array_key: Some("id".to_owned()),
+            ..arguments("person1", "person2", false)
+        };
+        let (_, _, config) = App::parse_args_with_env(args, |_| None).unwrap();
+        assert_eq!(config.array_match_strategy, ArrayMatchStrategy::Set);
+        assert_eq!(config.array_key.as_deref(), Some("id"));
+
+        let key_args = Arguments {
+            array_key: Some("id".to_owned()),
+            array_match: Some(ArrayMatchStrategy::Key),
+            ..arguments("person1", "person2", false)
+        };
+        let (_, _, config) = App::parse_args_with_env(key_args, |_| None).unwrap();
+        assert_eq!(config.array_match_strategy, ArrayMatchStrategy::Key);
+    }
+
+    #[test]
+    fn test_string_similarity_out_of_range() {
+        for threshold in [-0.1, 1.5, f64::NAN] {
+            let args = Arguments {
+                string_similarity: Some(threshold),
+                ..arguments("person1", "person2", false)
+            };
+            let result = App::parse_args_with_env(args, |_| None);
+            assert!(matches!(result, Err(DtfError::MissingArgument(_))));
         }
+    }
 
-        if render_array_diffs {
-            html_renderer.render_array_diff_table(&mut buf, array_diffs.unwrap())?;
+    #[test]
+    fn test_sample_too_small() {
+        for size in [0, 1] {
+            let args = Arguments {
+                sample: Some(size),
+                ..arguments("person1", "person2", false)
+            };
+            let result = App::parse_args_with_env(args, |_| None);
+            assert!(matches!(result, Err(DtfError::MissingArgument(_))));
         }
+    }
 
-        // At this point the file name is sure to exist
-        let mut file = File::create(self.context.config.browser_view.as_ref().unwrap())
-            .map_err(|e| DtfError::DiffError(format!("Could not create file: {}", e)))?;
+    #[test]
+    fn test_compare_keys_only() {
+        let args = Arguments {
+            check_files: vec![
+                "test_data/json/person3.json".to_owned(),
+                "test_data/json/person4.json".to_owned(),
+            ],
+            compare_keys_only: true,
+            ..Default::default()
+        };
 
-        write!(file, "{}", buf.finish()).map_err(|e| DtfError::DiffError(format!("{}", e)))
+        let (key_diff, type_diff, value_diff, array_diff) = App::new(args).unwrap().diffs;
+
+        assert!(!key_diff.unwrap().is_empty());
+        assert!(type_diff.is_none());
+        assert!(value_diff.is_none());
+        assert!(array_diff.is_none());
     }
 
-    fn are_diffs_empty(diffs: &DiffCollection) -> bool {
-        diffs.0.is_none() && diffs.1.is_none() && diffs.2.is_none() && diffs.3.is_none()
+    #[test]
+    fn test_arrays_only() {
+        let args = Arguments {
+            arrays_only: true,
+            check_key_order: true,
+            ..arguments("person1", "person2", false)
+        };
+
+        let app = App::new(args).unwrap();
+        let rendered_tables = app.rendered_tables();
+
+        assert_eq!(rendered_tables.len(), 1);
+        assert!(rendered_tables[0].contains("Array Differences"));
+        let stats = DiffStats::from_diffs(&app.diffs);
+        assert_eq!(stats.total(), stats.array_diffs);
+        assert_ne!(stats.array_diffs, 0);
+    }
+
+    #[test]
+    fn test_count() {
+        let args = Arguments {
+            count: true,
+            ..arguments("person1", "person2", false)
+        };
+
+        let app = App::new(args).unwrap();
+
+        let total = count_diffs(&app.diffs);
+        assert_ne!(total, 0);
+        assert_eq!(app.render_count(), format!("{}\n", total));
+        assert!(app.render_count().trim_end().parse::<usize>().is_ok());
+    }
+
+    #[test]
+    fn test_fail_fast() {
+        let args = Arguments {
+            fail_fast: true,
+            ..arguments("person1", "person2", false)
+        };
+
+        let app = App::new(args).unwrap();
+
+        assert_eq!(count_diffs(&app.diffs), 1);
+        assert!(matches!(app.check_fail_fast(), Outcome::Failed(_)));
+
+        let args = Arguments {
+            fail_fast: true,
+            ..arguments("person1", "person1", false)
+        };
+        let app = App::new(args).unwrap();
+        assert_eq!(count_diffs(&app.diffs), 0);
+        assert_eq!(app.check_fail_fast(), Outcome::Passed);
+    }
+
+    #[test]
+    fn test_fail_on_severity() {
+        let severity_rules = || {
+            vec![
+                "key=info".parse().unwrap(),
+                "type=info".parse().unwrap(),
+                "value=warning".parse().unwrap(),
+                "array=info".parse().unwrap(),
+            ]
+        };
+        let args = Arguments {
+            severity: severity_rules(),
+            fail_on_severity: Some(Severity::Warning),
+            ..arguments("person1", "person2", false)
+        };
+        let app = App::new(args).unwrap();
+        assert!(matches!(app.outcome(), Outcome::Failed(_)));
+
+        let args = Arguments {
+            severity: severity_rules(),
+            fail_on_severity: Some(Severity::Error),
+            ..arguments("person1", "person2", false)
+        };
+        let app = App::new(args).unwrap();
+        assert_eq!(app.outcome(), Outcome::Passed);
+    }
+
+    #[test]
+    fn test_hash() {
+        let dir = std::env::temp_dir().join(format!("dtf_hash_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_a = dir.join("a.json").to_string_lossy().into_owned();
+        let file_b = dir.join("b.json").to_string_lossy().into_owned();
+        fs::write(&file_a, r#"{"name": "John", "age": 30}"#).unwrap();
+        fs::write(&file_b, "{\n  \"age\": 30,\n  \"name\": \"John\"\n}\n").unwrap();
+        let args = Arguments {
+            check_files: vec![file_a.clone(), file_b.clone()],
+            hash: true,
+            ..Default::default()
+        };
+
+        let app = App::new(args).unwrap();
+
+        assert_eq!(count_diffs(&app.diffs), 0);
+        let file_hashes = app.context.file_hashes.as_ref().unwrap();
+        assert!(!file_hashes.byte_identical);
+        assert!(file_hashes
+            .render(app.context.get_file_names(), true)
+            .ends_with("The files are structurally identical, but their bytes differ"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_category_outputs() {
+        let dir = std::env::temp_dir().join(format!("dtf_category_outputs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key_out = dir.join("keys.json").to_string_lossy().into_owned();
+        let value_out = dir.join("values.html").to_string_lossy().into_owned();
+        let args = Arguments {
+            check_files: vec![
+                "test_data/json/person1.json".to_owned(),
+                "test_data/json/person2.json".to_owned(),
+            ],
+            key_out: Some(key_out.clone()),
+            value_out: Some(value_out.clone()),
+            ..Default::default()
+        };
+
+        App::new(args).unwrap().write_category_outputs().unwrap();
+
+        let keys: Value = serde_json::from_str(&fs::read_to_string(&key_out).unwrap()).unwrap();
+        assert!(!keys["keyDiffs"].as_array().unwrap().is_empty());
+        assert!(keys["valueDiffs"].as_array().unwrap().is_empty());
+        let values = fs::read_to_string(&value_out).unwrap();
+        assert!(values.contains("Value Differences"));
+        assert!(!values.contains("Key Differences"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_baseline_diffs() {
+        let path = std::env::temp_dir().join(format!("dtf_baseline_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let write_args = Arguments {
+            write_to_file: Some(path.clone()),
+            ..arguments("person1", "person2", false)
+        };
+        let app = App::new(write_args).unwrap();
+        app.file_handler
+            .write_to_file(app.diffs.clone(), &app.context)
+            .unwrap();
+
+        // Every difference is known
+        let args = Arguments {
+            baseline_diffs: Some(path.clone()),
+            ..arguments("person1", "person2", false)
+        };
+        let app = App::new(args).unwrap();
+        assert_eq!(count_diffs(&app.diffs), 0);
+        assert_eq!(app.check_new_diffs(&path), Outcome::Passed);
+
+        // The differences of another file are new
+        let args = Arguments {
+            baseline_diffs: Some(path.clone()),
+            ..arguments("person1", "person3", false)
+        };
+        let app = App::new(args).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_ne!(count_diffs(&app.diffs), 0);
+        assert!(matches!(app.check_new_diffs(&path), Outcome::Failed(_)));
+    }
+
+    #[test]
+    fn test_positions() {
+        let args = Arguments {
+            format: OutputFormat::Json,
+            positions: true,
+            ..arguments("person1", "person2", false)
+        };
+
+        let app = App::new(args).unwrap();
+
+        let data: serde_json::Value = serde_json::from_str(&app.render_json().unwrap()).unwrap();
+        let key_diffs = data["keyDiffs"].as_array().unwrap();
+        let age = key_diffs.iter().find(|kd| kd["key"] == "age").unwrap();
+        assert_eq!(age["side"], "A");
+        assert_eq!(age["positionA"], json!({"line": 3, "column": 3}));
+        assert!(age.get("positionB").is_none());
+        let name = data["valueDiffs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|vd| vd["key"] == "name")
+            .unwrap();
+        assert_eq!(name["positionA"], json!({"line": 2, "column": 3}));
+        assert_eq!(name["positionB"], json!({"line": 2, "column": 3}));
+    }
+
+    #[test]
+    fn test_read_missing_saved_file() {
+        let args = Arguments {
+            read_from_file: "test_data/missing_results.json".to_owned(),
+            ..Default::default()
+        };
+        let result = App::new(args);
+        assert!(matches!(result, Err(DtfError::FileNotFound(_))));
+    }
+
+    fn serialize(diffs: &DiffCollection) -> String {
+        serde_json::to_string(diffs).unwrap()
     }
 }
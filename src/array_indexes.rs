@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::value_types::{lookup_json, lookup_yaml};
+
+/// The indexes of the array items only one of the files has,
+/// by the key of the array, whether the item is in the first file and the item itself.
+/// Equal items have the indexes of all their occurrences, in the order of their differences.
+pub type ArrayIndexes = HashMap<(String, bool, String), Vec<usize>>;
+
+/// Looks up where the items of the array differences are in the arrays of the JSON data
+pub fn find_json_array_indexes(
+    array_diffs: &[ArrayDiff],
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> ArrayIndexes {
+    find_array_indexes(array_diffs, |ad, in_a| {
+        let (data, other) = if in_a { (data1, data2) } else { (data2, data1) };
+        let item = serde_json::from_str::<Value>(&ad.value).ok();
+        let array = lookup_json(data, &ad.key)?.as_array()?;
+        let other = lookup_json(other, &ad.key)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice);
+        Some(occurrences(array, other, |value| {
+            item.as_ref() == Some(value) || value.as_str() == Some(&ad.value)
+        }))
+    })
+}
+
+/// Looks up where the items of the array differences are in the sequences of the YAML data
+pub fn find_yaml_array_indexes(
+    array_diffs: &[ArrayDiff],
+    data1: &Mapping,
+    data2: &Mapping,
+) -> ArrayIndexes {
+    find_array_indexes(array_diffs, |ad, in_a| {
+        let (data, other) = if in_a { (data1, data2) } else { (data2, data1) };
+        let item = serde_yaml::from_str::<YamlValue>(&ad.value).ok();
+        let sequence = lookup_yaml(data, &ad.key)?.as_sequence()?;
+        let other = lookup_yaml(other, &ad.key)
+            .and_then(YamlValue::as_sequence)
+            .map(Vec::as_slice);
+        Some(occurrences(sequence, other, |value| {
+            item.as_ref() == Some(value) || value.as_str() == Some(&ad.value)
+        }))
+    })
+}
+
+/// Gives every difference an index of its item not taken by an earlier difference of the same array,
+/// so equal items get the indexes of different occurrences
+fn find_array_indexes(
+    array_diffs: &[ArrayDiff],
+    occurrences_of: impl Fn(&ArrayDiff, bool) -> Option<Vec<usize>>,
+) -> ArrayIndexes {
+    let mut taken: HashMap<(&str, bool), HashSet<usize>> = HashMap::new();
+    let mut array_indexes = ArrayIndexes::new();
+    for ad in array_diffs {
+        let in_a = is_in_first_file(&ad.descriptor);
+        let taken = taken.entry((ad.key.as_str(), in_a)).or_default();
+        let index = occurrences_of(ad, in_a)
+            .and_then(|indexes| indexes.into_iter().find(|index| !taken.contains(index)));
+        if let Some(index) = index {
+            taken.insert(index);
+            array_indexes
+                .entry((ad.key.clone(), in_a, ad.value.clone()))
+                .or_default()
+                .push(index);
+        }
+    }
+    array_indexes
+}
+
+/// The indexes of the matching items, the ones the other array has something else at first,
+/// so an item equal to the one at the same index of the other array is only used last
+fn occurrences<T: PartialEq>(
+    array: &[T],
+    other: Option<&[T]>,
+    is_match: impl Fn(&T) -> bool,
+) -> Vec<usize> {
+    let (changed, same): (Vec<usize>, Vec<usize>) = array
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| is_match(value))
+        .map(|(index, _)| index)
+        .partition(|&index| other.and_then(|other| other.get(index)) != Some(&array[index]));
+    changed.into_iter().chain(same).collect()
+}
+
+/// Get the indexes of the occurrences of an array item in the file containing it, if they are known
+pub fn array_indexes_of<'a>(
+    array_indexes: &'a ArrayIndexes,
+    key: &str,
+    in_a: bool,
+    value: &str,
+) -> &'a [usize] {
+    array_indexes
+        .get(&(key.to_owned(), in_a, value.to_owned()))
+        .map_or(&[], Vec::as_slice)
+}
+
+/// Hands out the indexes of equal array items one occurrence after the other,
+/// so each of them is shown with a different index
+#[derive(Default)]
+pub struct IndexCursor {
+    taken: HashMap<(String, bool, String), usize>,
+}
+
+impl IndexCursor {
+    /// Get the index of the next occurrence of the array item, if it's known
+    pub fn next(
+        &mut self,
+        array_indexes: &ArrayIndexes,
+        key: &str,
+        in_a: bool,
+        value: &str,
+    ) -> Option<usize> {
+        let taken = self
+            .taken
+            .entry((key.to_owned(), in_a, value.to_owned()))
+            .or_default();
+        let index = array_indexes_of(array_indexes, key, in_a, value)
+            .get(*taken)
+            .copied();
+        *taken += 1;
+        index
+    }
+}
+
+/// Appends the index to a displayed array item, if it's known
+pub fn annotate_index(value: String, index: Option<usize>) -> String {
+    match index {
+        Some(index) => format!("{} (index {})", value, index),
+        None => value,
+    }
+}
+
+/// Splits the items of an array into the runs of at least two consecutive indexes, by their first and last
+/// index, and the items left with their indexes, which keep their order
+pub fn collapse_index_ranges(
+    values: Vec<(&str, Option<usize>)>,
+) -> (Vec<(&str, Option<usize>)>, Vec<(usize, usize)>) {
+    let mut indexes: Vec<usize> = values.iter().filter_map(|(_, index)| *index).collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+
+    let mut runs: Vec<(usize, usize)> = vec![];
+    for index in indexes {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == index => *end = index,
+            _ => runs.push((index, index)),
+        }
+    }
+    let ranges: Vec<(usize, usize)> = runs
+        .into_iter()
+        .filter(|(start, end)| start < end)
+        .collect();
+    let collapsed = |index: usize| {
+        ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&index))
+    };
+    let rest = values
+        .into_iter()
+        .filter(|(_, index)| !index.is_some_and(collapsed))
+        .collect();
+    (rest, ranges)
+}
+
+/// Describes a range of array items only one of the files has, like `indices 10–42 added in B`
+pub fn describe_range(range: (usize, usize), in_a: bool, ascii: bool) -> String {
+    let (start, end) = range;
+    let dash = if ascii { "-" } else { "\u{2013}" };
+    let change = if in_a { "removed in B" } else { "added in B" };
+    format!("indices {}{}{} {}", start, dash, end, change)
+}
+
+/// Checks if the item of the array difference is in the first file
+pub fn is_in_first_file(descriptor: &ArrayDiffDesc) -> bool {
+    matches!(descriptor, ArrayDiffDesc::AHas | ArrayDiffDesc::BMisses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_index_ranges() {
+        let values = vec![
+            ("a", Some(10)),
+            ("b", Some(12)),
+            ("c", Some(11)),
+            ("d", Some(20)),
+            ("f", Some(30)),
+            ("e", Some(31)),
+            ("g", None),
+        ];
+
+        let (rest, ranges) = collapse_index_ranges(values);
+
+        assert_eq!(rest, vec![("d", Some(20)), ("g", None)]);
+        assert_eq!(ranges, vec![(10, 12), (30, 31)]);
+        assert_eq!(
+            describe_range((10, 12), false, false),
+            "indices 10\u{2013}12 added in B"
+        );
+        assert_eq!(
+            describe_range((30, 31), true, true),
+            "indices 30-31 removed in B"
+        );
+    }
+
+    #[test]
+    fn test_find_json_array_indexes() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"tags": ["a", "b", "c", "x"], "nested": {"ids": [1, 2]}}"#)
+                .unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"tags": ["c", "a", "y"], "nested": {"ids": [2, 3]}}"#)
+                .unwrap();
+        let array_diff = |descriptor: ArrayDiffDesc, key: &str, value: &str| ArrayDiff {
+            descriptor,
+            key: key.to_owned(),
+            value: value.to_owned(),
+        };
+
+        let array_indexes = find_json_array_indexes(
+            &[
+                array_diff(ArrayDiffDesc::AHas, "tags", "\"b\""),
+                array_diff(ArrayDiffDesc::AHas, "tags", "x"),
+                array_diff(ArrayDiffDesc::BHas, "tags", "\"y\""),
+                array_diff(ArrayDiffDesc::AHas, "nested.ids", "1"),
+                array_diff(ArrayDiffDesc::BHas, "nested.ids", "3"),
+                array_diff(ArrayDiffDesc::BHas, "missing", "1"),
+            ],
+            &data1,
+            &data2,
+        );
+
+        assert_eq!(array_indexes_of(&array_indexes, "tags", true, "\"b\""), [1]);
+        assert_eq!(array_indexes_of(&array_indexes, "tags", true, "x"), [3]);
+        assert_eq!(
+            array_indexes_of(&array_indexes, "tags", false, "\"y\""),
+            [2]
+        );
+        assert_eq!(
+            array_indexes_of(&array_indexes, "nested.ids", true, "1"),
+            [0]
+        );
+        assert_eq!(
+            array_indexes_of(&array_indexes, "nested.ids", false, "3"),
+            [1]
+        );
+        assert!(array_indexes_of(&array_indexes, "missing", false, "1").is_empty());
+    }
+
+    #[test]
+    fn test_find_yaml_array_indexes() {
+        let data1: Mapping = serde_yaml::from_str("list:\n  - name: a\n  - name: b\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("list:\n  - name: b\n").unwrap();
+        let array_diff = ArrayDiff {
+            descriptor: ArrayDiffDesc::AHas,
+            key: "list".to_owned(),
+            value: "name: a\n".to_owned(),
+        };
+
+        let array_indexes = find_yaml_array_indexes(&[array_diff], &data1, &data2);
+
+        assert_eq!(
+            array_indexes_of(&array_indexes, "list", true, "name: a\n"),
+            [0]
+        );
+    }
+
+    #[test]
+    fn test_find_json_array_indexes_ordered_duplicates() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"tags": ["x", "y", "x", "x"]}"#).unwrap();
+        let data2: Map<String, Value> = serde_json::from_str(r#"{"tags": ["x", "z"]}"#).unwrap();
+        let array_diff = |descriptor: ArrayDiffDesc, value: &str| ArrayDiff {
+            descriptor,
+            key: "tags".to_owned(),
+            value: value.to_owned(),
+        };
+
+        let array_indexes = find_json_array_indexes(
+            &[
+                array_diff(ArrayDiffDesc::AHas, "y"),
+                array_diff(ArrayDiffDesc::AHas, "x"),
+                array_diff(ArrayDiffDesc::AHas, "x"),
+                array_diff(ArrayDiffDesc::BHas, "z"),
+            ],
+            &data1,
+            &data2,
+        );
+
+        assert_eq!(array_indexes_of(&array_indexes, "tags", true, "x"), [2, 3]);
+        assert_eq!(array_indexes_of(&array_indexes, "tags", true, "y"), [1]);
+
+        let mut cursor = IndexCursor::default();
+        assert_eq!(cursor.next(&array_indexes, "tags", true, "x"), Some(2));
+        assert_eq!(cursor.next(&array_indexes, "tags", true, "x"), Some(3));
+        assert_eq!(cursor.next(&array_indexes, "tags", true, "x"), None);
+        assert_eq!(cursor.next(&array_indexes, "tags", false, "z"), Some(1));
+    }
+}
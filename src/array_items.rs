@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use serde_json::Value;
+use serde_yaml::Value as YamlValue;
+
+use crate::{
+    array_indexes::is_in_first_file,
+    canonical::{json_item_key, yaml_item_key},
+    dtfterminal_types::DiffCollection,
+    value_filters::parse_instant,
+};
+
+/// Drops the array differences of JSON items that only differ in how they are written,
+/// like objects with their keys in a different order or `1.0` and `1`. They are in both arrays after all.
+/// With `parse_dates`, timestamps of the same instant are written the same way too, even inside objects.
+pub fn drop_equivalent_json_items(diffs: DiffCollection, parse_dates: bool) -> DiffCollection {
+    drop_equivalent_items(diffs, |value| {
+        serde_json::from_str::<Value>(value).map_or_else(
+            |_| value.to_owned(),
+            |item| {
+                if parse_dates {
+                    json_item_key(&json_instants_in_utc(item))
+                } else {
+                    json_item_key(&item)
+                }
+            },
+        )
+    })
+}
+
+/// Drops the array differences of YAML items that only differ in how they are written, like `drop_equivalent_json_items`
+pub fn drop_equivalent_yaml_items(diffs: DiffCollection, parse_dates: bool) -> DiffCollection {
+    drop_equivalent_items(diffs, |value| {
+        serde_yaml::from_str::<YamlValue>(value).map_or_else(
+            |_| value.to_owned(),
+            |item| {
+                if parse_dates {
+                    yaml_item_key(&yaml_instants_in_utc(item))
+                } else {
+                    yaml_item_key(&item)
+                }
+            },
+        )
+    })
+}
+
+/// Rewrites the timestamps in the JSON item to UTC, so the same instants are the same strings
+fn json_instants_in_utc(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(instant_in_utc(s)),
+        Value::Array(array) => Value::Array(array.into_iter().map(json_instants_in_utc).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, json_instants_in_utc(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Rewrites the timestamps in the YAML item to UTC, like `json_instants_in_utc`
+fn yaml_instants_in_utc(value: YamlValue) -> YamlValue {
+    match value {
+        YamlValue::String(s) => YamlValue::String(instant_in_utc(s)),
+        YamlValue::Sequence(sequence) => {
+            YamlValue::Sequence(sequence.into_iter().map(yaml_instants_in_utc).collect())
+        }
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (key, yaml_instants_in_utc(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn instant_in_utc(value: String) -> String {
+    match parse_instant(&value) {
+        Some(instant) => instant.with_timezone(&Utc).to_rfc3339(),
+        None => value,
+    }
+}
+
+/// Pairs the items only the first file has with equivalent items only the second file has, under the same key,
+/// and drops both. An item is paired at most once, so duplicates still count.
+fn drop_equivalent_items(
+    diffs: DiffCollection,
+    item_key: impl Fn(&str) -> String,
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let array_diff = array_diff.map(|array_diffs| {
+        let item_keys: Vec<(&str, String)> = array_diffs
+            .iter()
+            .map(|ad| (ad.key.as_str(), item_key(&ad.value)))
+            .collect();
+
+        // The indexes of the unpaired items of the second file by their array and item key
+        let mut items_b: HashMap<&(&str, String), Vec<usize>> = HashMap::new();
+        for (index, ad) in array_diffs.iter().enumerate().rev() {
+            if !is_in_first_file(&ad.descriptor) {
+                items_b.entry(&item_keys[index]).or_default().push(index);
+            }
+        }
+
+        let mut paired = vec![false; array_diffs.len()];
+        for (index, ad) in array_diffs.iter().enumerate() {
+            if !is_in_first_file(&ad.descriptor) {
+                continue;
+            }
+            if let Some(index_b) = items_b
+                .get_mut(&item_keys[index])
+                .and_then(|indexes| indexes.pop())
+            {
+                paired[index] = true;
+                paired[index_b] = true;
+            }
+        }
+
+        array_diffs
+            .into_iter()
+            .zip(paired)
+            .filter(|(_, paired)| !paired)
+            .map(|(ad, _)| ad)
+            .collect()
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+
+    use super::*;
+
+    fn array_diff(descriptor: ArrayDiffDesc, value: &str) -> ArrayDiff {
+        ArrayDiff {
+            descriptor,
+            key: "users".to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_drop_equivalent_json_items() {
+        let diffs = (
+            None,
+            None,
+            None,
+            Some(vec![
+                array_diff(ArrayDiffDesc::AHas, r#"{"id":1.0,"name":"a"}"#),
+                array_diff(ArrayDiffDesc::AHas, r#"{"id":2,"name":"b"}"#),
+                array_diff(ArrayDiffDesc::BHas, r#"{"name":"a","id":1}"#),
+                array_diff(ArrayDiffDesc::BHas, r#"{"id":3,"name":"b"}"#),
+            ]),
+        );
+
+        let (_, _, _, array_diff) = drop_equivalent_json_items(diffs, false);
+
+        let values: Vec<String> = array_diff.unwrap().into_iter().map(|ad| ad.value).collect();
+        assert_eq!(
+            values,
+            vec![r#"{"id":2,"name":"b"}"#, r#"{"id":3,"name":"b"}"#]
+        );
+    }
+
+    #[test]
+    fn test_drop_equivalent_yaml_items() {
+        let diffs = (
+            None,
+            None,
+            None,
+            Some(vec![
+                array_diff(ArrayDiffDesc::AHas, "id: 1\nname: a\n"),
+                array_diff(ArrayDiffDesc::AHas, "id: 1\nname: a\n"),
+                array_diff(ArrayDiffDesc::BHas, "name: a\nid: 1.0\n"),
+            ]),
+        );
+
+        let (_, _, _, array_diff) = drop_equivalent_yaml_items(diffs, false);
+
+        let array_diff = array_diff.unwrap();
+        assert_eq!(array_diff.len(), 1);
+        assert_eq!(array_diff[0].descriptor, ArrayDiffDesc::AHas);
+    }
+
+    #[test]
+    fn test_drop_equivalent_json_items_parse_dates() {
+        let diffs = || {
+            (
+                None,
+                None,
+                None,
+                Some(vec![
+                    array_diff(ArrayDiffDesc::AHas, r#""2024-01-01T00:00:00Z""#),
+                    array_diff(ArrayDiffDesc::AHas, r#"{"at":"2024-01-01T02:00:00+02:00"}"#),
+                    array_diff(ArrayDiffDesc::BHas, r#""2024-01-01T00:00:00+00:00""#),
+                    array_diff(ArrayDiffDesc::BHas, r#"{"at":"2024-01-01T00:00:00Z"}"#),
+                ]),
+            )
+        };
+
+        let (_, _, _, array_diff) = drop_equivalent_json_items(diffs(), true);
+        assert!(array_diff.unwrap().is_empty());
+
+        let (_, _, _, array_diff) = drop_equivalent_json_items(diffs(), false);
+        assert_eq!(array_diff.unwrap().len(), 4);
+    }
+}
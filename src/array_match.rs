@@ -0,0 +1,229 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use libdtf::core::diff_types::{ArrayDiff, KeyDiff, ValueDiff};
+use log::debug;
+use serde_json::{Map, Value};
+
+use crate::{
+    array_indexes::{is_in_first_file, ArrayIndexes, IndexCursor},
+    dtfterminal_types::{ArrayMatchStrategy, Config, DiffCollection},
+};
+
+/// Pairs the objects only one of the files has in an array by the fields they share.
+/// The differing fields of a pair are reported as Key and Value differences at the index
+/// the object has in the first file, instead of an Array difference for each object.
+/// With the key strategy only the objects without the `array_key` field are paired this way.
+pub fn pair_similar_items(
+    diffs: DiffCollection,
+    config: &Config,
+    array_indexes: &ArrayIndexes,
+    (file_a, file_b): (&str, &str),
+) -> DiffCollection {
+    let (mut key_diff, type_diff, value_diff, array_diff) = diffs;
+    let (mut value_diffs, array_diffs) = match (value_diff, array_diff) {
+        (Some(value_diffs), Some(array_diffs)) => (value_diffs, array_diffs),
+        (value_diff, array_diff) => return (key_diff, type_diff, value_diff, array_diff),
+    };
+
+    let array_key = match config.array_match_strategy {
+        ArrayMatchStrategy::Key => config.array_key.as_deref(),
+        _ => None,
+    };
+    let objects: Vec<Option<Map<String, Value>>> = array_diffs
+        .iter()
+        .map(|ad| {
+            parse_object(&ad.value)
+                .filter(|object| array_key.map_or(true, |key| !object.contains_key(key)))
+        })
+        .collect();
+    let mut cursor = IndexCursor::default();
+    let indexes: Vec<Option<usize>> = array_diffs
+        .iter()
+        .map(|ad| {
+            let in_a = is_in_first_file(&ad.descriptor);
+            cursor.next(array_indexes, &ad.key, in_a, &ad.value)
+        })
+        .collect();
+
+    let mut candidates = vec![];
+    for (a, ad_a) in array_diffs.iter().enumerate() {
+        for (b, ad_b) in array_diffs.iter().enumerate() {
+            if !is_in_first_file(&ad_a.descriptor)
+                || is_in_first_file(&ad_b.descriptor)
+                || ad_a.key != ad_b.key
+            {
+                continue;
+            }
+            if let (Some(object_a), Some(object_b)) = (&objects[a], &objects[b]) {
+                if let Some(shared) = shared_fields(object_a, object_b) {
+                    candidates.push((shared, a, b));
+                }
+            }
+        }
+    }
+    candidates.sort_by_key(|&(shared, _, _)| Reverse(shared));
+
+    let mut paired = HashSet::new();
+    for (_, a, b) in candidates {
+        if paired.contains(&a) || paired.contains(&b) {
+            continue;
+        }
+        let ad_a = &array_diffs[a];
+        let index = match indexes[a] {
+            Some(index) => index,
+            None => continue,
+        };
+        paired.insert(a);
+        paired.insert(b);
+
+        let item_key = format!("{}[{}]", ad_a.key, index);
+        debug!("Matched array items by similarity at {}", item_key);
+        if let (Some(object_a), Some(object_b)) = (&objects[a], &objects[b]) {
+            for (field, value_a) in object_a {
+                let key = format!("{}.{}", item_key, field);
+                match object_b.get(field) {
+                    Some(value_b) if value_a != value_b => value_diffs.push(ValueDiff {
+                        key,
+                        value1: value_a.to_string(),
+                        value2: value_b.to_string(),
+                    }),
+                    Some(_) => {}
+                    None => push_key_diff(&mut key_diff, key, file_a, file_b),
+                }
+            }
+            for field in object_b
+                .keys()
+                .filter(|field| !object_a.contains_key(*field))
+            {
+                let key = format!("{}.{}", item_key, field);
+                push_key_diff(&mut key_diff, key, file_b, file_a);
+            }
+        }
+    }
+
+    let array_diffs: Vec<ArrayDiff> = array_diffs
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !paired.contains(i))
+        .map(|(_, ad)| ad)
+        .collect();
+    (key_diff, type_diff, Some(value_diffs), Some(array_diffs))
+}
+
+/// Counts the fields two objects have with equal values,
+/// if at least half of all their fields are such
+fn shared_fields(object_a: &Map<String, Value>, object_b: &Map<String, Value>) -> Option<usize> {
+    let common = object_a
+        .keys()
+        .filter(|field| object_b.contains_key(*field))
+        .count();
+    let shared = object_a
+        .iter()
+        .filter(|(field, value)| object_b.get(*field) == Some(value))
+        .count();
+    let all = object_a.len() + object_b.len() - common;
+    (shared > 0 && shared * 2 >= all).then_some(shared)
+}
+
+/// Parses a serialized array item of either format, if it's an object
+fn parse_object(value: &str) -> Option<Map<String, Value>> {
+    let value = serde_json::from_str::<Value>(value)
+        .ok()
+        .or_else(|| serde_yaml::from_str::<Value>(value).ok())?;
+    match value {
+        Value::Object(object) => Some(object),
+        _ => None,
+    }
+}
+
+/// Adds a Key difference, if Key differences are checked
+fn push_key_diff(key_diff: &mut Option<Vec<KeyDiff>>, key: String, has: &str, misses: &str) {
+    if let Some(key_diffs) = key_diff {
+        key_diffs.push(KeyDiff {
+            key,
+            has: has.to_owned(),
+            misses: misses.to_owned(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::ArrayDiffDesc;
+
+    use crate::{array_indexes::find_json_array_indexes, dtfterminal_types::ConfigBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_pair_similar_items() {
+        let data1: Map<String, Value> = serde_json::from_str(
+            r#"{"items": [{"name": "apple", "color": "red", "qty": 1}, {"name": "plum"}]}"#,
+        )
+        .unwrap();
+        let data2: Map<String, Value> = serde_json::from_str(
+            r#"{"items": [{"name": "pear"}, {"name": "apple", "color": "red", "qty": 2}]}"#,
+        )
+        .unwrap();
+        let array_diff = |descriptor: ArrayDiffDesc, value: &Value| ArrayDiff {
+            descriptor,
+            key: "items".to_owned(),
+            value: value.to_string(),
+        };
+        let array_diffs = vec![
+            array_diff(ArrayDiffDesc::AHas, &data1["items"][0]),
+            array_diff(ArrayDiffDesc::AHas, &data1["items"][1]),
+            array_diff(ArrayDiffDesc::BHas, &data2["items"][0]),
+            array_diff(ArrayDiffDesc::BHas, &data2["items"][1]),
+        ];
+        let array_indexes = find_json_array_indexes(&array_diffs, &data1, &data2);
+        let config = ConfigBuilder::new()
+            .array_match_strategy(ArrayMatchStrategy::Similarity)
+            .build();
+
+        let (_, _, value_diff, array_diff) = pair_similar_items(
+            (Some(vec![]), None, Some(vec![]), Some(array_diffs)),
+            &config,
+            &array_indexes,
+            ("a.json", "b.json"),
+        );
+
+        let value_diffs = value_diff.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "items[0].qty");
+        assert_eq!(value_diffs[0].value1, "1");
+        assert_eq!(value_diffs[0].value2, "2");
+        let unpaired: Vec<String> = array_diff.unwrap().into_iter().map(|ad| ad.value).collect();
+        assert_eq!(
+            unpaired,
+            vec![
+                r#"{"name":"plum"}"#.to_owned(),
+                r#"{"name":"pear"}"#.to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_fields() {
+        let object = |json: &str| serde_json::from_str::<Map<String, Value>>(json).unwrap();
+
+        assert_eq!(
+            shared_fields(
+                &object(r#"{"a": 1, "b": 2}"#),
+                &object(r#"{"a": 1, "b": 3}"#)
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            shared_fields(&object(r#"{"a": 1}"#), &object(r#"{"a": 2}"#)),
+            None
+        );
+        assert_eq!(
+            shared_fields(
+                &object(r#"{"a": 1, "b": 2, "c": 3}"#),
+                &object(r#"{"a": 1, "d": 4}"#)
+            ),
+            None
+        );
+    }
+}
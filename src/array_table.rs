@@ -1,14 +1,12 @@
-use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+use colored::Colorize;
+use libdtf::core::diff_types::ArrayDiff;
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
 };
 
-use crate::utils::{get_display_values_by_column, group_by_key};
-use crate::{
-    dtfterminal_types::{TableContext, TermTable, WorkingContext},
-    utils::is_yaml_file,
-};
+use crate::dtfterminal_types::{Baseline, DiffCategory, TableContext, TermTable, WorkingContext};
+use crate::utils::{cap_array_rows, get_aligned_display_values, group_by_key};
 
 /// Table to display array differences in the terminal
 pub struct ArrayTable<'a> {
@@ -22,7 +20,11 @@ impl<'a> TermTable<ArrayDiff> for ArrayTable<'a> {
 
     fn create_table(&mut self, data: &[ArrayDiff]) {
         self.add_header();
-        self.add_rows(data);
+        if data.is_empty() {
+            self.context.add_no_differences_row();
+        } else {
+            self.add_rows(data);
+        }
     }
 
     fn add_header(&mut self) {
@@ -35,26 +37,37 @@ impl<'a> TermTable<ArrayDiff> for ArrayTable<'a> {
 
     fn add_rows(&mut self, data: &[ArrayDiff]) {
         let map = group_by_key(data);
-        let file_name_a = self.context.working_context().get_file_names().0;
-        let join_str = if is_yaml_file(file_name_a) { "" } else { ",\n" };
 
         for (key, values) in map {
-            let display_values1: Vec<String> = get_display_values_by_column(
-                self.context.working_context(),
-                &values,
-                ArrayDiffDesc::AHas,
-            );
-            let display_values2 = get_display_values_by_column(
-                self.context.working_context(),
-                &values,
-                ArrayDiffDesc::BHas,
+            let mut rows = get_aligned_display_values(self.context.working_context(), &values);
+            let summary = cap_array_rows(
+                &mut rows,
+                self.context
+                    .working_context()
+                    .config
+                    .max_array_diffs_per_key,
             );
+            let row_count = rows.len();
+            for (index, (value_a, value_b)) in rows.into_iter().enumerate() {
+                let key_cell = self.key_cell(key, index);
 
-            self.context.add_row(Row::new(vec![
-                TableCell::new(key),
-                TableCell::new(display_values1.join(join_str)),
-                TableCell::new(display_values2.join(join_str)),
-            ]));
+                let value_a = self.color_value(key, value_a, Baseline::A);
+                let value_b = self.color_value(key, value_b, Baseline::B);
+                self.context.add_row(Row::new(vec![
+                    TableCell::new(key_cell),
+                    TableCell::new(value_a),
+                    TableCell::new(value_b),
+                ]));
+            }
+
+            if let Some(summary) = summary {
+                self.context.add_row(Row::new(vec![
+                    TableCell::new(self.key_cell(key, row_count)),
+                    TableCell::builder(summary)
+                        .col_span(2)
+                        .alignment(Alignment::Center),
+                ]));
+            }
         }
     }
 }
@@ -68,6 +81,28 @@ impl<'a> ArrayTable<'a> {
         table
     }
 
+    /// The key is only displayed in the first row of the group
+    fn key_cell(&self, key: &str, index: usize) -> String {
+        if index == 0 {
+            self.context.format_key(DiffCategory::Array, key)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Colors a value by the severity of the difference if a baseline is set.
+    /// `file` is the one the value is only contained by.
+    fn color_value(&self, key: &str, value: String, file: Baseline) -> String {
+        let config = &self.context.working_context().config;
+        if config.baseline.is_none() || value.is_empty() {
+            return value;
+        }
+
+        let severity =
+            config.presence_severity_of(DiffCategory::Array, key, config.baseline == Some(file));
+        value.color(severity.color()).to_string()
+    }
+
     /// Adds the header row to the table
     fn add_title_row(&mut self) {
         self.context
@@ -0,0 +1,106 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::{DiffCollection, WorkingContext},
+    path_matcher::PathMatcher,
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// Drops the differences of the ordered arrays in the JSON data where one array only appends items to the other.
+/// Arrays diverging before the end of the shorter one are still reported as a whole.
+pub fn ignore_json_array_tails(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+    context: &WorkingContext,
+) -> DiffCollection {
+    ignore_array_tails(diffs, context, |path| {
+        match (lookup_json(data1, path), lookup_json(data2, path)) {
+            (Some(Value::Array(array1)), Some(Value::Array(array2))) => {
+                is_appended_to(array1, array2)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Drops the differences of the ordered sequences in the YAML data where one sequence only appends items to the other.
+/// Sequences diverging before the end of the shorter one are still reported as a whole.
+pub fn ignore_yaml_array_tails(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+    context: &WorkingContext,
+) -> DiffCollection {
+    ignore_array_tails(diffs, context, |path| {
+        match (lookup_yaml(data1, path), lookup_yaml(data2, path)) {
+            (Some(YamlValue::Sequence(sequence1)), Some(YamlValue::Sequence(sequence2))) => {
+                is_appended_to(sequence1, sequence2)
+            }
+            _ => false,
+        }
+    })
+}
+
+fn ignore_array_tails(
+    diffs: DiffCollection,
+    context: &WorkingContext,
+    is_appended_array: impl Fn(&str) -> bool,
+) -> DiffCollection {
+    let all_ordered = context.lib_working_context.config.array_same_order;
+    let ordered_paths = PathMatcher::compile_all(&context.config.ordered_array_paths);
+    let is_tail = |key: &str| {
+        array_paths(key).any(|path| {
+            (all_ordered || ordered_paths.iter().any(|matcher| matcher.matches(path)))
+                && is_appended_array(path)
+        })
+    };
+
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|kd| kd.into_iter().filter(|d| !is_tail(&d.key)).collect()),
+        type_diff.map(|td| td.into_iter().filter(|d| !is_tail(&d.key)).collect()),
+        value_diff.map(|vd| vd.into_iter().filter(|d| !is_tail(&d.key)).collect()),
+        array_diff.map(|ad| ad.into_iter().filter(|d| !is_tail(&d.key)).collect()),
+    )
+}
+
+/// The paths in the key that can point to an array: the ones followed by an index, and the key itself
+pub(crate) fn array_paths(key: &str) -> impl Iterator<Item = &str> {
+    key.match_indices('[')
+        .map(move |(index, _)| &key[..index])
+        .chain(std::iter::once(key))
+}
+
+/// Checks if the longer of the two arrays is the shorter one with items appended to it
+fn is_appended_to<T: PartialEq>(array1: &[T], array2: &[T]) -> bool {
+    let (shorter, longer) = if array1.len() < array2.len() {
+        (array1, array2)
+    } else {
+        (array2, array1)
+    };
+    shorter.len() < longer.len() && longer.starts_with(shorter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_paths() {
+        assert_eq!(
+            array_paths("steps[1].args[0]").collect::<Vec<_>>(),
+            vec!["steps", "steps[1].args", "steps[1].args[0]"]
+        );
+        assert_eq!(array_paths("tags").collect::<Vec<_>>(), vec!["tags"]);
+    }
+
+    #[test]
+    fn test_is_appended_to() {
+        assert!(is_appended_to(&[1, 2], &[1, 2, 3]));
+        assert!(is_appended_to(&[1, 2, 3], &[1, 2]));
+        assert!(!is_appended_to(&[1, 2], &[1, 2]));
+        assert!(!is_appended_to(&[1, 2], &[2, 1, 3]));
+    }
+}
@@ -0,0 +1,88 @@
+use crate::dtfterminal_types::DiffStats;
+
+const LABEL: &str = "datadiff";
+const IDENTICAL: &str = "identical";
+const COLOR_IDENTICAL: &str = "#4c1";
+const COLOR_DIFFERENT: &str = "#e05d44";
+/// Rough width of a character of the 11px Verdana the badge is rendered with
+const CHAR_WIDTH: usize = 7;
+/// Horizontal padding of both halves of the badge
+const PADDING: usize = 10;
+
+/// Renders a shields.io style SVG badge summarizing the check, e.g. `datadiff | 12 differences`
+pub fn render_badge(stats: &DiffStats) -> String {
+    let (message, color) = match stats.total() {
+        0 => (IDENTICAL.to_owned(), COLOR_IDENTICAL),
+        1 => ("1 difference".to_owned(), COLOR_DIFFERENT),
+        total => (format!("{} differences", total), COLOR_DIFFERENT),
+    };
+
+    let label_width = text_width(LABEL);
+    let message_width = text_width(&message);
+    let width = label_width + message_width;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r">
+    <rect width="{width}" height="20" rx="3" fill="#fff"/>
+  </clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        width = width,
+        label_width = label_width,
+        message_width = message_width,
+        label = LABEL,
+        message = message,
+        color = color,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count() * CHAR_WIDTH + PADDING
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_badge_with_differences() {
+        let stats = DiffStats {
+            key_diffs: 2,
+            type_diffs: 1,
+            value_diffs: 6,
+            array_diffs: 3,
+        };
+
+        let badge = render_badge(&stats);
+
+        assert!(badge.contains(r#"aria-label="datadiff: 12 differences""#));
+        assert!(badge.contains(">12 differences</text>"));
+        assert!(badge.contains(COLOR_DIFFERENT));
+    }
+
+    #[test]
+    fn test_render_badge_identical() {
+        let badge = render_badge(&DiffStats::default());
+
+        assert!(badge.contains(">datadiff</text>"));
+        assert!(badge.contains(">identical</text>"));
+        assert!(badge.contains(COLOR_IDENTICAL));
+        assert!(!badge.contains(COLOR_DIFFERENT));
+    }
+}
@@ -0,0 +1,46 @@
+//! Drops the trailing line breaks of YAML strings, which block scalars keep or strip by their chomping indicator:
+//! `|` keeps one, `|-` none and `|+` all of them. serde_yaml doesn't tell which strings were block scalars,
+//! so every string is trimmed. Plain scalars can't end with a line break anyway, only quoted ones.
+
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// Removes the trailing line breaks of the strings of the YAML data
+pub fn ignore_chomping(data: Mapping) -> Mapping {
+    data.into_iter()
+        .map(|(key, value)| (key, ignore_chomping_value(value)))
+        .collect()
+}
+
+fn ignore_chomping_value(value: YamlValue) -> YamlValue {
+    match value {
+        YamlValue::String(s) if s.ends_with('\n') => {
+            YamlValue::String(s.trim_end_matches('\n').to_owned())
+        }
+        YamlValue::Sequence(sequence) => {
+            YamlValue::Sequence(sequence.into_iter().map(ignore_chomping_value).collect())
+        }
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(ignore_chomping(mapping)),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_chomping() {
+        let data: Mapping = serde_yaml::from_str(
+            "clip: |\n  line 1\n  line 2\nkeep: |+\n  line 1\n  line 2\n\nitems:\n  - |\n    item\n",
+        )
+        .unwrap();
+
+        let data = ignore_chomping(data);
+
+        let expected: Mapping = serde_yaml::from_str(
+            "clip: \"line 1\\nline 2\"\nkeep: \"line 1\\nline 2\"\nitems: [item]\n",
+        )
+        .unwrap();
+        assert_eq!(data, expected);
+    }
+}
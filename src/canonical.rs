@@ -0,0 +1,232 @@
+//! Rewrites documents into a canonical form, so equivalent representations compare as equal:
+//! * The keys of objects are sorted.
+//! * Numbers with an integral value are written as integers, e.g. `1.0` and `1e2` become `1` and `100`.
+//! * Arrays of scalars (set-like arrays) are sorted, so their order doesn't matter even with `-o`.
+//!   Arrays containing objects or arrays keep their order.
+//! * Optionally, the leading and trailing whitespace of strings is trimmed.
+
+use std::cmp::Ordering;
+
+use serde_json::{Map, Number, Value};
+use serde_yaml::{Mapping, Number as YamlNumber, Value as YamlValue};
+
+/// Rewrites the JSON data into its canonical form
+pub fn canonicalize_json(data: Map<String, Value>, trim_strings: bool) -> Map<String, Value> {
+    let mut entries: Vec<(String, Value)> = data
+        .into_iter()
+        .map(|(key, value)| (key, canonicalize_json_value(value, trim_strings)))
+        .collect();
+    entries.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+    entries.into_iter().collect()
+}
+
+/// Rewrites the YAML data into its canonical form
+pub fn canonicalize_yaml(data: Mapping, trim_strings: bool) -> Mapping {
+    let mut entries: Vec<(YamlValue, YamlValue)> = data
+        .into_iter()
+        .map(|(key, value)| (key, canonicalize_yaml_value(value, trim_strings)))
+        .collect();
+    entries.sort_by_cached_key(|(key, _)| yaml_sort_key(key));
+    entries.into_iter().collect()
+}
+
+fn canonicalize_json_value(value: Value, trim_strings: bool) -> Value {
+    match value {
+        Value::Number(number) => Value::Number(canonical_json_number(number)),
+        Value::String(s) if trim_strings => Value::String(s.trim().to_owned()),
+        Value::Array(array) => {
+            let mut array: Vec<Value> = array
+                .into_iter()
+                .map(|item| canonicalize_json_value(item, trim_strings))
+                .collect();
+            if array.iter().all(is_json_scalar) {
+                array.sort_by(compare_json_scalars);
+            }
+            Value::Array(array)
+        }
+        Value::Object(object) => Value::Object(canonicalize_json(object, trim_strings)),
+        other => other,
+    }
+}
+
+fn canonicalize_yaml_value(value: YamlValue, trim_strings: bool) -> YamlValue {
+    match value {
+        YamlValue::Number(number) => YamlValue::Number(canonical_yaml_number(number)),
+        YamlValue::String(s) if trim_strings => YamlValue::String(s.trim().to_owned()),
+        YamlValue::Sequence(sequence) => {
+            let mut sequence: Vec<YamlValue> = sequence
+                .into_iter()
+                .map(|item| canonicalize_yaml_value(item, trim_strings))
+                .collect();
+            if sequence.iter().all(is_yaml_scalar) {
+                sequence.sort_by_cached_key(yaml_sort_key);
+            }
+            YamlValue::Sequence(sequence)
+        }
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(canonicalize_yaml(mapping, trim_strings)),
+        other => other,
+    }
+}
+
+/// Serializes an array item for comparing the membership of arrays: the keys of its objects are sorted
+/// and its numbers canonical, so equal items written differently get the same string.
+/// Unlike in the canonical form, the arrays inside the item keep their order.
+pub fn json_item_key(value: &Value) -> String {
+    match value {
+        Value::Number(number) => canonical_json_number(number.clone()).to_string(),
+        Value::Array(array) => {
+            let items: Vec<String> = array.iter().map(json_item_key).collect();
+            format!("[{}]", items.join(","))
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<String> = object
+                .iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), json_item_key(value))
+                })
+                .collect();
+            entries.sort();
+            format!("{{{}}}", entries.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Serializes a YAML array item for comparing the membership of arrays, like `json_item_key`.
+/// Strings are quoted like in JSON, so they can't be mistaken for the separators.
+pub fn yaml_item_key(value: &YamlValue) -> String {
+    match value {
+        YamlValue::Null => "null".to_owned(),
+        YamlValue::Bool(b) => b.to_string(),
+        YamlValue::Number(number) => canonical_yaml_number(number.clone()).to_string(),
+        YamlValue::String(s) => Value::from(s.as_str()).to_string(),
+        YamlValue::Sequence(sequence) => {
+            let items: Vec<String> = sequence.iter().map(yaml_item_key).collect();
+            format!("[{}]", items.join(","))
+        }
+        YamlValue::Mapping(mapping) => {
+            let mut entries: Vec<String> = mapping
+                .iter()
+                .map(|(key, value)| format!("{}:{}", yaml_item_key(key), yaml_item_key(value)))
+                .collect();
+            entries.sort();
+            format!("{{{}}}", entries.join(","))
+        }
+        YamlValue::Tagged(tagged) => format!("{} {}", tagged.tag, yaml_item_key(&tagged.value)),
+    }
+}
+
+/// Writes floats with an integral value as integers
+fn canonical_json_number(number: Number) -> Number {
+    match number.as_f64() {
+        Some(float) if number.is_f64() && is_integral(float) => Number::from(float as i64),
+        _ => number,
+    }
+}
+
+/// Writes floats with an integral value as integers
+fn canonical_yaml_number(number: YamlNumber) -> YamlNumber {
+    match number.as_f64() {
+        Some(float) if number.is_f64() && is_integral(float) => YamlNumber::from(float as i64),
+        _ => number,
+    }
+}
+
+fn is_integral(float: f64) -> bool {
+    float.fract() == 0.0 && float.abs() < i64::MAX as f64
+}
+
+fn is_json_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+fn is_yaml_scalar(value: &YamlValue) -> bool {
+    !matches!(
+        value,
+        YamlValue::Sequence(_) | YamlValue::Mapping(_) | YamlValue::Tagged(_)
+    )
+}
+
+/// Orders numbers by their value, everything else by its JSON representation
+fn compare_json_scalars(value1: &Value, value2: &Value) -> Ordering {
+    match (value1.as_f64(), value2.as_f64()) {
+        (Some(number1), Some(number2)) => number1.total_cmp(&number2),
+        _ => value1.to_string().cmp(&value2.to_string()),
+    }
+}
+
+fn yaml_sort_key(value: &YamlValue) -> String {
+    serde_yaml::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_json() {
+        let data: Map<String, Value> = serde_json::from_str(
+            r#"{"b": 1.0, "a": [3, 1, 2], "c": 1e2, "d": [{"y": 2.5}, {"x": 1}], "e": " text "}"#,
+        )
+        .unwrap();
+
+        let canonical = canonicalize_json(data.clone(), false);
+        assert_eq!(
+            Value::Object(canonical),
+            serde_json::json!({
+                "a": [1, 2, 3],
+                "b": 1,
+                "c": 100,
+                "d": [{"y": 2.5}, {"x": 1}],
+                "e": " text "
+            })
+        );
+
+        let canonical = canonicalize_json(data, true);
+        assert_eq!(canonical.get("e"), Some(&Value::String("text".to_owned())));
+    }
+
+    #[test]
+    fn test_json_item_key() {
+        let item1 = serde_json::json!({"id": 1.0, "tags": [2, 1], "name": "a"});
+        let item2 = serde_json::json!({"name": "a", "tags": [2, 1], "id": 1});
+        let item3 = serde_json::json!({"name": "a", "tags": [1, 2], "id": 1});
+
+        assert_eq!(json_item_key(&item1), json_item_key(&item2));
+        assert_ne!(json_item_key(&item1), json_item_key(&item3));
+        assert_ne!(
+            json_item_key(&serde_json::json!("1")),
+            json_item_key(&serde_json::json!(1))
+        );
+    }
+
+    #[test]
+    fn test_yaml_item_key() {
+        let item = |yaml: &str| serde_yaml::from_str::<YamlValue>(yaml).unwrap();
+
+        assert_eq!(
+            yaml_item_key(&item("{id: 1.0, name: a}")),
+            yaml_item_key(&item("{name: a, id: 1}"))
+        );
+        assert_ne!(
+            yaml_item_key(&item("{a: x, b: y}")),
+            yaml_item_key(&item("{a: 'x,\"b\":\"y'}"))
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_yaml() {
+        let data: Mapping = serde_yaml::from_str("b: 2.0\na: [c, a, b]\n").unwrap();
+
+        let canonical = canonicalize_yaml(data, false);
+
+        assert_eq!(
+            canonical,
+            serde_yaml::from_str::<Mapping>("a: [a, b, c]\nb: 2\n").unwrap()
+        );
+        assert_eq!(
+            canonical.keys().collect::<Vec<&YamlValue>>(),
+            vec![&YamlValue::from("a"), &YamlValue::from("b")]
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use std::ops::ControlFlow;
+
+use libdtf::core::diff_types::ArrayDiff;
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    array_indexes::{find_json_array_indexes, find_yaml_array_indexes, ArrayIndexes},
+    array_items::{drop_equivalent_json_items, drop_equivalent_yaml_items},
+    array_match::pair_similar_items,
+    array_tail::{ignore_json_array_tails, ignore_yaml_array_tails},
+    dtfterminal_types::{DiffCollection, DtfError, TypeNaming, WorkingContext},
+    empty_values::{ignore_json_empty_diffs, ignore_yaml_empty_diffs},
+    intersection::{drop_json_presence_only_items, drop_yaml_presence_only_items},
+    line_diff::apply_line_diffs,
+    numeric_strings::{coerce_json_numeric_strings, coerce_yaml_numeric_strings},
+    path_filters::filter_paths,
+    value_filters::{filter_value_diffs, truncate_value_diffs},
+    value_types::{name_json_schema_types, name_yaml_schema_types},
+};
+
+/// The data of a file the differences were found in, JSON objects or YAML mappings.
+/// Gives the steps after the check the lookups of the format, so they run the same for both.
+pub trait CheckedData {
+    type Key: PartialEq;
+
+    /// The keys of the mapping, in order
+    fn entry_keys(&self) -> Vec<&Self::Key>;
+
+    fn has_key(&self, key: &Self::Key) -> bool;
+
+    /// The mapping at the key, if its value is one
+    fn nested(&self, key: &Self::Key) -> Option<&Self>;
+
+    /// The entry of the key alone, nested under the keys of its ancestors, so it's at the same path as in the whole data.
+    /// Only the ancestors are left if the key is missing.
+    fn chunk(&self, key: &Self::Key, ancestors: &[&Self::Key]) -> Self;
+
+    fn drop_equivalent_items(diffs: DiffCollection, parse_dates: bool) -> DiffCollection;
+
+    fn drop_presence_only_items(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+    ) -> DiffCollection;
+
+    fn find_array_indexes(array_diffs: &[ArrayDiff], data1: &Self, data2: &Self) -> ArrayIndexes;
+
+    fn ignore_array_tails(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+        context: &WorkingContext,
+    ) -> DiffCollection;
+
+    fn ignore_empty_diffs(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection;
+
+    fn coerce_numeric_strings(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection;
+
+    fn name_schema_types(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection;
+}
+
+impl CheckedData for Map<String, Value> {
+    type Key = String;
+
+    fn entry_keys(&self) -> Vec<&String> {
+        self.keys().collect()
+    }
+
+    fn has_key(&self, key: &String) -> bool {
+        self.contains_key(key)
+    }
+
+    fn nested(&self, key: &String) -> Option<&Self> {
+        self.get(key).and_then(Value::as_object)
+    }
+
+    fn chunk(&self, key: &String, ancestors: &[&String]) -> Self {
+        let entry = self.get(key).map(|value| (key.clone(), value.clone()));
+        ancestors
+            .iter()
+            .rev()
+            .fold(entry.into_iter().collect(), |chunk, ancestor| {
+                Map::from_iter([((*ancestor).clone(), Value::Object(chunk))])
+            })
+    }
+
+    fn drop_equivalent_items(diffs: DiffCollection, parse_dates: bool) -> DiffCollection {
+        drop_equivalent_json_items(diffs, parse_dates)
+    }
+
+    fn drop_presence_only_items(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+    ) -> DiffCollection {
+        drop_json_presence_only_items(diffs, data1, data2)
+    }
+
+    fn find_array_indexes(array_diffs: &[ArrayDiff], data1: &Self, data2: &Self) -> ArrayIndexes {
+        find_json_array_indexes(array_diffs, data1, data2)
+    }
+
+    fn ignore_array_tails(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+        context: &WorkingContext,
+    ) -> DiffCollection {
+        ignore_json_array_tails(diffs, data1, data2, context)
+    }
+
+    fn ignore_empty_diffs(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        ignore_json_empty_diffs(diffs, data1, data2)
+    }
+
+    fn coerce_numeric_strings(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        coerce_json_numeric_strings(diffs, data1, data2)
+    }
+
+    fn name_schema_types(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        name_json_schema_types(diffs, data1, data2)
+    }
+}
+
+impl CheckedData for Mapping {
+    type Key = YamlValue;
+
+    fn entry_keys(&self) -> Vec<&YamlValue> {
+        self.keys().collect()
+    }
+
+    fn has_key(&self, key: &YamlValue) -> bool {
+        self.contains_key(key)
+    }
+
+    fn nested(&self, key: &YamlValue) -> Option<&Self> {
+        self.get(key).and_then(YamlValue::as_mapping)
+    }
+
+    fn chunk(&self, key: &YamlValue, ancestors: &[&YamlValue]) -> Self {
+        let entry = self.get(key).map(|value| (key.clone(), value.clone()));
+        ancestors
+            .iter()
+            .rev()
+            .fold(entry.into_iter().collect(), |chunk, ancestor| {
+                Mapping::from_iter([((*ancestor).clone(), YamlValue::Mapping(chunk))])
+            })
+    }
+
+    fn drop_equivalent_items(diffs: DiffCollection, parse_dates: bool) -> DiffCollection {
+        drop_equivalent_yaml_items(diffs, parse_dates)
+    }
+
+    fn drop_presence_only_items(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+    ) -> DiffCollection {
+        drop_yaml_presence_only_items(diffs, data1, data2)
+    }
+
+    fn find_array_indexes(array_diffs: &[ArrayDiff], data1: &Self, data2: &Self) -> ArrayIndexes {
+        find_yaml_array_indexes(array_diffs, data1, data2)
+    }
+
+    fn ignore_array_tails(
+        diffs: DiffCollection,
+        data1: &Self,
+        data2: &Self,
+        context: &WorkingContext,
+    ) -> DiffCollection {
+        ignore_yaml_array_tails(diffs, data1, data2, context)
+    }
+
+    fn ignore_empty_diffs(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        ignore_yaml_empty_diffs(diffs, data1, data2)
+    }
+
+    fn coerce_numeric_strings(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        coerce_yaml_numeric_strings(diffs, data1, data2)
+    }
+
+    fn name_schema_types(diffs: DiffCollection, data1: &Self, data2: &Self) -> DiffCollection {
+        name_yaml_schema_types(diffs, data1, data2)
+    }
+}
+
+/// Rewrites the differences found by the check with the options applied after it, in the same order for both formats.
+/// The data is of the whole files, not the sampled or normalized data that was compared.
+pub fn process_diffs<D: CheckedData>(
+    diffs: DiffCollection,
+    data1: &D,
+    data2: &D,
+    context: &WorkingContext,
+) -> DiffCollection {
+    let config = &context.config;
+    let diffs = D::drop_equivalent_items(diffs, config.parse_dates);
+    let diffs = if config.intersection {
+        D::drop_presence_only_items(diffs, data1, data2)
+    } else {
+        diffs
+    };
+    let diffs = if config.array_match_strategy.pairs_by_similarity() {
+        let array_indexes =
+            D::find_array_indexes(diffs.3.as_deref().unwrap_or_default(), data1, data2);
+        pair_similar_items(diffs, config, &array_indexes, context.get_file_names())
+    } else {
+        diffs
+    };
+    let diffs = if config.ignore_array_tail {
+        D::ignore_array_tails(diffs, data1, data2, context)
+    } else {
+        diffs
+    };
+    let diffs = if config.empty_equivalence {
+        D::ignore_empty_diffs(diffs, data1, data2)
+    } else {
+        diffs
+    };
+    let diffs = if config.coerce_numeric_strings {
+        D::coerce_numeric_strings(diffs, data1, data2)
+    } else {
+        diffs
+    };
+    let diffs = match config.type_naming {
+        TypeNaming::JsonSchema => D::name_schema_types(diffs, data1, data2),
+        TypeNaming::Default => diffs,
+    };
+    let diffs = filter_value_diffs(diffs, config);
+    let diffs = filter_paths(diffs, config);
+    let diffs = apply_line_diffs(diffs, config);
+    truncate_value_diffs(diffs, config)
+}
+
+/// Checks the data chunk by chunk like `walk_chunks`, collecting the differences of every chunk.
+/// The check of empty data gives the checked categories, even if there are no chunks.
+pub fn check_in_chunks<D, F>(data1: &D, data2: &D, mut check: F) -> Result<DiffCollection, DtfError>
+where
+    D: CheckedData + Default,
+    F: FnMut(D, D) -> Result<DiffCollection, DtfError>,
+{
+    let mut diffs = check(D::default(), D::default())?;
+    walk_chunks(data1, data2, &mut |chunk1, chunk2| {
+        let (key_diff, type_diff, value_diff, array_diff) = check(chunk1, chunk2)?;
+        append(&mut diffs.0, key_diff);
+        append(&mut diffs.1, type_diff);
+        append(&mut diffs.2, value_diff);
+        append(&mut diffs.3, array_diff);
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(diffs)
+}
+
+fn append<T>(diffs: &mut Option<Vec<T>>, chunk_diffs: Option<Vec<T>>) {
+    if let Some(chunk_diffs) = chunk_diffs {
+        diffs.get_or_insert_with(Vec::new).extend(chunk_diffs);
+    }
+}
+
+/// Checks the data in chunks: the entry of a single key at a time, walking into the mappings both files have at a key.
+/// Each chunk is nested under its ancestors, so it's checked at its path like in the whole data.
+/// The keys of the first file come first, then those only the second file has. The walk stops when `check` breaks.
+pub fn walk_chunks<D, F>(data1: &D, data2: &D, check: &mut F) -> Result<ControlFlow<()>, DtfError>
+where
+    D: CheckedData,
+    F: FnMut(D, D) -> Result<ControlFlow<()>, DtfError>,
+{
+    walk(data1, data2, &mut vec![], check)
+}
+
+fn walk<'a, D, F>(
+    data1: &'a D,
+    data2: &'a D,
+    ancestors: &mut Vec<&'a D::Key>,
+    check: &mut F,
+) -> Result<ControlFlow<()>, DtfError>
+where
+    D: CheckedData,
+    F: FnMut(D, D) -> Result<ControlFlow<()>, DtfError>,
+{
+    let keys = data1.entry_keys().into_iter().chain(
+        data2
+            .entry_keys()
+            .into_iter()
+            .filter(|key| !data1.has_key(key)),
+    );
+    for key in keys {
+        let flow = match (data1.nested(key), data2.nested(key)) {
+            (Some(nested1), Some(nested2)) => {
+                ancestors.push(key);
+                let flow = walk(nested1, nested2, ancestors, check)?;
+                ancestors.pop();
+                flow
+            }
+            _ => check(data1.chunk(key, ancestors), data2.chunk(key, ancestors))?,
+        };
+        if flow.is_break() {
+            return Ok(flow);
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{KeyDiff, TypeDiff, ValueDiff};
+    use serde_json::json;
+
+    use crate::{dtfterminal_types::ConfigBuilder, utils::create_working_context};
+
+    use super::*;
+
+    fn diffs() -> DiffCollection {
+        (
+            Some(vec![KeyDiff {
+                key: "note".to_owned(),
+                has: "a".to_owned(),
+                misses: "b".to_owned(),
+            }]),
+            Some(vec![TypeDiff {
+                key: "port".to_owned(),
+                type1: "string".to_owned(),
+                type2: "number".to_owned(),
+            }]),
+            Some(vec![ValueDiff {
+                key: "port".to_owned(),
+                value1: "\"8080\"".to_owned(),
+                value2: "8080".to_owned(),
+            }]),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_process_diffs_of_both_formats() {
+        let context = create_working_context(
+            &ConfigBuilder::new()
+                .file_a(Some("a".to_owned()))
+                .file_b(Some("b".to_owned()))
+                .empty_equivalence(true)
+                .coerce_numeric_strings(true)
+                .build(),
+        )
+        .unwrap();
+        let json1 = json!({"port": "8080", "note": null});
+        let json2 = json!({"port": 8080});
+        let yaml1: Mapping = serde_yaml::from_str("port: '8080'\nnote: ~\n").unwrap();
+        let yaml2: Mapping = serde_yaml::from_str("port: 8080\n").unwrap();
+
+        for (key_diff, type_diff, value_diff, _) in [
+            process_diffs(
+                diffs(),
+                json1.as_object().unwrap(),
+                json2.as_object().unwrap(),
+                &context,
+            ),
+            process_diffs(diffs(), &yaml1, &yaml2, &context),
+        ] {
+            assert!(key_diff.unwrap().is_empty());
+            assert!(type_diff.unwrap().is_empty());
+            assert!(value_diff.unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_check_in_chunks_collects_every_chunk() {
+        let json1 = json!({"a": {"b": 1, "c": 2}, "d": 1});
+        let json2 = json!({"a": {"b": 2, "c": 2}, "e": 1});
+        let mut checked = 0;
+
+        let (key_diff, type_diff, value_diff, array_diff) = check_in_chunks(
+            json1.as_object().unwrap(),
+            json2.as_object().unwrap(),
+            |chunk1, chunk2| {
+                checked += 1;
+                let value_diff = (chunk1 != chunk2).then(|| ValueDiff {
+                    key: String::new(),
+                    value1: Value::Object(chunk1).to_string(),
+                    value2: Value::Object(chunk2).to_string(),
+                });
+                Ok((
+                    None,
+                    Some(vec![]),
+                    Some(value_diff.into_iter().collect()),
+                    None,
+                ))
+            },
+        )
+        .unwrap();
+
+        // The empty data, a.b, a.c, d and e
+        assert_eq!(checked, 5);
+        assert_eq!(key_diff, None);
+        assert_eq!(type_diff, Some(vec![]));
+        assert_eq!(value_diff.unwrap().len(), 3);
+        assert_eq!(array_diff, None);
+    }
+}
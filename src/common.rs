@@ -0,0 +1,101 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// Builds the structure the two JSON documents agree on: the keys present in both with equal values.
+/// Objects are intersected recursively and kept if they share anything, other values, arrays included, only if they are equal.
+pub fn common_json(data1: &Map<String, Value>, data2: &Map<String, Value>) -> Map<String, Value> {
+    data1
+        .iter()
+        .filter_map(|(key, value1)| {
+            let common = common_json_value(value1, data2.get(key)?)?;
+            Some((key.clone(), common))
+        })
+        .collect()
+}
+
+/// Builds the structure the two YAML documents agree on: the keys present in both with equal values.
+/// Mappings are intersected recursively and kept if they share anything, other values, sequences included, only if they are equal.
+pub fn common_yaml(data1: &Mapping, data2: &Mapping) -> Mapping {
+    data1
+        .iter()
+        .filter_map(|(key, value1)| {
+            let common = common_yaml_value(value1, data2.get(key)?)?;
+            Some((key.clone(), common))
+        })
+        .collect()
+}
+
+fn common_json_value(value1: &Value, value2: &Value) -> Option<Value> {
+    match (value1, value2) {
+        (Value::Object(object1), Value::Object(object2)) => {
+            let common = common_json(object1, object2);
+            let keep = !common.is_empty() || (object1.is_empty() && object2.is_empty());
+            keep.then_some(Value::Object(common))
+        }
+        _ => (value1 == value2).then(|| value1.clone()),
+    }
+}
+
+fn common_yaml_value(value1: &YamlValue, value2: &YamlValue) -> Option<YamlValue> {
+    match (value1, value2) {
+        (YamlValue::Mapping(mapping1), YamlValue::Mapping(mapping2)) => {
+            let common = common_yaml(mapping1, mapping2);
+            let keep = !common.is_empty() || (mapping1.is_empty() && mapping2.is_empty());
+            keep.then_some(YamlValue::Mapping(common))
+        }
+        _ => (value1 == value2).then(|| value1.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_common_json() {
+        let data1: Map<String, Value> = serde_json::from_str(
+            r#"{
+                "name": "service",
+                "version": 1,
+                "server": {"host": "localhost", "port": 80, "tls": {"enabled": true}},
+                "tags": ["a", "b"],
+                "owners": ["x"],
+                "labels": {},
+                "only_a": true
+            }"#,
+        )
+        .unwrap();
+        let data2: Map<String, Value> = serde_json::from_str(
+            r#"{
+                "name": "service",
+                "version": 2,
+                "server": {"host": "localhost", "port": 8080, "tls": {"enabled": false}},
+                "tags": ["a", "b"],
+                "owners": ["y"],
+                "labels": {}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Value::Object(common_json(&data1, &data2)),
+            json!({
+                "name": "service",
+                "server": {"host": "localhost"},
+                "tags": ["a", "b"],
+                "labels": {}
+            })
+        );
+    }
+
+    #[test]
+    fn test_common_yaml() {
+        let data1: Mapping = serde_yaml::from_str("a: 1\nb:\n  c: 2\n  d: 3\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("a: 2\nb:\n  c: 2\n  d: 4\n").unwrap();
+
+        let common: Mapping = serde_yaml::from_str("b:\n  c: 2\n").unwrap();
+        assert_eq!(common_yaml(&data1, &data2), common);
+    }
+}
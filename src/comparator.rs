@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use libdtf::core::diff_types::ValueDiff;
+use log::debug;
+use serde_json::Value;
+
+use crate::path_matcher::PathMatcher;
+
+/// Domain-specific equality of values, like semantic versions or URLs ignoring a trailing slash.
+/// Returning `Some` decides if the values at the path are equal, `None` defers to the default comparison.
+pub trait ValueComparator {
+    fn equal(&self, path: &str, a: &Value, b: &Value) -> Option<bool>;
+}
+
+/// A comparator registered for the paths matching a pattern
+#[derive(Clone)]
+pub struct PathComparator {
+    matcher: PathMatcher,
+    comparator: Arc<dyn ValueComparator + Send + Sync>,
+}
+
+impl PathComparator {
+    pub fn new(
+        pattern: &str,
+        comparator: impl ValueComparator + Send + Sync + 'static,
+    ) -> PathComparator {
+        PathComparator {
+            matcher: PathMatcher::new(pattern),
+            comparator: Arc::new(comparator),
+        }
+    }
+}
+
+/// Asks the comparators registered for the key of the difference, in order, if its values are equal.
+/// The first one with an opinion decides, if none of them has one, the values stay different.
+pub fn are_equal_by_comparators(value_diff: &ValueDiff, comparators: &[PathComparator]) -> bool {
+    let mut matching = comparators
+        .iter()
+        .filter(|pc| pc.matcher.matches(&value_diff.key))
+        .peekable();
+    if matching.peek().is_none() {
+        return false;
+    }
+
+    let a = parse_value(&value_diff.value1);
+    let b = parse_value(&value_diff.value2);
+    let equal = matching
+        .find_map(|pc| pc.comparator.equal(&value_diff.key, &a, &b))
+        .unwrap_or(false);
+    if equal {
+        debug!(
+            "Skipped {}: the values are equal by a custom comparator",
+            value_diff.key
+        );
+    }
+    equal
+}
+
+/// Parses a serialized value of either format, falling back to the value as a string
+fn parse_value(value: &str) -> Value {
+    serde_json::from_str(value)
+        .ok()
+        .or_else(|| serde_yaml::from_str(value).ok())
+        .unwrap_or_else(|| Value::String(value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Treats versions as equal if they only differ in their omitted zero parts, like `1.0` and `1.0.0`
+    struct SemverComparator;
+
+    impl ValueComparator for SemverComparator {
+        fn equal(&self, _path: &str, a: &Value, b: &Value) -> Option<bool> {
+            let parts = |value: &Value| -> Option<Vec<u64>> {
+                let mut parts = value
+                    .as_str()?
+                    .split('.')
+                    .map(|part| part.parse().ok())
+                    .collect::<Option<Vec<u64>>>()?;
+                parts.resize(3, 0);
+                Some(parts)
+            };
+            Some(parts(a)? == parts(b)?)
+        }
+    }
+
+    #[test]
+    fn test_are_equal_by_comparators() {
+        let comparators = vec![PathComparator::new("*.version", SemverComparator)];
+        let value_diff = |key: &str, value1: &str, value2: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: value1.to_owned(),
+            value2: value2.to_owned(),
+        };
+
+        assert!(are_equal_by_comparators(
+            &value_diff("package.version", "\"1.0\"", "\"1.0.0\""),
+            &comparators
+        ));
+        assert!(!are_equal_by_comparators(
+            &value_diff("package.version", "\"1.0\"", "\"1.1.0\""),
+            &comparators
+        ));
+        // Not a version, so the comparator defers
+        assert!(!are_equal_by_comparators(
+            &value_diff("package.version", "\"latest\"", "\"1.0.0\""),
+            &comparators
+        ));
+        // Outside the registered paths
+        assert!(!are_equal_by_comparators(
+            &value_diff("name", "\"1.0\"", "\"1.0.0\""),
+            &comparators
+        ));
+    }
+}
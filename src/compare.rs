@@ -0,0 +1,508 @@
+use std::fmt;
+
+use libdtf::core::diff_types::{ArrayDiff, KeyDiff, TypeDiff, ValueDiff};
+
+use crate::{
+    dtfterminal_types::{Config, DiffCollection, DtfError, OutputFormat, WorkingContext},
+    explain::explain_diffs,
+    html_renderer::HtmlRenderer,
+    json_app::compare_json_str_sided,
+    redact::redact_diffs,
+    render::render_diffs,
+    utils::{count_diffs, create_working_context},
+    yaml_app::compare_yaml_str_sided,
+};
+
+/// The data formats that can be compared
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFormat::Json => write!(f, "JSON"),
+            DataFormat::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+/// A single difference of any category, as passed to the classifier of `classify_diffs`
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffKind {
+    Key(KeyDiff),
+    Type(TypeDiff),
+    Value(ValueDiff),
+    Array(ArrayDiff),
+}
+
+impl DiffKind {
+    /// Get the key path of the difference
+    pub fn key(&self) -> &str {
+        match self {
+            DiffKind::Key(kd) => &kd.key,
+            DiffKind::Type(td) => &td.key,
+            DiffKind::Value(vd) => &vd.key,
+            DiffKind::Array(ad) => &ad.key,
+        }
+    }
+}
+
+/// What happens to a difference after it's classified
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision {
+    Keep,
+    Drop,
+    /// Keep the given difference instead, e.g. with its values redacted.
+    /// A difference of another category is ignored, the original is kept.
+    Annotate(DiffKind),
+}
+
+/// Why two documents asserted to be equal are not.
+/// Displayed as a list of the differences, so it reads well in the panic message of a failed test.
+pub enum DiffReport {
+    /// The documents differ, described by a sentence each
+    Differences {
+        file_names: (String, String),
+        diffs: DiffCollection,
+        sentences: Vec<String>,
+    },
+    /// The documents couldn't be compared
+    Error(DtfError),
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffReport::Differences {
+                file_names: (file_a, file_b),
+                sentences,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} and {} have {} differences:",
+                    file_a,
+                    file_b,
+                    sentences.len()
+                )?;
+                for sentence in sentences {
+                    write!(f, "\n  - {}", sentence)?;
+                }
+                Ok(())
+            }
+            DiffReport::Error(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Debug for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Shown by `unwrap` and `expect`, which should be as readable as `Display`
+        write!(f, "{}", self)
+    }
+}
+
+/// Checks that two documents have no differences of the checked categories, for using them as assertions in tests
+pub fn assert_equal(
+    a: &str,
+    b: &str,
+    format: DataFormat,
+    context: &WorkingContext,
+) -> Result<(), DiffReport> {
+    let (diffs, mut context) = compare_str(a, b, format, context).map_err(DiffReport::Error)?;
+    if count_diffs(&diffs) == 0 {
+        return Ok(());
+    }
+
+    // Every checked category is described, regardless of what would be rendered
+    let config = &mut context.config;
+    config.render_key_diffs = true;
+    config.render_type_diffs = true;
+    config.render_value_diffs = true;
+    config.render_array_diffs = true;
+    let sentences = explain_diffs(&diffs, &context, |_| None);
+    let (file_a, file_b) = context.get_file_names();
+    Err(DiffReport::Differences {
+        file_names: (file_a.to_owned(), file_b.to_owned()),
+        diffs,
+        sentences,
+    })
+}
+
+/// Checks two documents for differences and renders the result as an HTML document.
+/// Doesn't touch the filesystem or the browser, so it can be used where those aren't available (e.g. WASM).
+pub fn compare_and_render(
+    a: &str,
+    b: &str,
+    format: DataFormat,
+    context: &WorkingContext,
+) -> Result<String, DtfError> {
+    let (diffs, context) = compare_str(a, b, format, context)?;
+    let diffs = redact_diffs(diffs, &context.config.redact_patterns);
+    HtmlRenderer::new(&context).render_document(&diffs)
+}
+
+/// Like `compare_and_render`, but every difference is classified before rendering
+pub fn compare_and_render_with<F>(
+    a: &str,
+    b: &str,
+    format: DataFormat,
+    context: &WorkingContext,
+    classify: F,
+) -> Result<String, DtfError>
+where
+    F: FnMut(&DiffKind) -> Decision,
+{
+    let (diffs, context) = compare_str(a, b, format, context)?;
+    let diffs = classify_diffs(diffs, classify);
+    let diffs = redact_diffs(diffs, &context.config.redact_patterns);
+    HtmlRenderer::new(&context).render_document(&diffs)
+}
+
+/// Parses and compares two documents, then renders the differences in the output format, all in one call.
+/// The documents are compared as JSON if both of them are JSON, as YAML otherwise.
+/// Without file names in the configuration they are called `a` and `b` with the extension of the data format.
+pub fn diff_to_string(
+    a: &str,
+    b: &str,
+    format: OutputFormat,
+    mut config: Config,
+) -> Result<String, DtfError> {
+    let is_json = |document: &str| serde_json::from_str::<serde_json::Value>(document).is_ok();
+    let (data_format, extension) = if is_json(a) && is_json(b) {
+        (DataFormat::Json, "json")
+    } else {
+        (DataFormat::Yaml, "yaml")
+    };
+    config
+        .file_a
+        .get_or_insert_with(|| format!("a.{}", extension));
+    config
+        .file_b
+        .get_or_insert_with(|| format!("b.{}", extension));
+    let context = create_working_context(&config)?;
+
+    let (diffs, context) = compare_str(a, b, data_format, &context)?;
+    let diffs = redact_diffs(diffs, &context.config.redact_patterns);
+    render_diffs(&diffs, &context, format)
+}
+
+/// Passes every difference to the classifier, which decides to keep, drop or replace it
+pub fn classify_diffs<F>(diffs: DiffCollection, mut classify: F) -> DiffCollection
+where
+    F: FnMut(&DiffKind) -> Decision,
+{
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|diffs| {
+            classify_all(diffs, DiffKind::Key, &mut classify, |diff| match diff {
+                DiffKind::Key(kd) => Some(kd),
+                _ => None,
+            })
+        }),
+        type_diff.map(|diffs| {
+            classify_all(diffs, DiffKind::Type, &mut classify, |diff| match diff {
+                DiffKind::Type(td) => Some(td),
+                _ => None,
+            })
+        }),
+        value_diff.map(|diffs| {
+            classify_all(diffs, DiffKind::Value, &mut classify, |diff| match diff {
+                DiffKind::Value(vd) => Some(vd),
+                _ => None,
+            })
+        }),
+        array_diff.map(|diffs| {
+            classify_all(diffs, DiffKind::Array, &mut classify, |diff| match diff {
+                DiffKind::Array(ad) => Some(ad),
+                _ => None,
+            })
+        }),
+    )
+}
+
+/// Classifies the differences of a single category
+fn classify_all<T: Clone, F>(
+    diffs: Vec<T>,
+    wrap: fn(T) -> DiffKind,
+    classify: &mut F,
+    unwrap: fn(DiffKind) -> Option<T>,
+) -> Vec<T>
+where
+    F: FnMut(&DiffKind) -> Decision,
+{
+    diffs
+        .into_iter()
+        .filter_map(|diff| {
+            let kind = wrap(diff.clone());
+            match classify(&kind) {
+                Decision::Keep => Some(diff),
+                Decision::Drop => None,
+                Decision::Annotate(annotated) => Some(unwrap(annotated).unwrap_or(diff)),
+            }
+        })
+        .collect()
+}
+
+/// Parses and compares two documents, giving the context with the file having the key of each key difference,
+/// so the differences are described like those of the command line, even if the files have the same name
+fn compare_str(
+    a: &str,
+    b: &str,
+    format: DataFormat,
+    context: &WorkingContext,
+) -> Result<(DiffCollection, WorkingContext), DtfError> {
+    let (diffs, key_sides) = match format {
+        DataFormat::Json => compare_json_str_sided(a, b, context)?,
+        DataFormat::Yaml => compare_yaml_str_sided(a, b, context)?,
+    };
+    let mut context = context.clone();
+    context.key_sides = key_sides;
+    Ok((diffs, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::WorkingFile;
+
+    use crate::dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext};
+
+    use super::*;
+
+    #[test]
+    fn test_compare_and_render_json() {
+        let html = compare_and_render(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "John"}"#,
+            DataFormat::Json,
+            &get_working_context(),
+        )
+        .unwrap();
+
+        assert!(html.contains("Key Differences"));
+        assert!(html.contains("age"));
+    }
+
+    #[test]
+    fn test_compare_and_render_yaml() {
+        let html = compare_and_render(
+            "name: John\nage: 30\n",
+            "name: John\n",
+            DataFormat::Yaml,
+            &get_working_context(),
+        )
+        .unwrap();
+
+        assert!(html.contains("Key Differences"));
+        assert!(html.contains("age"));
+    }
+
+    #[test]
+    fn test_classify_diffs_redacts_values_under_path() {
+        let value_diff = |key: &str, value1: &str, value2: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: value1.to_owned(),
+            value2: value2.to_owned(),
+        };
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "secrets.token".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![
+                value_diff("secrets.password", "\"hunter2\"", "\"hunter3\""),
+                value_diff("name", "\"John\"", "\"Jane\""),
+                value_diff("debug", "true", "false"),
+            ]),
+            None,
+        );
+
+        let (key_diffs, type_diffs, value_diffs, _) = classify_diffs(diffs, |diff| match diff {
+            DiffKind::Value(vd) if vd.key.starts_with("secrets.") => {
+                Decision::Annotate(DiffKind::Value(ValueDiff {
+                    key: vd.key.clone(),
+                    value1: "[redacted]".to_owned(),
+                    value2: "[redacted]".to_owned(),
+                }))
+            }
+            _ if diff.key() == "debug" => Decision::Drop,
+            _ => Decision::Keep,
+        });
+
+        assert_eq!(key_diffs.unwrap().len(), 1);
+        assert!(type_diffs.is_none());
+        assert_eq!(
+            value_diffs.unwrap(),
+            vec![
+                value_diff("secrets.password", "[redacted]", "[redacted]"),
+                value_diff("name", "\"John\"", "\"Jane\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_and_render_with() {
+        let html = compare_and_render_with(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "John"}"#,
+            DataFormat::Json,
+            &get_working_context(),
+            |_| Decision::Drop,
+        )
+        .unwrap();
+
+        assert!(html.contains("The data is identical!"));
+    }
+
+    #[test]
+    fn test_diff_to_string() {
+        let config = ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .check_for_value_diffs(true)
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .build();
+        let render = |a: &str, b: &str, format: OutputFormat| {
+            diff_to_string(a, b, format, config.clone()).unwrap()
+        };
+        let (a, b) = (r#"{"name": "John", "age": 30}"#, r#"{"name": "Jane"}"#);
+
+        let table = render(a, b, OutputFormat::Table);
+        assert!(table.contains("Key Differences"));
+        assert!(table.contains("a.json"));
+        assert_eq!(
+            render(a, a, OutputFormat::Table),
+            "The data is identical!\n"
+        );
+
+        let markdown = render(a, b, OutputFormat::Markdown);
+        assert!(markdown.starts_with("## Key Differences\n"));
+        assert!(markdown.contains("| name | \"John\" | \"Jane\" |"));
+
+        let html = render(a, b, OutputFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(a, b, OutputFormat::Json)).unwrap();
+        assert_eq!(json["keyDiffs"][0]["key"], "age");
+        assert_eq!(json["valueDiffs"][0]["key"], "name");
+
+        let ndjson = render(a, b, OutputFormat::Ndjson);
+        assert!(ndjson
+            .lines()
+            .all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+
+        assert_eq!(render(a, b, OutputFormat::Paths), "age\nname\n");
+    }
+
+    #[test]
+    fn test_diff_to_string_yaml() {
+        let config = ConfigBuilder::new()
+            .check_for_value_diffs(true)
+            .render_value_diffs(true)
+            .build();
+
+        let markdown = diff_to_string(
+            "name: John\n",
+            "name: Jane\n",
+            OutputFormat::Markdown,
+            config,
+        )
+        .unwrap();
+
+        assert!(markdown.contains("| Key | a.yaml | b.yaml |"));
+        assert!(markdown.contains("| name |"));
+    }
+
+    #[test]
+    fn test_assert_equal() {
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("expected.json".to_owned()),
+                WorkingFile::new("actual.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new()
+                .check_for_key_diffs(true)
+                .check_for_value_diffs(true)
+                .build(),
+        );
+        let expected = r#"{"name": "John", "age": 30}"#;
+
+        assert!(assert_equal(
+            expected,
+            r#"{"age": 30, "name": "John"}"#,
+            DataFormat::Json,
+            &context
+        )
+        .is_ok());
+
+        let report =
+            assert_equal(expected, r#"{"name": "Jane"}"#, DataFormat::Json, &context).unwrap_err();
+        assert_eq!(
+            report.to_string(),
+            "expected.json and actual.json have 2 differences:\n  \
+             - Key `age` exists in expected.json but not actual.json\n  \
+             - Value of `name` changed from \"John\" to \"Jane\""
+        );
+
+        let report = assert_equal("{", "{}", DataFormat::Json, &context).unwrap_err();
+        assert!(matches!(report, DiffReport::Error(DtfError::DiffError(_))));
+    }
+
+    #[test]
+    fn test_assert_equal_same_file_names() {
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("config.json".to_owned()),
+                WorkingFile::new("config.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().check_for_key_diffs(true).build(),
+        );
+
+        let report = assert_equal(
+            r#"{"name": "John"}"#,
+            r#"{"name": "John", "city": "Budapest"}"#,
+            DataFormat::Json,
+            &context,
+        )
+        .unwrap_err();
+
+        // The side is told by the data having the key, as the names are the same
+        assert_eq!(
+            report.to_string(),
+            "config.json and config.json have 1 differences:\n  \
+             - Key `city` exists in config.json (B) but not config.json (A)"
+        );
+    }
+
+    #[test]
+    fn test_compare_and_render_empty_objects() {
+        let html =
+            compare_and_render("{}", "{}", DataFormat::Json, &get_working_context()).unwrap();
+
+        assert!(html.contains("The data is identical!"));
+        assert!(!html.contains("Key Differences"));
+    }
+
+    fn get_working_context() -> WorkingContext {
+        WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new()
+                .check_for_key_diffs(true)
+                .render_key_diffs(true)
+                .build(),
+        )
+    }
+}
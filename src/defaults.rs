@@ -0,0 +1,157 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// Fills in the keys missing from the JSON data with their default values.
+/// Present values are never overridden, nested objects are merged at every level.
+pub fn apply_json_defaults(data: &mut Map<String, Value>, defaults: &Map<String, Value>) {
+    for (key, default) in defaults {
+        match (data.get_mut(key), default) {
+            (Some(Value::Object(value)), Value::Object(default)) => {
+                apply_json_defaults(value, default)
+            }
+            (Some(_), _) => {}
+            (None, _) => {
+                data.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Fills in the keys missing from the YAML data with their default values.
+/// Present values are never overridden, nested mappings are merged at every level.
+pub fn apply_yaml_defaults(data: &mut Mapping, defaults: &Mapping) {
+    for (key, default) in defaults {
+        match (data.get_mut(key), default) {
+            (Some(YamlValue::Mapping(value)), YamlValue::Mapping(default)) => {
+                apply_yaml_defaults(value, default)
+            }
+            (Some(_), _) => {}
+            (None, _) => {
+                data.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::WorkingFile;
+
+    use crate::{
+        dtfterminal_types::{
+            ConfigBuilder, DiffCollection, LibConfig, LibWorkingContext, WorkingContext,
+        },
+        json_app::JsonApp,
+        yaml_app::YamlApp,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_apply_json_defaults() {
+        let mut data = json_map(r#"{"name": "John", "server": {"port": 8080}}"#);
+        let defaults = json_map(
+            r#"{"name": "Default", "timeout": 30, "server": {"port": 80, "host": "localhost"}}"#,
+        );
+
+        apply_json_defaults(&mut data, &defaults);
+
+        assert_eq!(
+            data,
+            json_map(
+                r#"{"name": "John", "timeout": 30, "server": {"port": 8080, "host": "localhost"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_default_makes_data_equal() {
+        let defaults = json_map(r#"{"timeout": 30}"#);
+        let diffs = check_json(
+            r#"{"name": "John"}"#,
+            r#"{"name": "John", "timeout": 30}"#,
+            &defaults,
+        );
+
+        assert!(diffs.0.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_json_default_differs() {
+        let defaults = json_map(r#"{"timeout": 30}"#);
+        let diffs = check_json(
+            r#"{"name": "John"}"#,
+            r#"{"name": "John", "timeout": 60}"#,
+            &defaults,
+        );
+
+        assert!(diffs.0.unwrap().is_empty());
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "timeout");
+    }
+
+    #[test]
+    fn test_yaml_defaults() {
+        let defaults = yaml_mapping("timeout: 30\nserver:\n  host: localhost\n");
+
+        let diffs = check_yaml(
+            "server:\n  port: 80\n",
+            "timeout: 30\nserver:\n  port: 80\n  host: localhost\n",
+            &defaults,
+        );
+        assert!(diffs.0.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+
+        let diffs = check_yaml(
+            "server:\n  port: 80\n",
+            "timeout: 30\nserver:\n  port: 80\n  host: example.com\n",
+            &defaults,
+        );
+        assert!(diffs.0.unwrap().is_empty());
+        assert_eq!(diffs.2.unwrap()[0].key, "server.host");
+    }
+
+    fn check_json(a: &str, b: &str, defaults: &Map<String, Value>) -> DiffCollection {
+        let mut data1 = json_map(a);
+        let mut data2 = json_map(b);
+        apply_json_defaults(&mut data1, defaults);
+        apply_json_defaults(&mut data2, defaults);
+        JsonApp::from_data(data1, data2, get_working_context())
+            .perform_new_check()
+            .unwrap()
+    }
+
+    fn check_yaml(a: &str, b: &str, defaults: &Mapping) -> DiffCollection {
+        let mut data1 = yaml_mapping(a);
+        let mut data2 = yaml_mapping(b);
+        apply_yaml_defaults(&mut data1, defaults);
+        apply_yaml_defaults(&mut data2, defaults);
+        YamlApp::from_data(data1, data2, get_working_context())
+            .perform_new_check()
+            .unwrap()
+    }
+
+    fn json_map(data: &str) -> Map<String, Value> {
+        serde_json::from_str(data).unwrap()
+    }
+
+    fn yaml_mapping(data: &str) -> Mapping {
+        serde_yaml::from_str(data).unwrap()
+    }
+
+    fn get_working_context() -> WorkingContext {
+        WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a".to_owned()),
+                WorkingFile::new("b".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new()
+                .check_for_key_diffs(true)
+                .check_for_value_diffs(true)
+                .build(),
+        )
+    }
+}
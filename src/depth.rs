@@ -0,0 +1,186 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::dtfterminal_types::DtfError;
+
+/// How deep values can be nested, the recursion limit serde_json and serde_yaml parse with.
+/// The checkers recurse into every level, so data nested deeper could overflow the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// Fails if a JSON value is nested deeper than `MAX_DEPTH`.
+/// Walks the value without recursion, so the check itself can't overflow the stack.
+pub fn check_json_depth(value: &Value) -> Result<(), DtfError> {
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        check(depth)?;
+        match value {
+            Value::Array(items) => stack.extend(items.iter().map(|item| (item, depth + 1))),
+            Value::Object(object) => stack.extend(object.values().map(|item| (item, depth + 1))),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fails if the values of a JSON object are nested deeper than `MAX_DEPTH`, like `check_json_depth`
+pub fn check_json_map_depth(data: &Map<String, Value>) -> Result<(), DtfError> {
+    data.values().try_for_each(check_json_depth)
+}
+
+/// Fails if the keys or values of a YAML mapping are nested deeper than `MAX_DEPTH`, walking it without recursion
+pub fn check_yaml_depth(data: &Mapping) -> Result<(), DtfError> {
+    let mut stack: Vec<(&YamlValue, usize)> = data
+        .iter()
+        .flat_map(|(key, value)| [(key, 0), (value, 0)])
+        .collect();
+    while let Some((value, depth)) = stack.pop() {
+        check(depth)?;
+        match value {
+            YamlValue::Sequence(items) => stack.extend(items.iter().map(|item| (item, depth + 1))),
+            YamlValue::Mapping(mapping) => stack.extend(
+                mapping
+                    .iter()
+                    .flat_map(|(key, value)| [(key, depth + 1), (value, depth + 1)]),
+            ),
+            YamlValue::Tagged(tagged) => stack.push((&tagged.value, depth + 1)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fails if the tables and arrays of a TOML document are nested deeper than `MAX_DEPTH`, walking it without recursion
+#[cfg(feature = "cli")]
+pub fn check_toml_depth(table: &toml::Table) -> Result<(), DtfError> {
+    let mut stack: Vec<(&toml::Value, usize)> = table.values().map(|value| (value, 0)).collect();
+    while let Some((value, depth)) = stack.pop() {
+        check(depth)?;
+        match value {
+            toml::Value::Array(items) => stack.extend(items.iter().map(|item| (item, depth + 1))),
+            toml::Value::Table(table) => stack.extend(table.values().map(|item| (item, depth + 1))),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Fails if the brackets of a JSON or JSON5 text are nested deeper than `MAX_DEPTH`, before it's parsed.
+/// Brackets in strings and comments aren't counted. It only keeps a parser without a recursion limit
+/// from overflowing the stack, the exact depth is checked on the parsed values.
+pub fn check_text_depth(content: &str) -> Result<(), DtfError> {
+    let mut depth: usize = 0;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => skip_string(&mut chars, c),
+            '/' if chars.peek() == Some(&'/') => {
+                chars.find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                skip_block_comment(&mut chars);
+            }
+            '{' | '[' => {
+                depth += 1;
+                // The root object and the containers of the deepest values add two levels
+                check(depth.saturating_sub(2))?;
+            }
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn skip_string(chars: &mut impl Iterator<Item = char>, quote: char) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => return,
+            _ => {}
+        }
+    }
+}
+
+fn skip_block_comment(chars: &mut impl Iterator<Item = char>) {
+    let mut previous = None;
+    for c in chars {
+        if previous == Some('*') && c == '/' {
+            return;
+        }
+        previous = Some(c);
+    }
+}
+
+fn check(depth: usize) -> Result<(), DtfError> {
+    if depth > MAX_DEPTH {
+        return Err(DtfError::LimitError(format!(
+            "the values are nested deeper than {} levels",
+            MAX_DEPTH
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_yaml_depth() {
+        let nested = |depth: usize| {
+            let mut value = YamlValue::from(1);
+            for _ in 0..depth {
+                let mut mapping = Mapping::new();
+                mapping.insert(YamlValue::from("a"), value);
+                value = YamlValue::Mapping(mapping);
+            }
+            let mut data = Mapping::new();
+            data.insert(YamlValue::from("root"), value);
+            data
+        };
+
+        assert!(matches!(
+            check_yaml_depth(&nested(MAX_DEPTH + 10)),
+            Err(DtfError::LimitError(_))
+        ));
+        assert!(check_yaml_depth(&nested(MAX_DEPTH)).is_ok());
+    }
+
+    #[test]
+    fn test_check_text_depth() {
+        let nested =
+            |depth: usize| format!("{{\"a\": {}1{}}}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(matches!(
+            check_text_depth(&nested(MAX_DEPTH + 10)),
+            Err(DtfError::LimitError(_))
+        ));
+        assert!(check_text_depth(&nested(MAX_DEPTH)).is_ok());
+        // Brackets in strings and comments aren't nesting
+        let brackets = "[".repeat(MAX_DEPTH + 10);
+        let text = format!(
+            "{{\"a\": \"{0}\", 'b': '\\'{0}', // {0}\n /* {0} */ \"c\": []}}",
+            brackets
+        );
+        assert!(check_text_depth(&text).is_ok());
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_check_toml_depth() {
+        let nested = |depth: usize| -> toml::Table {
+            format!("{} = 1\n", vec!["a"; depth + 1].join("."))
+                .parse()
+                .unwrap()
+        };
+
+        assert!(matches!(
+            check_toml_depth(&nested(MAX_DEPTH + 10)),
+            Err(DtfError::LimitError(_))
+        ));
+        assert!(check_toml_depth(&nested(MAX_DEPTH)).is_ok());
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeSet;
+
+use colored::{Color, Colorize};
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table, TableStyle,
+};
+
+use libdtf::core::diff_types::{ArrayDiff, KeyDiff, TypeDiff, ValueDiff};
+
+use crate::{
+    dtfterminal_types::{
+        describe_array_diff_desc, Config, ConfigBuilder, DiffCategory, DiffCollection, DtfError,
+        SavedContext, WorkingContext,
+    },
+    file_handler::FileHandler,
+    side::Side,
+    Arguments,
+};
+
+/// What happened to a difference between two saved results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffStatus {
+    New,
+    Resolved,
+    Persisting,
+}
+
+impl DiffStatus {
+    /// The text and color the status is displayed with in the terminal
+    fn display(&self) -> (&'static str, Color) {
+        match self {
+            DiffStatus::New => ("New", Color::Red),
+            DiffStatus::Resolved => ("Resolved", Color::Green),
+            DiffStatus::Persisting => ("Persisting", Color::Yellow),
+        }
+    }
+}
+
+/// A difference from a saved result, identified by its category, key and value
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiffEntry {
+    pub category: DiffCategory,
+    pub key: String,
+    pub value: String,
+}
+
+impl DiffEntry {
+    pub fn new(category: DiffCategory, key: &str, value: String) -> DiffEntry {
+        DiffEntry {
+            category,
+            key: key.to_owned(),
+            value,
+        }
+    }
+}
+
+/// Compares two saved results and lists the differences that are new, resolved or persisting
+pub struct DiffOfDiffsApp {
+    old: SavedContext,
+    new: SavedContext,
+    config: Config,
+}
+
+impl DiffOfDiffsApp {
+    /// Loads the two saved results defined by the command line arguments
+    pub fn new(args: Arguments) -> Result<DiffOfDiffsApp, DtfError> {
+        let old = FileHandler::read_saved_context(&args.diff_of_diffs[0])?;
+        let new = FileHandler::read_saved_context(&args.diff_of_diffs[1])?;
+        let config = ConfigBuilder::new()
+            .render_key_diffs(args.key_diffs)
+            .render_type_diffs(args.type_diffs)
+            .render_value_diffs(args.value_diffs)
+            .render_array_diffs(args.array_diffs)
+            .build();
+
+        Ok(DiffOfDiffsApp { old, new, config })
+    }
+
+    /// Renders the table of changes to the terminal
+    pub fn execute(&self) -> Result<(), DtfError> {
+        let changes = compare_saved_contexts(&self.old, &self.new, &self.config);
+        if changes.is_empty() {
+            println!("The saved results are identical!");
+        } else {
+            println!("{}", render_table(&changes));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares the differences of two saved results in the categories enabled for rendering.
+/// Sorted by status, then by the entries themselves.
+pub fn compare_saved_contexts(
+    old: &SavedContext,
+    new: &SavedContext,
+    config: &Config,
+) -> Vec<(DiffStatus, DiffEntry)> {
+    let old_entries = collect_entries(old, config);
+    let new_entries = collect_entries(new, config);
+
+    let mut changes: Vec<(DiffStatus, DiffEntry)> = new_entries
+        .difference(&old_entries)
+        .map(|entry| (DiffStatus::New, entry.clone()))
+        .chain(
+            old_entries
+                .difference(&new_entries)
+                .map(|entry| (DiffStatus::Resolved, entry.clone())),
+        )
+        .chain(
+            old_entries
+                .intersection(&new_entries)
+                .map(|entry| (DiffStatus::Persisting, entry.clone())),
+        )
+        .collect();
+    changes.sort();
+    changes
+}
+
+/// Collects the differences of a saved result as comparable entries.
+/// Files are referred to as A and B, so results of renamed files can still be compared.
+fn collect_entries(saved_context: &SavedContext, config: &Config) -> BTreeSet<DiffEntry> {
+    let mut entries = BTreeSet::new();
+    let file_names = (
+        saved_context.config.file_a.as_str(),
+        saved_context.config.file_b.as_str(),
+    );
+
+    if config.render_key_diffs {
+        entries.extend(
+            saved_context
+                .key_diff
+                .iter()
+                .map(|kd| key_entry(&kd.key_diff, kd.side(file_names))),
+        );
+    }
+
+    if config.render_type_diffs {
+        entries.extend(saved_context.type_diff.iter().map(type_entry));
+    }
+
+    if config.render_value_diffs {
+        entries.extend(saved_context.value_diff.iter().map(value_entry));
+    }
+
+    if config.render_array_diffs {
+        entries.extend(saved_context.array_diff.iter().map(array_entry));
+    }
+
+    entries
+}
+
+/// Drops the differences of a new check that are in a saved result, matched by category, key and values.
+/// Like with saved results, the files are referred to as A and B, the context telling which has the keys.
+pub fn subtract_known_diffs(
+    diffs: DiffCollection,
+    known: &SavedContext,
+    context: &WorkingContext,
+) -> DiffCollection {
+    let all_categories = ConfigBuilder::new()
+        .render_key_diffs(true)
+        .render_type_diffs(true)
+        .render_value_diffs(true)
+        .render_array_diffs(true)
+        .build();
+    let known_entries = collect_entries(known, &all_categories);
+    let is_new = |entry: DiffEntry| !known_entries.contains(&entry);
+
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|kd| {
+            kd.into_iter()
+                .filter(|d| is_new(key_entry(d, context.key_side(d))))
+                .collect()
+        }),
+        type_diff.map(|td| td.into_iter().filter(|d| is_new(type_entry(d))).collect()),
+        value_diff.map(|vd| vd.into_iter().filter(|d| is_new(value_entry(d))).collect()),
+        array_diff.map(|ad| ad.into_iter().filter(|d| is_new(array_entry(d))).collect()),
+    )
+}
+
+/// Lists the differences of a check as comparable entries, the context telling which file has the keys
+pub fn diff_entries(diffs: &DiffCollection, context: &WorkingContext) -> Vec<DiffEntry> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    key_diff
+        .iter()
+        .flatten()
+        .map(|kd| key_entry(kd, context.key_side(kd)))
+        .chain(type_diff.iter().flatten().map(type_entry))
+        .chain(value_diff.iter().flatten().map(value_entry))
+        .chain(array_diff.iter().flatten().map(array_entry))
+        .collect()
+}
+
+fn key_entry(key_diff: &KeyDiff, side: Side) -> DiffEntry {
+    DiffEntry::new(
+        DiffCategory::Key,
+        &key_diff.key,
+        format!("Only {} has", side),
+    )
+}
+
+fn type_entry(type_diff: &TypeDiff) -> DiffEntry {
+    DiffEntry::new(
+        DiffCategory::Type,
+        &type_diff.key,
+        format!("{} -> {}", type_diff.type1, type_diff.type2),
+    )
+}
+
+fn value_entry(value_diff: &ValueDiff) -> DiffEntry {
+    DiffEntry::new(
+        DiffCategory::Value,
+        &value_diff.key,
+        format!("{} -> {}", value_diff.value1, value_diff.value2),
+    )
+}
+
+fn array_entry(array_diff: &ArrayDiff) -> DiffEntry {
+    DiffEntry::new(
+        DiffCategory::Array,
+        &array_diff.key,
+        format!(
+            "{} {}",
+            describe_array_diff_desc(&array_diff.descriptor),
+            array_diff.value
+        ),
+    )
+}
+
+/// Builds the terminal table of the changes
+fn render_table(changes: &[(DiffStatus, DiffEntry)]) -> String {
+    let mut table = Table::new();
+    table.max_column_width = 80;
+    table.style = TableStyle::extended();
+
+    table.add_row(Row::new(vec![TableCell::builder(
+        "Differences Between Saved Results",
+    )
+    .col_span(4)
+    .alignment(Alignment::Center)]));
+    table.add_row(Row::new(vec![
+        TableCell::new("Status"),
+        TableCell::new("Category"),
+        TableCell::new("Key"),
+        TableCell::new("Difference"),
+    ]));
+
+    for (status, entry) in changes {
+        let (status_text, status_color) = status.display();
+        table.add_row(Row::new(vec![
+            TableCell::new(status_text.color(status_color)),
+            TableCell::new(entry.category),
+            TableCell::new(&entry.key),
+            TableCell::new(&entry.value),
+        ]));
+    }
+
+    table.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::WorkingFile;
+
+    use crate::{
+        dtfterminal_types::{LibConfig, LibWorkingContext, SavedConfig},
+        side::SidedKeyDiff,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_compare_saved_contexts() {
+        let old = saved_context(
+            "old_a.json",
+            "old_b.json",
+            vec![key_diff("id", "old_a.json", "old_b.json")],
+            vec![
+                value_diff("name", "John", "Jane"),
+                value_diff("age", "30", "31"),
+            ],
+        );
+        let new = saved_context(
+            "new_a.json",
+            "new_b.json",
+            vec![key_diff("id", "new_a.json", "new_b.json")],
+            vec![
+                value_diff("name", "John", "Jane"),
+                value_diff("city", "A", "B"),
+            ],
+        );
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .build();
+
+        let changes = compare_saved_contexts(&old, &new, &config);
+
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    DiffStatus::New,
+                    DiffEntry::new(DiffCategory::Value, "city", "A -> B".to_owned())
+                ),
+                (
+                    DiffStatus::Resolved,
+                    DiffEntry::new(DiffCategory::Value, "age", "30 -> 31".to_owned())
+                ),
+                (
+                    DiffStatus::Persisting,
+                    DiffEntry::new(DiffCategory::Key, "id", "Only A has".to_owned())
+                ),
+                (
+                    DiffStatus::Persisting,
+                    DiffEntry::new(DiffCategory::Value, "name", "John -> Jane".to_owned())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_saved_contexts_only_rendered_categories() {
+        let old = saved_context("a.json", "b.json", vec![], vec![]);
+        let new = saved_context(
+            "a.json",
+            "b.json",
+            vec![key_diff("id", "a.json", "b.json")],
+            vec![value_diff("name", "John", "Jane")],
+        );
+        let config = ConfigBuilder::new().render_key_diffs(true).build();
+
+        let changes = compare_saved_contexts(&old, &new, &config);
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].1.category, DiffCategory::Key);
+    }
+
+    #[test]
+    fn test_subtract_known_diffs() {
+        let known = saved_context(
+            "old_a.json",
+            "old_b.json",
+            vec![key_diff("id", "old_a.json", "old_b.json")],
+            vec![value_diff("name", "John", "Jane")],
+        );
+        let diffs = (
+            Some(vec![
+                key_diff("id", "a.json", "b.json"),
+                key_diff("id", "b.json", "a.json"),
+            ]),
+            None,
+            Some(vec![
+                value_diff("name", "John", "Jane"),
+                value_diff("name", "John", "Joe"),
+            ]),
+            None,
+        );
+
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().build(),
+        );
+
+        let (key_diff, _, value_diff, _) = subtract_known_diffs(diffs, &known, &context);
+
+        // Only B having the key or a different value are new differences
+        assert_eq!(
+            key_diff.unwrap(),
+            vec![self::key_diff("id", "b.json", "a.json")]
+        );
+        assert_eq!(
+            value_diff.unwrap(),
+            vec![self::value_diff("name", "John", "Joe")]
+        );
+    }
+
+    #[test]
+    fn test_compare_saved_contexts_same_file_names() {
+        let sided = |side| SidedKeyDiff {
+            key_diff: key_diff("id", "config.json", "config.json"),
+            side: Some(side),
+        };
+        let mut old = saved_context("config.json", "config.json", vec![], vec![]);
+        old.key_diff = vec![sided(Side::B)];
+        let mut new = saved_context("config.json", "config.json", vec![], vec![]);
+        new.key_diff = vec![sided(Side::A)];
+        let config = ConfigBuilder::new().render_key_diffs(true).build();
+
+        let changes = compare_saved_contexts(&old, &new, &config);
+
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    DiffStatus::New,
+                    DiffEntry::new(DiffCategory::Key, "id", "Only A has".to_owned())
+                ),
+                (
+                    DiffStatus::Resolved,
+                    DiffEntry::new(DiffCategory::Key, "id", "Only B has".to_owned())
+                ),
+            ]
+        );
+    }
+
+    fn saved_context(
+        file_a: &str,
+        file_b: &str,
+        key_diff: Vec<KeyDiff>,
+        value_diff: Vec<ValueDiff>,
+    ) -> SavedContext {
+        SavedContext::new(
+            key_diff.into_iter().map(SidedKeyDiff::from).collect(),
+            vec![],
+            value_diff,
+            vec![],
+            SavedConfig::new(
+                true,
+                false,
+                true,
+                false,
+                file_a.to_owned(),
+                file_b.to_owned(),
+                false,
+            ),
+        )
+    }
+
+    fn key_diff(key: &str, has: &str, misses: &str) -> KeyDiff {
+        KeyDiff {
+            key: key.to_owned(),
+            has: has.to_owned(),
+            misses: misses.to_owned(),
+        }
+    }
+
+    fn value_diff(key: &str, value1: &str, value2: &str) -> ValueDiff {
+        ValueDiff {
+            key: key.to_owned(),
+            value1: value1.to_owned(),
+            value2: value2.to_owned(),
+        }
+    }
+}
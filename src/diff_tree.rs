@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use libdtf::core::diff_types::{ArrayDiff, KeyDiff, TypeDiff, ValueDiff};
+use serde::{Deserialize, Serialize};
+
+use crate::dtfterminal_types::DiffCollection;
+
+/// The differences arranged by their key paths.
+/// Every node holds the differences found exactly at its path, and its children by key segment.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DiffTree {
+    pub key_diffs: Vec<KeyDiff>,
+    pub type_diffs: Vec<TypeDiff>,
+    pub value_diffs: Vec<ValueDiff>,
+    pub array_diffs: Vec<ArrayDiff>,
+    pub children: BTreeMap<String, DiffTree>,
+}
+
+impl DiffTree {
+    /// Builds the tree from the flat lists of differences
+    pub fn from_diffs(diffs: &DiffCollection) -> DiffTree {
+        let (key_diff, type_diff, value_diff, array_diff) = diffs;
+        let mut tree = DiffTree::default();
+
+        for kd in key_diff.iter().flatten() {
+            tree.node_mut(&kd.key).key_diffs.push(kd.clone());
+        }
+        for td in type_diff.iter().flatten() {
+            tree.node_mut(&td.key).type_diffs.push(td.clone());
+        }
+        for vd in value_diff.iter().flatten() {
+            tree.node_mut(&vd.key).value_diffs.push(vd.clone());
+        }
+        for ad in array_diff.iter().flatten() {
+            tree.node_mut(&ad.key).array_diffs.push(ad.clone());
+        }
+
+        tree
+    }
+
+    /// Get the node at the given key path, if there are any differences under it
+    pub fn node(&self, key: &str) -> Option<&DiffTree> {
+        split_key_path(key)
+            .iter()
+            .try_fold(self, |node, segment| node.children.get(segment))
+    }
+
+    /// Checks if the node and its children have no differences
+    pub fn is_empty(&self) -> bool {
+        self.key_diffs.is_empty()
+            && self.type_diffs.is_empty()
+            && self.value_diffs.is_empty()
+            && self.array_diffs.is_empty()
+            && self.children.values().all(DiffTree::is_empty)
+    }
+
+    /// Get the node at the given key path, creating the missing nodes on the way
+    fn node_mut(&mut self, key: &str) -> &mut DiffTree {
+        split_key_path(key).into_iter().fold(self, |node, segment| {
+            node.children.entry(segment).or_default()
+        })
+    }
+}
+
+/// Splits a key path like `friends[0].name` into its segments: `friends`, `[0]`, `name`
+pub(crate) fn split_key_path(key: &str) -> Vec<String> {
+    let mut segments = vec![];
+    for part in key.split('.').filter(|part| !part.is_empty()) {
+        match part.find('[') {
+            Some(index) if index > 0 => {
+                segments.push(part[..index].to_owned());
+                segments.extend(
+                    part[index..]
+                        .split_inclusive(']')
+                        .map(|segment| segment.to_owned()),
+                );
+            }
+            _ => segments.push(part.to_owned()),
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_key_path() {
+        assert_eq!(split_key_path("name"), vec!["name"]);
+        assert_eq!(split_key_path("nested.a_has"), vec!["nested", "a_has"]);
+        assert_eq!(
+            split_key_path("friends[0].ids[1]"),
+            vec!["friends", "[0]", "ids", "[1]"]
+        );
+        assert!(split_key_path("").is_empty());
+    }
+
+    #[test]
+    fn test_from_diffs() {
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "nested.a_has".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![ValueDiff {
+                key: "name".to_owned(),
+                value1: "John".to_owned(),
+                value2: "Jane".to_owned(),
+            }]),
+            None,
+        );
+
+        let tree = DiffTree::from_diffs(&diffs);
+
+        let nested = tree.children.get("nested").unwrap();
+        assert!(nested.key_diffs.is_empty());
+        assert_eq!(nested.children.get("a_has").unwrap().key_diffs.len(), 1);
+        assert_eq!(
+            tree.node("nested.a_has").unwrap().key_diffs[0].key,
+            "nested.a_has"
+        );
+        assert_eq!(tree.node("name").unwrap().value_diffs.len(), 1);
+        assert!(tree.node("missing").is_none());
+        assert!(!tree.is_empty());
+        assert!(serde_json::to_string(&tree).is_ok());
+    }
+}
@@ -0,0 +1,95 @@
+use std::rc::Rc;
+
+use serde_json::{Map, Value};
+use serde_yaml::Mapping;
+
+use crate::{dtfterminal_types::DtfError, file_handler::FileHandler};
+
+/// Keeps the parsed baseline file, so comparing it against many files only reads and parses it once.
+/// Only the file it was created for is cached, every other file is read each time.
+/// The cached data is shared with the comparisons instead of being copied for each of them.
+#[derive(Debug, Default)]
+pub struct DocumentCache {
+    path: Option<String>,
+    json: Option<Rc<Map<String, Value>>>,
+    yaml: Option<Rc<Mapping>>,
+}
+
+impl DocumentCache {
+    /// Caches the file at the path once it's read
+    pub fn new(path: String) -> DocumentCache {
+        DocumentCache {
+            path: Some(path),
+            ..DocumentCache::default()
+        }
+    }
+
+    /// Reads a file like `FileHandler::read_as_json`, the cached file only the first time
+    pub fn read_as_json(
+        &mut self,
+        file_path: &str,
+        max_size: Option<u64>,
+        jsonc: bool,
+    ) -> Result<Rc<Map<String, Value>>, DtfError> {
+        if self.path.as_deref() != Some(file_path) {
+            return FileHandler::read_as_json(file_path, max_size, jsonc).map(Rc::new);
+        }
+        if let Some(data) = &self.json {
+            return Ok(Rc::clone(data));
+        }
+        let data = Rc::new(FileHandler::read_as_json(file_path, max_size, jsonc)?);
+        self.json = Some(Rc::clone(&data));
+        Ok(data)
+    }
+
+    /// Reads a file like `FileHandler::read_yaml_file`, the cached file only the first time
+    pub fn read_yaml_file(
+        &mut self,
+        file_path: &str,
+        max_size: Option<u64>,
+    ) -> Result<Rc<Mapping>, DtfError> {
+        if self.path.as_deref() != Some(file_path) {
+            return FileHandler::read_yaml_file(file_path, max_size).map(Rc::new);
+        }
+        if let Some(data) = &self.yaml {
+            return Ok(Rc::clone(data));
+        }
+        let data = Rc::new(FileHandler::read_yaml_file(file_path, max_size)?);
+        self.yaml = Some(Rc::clone(&data));
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_read_as_json() {
+        let dir = std::env::temp_dir().join(format!("dtf_document_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let baseline = dir.join("baseline.json").to_string_lossy().into_owned();
+        let other = dir.join("other.json").to_string_lossy().into_owned();
+        fs::write(&baseline, r#"{"version": 1}"#).unwrap();
+        fs::write(&other, r#"{"version": 1}"#).unwrap();
+        let mut cache = DocumentCache::new(baseline.clone());
+
+        let first = cache.read_as_json(&baseline, None, false).unwrap();
+        cache.read_as_json(&other, None, false).unwrap();
+        fs::write(&baseline, r#"{"version": 2}"#).unwrap();
+        fs::write(&other, r#"{"version": 2}"#).unwrap();
+
+        // Only the baseline is kept from the first read, and shared instead of copied
+        let cached = cache.read_as_json(&baseline, None, false).unwrap();
+        assert_eq!(cached["version"], 1);
+        assert!(Rc::ptr_eq(&first, &cached));
+        assert_eq!(
+            cache.read_as_json(&other, None, false).unwrap()["version"],
+            2
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
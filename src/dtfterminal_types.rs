@@ -1,12 +1,49 @@
-use std::{error::Error, fmt};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    str::FromStr,
+};
 
-use libdtf::core::diff_types::{ArrayDiff, Diff, KeyDiff, TypeDiff, ValueDiff};
+use chrono::{SecondsFormat, Utc};
+use colored::{Color, Colorize};
+pub use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+use libdtf::core::diff_types::{Diff, KeyDiff, TypeDiff, ValueDiff};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use term_table::{row::Row, Table, TableStyle};
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table, TableStyle,
+};
+
+use crate::{
+    array_indexes::{ArrayIndexes, IndexCursor},
+    comparator::PathComparator,
+    hash::FileHashes,
+    line_diff::LineDiffKeys,
+    path_matcher::PathMatcher,
+    positions::KeyPositions,
+    redact::RedactedTexts,
+    sample::SampledArray,
+    side::{KeySides, Side, SidedKeyDiff},
+    utils::{ASCII_CHECKMARK, ASCII_MULTIPLY, ASCII_RIGHT_ARROW, CHECKMARK, MULTIPLY, RIGHT_ARROW},
+    value_types::ValueTypes,
+};
 
 pub type LibConfig = libdtf::core::diff_types::Config;
 pub type LibWorkingContext = libdtf::core::diff_types::WorkingContext;
 
+/// The column width used when the terminal width can't be determined (e.g. output is piped)
+const DEFAULT_COLUMN_WIDTH: usize = 80;
+/// The number of columns of the difference tables
+const TABLE_COLUMNS: usize = 3;
+/// The narrowest a column can get, so tables stay readable on very narrow terminals
+const MIN_COLUMN_WIDTH: usize = 10;
+/// Shown in the tables without differences, if empty tables are shown
+const NO_DIFFERENCES: &str = "(no differences)";
+
 /// Stores the data required for rendering a table of the differences to the terminal
 pub struct TableContext<'a> {
     working_context: &'a WorkingContext,
@@ -16,8 +53,12 @@ pub struct TableContext<'a> {
 impl<'a> TableContext<'a> {
     pub fn new(working_context: &'a WorkingContext) -> TableContext {
         let mut table = Table::new();
-        table.max_column_width = 80;
-        table.style = TableStyle::extended();
+        table.max_column_width = column_width(terminal_width());
+        table.style = if working_context.config.ascii {
+            TableStyle::simple()
+        } else {
+            TableStyle::extended()
+        };
         TableContext {
             working_context,
             table,
@@ -39,10 +80,76 @@ impl<'a> TableContext<'a> {
         self.table.add_row(row);
     }
 
+    /// Adds a row noting that the table has no differences, for the tables shown even if empty
+    pub fn add_no_differences_row(&mut self) {
+        self.table
+            .add_row(Row::new(vec![TableCell::builder(NO_DIFFERENCES)
+                .col_span(TABLE_COLUMNS)
+                .alignment(Alignment::Center)]));
+    }
+
     /// Returns the built terminal table string
     pub fn render(&self) -> String {
         self.table.render()
     }
+
+    /// Returns the width the columns of the table can take up
+    pub fn column_width(&self) -> usize {
+        self.table.max_column_width
+    }
+
+    /// Returns the mark of a key being present in a file or missing from it
+    pub fn presence_mark(&self, has: bool) -> &'static str {
+        match (self.working_context.config.ascii, has) {
+            (true, true) => ASCII_CHECKMARK,
+            (true, false) => ASCII_MULTIPLY,
+            (false, true) => CHECKMARK,
+            (false, false) => MULTIPLY,
+        }
+    }
+
+    /// Returns the arrow pointing from the value in file A to the one in file B
+    pub fn arrow(&self) -> &'static str {
+        if self.working_context.config.ascii {
+            ASCII_RIGHT_ARROW
+        } else {
+            RIGHT_ARROW
+        }
+    }
+
+    /// Formats a key for the table, colored by its severity if severities are configured
+    pub fn format_key(&self, category: DiffCategory, key: &str) -> String {
+        let config = &self.working_context.config;
+        if config.uses_severities() {
+            key.color(config.severity_of(category, key).color())
+                .to_string()
+        } else {
+            key.to_owned()
+        }
+    }
+}
+
+/// Distributes the width of the terminal across the columns of a table.
+/// Each column is padded by a space on both sides and separated by a border.
+fn column_width(terminal_width: Option<usize>) -> usize {
+    match terminal_width {
+        Some(width) => {
+            let decorations = TABLE_COLUMNS * 2 + TABLE_COLUMNS + 1;
+            (width.saturating_sub(decorations) / TABLE_COLUMNS).max(MIN_COLUMN_WIDTH)
+        }
+        None => DEFAULT_COLUMN_WIDTH,
+    }
+}
+
+/// Returns the width of the terminal, if the output is a terminal
+#[cfg(feature = "cli")]
+fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+#[cfg(not(feature = "cli"))]
+fn terminal_width() -> Option<usize> {
+    None
 }
 
 /// Gives terminal tables the required functionality
@@ -71,6 +178,25 @@ pub type DiffCollection = (
     Option<Vec<ArrayDiff>>,
 );
 
+/// Get the array differences with the given descriptor, e.g. only the values B has in addition
+pub fn filter_by_desc(array_diffs: &[ArrayDiff], descriptor: ArrayDiffDesc) -> Vec<&ArrayDiff> {
+    array_diffs
+        .iter()
+        .filter(|ad| ad.descriptor == descriptor)
+        .collect()
+}
+
+/// A human readable description of an array difference descriptor.
+/// `ArrayDiffDesc` is defined in libdtf, so it can't implement `Display` in this crate.
+pub fn describe_array_diff_desc(descriptor: &ArrayDiffDesc) -> &'static str {
+    match descriptor {
+        ArrayDiffDesc::AHas => "Only A has",
+        ArrayDiffDesc::AMisses => "A misses",
+        ArrayDiffDesc::BHas => "Only B has",
+        ArrayDiffDesc::BMisses => "B misses",
+    }
+}
+
 /// The structure a result set gets saved in for later re-use
 #[derive(Serialize, Deserialize)]
 pub struct SavedConfig {
@@ -81,6 +207,9 @@ pub struct SavedConfig {
     pub file_a: String,
     pub file_b: String,
     pub array_same_order: bool,
+    /// The arrays compared as ordered while the rest are not, missing from results saved before it was added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ordered_array_paths: Vec<String>,
 }
 
 impl SavedConfig {
@@ -101,6 +230,388 @@ impl SavedConfig {
             file_a,
             file_b,
             array_same_order,
+            ordered_array_paths: Vec::new(),
+        }
+    }
+
+    pub fn with_ordered_array_paths(mut self, ordered_array_paths: Vec<String>) -> SavedConfig {
+        self.ordered_array_paths = ordered_array_paths;
+        self
+    }
+}
+
+/// The categories of differences that can be checked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DiffCategory {
+    Key,
+    Type,
+    Value,
+    Array,
+}
+
+impl fmt::Display for DiffCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffCategory::Key => write!(f, "Key"),
+            DiffCategory::Type => write!(f, "Type"),
+            DiffCategory::Value => write!(f, "Value"),
+            DiffCategory::Array => write!(f, "Array"),
+        }
+    }
+}
+
+impl DiffCategory {
+    /// The first letter of the category, used to tag the paths of `--format paths`
+    pub fn tag(&self) -> char {
+        match self {
+            DiffCategory::Key => 'K',
+            DiffCategory::Type => 'T',
+            DiffCategory::Value => 'V',
+            DiffCategory::Array => 'A',
+        }
+    }
+}
+
+impl FromStr for DiffCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "key" => Ok(DiffCategory::Key),
+            "type" => Ok(DiffCategory::Type),
+            "value" => Ok(DiffCategory::Value),
+            "array" => Ok(DiffCategory::Array),
+            _ => Err(format!("Unknown difference category: {}", s)),
+        }
+    }
+}
+
+/// A file the differences of a single category are written to, in the format of its extension
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryOutput {
+    pub category: DiffCategory,
+    pub path: String,
+}
+
+impl CategoryOutput {
+    pub fn new(category: DiffCategory, path: String) -> CategoryOutput {
+        CategoryOutput { category, path }
+    }
+
+    /// Keeps only the differences of the category of the output
+    pub fn diffs(&self, diffs: &DiffCollection) -> DiffCollection {
+        let mut results = DiffResults::new();
+        if let Some(category_diffs) = DiffResults::from(diffs.clone()).remove(self.category) {
+            results.insert(category_diffs);
+        }
+        results.into()
+    }
+
+    /// A copy of the context rendering only the category of the output
+    pub fn working_context(&self, context: &WorkingContext) -> WorkingContext {
+        let mut context = context.clone();
+        context.config.render_key_diffs = self.category == DiffCategory::Key;
+        context.config.render_type_diffs = self.category == DiffCategory::Type;
+        context.config.render_value_diffs = self.category == DiffCategory::Value;
+        context.config.render_array_diffs = self.category == DiffCategory::Array;
+        context
+    }
+
+    /// The format the output is written in, by the extension of its file.
+    /// Files without a known extension get the tables, like the terminal.
+    pub fn format(&self) -> OutputFormat {
+        let extension = std::path::Path::new(&self.path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("html" | "htm") => OutputFormat::Html,
+            Some("md" | "markdown") => OutputFormat::Markdown,
+            Some("json") => OutputFormat::Json,
+            Some("ndjson" | "jsonl") => OutputFormat::Ndjson,
+            _ => OutputFormat::Table,
+        }
+    }
+}
+
+/// How important a difference is. Ordered from the least to the most important.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The color differences of this severity are rendered with in the terminal
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Info => Color::Blue,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!("Unknown severity: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Assigns a severity to a category of differences, optionally only under the given path.
+/// Parsed from `category[:path]=severity`, e.g. `array=info` or `value:pipeline.steps=error`.
+/// The path is compiled once when parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeverityRule {
+    pub category: DiffCategory,
+    pub path: Option<PathMatcher>,
+    pub severity: Severity,
+}
+
+impl SeverityRule {
+    /// Checks if the rule applies to a difference
+    pub fn matches(&self, category: DiffCategory, key: &str) -> bool {
+        self.category == category && self.path.as_ref().map_or(true, |path| path.matches(key))
+    }
+}
+
+impl FromStr for SeverityRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, severity) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Expected category[:path]=severity, got: {}", s))?;
+        let (category, path) = match target.split_once(':') {
+            Some((category, path)) => (category, Some(PathMatcher::new(path))),
+            None => (target, None),
+        };
+
+        Ok(SeverityRule {
+            category: category.parse()?,
+            path,
+            severity: severity.parse()?,
+        })
+    }
+}
+
+/// The largest difference between two numbers still considered equal, optionally only under the given path.
+/// Parsed from `[path=]epsilon`, e.g. `0.001` or `price=0.01`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tolerance {
+    pub path: Option<String>,
+    pub epsilon: f64,
+}
+
+impl Tolerance {
+    /// Splits the tolerances into the global one and the ones by their paths. The last one given for a path wins.
+    pub fn split(tolerances: Vec<Tolerance>) -> (Option<f64>, HashMap<String, f64>) {
+        let mut epsilon = None;
+        let mut path_epsilons = HashMap::new();
+        for tolerance in tolerances {
+            match tolerance.path {
+                Some(path) => {
+                    path_epsilons.insert(path, tolerance.epsilon);
+                }
+                None => epsilon = Some(tolerance.epsilon),
+            }
+        }
+        (epsilon, path_epsilons)
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, epsilon) = match s.rsplit_once('=') {
+            Some((path, epsilon)) => (Some(path.to_owned()), epsilon),
+            None => (None, s),
+        };
+        let epsilon: f64 = epsilon
+            .parse()
+            .map_err(|_| format!("Expected [path=]epsilon, got: {}", s))?;
+        if epsilon.is_nan() || epsilon < 0.0 {
+            return Err(format!("The epsilon must not be negative, got: {}", s));
+        }
+
+        Ok(Tolerance { path, epsilon })
+    }
+}
+
+/// The separators of numbers written as strings, parsed from the thousands separator followed by the decimal one,
+/// e.g. `,.` for `1,234.56` or `.,` for `1.234,56`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub thousands_separator: char,
+    pub decimal_separator: char,
+}
+
+impl NumberFormat {
+    /// Parses a number written with the separators of the format, e.g. `1,234.56`
+    pub fn parse(&self, value: &str) -> Option<f64> {
+        let number: String = value
+            .trim()
+            .chars()
+            .filter(|c| *c != self.thousands_separator)
+            .map(|c| if c == self.decimal_separator { '.' } else { c })
+            .collect();
+        number.parse().ok()
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().collect::<Vec<char>>()[..] {
+            [thousands_separator, decimal_separator]
+                if thousands_separator != decimal_separator
+                    && !thousands_separator.is_ascii_digit()
+                    && !decimal_separator.is_ascii_digit() =>
+            {
+                Ok(NumberFormat {
+                    thousands_separator,
+                    decimal_separator,
+                })
+            }
+            _ => Err(format!(
+                "Expected the thousands separator followed by the decimal one, e.g. ',.', got: {}",
+                s
+            )),
+        }
+    }
+}
+
+/// The file treated as the expected state. Keys and array items are reported relative to it:
+/// what the other file misses is an error, what it has in addition is a warning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Baseline {
+    A,
+    B,
+}
+
+impl FromStr for Baseline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "a" => Ok(Baseline::A),
+            "b" => Ok(Baseline::B),
+            _ => Err(format!("Unknown baseline: {}, expected a or b", s)),
+        }
+    }
+}
+
+/// How the differences are written to the terminal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Paths,
+    Ndjson,
+    Markdown,
+    Html,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "paths" => Ok(OutputFormat::Paths),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Checks if the format is meant to be read by programs, so errors should be written in it as well
+    pub fn is_machine_readable(&self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Ndjson)
+    }
+}
+
+/// How the items of two arrays are matched with each other
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayMatchStrategy {
+    /// Items at the same index are compared, like with `-o`
+    Positional,
+    /// Objects with the same `array_key` field are displayed together, the rest fall back to `Similarity`
+    Key,
+    /// Objects sharing most of their fields are paired, reporting their differing fields as value differences
+    Similarity,
+    /// Arrays are compared as sets of items, ignoring their order
+    #[default]
+    Set,
+}
+
+impl FromStr for ArrayMatchStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "positional" => Ok(ArrayMatchStrategy::Positional),
+            "key" => Ok(ArrayMatchStrategy::Key),
+            "similarity" => Ok(ArrayMatchStrategy::Similarity),
+            "set" => Ok(ArrayMatchStrategy::Set),
+            _ => Err(format!("Unknown array match strategy: {}", s)),
+        }
+    }
+}
+
+impl ArrayMatchStrategy {
+    /// Checks if objects only one of the files has are paired by their shared fields
+    pub fn pairs_by_similarity(&self) -> bool {
+        matches!(
+            self,
+            ArrayMatchStrategy::Key | ArrayMatchStrategy::Similarity
+        )
+    }
+}
+
+/// How the types of the values are named in the type differences
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TypeNaming {
+    /// The names of the comparison, like `bool` and `number`
+    #[default]
+    Default,
+    /// The names of JSON Schema, like `boolean` and `integer`
+    JsonSchema,
+}
+
+impl FromStr for TypeNaming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(TypeNaming::Default),
+            "json-schema" | "jsonschema" => Ok(TypeNaming::JsonSchema),
+            _ => Err(format!("Unknown type naming: {}", s)),
         }
     }
 }
@@ -121,11 +632,152 @@ pub struct Config {
     pub file_a: Option<String>,
     pub file_b: Option<String>,
     pub array_same_order: bool,
+    pub ordered_array_paths: Vec<String>,
+    pub severity_rules: Vec<SeverityRule>,
+    pub min_severity: Severity,
+    pub fail_on_severity: Option<Severity>,
+    pub max_size: Option<u64>,
+    pub max_diffs: Option<usize>,
+    pub array_key: Option<String>,
+    pub parse_dates: bool,
+    pub emit_header: bool,
+    pub intersection: bool,
+    pub defaults: Option<String>,
+    pub multiline_string_diff: bool,
+    pub baseline: Option<Baseline>,
+    pub output_format: OutputFormat,
+    pub value_truncate_len: Option<usize>,
+    pub max_array_diffs_per_key: Option<usize>,
+    pub explain: bool,
+    pub check_key_order: bool,
+    pub schema: bool,
+    pub show_types_in_values: bool,
+    pub only_paths: Vec<String>,
+    pub ignore_paths: Vec<String>,
+    pub badge: Option<String>,
+    pub max_value_len: Option<usize>,
+    pub embed_data: bool,
+    pub epsilon: Option<f64>,
+    /// The tolerances by the paths they apply under, compiled when the config is built
+    pub path_epsilons: Vec<(PathMatcher, f64)>,
+    pub tag_paths: bool,
+    pub normalize_unicode: bool,
+    pub unwrap_a: Option<String>,
+    pub unwrap_b: Option<String>,
+    pub sort_array_values: bool,
+    pub canonicalize: bool,
+    pub trim_strings: bool,
+    pub side_by_side: bool,
+    pub array_match_strategy: ArrayMatchStrategy,
+    pub ascii: bool,
+    pub string_similarity_threshold: Option<f64>,
+    pub flatten: bool,
+    pub flatten_arrays: bool,
+    pub value_comparators: Vec<PathComparator>,
+    pub common: Option<String>,
+    pub append: bool,
+    pub show_empty_tables: bool,
+    pub report_title: Option<String>,
+    pub report_description: Option<String>,
+    pub ignore_array_tail: bool,
+    pub redact_patterns: Vec<Regex>,
+    pub baseline_diffs: Option<String>,
+    pub positions: bool,
+    pub empty_equivalence: bool,
+    pub ignore_yaml_tags: bool,
+    pub count: bool,
+    pub category_outputs: Vec<CategoryOutput>,
+    pub number_format: Option<NumberFormat>,
+    pub ignore_chomping: bool,
+    pub top: Option<usize>,
+    pub jsonc: bool,
+    pub set_array_diffs: bool,
+    pub coerce_numeric_strings: bool,
+    pub fail_fast: bool,
+    pub primary_key: Option<String>,
+    pub identical_message: Option<String>,
+    pub sample: Option<usize>,
+    pub sample_seed: u64,
+    pub type_naming: TypeNaming,
+    pub hash: bool,
+    pub matrices: bool,
+    pub collapse_ranges: bool,
+    pub compare_keys_only: bool,
     pub browser_view: Option<String>,
     pub printer_friendly: bool,
     pub no_browser_show: bool,
 }
 
+/// Shown instead of the differences if there are none and no message was given
+pub const DEFAULT_IDENTICAL_MESSAGE: &str = "The data is identical!";
+
+impl Config {
+    /// The message shown instead of the differences if there are none
+    pub fn identical_message(&self) -> &str {
+        self.identical_message
+            .as_deref()
+            .unwrap_or(DEFAULT_IDENTICAL_MESSAGE)
+    }
+
+    /// Checks if the user assigned any severities
+    pub fn uses_severities(&self) -> bool {
+        !self.severity_rules.is_empty()
+    }
+
+    /// Get the severity of a difference of presence (Key differences and Array differences),
+    /// where `baseline_has` tells if the baseline file is the one having the key or item.
+    /// A matching rule wins. Otherwise with a baseline, what the other file has in addition is a warning.
+    pub fn presence_severity_of(
+        &self,
+        category: DiffCategory,
+        key: &str,
+        baseline_has: bool,
+    ) -> Severity {
+        match (self.matching_severity(category, key), self.baseline) {
+            (Some(severity), _) => severity,
+            (None, Some(_)) if !baseline_has => Severity::Warning,
+            (None, _) => Severity::Error,
+        }
+    }
+
+    /// Checks if the key of the difference is in the baseline file
+    pub fn baseline_has_key(&self, side: Side) -> bool {
+        matches!(
+            (self.baseline, side),
+            (Some(Baseline::A), Side::A) | (Some(Baseline::B), Side::B)
+        )
+    }
+
+    /// Checks if the array item of the difference is in the baseline file
+    pub fn baseline_has_array_item(&self, descriptor: &ArrayDiffDesc) -> bool {
+        matches!(
+            (self.baseline, descriptor),
+            (
+                Some(Baseline::A),
+                ArrayDiffDesc::AHas | ArrayDiffDesc::BMisses
+            ) | (
+                Some(Baseline::B),
+                ArrayDiffDesc::BHas | ArrayDiffDesc::AMisses
+            )
+        )
+    }
+
+    /// Get the severity of a difference. The last matching rule wins, differences without one are errors.
+    pub fn severity_of(&self, category: DiffCategory, key: &str) -> Severity {
+        self.matching_severity(category, key)
+            .unwrap_or(Severity::Error)
+    }
+
+    /// Get the severity of the last matching rule
+    fn matching_severity(&self, category: DiffCategory, key: &str) -> Option<Severity> {
+        self.severity_rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(category, key))
+            .map(|rule| rule.severity)
+    }
+}
+
 /// Helper class for creating Config instances
 #[derive(Default)]
 pub struct ConfigBuilder {
@@ -142,6 +794,76 @@ pub struct ConfigBuilder {
     file_a: Option<String>,
     file_b: Option<String>,
     array_same_order: bool,
+    ordered_array_paths: Vec<String>,
+    severity_rules: Vec<SeverityRule>,
+    min_severity: Severity,
+    fail_on_severity: Option<Severity>,
+    max_size: Option<u64>,
+    max_diffs: Option<usize>,
+    array_key: Option<String>,
+    parse_dates: bool,
+    emit_header: bool,
+    intersection: bool,
+    defaults: Option<String>,
+    multiline_string_diff: bool,
+    baseline: Option<Baseline>,
+    output_format: OutputFormat,
+    value_truncate_len: Option<usize>,
+    max_array_diffs_per_key: Option<usize>,
+    explain: bool,
+    check_key_order: bool,
+    schema: bool,
+    show_types_in_values: bool,
+    only_paths: Vec<String>,
+    ignore_paths: Vec<String>,
+    badge: Option<String>,
+    max_value_len: Option<usize>,
+    embed_data: bool,
+    epsilon: Option<f64>,
+    path_epsilons: HashMap<String, f64>,
+    tag_paths: bool,
+    normalize_unicode: bool,
+    unwrap_a: Option<String>,
+    unwrap_b: Option<String>,
+    sort_array_values: bool,
+    canonicalize: bool,
+    trim_strings: bool,
+    side_by_side: bool,
+    array_match_strategy: ArrayMatchStrategy,
+    ascii: bool,
+    string_similarity_threshold: Option<f64>,
+    flatten: bool,
+    flatten_arrays: bool,
+    value_comparators: Vec<PathComparator>,
+    common: Option<String>,
+    append: bool,
+    show_empty_tables: bool,
+    report_title: Option<String>,
+    report_description: Option<String>,
+    ignore_array_tail: bool,
+    redact_patterns: Vec<Regex>,
+    baseline_diffs: Option<String>,
+    positions: bool,
+    empty_equivalence: bool,
+    ignore_yaml_tags: bool,
+    count: bool,
+    category_outputs: Vec<CategoryOutput>,
+    number_format: Option<NumberFormat>,
+    ignore_chomping: bool,
+    top: Option<usize>,
+    jsonc: bool,
+    set_array_diffs: bool,
+    coerce_numeric_strings: bool,
+    fail_fast: bool,
+    primary_key: Option<String>,
+    identical_message: Option<String>,
+    sample: Option<usize>,
+    sample_seed: u64,
+    type_naming: TypeNaming,
+    hash: bool,
+    matrices: bool,
+    collapse_ranges: bool,
+    compare_keys_only: bool,
     browser_view: Option<String>,
     printer_friendly: bool,
     no_browser_show: bool,
@@ -163,6 +885,76 @@ impl ConfigBuilder {
             file_a: None,
             file_b: None,
             array_same_order: false,
+            ordered_array_paths: Vec::new(),
+            severity_rules: Vec::new(),
+            min_severity: Severity::Info,
+            fail_on_severity: None,
+            max_size: None,
+            max_diffs: None,
+            array_key: None,
+            parse_dates: false,
+            emit_header: false,
+            intersection: false,
+            defaults: None,
+            multiline_string_diff: false,
+            baseline: None,
+            output_format: OutputFormat::Table,
+            value_truncate_len: None,
+            max_array_diffs_per_key: None,
+            explain: false,
+            check_key_order: false,
+            schema: false,
+            show_types_in_values: false,
+            only_paths: vec![],
+            ignore_paths: vec![],
+            badge: None,
+            max_value_len: None,
+            embed_data: false,
+            epsilon: None,
+            path_epsilons: HashMap::new(),
+            tag_paths: false,
+            normalize_unicode: false,
+            unwrap_a: None,
+            unwrap_b: None,
+            sort_array_values: false,
+            canonicalize: false,
+            trim_strings: false,
+            side_by_side: false,
+            array_match_strategy: ArrayMatchStrategy::Set,
+            ascii: false,
+            string_similarity_threshold: None,
+            flatten: false,
+            flatten_arrays: false,
+            value_comparators: vec![],
+            common: None,
+            append: false,
+            show_empty_tables: false,
+            report_title: None,
+            report_description: None,
+            ignore_array_tail: false,
+            redact_patterns: Vec::new(),
+            baseline_diffs: None,
+            positions: false,
+            empty_equivalence: false,
+            ignore_yaml_tags: false,
+            count: false,
+            category_outputs: vec![],
+            number_format: None,
+            ignore_chomping: false,
+            top: None,
+            jsonc: false,
+            set_array_diffs: false,
+            coerce_numeric_strings: false,
+            fail_fast: false,
+            primary_key: None,
+            identical_message: None,
+            sample: None,
+            sample_seed: 0,
+            type_naming: TypeNaming::Default,
+            hash: false,
+            matrices: false,
+            collapse_ranges: false,
+            compare_keys_only: false,
             browser_view: None,
             printer_friendly: false,
             no_browser_show: false,
@@ -234,110 +1026,1273 @@ impl ConfigBuilder {
         self
     }
 
-    pub fn browser_view(mut self, browser_view: Option<String>) -> ConfigBuilder {
-        self.browser_view = browser_view;
+    pub fn ordered_array_paths(mut self, ordered_array_paths: Vec<String>) -> ConfigBuilder {
+        self.ordered_array_paths = ordered_array_paths;
         self
     }
 
-    pub fn printer_friendly(mut self, printer_friendly: bool) -> ConfigBuilder {
-        self.printer_friendly = printer_friendly;
+    pub fn severity_rules(mut self, severity_rules: Vec<SeverityRule>) -> ConfigBuilder {
+        self.severity_rules = severity_rules;
         self
     }
 
-    pub fn no_browser_show(mut self, no_browser_show: bool) -> ConfigBuilder {
-        self.no_browser_show = no_browser_show;
+    pub fn min_severity(mut self, min_severity: Severity) -> ConfigBuilder {
+        self.min_severity = min_severity;
         self
     }
 
-    pub fn build(self) -> Config {
-        Config {
-            check_for_key_diffs: self.check_for_key_diffs,
-            check_for_type_diffs: self.check_for_type_diffs,
-            check_for_value_diffs: self.check_for_value_diffs,
-            check_for_array_diffs: self.check_for_array_diffs,
-            render_key_diffs: self.render_key_diffs,
-            render_type_diffs: self.render_type_diffs,
-            render_value_diffs: self.render_value_diffs,
-            render_array_diffs: self.render_array_diffs,
-            read_from_file: self.read_from_file,
-            write_to_file: self.write_to_file,
-            file_a: self.file_a,
-            file_b: self.file_b,
-            array_same_order: self.array_same_order,
-            browser_view: self.browser_view,
-            printer_friendly: self.printer_friendly,
-            no_browser_show: self.no_browser_show,
-        }
+    pub fn fail_on_severity(mut self, fail_on_severity: Option<Severity>) -> ConfigBuilder {
+        self.fail_on_severity = fail_on_severity;
+        self
     }
-}
 
-/// Contextual data for the current run
-#[derive(Clone)]
-pub struct WorkingContext {
-    pub lib_working_context: LibWorkingContext,
-    pub config: Config,
-}
+    pub fn max_size(mut self, max_size: Option<u64>) -> ConfigBuilder {
+        self.max_size = max_size;
+        self
+    }
 
-impl WorkingContext {
-    pub fn new(lib_working_context: LibWorkingContext, config: Config) -> WorkingContext {
-        WorkingContext {
-            lib_working_context,
-            config,
-        }
+    pub fn max_diffs(mut self, max_diffs: Option<usize>) -> ConfigBuilder {
+        self.max_diffs = max_diffs;
+        self
     }
 
-    /// Get the file names of the two files being compared
-    pub fn get_file_names(&self) -> (&str, &str) {
-        let file_name_a = self.lib_working_context.file_a.name.as_str();
-        let file_name_b = self.lib_working_context.file_b.name.as_str();
-        (file_name_a, file_name_b)
+    pub fn array_key(mut self, array_key: Option<String>) -> ConfigBuilder {
+        self.array_key = array_key;
+        self
     }
-}
 
-/// How a WorkingContext gets stored on disk
-#[derive(Serialize, Deserialize)]
-pub struct SavedContext {
-    pub key_diff: Vec<KeyDiff>,
-    pub type_diff: Vec<TypeDiff>,
-    pub value_diff: Vec<ValueDiff>,
-    pub array_diff: Vec<ArrayDiff>,
-    pub config: SavedConfig,
-}
+    pub fn parse_dates(mut self, parse_dates: bool) -> ConfigBuilder {
+        self.parse_dates = parse_dates;
+        self
+    }
 
-impl SavedContext {
-    pub fn new(
-        key_diff: Vec<KeyDiff>,
-        type_diff: Vec<TypeDiff>,
-        value_diff: Vec<ValueDiff>,
-        array_diff: Vec<ArrayDiff>,
-        config: SavedConfig,
-    ) -> SavedContext {
-        SavedContext {
-            key_diff,
-            type_diff,
-            value_diff,
-            array_diff,
-            config,
-        }
+    pub fn emit_header(mut self, emit_header: bool) -> ConfigBuilder {
+        self.emit_header = emit_header;
+        self
     }
-}
 
-/// Custom Error type
-#[derive(Debug)]
-pub enum DtfError {
-    IoError(std::io::Error),
-    DiffError(String),
-    GeneralError(Box<DtfError>),
-}
+    pub fn intersection(mut self, intersection: bool) -> ConfigBuilder {
+        self.intersection = intersection;
+        self
+    }
 
-impl fmt::Display for DtfError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DtfError::IoError(err) => write!(f, "IO error: {}", err),
-            DtfError::DiffError(msg) => write!(f, "Diff error: {}", msg),
-            DtfError::GeneralError(err) => write!(f, "General error happened {}", err),
-        }
+    pub fn defaults(mut self, defaults: Option<String>) -> ConfigBuilder {
+        self.defaults = defaults;
+        self
     }
-}
 
-impl Error for DtfError {}
+    pub fn multiline_string_diff(mut self, multiline_string_diff: bool) -> ConfigBuilder {
+        self.multiline_string_diff = multiline_string_diff;
+        self
+    }
+
+    pub fn baseline(mut self, baseline: Option<Baseline>) -> ConfigBuilder {
+        self.baseline = baseline;
+        self
+    }
+
+    pub fn output_format(mut self, output_format: OutputFormat) -> ConfigBuilder {
+        self.output_format = output_format;
+        self
+    }
+
+    pub fn value_truncate_len(mut self, value_truncate_len: Option<usize>) -> ConfigBuilder {
+        self.value_truncate_len = value_truncate_len;
+        self
+    }
+
+    pub fn max_array_diffs_per_key(
+        mut self,
+        max_array_diffs_per_key: Option<usize>,
+    ) -> ConfigBuilder {
+        self.max_array_diffs_per_key = max_array_diffs_per_key;
+        self
+    }
+
+    pub fn explain(mut self, explain: bool) -> ConfigBuilder {
+        self.explain = explain;
+        self
+    }
+
+    pub fn check_key_order(mut self, check_key_order: bool) -> ConfigBuilder {
+        self.check_key_order = check_key_order;
+        self
+    }
+
+    pub fn schema(mut self, schema: bool) -> ConfigBuilder {
+        self.schema = schema;
+        self
+    }
+
+    pub fn show_types_in_values(mut self, show_types_in_values: bool) -> ConfigBuilder {
+        self.show_types_in_values = show_types_in_values;
+        self
+    }
+
+    pub fn only_paths(mut self, only_paths: Vec<String>) -> ConfigBuilder {
+        self.only_paths = only_paths;
+        self
+    }
+
+    pub fn ignore_paths(mut self, ignore_paths: Vec<String>) -> ConfigBuilder {
+        self.ignore_paths = ignore_paths;
+        self
+    }
+
+    pub fn badge(mut self, badge: Option<String>) -> ConfigBuilder {
+        self.badge = badge;
+        self
+    }
+
+    pub fn max_value_len(mut self, max_value_len: Option<usize>) -> ConfigBuilder {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    pub fn embed_data(mut self, embed_data: bool) -> ConfigBuilder {
+        self.embed_data = embed_data;
+        self
+    }
+
+    pub fn epsilon(mut self, epsilon: Option<f64>) -> ConfigBuilder {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn path_epsilons(mut self, path_epsilons: HashMap<String, f64>) -> ConfigBuilder {
+        self.path_epsilons = path_epsilons;
+        self
+    }
+
+    pub fn tag_paths(mut self, tag_paths: bool) -> ConfigBuilder {
+        self.tag_paths = tag_paths;
+        self
+    }
+
+    pub fn normalize_unicode(mut self, normalize_unicode: bool) -> ConfigBuilder {
+        self.normalize_unicode = normalize_unicode;
+        self
+    }
+
+    pub fn unwrap_a(mut self, unwrap_a: Option<String>) -> ConfigBuilder {
+        self.unwrap_a = unwrap_a;
+        self
+    }
+
+    pub fn unwrap_b(mut self, unwrap_b: Option<String>) -> ConfigBuilder {
+        self.unwrap_b = unwrap_b;
+        self
+    }
+
+    pub fn sort_array_values(mut self, sort_array_values: bool) -> ConfigBuilder {
+        self.sort_array_values = sort_array_values;
+        self
+    }
+
+    pub fn canonicalize(mut self, canonicalize: bool) -> ConfigBuilder {
+        self.canonicalize = canonicalize;
+        self
+    }
+
+    pub fn trim_strings(mut self, trim_strings: bool) -> ConfigBuilder {
+        self.trim_strings = trim_strings;
+        self
+    }
+
+    pub fn side_by_side(mut self, side_by_side: bool) -> ConfigBuilder {
+        self.side_by_side = side_by_side;
+        self
+    }
+
+    pub fn array_match_strategy(
+        mut self,
+        array_match_strategy: ArrayMatchStrategy,
+    ) -> ConfigBuilder {
+        self.array_match_strategy = array_match_strategy;
+        self
+    }
+
+    pub fn ascii(mut self, ascii: bool) -> ConfigBuilder {
+        self.ascii = ascii;
+        self
+    }
+
+    pub fn string_similarity_threshold(
+        mut self,
+        string_similarity_threshold: Option<f64>,
+    ) -> ConfigBuilder {
+        self.string_similarity_threshold = string_similarity_threshold;
+        self
+    }
+
+    pub fn flatten(mut self, flatten: bool) -> ConfigBuilder {
+        self.flatten = flatten;
+        self
+    }
+
+    pub fn flatten_arrays(mut self, flatten_arrays: bool) -> ConfigBuilder {
+        self.flatten_arrays = flatten_arrays;
+        self
+    }
+
+    pub fn value_comparators(mut self, value_comparators: Vec<PathComparator>) -> ConfigBuilder {
+        self.value_comparators = value_comparators;
+        self
+    }
+
+    pub fn common(mut self, common: Option<String>) -> ConfigBuilder {
+        self.common = common;
+        self
+    }
+
+    pub fn append(mut self, append: bool) -> ConfigBuilder {
+        self.append = append;
+        self
+    }
+
+    pub fn show_empty_tables(mut self, show_empty_tables: bool) -> ConfigBuilder {
+        self.show_empty_tables = show_empty_tables;
+        self
+    }
+
+    pub fn report_title(mut self, report_title: Option<String>) -> ConfigBuilder {
+        self.report_title = report_title;
+        self
+    }
+
+    pub fn report_description(mut self, report_description: Option<String>) -> ConfigBuilder {
+        self.report_description = report_description;
+        self
+    }
+
+    pub fn ignore_array_tail(mut self, ignore_array_tail: bool) -> ConfigBuilder {
+        self.ignore_array_tail = ignore_array_tail;
+        self
+    }
+
+    pub fn redact_patterns(mut self, redact_patterns: Vec<Regex>) -> ConfigBuilder {
+        self.redact_patterns = redact_patterns;
+        self
+    }
+
+    pub fn baseline_diffs(mut self, baseline_diffs: Option<String>) -> ConfigBuilder {
+        self.baseline_diffs = baseline_diffs;
+        self
+    }
+
+    pub fn positions(mut self, positions: bool) -> ConfigBuilder {
+        self.positions = positions;
+        self
+    }
+
+    pub fn empty_equivalence(mut self, empty_equivalence: bool) -> ConfigBuilder {
+        self.empty_equivalence = empty_equivalence;
+        self
+    }
+
+    pub fn ignore_yaml_tags(mut self, ignore_yaml_tags: bool) -> ConfigBuilder {
+        self.ignore_yaml_tags = ignore_yaml_tags;
+        self
+    }
+
+    pub fn count(mut self, count: bool) -> ConfigBuilder {
+        self.count = count;
+        self
+    }
+
+    pub fn category_outputs(mut self, category_outputs: Vec<CategoryOutput>) -> ConfigBuilder {
+        self.category_outputs = category_outputs;
+        self
+    }
+
+    pub fn number_format(mut self, number_format: Option<NumberFormat>) -> ConfigBuilder {
+        self.number_format = number_format;
+        self
+    }
+
+    pub fn ignore_chomping(mut self, ignore_chomping: bool) -> ConfigBuilder {
+        self.ignore_chomping = ignore_chomping;
+        self
+    }
+
+    pub fn top(mut self, top: Option<usize>) -> ConfigBuilder {
+        self.top = top;
+        self
+    }
+
+    pub fn jsonc(mut self, jsonc: bool) -> ConfigBuilder {
+        self.jsonc = jsonc;
+        self
+    }
+
+    pub fn set_array_diffs(mut self, set_array_diffs: bool) -> ConfigBuilder {
+        self.set_array_diffs = set_array_diffs;
+        self
+    }
+
+    pub fn coerce_numeric_strings(mut self, coerce_numeric_strings: bool) -> ConfigBuilder {
+        self.coerce_numeric_strings = coerce_numeric_strings;
+        self
+    }
+
+    pub fn fail_fast(mut self, fail_fast: bool) -> ConfigBuilder {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn primary_key(mut self, primary_key: Option<String>) -> ConfigBuilder {
+        self.primary_key = primary_key;
+        self
+    }
+
+    pub fn identical_message(mut self, identical_message: Option<String>) -> ConfigBuilder {
+        self.identical_message = identical_message;
+        self
+    }
+
+    pub fn sample(mut self, sample: Option<usize>) -> ConfigBuilder {
+        self.sample = sample;
+        self
+    }
+
+    pub fn sample_seed(mut self, sample_seed: u64) -> ConfigBuilder {
+        self.sample_seed = sample_seed;
+        self
+    }
+
+    pub fn type_naming(mut self, type_naming: TypeNaming) -> ConfigBuilder {
+        self.type_naming = type_naming;
+        self
+    }
+
+    pub fn hash(mut self, hash: bool) -> ConfigBuilder {
+        self.hash = hash;
+        self
+    }
+
+    pub fn matrices(mut self, matrices: bool) -> ConfigBuilder {
+        self.matrices = matrices;
+        self
+    }
+
+    pub fn collapse_ranges(mut self, collapse_ranges: bool) -> ConfigBuilder {
+        self.collapse_ranges = collapse_ranges;
+        self
+    }
+
+    pub fn compare_keys_only(mut self, compare_keys_only: bool) -> ConfigBuilder {
+        self.compare_keys_only = compare_keys_only;
+        self
+    }
+
+    pub fn browser_view(mut self, browser_view: Option<String>) -> ConfigBuilder {
+        self.browser_view = browser_view;
+        self
+    }
+
+    pub fn printer_friendly(mut self, printer_friendly: bool) -> ConfigBuilder {
+        self.printer_friendly = printer_friendly;
+        self
+    }
+
+    pub fn no_browser_show(mut self, no_browser_show: bool) -> ConfigBuilder {
+        self.no_browser_show = no_browser_show;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        Config {
+            check_for_key_diffs: self.check_for_key_diffs,
+            check_for_type_diffs: self.check_for_type_diffs,
+            check_for_value_diffs: self.check_for_value_diffs,
+            check_for_array_diffs: self.check_for_array_diffs,
+            render_key_diffs: self.render_key_diffs,
+            render_type_diffs: self.render_type_diffs,
+            render_value_diffs: self.render_value_diffs,
+            render_array_diffs: self.render_array_diffs,
+            read_from_file: self.read_from_file,
+            write_to_file: self.write_to_file,
+            file_a: self.file_a,
+            file_b: self.file_b,
+            array_same_order: self.array_same_order,
+            ordered_array_paths: self.ordered_array_paths,
+            severity_rules: self.severity_rules,
+            min_severity: self.min_severity,
+            fail_on_severity: self.fail_on_severity,
+            max_size: self.max_size,
+            max_diffs: self.max_diffs,
+            array_key: self.array_key,
+            parse_dates: self.parse_dates,
+            emit_header: self.emit_header,
+            intersection: self.intersection,
+            defaults: self.defaults,
+            multiline_string_diff: self.multiline_string_diff,
+            baseline: self.baseline,
+            output_format: self.output_format,
+            value_truncate_len: self.value_truncate_len,
+            max_array_diffs_per_key: self.max_array_diffs_per_key,
+            explain: self.explain,
+            check_key_order: self.check_key_order,
+            schema: self.schema,
+            show_types_in_values: self.show_types_in_values,
+            only_paths: self.only_paths,
+            ignore_paths: self.ignore_paths,
+            badge: self.badge,
+            max_value_len: self.max_value_len,
+            embed_data: self.embed_data,
+            epsilon: self.epsilon,
+            path_epsilons: self
+                .path_epsilons
+                .iter()
+                .map(|(path, epsilon)| (PathMatcher::new(path), *epsilon))
+                .collect(),
+            tag_paths: self.tag_paths,
+            normalize_unicode: self.normalize_unicode,
+            unwrap_a: self.unwrap_a,
+            unwrap_b: self.unwrap_b,
+            sort_array_values: self.sort_array_values,
+            canonicalize: self.canonicalize,
+            trim_strings: self.trim_strings,
+            side_by_side: self.side_by_side,
+            array_match_strategy: self.array_match_strategy,
+            ascii: self.ascii,
+            string_similarity_threshold: self.string_similarity_threshold,
+            flatten: self.flatten,
+            flatten_arrays: self.flatten_arrays,
+            value_comparators: self.value_comparators,
+            common: self.common,
+            append: self.append,
+            show_empty_tables: self.show_empty_tables,
+            report_title: self.report_title,
+            report_description: self.report_description,
+            ignore_array_tail: self.ignore_array_tail,
+            redact_patterns: self.redact_patterns,
+            baseline_diffs: self.baseline_diffs,
+            positions: self.positions,
+            empty_equivalence: self.empty_equivalence,
+            ignore_yaml_tags: self.ignore_yaml_tags,
+            count: self.count,
+            category_outputs: self.category_outputs,
+            number_format: self.number_format,
+            ignore_chomping: self.ignore_chomping,
+            top: self.top,
+            jsonc: self.jsonc,
+            set_array_diffs: self.set_array_diffs,
+            coerce_numeric_strings: self.coerce_numeric_strings,
+            fail_fast: self.fail_fast,
+            primary_key: self.primary_key,
+            identical_message: self.identical_message,
+            sample: self.sample,
+            sample_seed: self.sample_seed,
+            type_naming: self.type_naming,
+            hash: self.hash,
+            matrices: self.matrices,
+            collapse_ranges: self.collapse_ranges,
+            compare_keys_only: self.compare_keys_only,
+            browser_view: self.browser_view,
+            printer_friendly: self.printer_friendly,
+            no_browser_show: self.no_browser_show,
+        }
+    }
+}
+
+/// Contextual data for the current run
+#[derive(Clone)]
+pub struct WorkingContext {
+    pub lib_working_context: LibWorkingContext,
+    pub config: Config,
+    /// The types of the differing values, only looked up if they are to be shown
+    pub value_types: ValueTypes,
+    /// Where the items of the array differences are in the files, only looked up for new checks
+    pub array_indexes: ArrayIndexes,
+    /// Where the keys are in the files, only looked up if they are to be shown
+    pub key_positions: KeyPositions,
+    /// The arrays of which only a sample of the items was compared
+    pub sampled_arrays: Vec<SampledArray>,
+    /// The hashes of the files, only computed if they are to be shown
+    pub file_hashes: Option<FileHashes>,
+    /// Which file has the keys of the key differences, only looked up for new checks
+    pub key_sides: KeySides,
+    /// The value differences of multi-line strings, which are shown as the lines that differ
+    pub line_diff_keys: LineDiffKeys,
+    /// The original keys and array items of the redacted differences.
+    /// The data above stays by the original keys, so it's looked up through these.
+    pub redacted: RedactedTexts,
+}
+
+impl WorkingContext {
+    pub fn new(lib_working_context: LibWorkingContext, config: Config) -> WorkingContext {
+        WorkingContext {
+            lib_working_context,
+            config,
+            value_types: ValueTypes::new(),
+            array_indexes: ArrayIndexes::new(),
+            key_positions: KeyPositions::default(),
+            sampled_arrays: vec![],
+            file_hashes: None,
+            key_sides: KeySides::new(),
+            line_diff_keys: LineDiffKeys::new(),
+            redacted: RedactedTexts::default(),
+        }
+    }
+
+    /// Get the file names of the two files being compared
+    pub fn get_file_names(&self) -> (&str, &str) {
+        let file_name_a = self.lib_working_context.file_a.name.as_str();
+        let file_name_b = self.lib_working_context.file_b.name.as_str();
+        (file_name_a, file_name_b)
+    }
+
+    /// Get the file having the key of the difference, by the file names if it wasn't looked up
+    pub fn key_side(&self, key_diff: &KeyDiff) -> Side {
+        self.redacted
+            .original_key(&key_diff.key)
+            .and_then(|key| self.key_sides.get(key))
+            .copied()
+            .unwrap_or_else(|| Side::of(key_diff, self.get_file_names()))
+    }
+
+    /// Get the key differences with the files having their keys, to be written out
+    pub fn sided_key_diffs(&self, key_diffs: &[KeyDiff]) -> Vec<SidedKeyDiff> {
+        key_diffs
+            .iter()
+            .map(|key_diff| SidedKeyDiff {
+                key_diff: key_diff.clone(),
+                side: Some(self.key_side(key_diff)),
+            })
+            .collect()
+    }
+
+    /// Check if the values of the value difference are the lines that differ in multi-line strings
+    pub fn is_line_diff(&self, key: &str) -> bool {
+        self.redacted
+            .original_key(key)
+            .is_some_and(|key| self.line_diff_keys.contains(key))
+    }
+
+    /// Get the types of the differing values in the two files, if they were looked up
+    pub fn value_types_of(&self, key: &str) -> Option<&(String, String)> {
+        self.redacted
+            .original_key(key)
+            .and_then(|key| self.value_types.get(key))
+    }
+
+    /// Get the index of the next occurrence of an array item, in the order the items are shown
+    pub fn next_array_index(
+        &self,
+        cursor: &mut IndexCursor,
+        key: &str,
+        in_a: bool,
+        value: &str,
+    ) -> Option<usize> {
+        let key = self.redacted.original_key(key)?;
+        let value = self.redacted.original_item(value)?;
+        cursor.next(&self.array_indexes, key, in_a, value)
+    }
+
+    /// Adds the positions of the key of a serialized difference, if they are to be shown
+    pub fn annotate_positions(&self, diff: &mut serde_json::Value) {
+        if !self.config.positions {
+            return;
+        }
+        let key = diff.get("key").and_then(serde_json::Value::as_str);
+        if let Some(key) = key.and_then(|key| self.redacted.original_key(key)) {
+            let key = key.to_owned();
+            self.key_positions.annotate(diff, &key);
+        }
+    }
+
+    /// Check if some arrays have to be treated as ordered, while the rest are not
+    pub fn has_ordered_array_paths(&self) -> bool {
+        !self.lib_working_context.config.array_same_order
+            && !self.config.ordered_array_paths.is_empty()
+    }
+
+    /// Get the library context the Array differences are checked with.
+    /// With `set_array_diffs` the arrays are compared as sets, even if they are ordered otherwise.
+    pub fn array_diff_lib_working_context<'a>(
+        &'a self,
+        lib_working_context: &'a LibWorkingContext,
+    ) -> Cow<'a, LibWorkingContext> {
+        if self.config.set_array_diffs && lib_working_context.config.array_same_order {
+            let mut unordered = lib_working_context.clone();
+            unordered.config.array_same_order = false;
+            Cow::Owned(unordered)
+        } else {
+            Cow::Borrowed(lib_working_context)
+        }
+    }
+
+    /// Get a copy of the library context, that treats every array as ordered
+    pub fn ordered_lib_working_context(&self) -> LibWorkingContext {
+        let mut lib_working_context = self.lib_working_context.clone();
+        lib_working_context.config.array_same_order = true;
+        lib_working_context
+    }
+}
+
+/// How a WorkingContext gets stored on disk
+#[derive(Serialize, Deserialize)]
+pub struct SavedContext {
+    pub key_diff: Vec<SidedKeyDiff>,
+    pub type_diff: Vec<TypeDiff>,
+    pub value_diff: Vec<ValueDiff>,
+    pub array_diff: Vec<ArrayDiff>,
+    pub config: SavedConfig,
+}
+
+impl SavedContext {
+    pub fn new(
+        key_diff: Vec<SidedKeyDiff>,
+        type_diff: Vec<TypeDiff>,
+        value_diff: Vec<ValueDiff>,
+        array_diff: Vec<ArrayDiff>,
+        config: SavedConfig,
+    ) -> SavedContext {
+        SavedContext {
+            key_diff,
+            type_diff,
+            value_diff,
+            array_diff,
+            config,
+        }
+    }
+}
+
+/// The differences without the configuration of the check, for consumers only interested in the data
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffData {
+    pub key_diffs: Vec<SidedKeyDiff>,
+    pub type_diffs: Vec<TypeDiff>,
+    pub value_diffs: Vec<ValueDiff>,
+    pub array_diffs: Vec<ArrayDiff>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<DiffSummary>,
+}
+
+impl DiffData {
+    /// The differences with the sides of the key differences and a summary of the comparison,
+    /// as written by `--format json` and into the HTML report
+    pub fn summarized(diffs: &DiffCollection, context: &WorkingContext) -> DiffData {
+        DiffData {
+            key_diffs: context.sided_key_diffs(diffs.0.as_deref().unwrap_or_default()),
+            summary: Some(DiffSummary::new(diffs, context)),
+            ..DiffData::from(diffs.clone())
+        }
+    }
+}
+
+impl From<DiffCollection> for DiffData {
+    fn from(diffs: DiffCollection) -> DiffData {
+        let (key_diff, type_diff, value_diff, array_diff) = diffs;
+        DiffData {
+            key_diffs: key_diff
+                .unwrap_or_default()
+                .into_iter()
+                .map(SidedKeyDiff::from)
+                .collect(),
+            type_diffs: type_diff.unwrap_or_default(),
+            value_diffs: value_diff.unwrap_or_default(),
+            array_diffs: array_diff.unwrap_or_default(),
+            summary: None,
+        }
+    }
+}
+
+/// An overview of a comparison, so dashboards don't have to count the differences themselves.
+/// The counts are named like in the summary line of `--format ndjson`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSummary {
+    #[serde(flatten)]
+    pub stats: DiffStats,
+    pub total: usize,
+    /// Whether no differences were found
+    #[serde(default)]
+    pub identical: bool,
+    pub file_a: String,
+    pub file_b: String,
+    /// When the differences were rendered, in RFC 3339
+    pub timestamp: String,
+    /// The configuration of the check, like the one saved by `-w`
+    pub config: SavedConfig,
+    /// The arrays of which only a sample of the items was compared
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sampled_arrays: Vec<SampledArray>,
+    /// The SHA-256 hashes of the files, if they were computed with `--hash`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hashes: Option<FileHashes>,
+}
+
+impl DiffSummary {
+    pub fn new(diffs: &DiffCollection, context: &WorkingContext) -> DiffSummary {
+        DiffSummary::with_stats(DiffStats::from_diffs(diffs), context)
+    }
+
+    /// The summary of the differences already counted, like the ones `--format ndjson` wrote
+    pub fn with_stats(stats: DiffStats, context: &WorkingContext) -> DiffSummary {
+        let (file_a, file_b) = context.get_file_names();
+        let config = &context.config;
+        DiffSummary {
+            stats,
+            total: stats.total(),
+            identical: stats.total() == 0,
+            file_a: file_a.to_owned(),
+            file_b: file_b.to_owned(),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            config: SavedConfig::new(
+                config.check_for_key_diffs,
+                config.check_for_type_diffs,
+                config.check_for_value_diffs,
+                config.check_for_array_diffs,
+                file_a.to_owned(),
+                file_b.to_owned(),
+                context.lib_working_context.config.array_same_order,
+            )
+            .with_ordered_array_paths(config.ordered_array_paths.clone()),
+            sampled_arrays: context.sampled_arrays.clone(),
+            hashes: context.file_hashes.clone(),
+        }
+    }
+}
+
+/// The number of differences found in each category
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub key_diffs: usize,
+    pub type_diffs: usize,
+    pub value_diffs: usize,
+    pub array_diffs: usize,
+}
+
+impl DiffStats {
+    pub fn from_diffs(diffs: &DiffCollection) -> DiffStats {
+        DiffStats {
+            key_diffs: diffs.0.as_ref().map_or(0, Vec::len),
+            type_diffs: diffs.1.as_ref().map_or(0, Vec::len),
+            value_diffs: diffs.2.as_ref().map_or(0, Vec::len),
+            array_diffs: diffs.3.as_ref().map_or(0, Vec::len),
+        }
+    }
+
+    /// The number of differences in all the categories
+    pub fn total(&self) -> usize {
+        self.key_diffs + self.type_diffs + self.value_diffs + self.array_diffs
+    }
+}
+
+/// The differences of one category
+pub enum CategoryDiffs {
+    Key(Vec<KeyDiff>),
+    Type(Vec<TypeDiff>),
+    Value(Vec<ValueDiff>),
+    Array(Vec<ArrayDiff>),
+}
+
+impl CategoryDiffs {
+    /// The category the differences belong to
+    pub fn category(&self) -> DiffCategory {
+        match self {
+            CategoryDiffs::Key(_) => DiffCategory::Key,
+            CategoryDiffs::Type(_) => DiffCategory::Type,
+            CategoryDiffs::Value(_) => DiffCategory::Value,
+            CategoryDiffs::Array(_) => DiffCategory::Array,
+        }
+    }
+
+    /// The number of differences
+    pub fn len(&self) -> usize {
+        match self {
+            CategoryDiffs::Key(diffs) => diffs.len(),
+            CategoryDiffs::Type(diffs) => diffs.len(),
+            CategoryDiffs::Value(diffs) => diffs.len(),
+            CategoryDiffs::Array(diffs) => diffs.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The result of a check keyed by the categories checked for.
+/// Unlike `DiffCollection`, the categories can't be mixed up by their position.
+#[derive(Default)]
+pub struct DiffResults {
+    categories: BTreeMap<DiffCategory, CategoryDiffs>,
+}
+
+impl DiffResults {
+    pub fn new() -> DiffResults {
+        DiffResults::default()
+    }
+
+    /// Stores the differences of a category, replacing the ones it already had
+    pub fn insert(&mut self, diffs: CategoryDiffs) {
+        self.categories.insert(diffs.category(), diffs);
+    }
+
+    /// Takes out the differences of a category
+    pub fn remove(&mut self, category: DiffCategory) -> Option<CategoryDiffs> {
+        self.categories.remove(&category)
+    }
+
+    /// The differences of a category, if it was checked for
+    pub fn get(&self, category: DiffCategory) -> Option<&CategoryDiffs> {
+        self.categories.get(&category)
+    }
+
+    /// Check if the category was checked for
+    pub fn is_checked(&self, category: DiffCategory) -> bool {
+        self.categories.contains_key(&category)
+    }
+
+    /// The categories checked for, in the order of `DiffCategory`
+    pub fn categories(&self) -> impl Iterator<Item = DiffCategory> + '_ {
+        self.categories.keys().copied()
+    }
+
+    /// The number of differences in a category, 0 if it wasn't checked for
+    pub fn count(&self, category: DiffCategory) -> usize {
+        self.get(category).map_or(0, CategoryDiffs::len)
+    }
+
+    /// The number of differences in all the categories
+    pub fn total(&self) -> usize {
+        self.categories.values().map(CategoryDiffs::len).sum()
+    }
+
+    pub fn key_diffs(&self) -> Option<&[KeyDiff]> {
+        match self.get(DiffCategory::Key)? {
+            CategoryDiffs::Key(diffs) => Some(diffs),
+            _ => None,
+        }
+    }
+
+    pub fn type_diffs(&self) -> Option<&[TypeDiff]> {
+        match self.get(DiffCategory::Type)? {
+            CategoryDiffs::Type(diffs) => Some(diffs),
+            _ => None,
+        }
+    }
+
+    pub fn value_diffs(&self) -> Option<&[ValueDiff]> {
+        match self.get(DiffCategory::Value)? {
+            CategoryDiffs::Value(diffs) => Some(diffs),
+            _ => None,
+        }
+    }
+
+    pub fn array_diffs(&self) -> Option<&[ArrayDiff]> {
+        match self.get(DiffCategory::Array)? {
+            CategoryDiffs::Array(diffs) => Some(diffs),
+            _ => None,
+        }
+    }
+}
+
+impl From<DiffCollection> for DiffResults {
+    fn from(diffs: DiffCollection) -> DiffResults {
+        let (key_diffs, type_diffs, value_diffs, array_diffs) = diffs;
+        let mut results = DiffResults::new();
+        let categories = [
+            key_diffs.map(CategoryDiffs::Key),
+            type_diffs.map(CategoryDiffs::Type),
+            value_diffs.map(CategoryDiffs::Value),
+            array_diffs.map(CategoryDiffs::Array),
+        ];
+        for category_diffs in categories.into_iter().flatten() {
+            results.insert(category_diffs);
+        }
+        results
+    }
+}
+
+impl From<DiffResults> for DiffCollection {
+    fn from(results: DiffResults) -> DiffCollection {
+        let mut diffs: DiffCollection = (None, None, None, None);
+        for category_diffs in results.categories.into_values() {
+            match category_diffs {
+                CategoryDiffs::Key(key_diffs) => diffs.0 = Some(key_diffs),
+                CategoryDiffs::Type(type_diffs) => diffs.1 = Some(type_diffs),
+                CategoryDiffs::Value(value_diffs) => diffs.2 = Some(value_diffs),
+                CategoryDiffs::Array(array_diffs) => diffs.3 = Some(array_diffs),
+            }
+        }
+        diffs
+    }
+}
+
+/// How a finished comparison ends, deciding the exit code of the process.
+/// Differences failing the run aren't errors: the results are already written when they are found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// Differences were found that fail the run, e.g. with `--fail-fast`, described by the message
+    Failed(String),
+}
+
+/// Custom Error type
+#[derive(Debug)]
+pub enum DtfError {
+    IoError(std::io::Error),
+    FileNotFound(String),
+    ParseError {
+        path: String,
+        source: Box<dyn Error>,
+    },
+    MissingArgument(String),
+    HttpError(String),
+    DiffError(String),
+    LimitError(String),
+    GeneralError(Box<DtfError>),
+}
+
+impl fmt::Display for DtfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DtfError::IoError(err) => write!(f, "IO error: {}", err),
+            DtfError::FileNotFound(path) => write!(f, "File not found: {}", path),
+            DtfError::ParseError { path, source } => {
+                write!(f, "Could not parse {}: {}", path, source)
+            }
+            DtfError::MissingArgument(msg) => write!(f, "Missing argument: {}", msg),
+            DtfError::HttpError(msg) => write!(f, "HTTP error: {}", msg),
+            DtfError::DiffError(msg) => write!(f, "Diff error: {}", msg),
+            DtfError::LimitError(msg) => write!(f, "Limit exceeded: {}", msg),
+            DtfError::GeneralError(err) => write!(f, "General error happened {}", err),
+        }
+    }
+}
+
+impl DtfError {
+    /// A stable name of the kind of the error, for programs reading it
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DtfError::IoError(_) => "io",
+            DtfError::FileNotFound(_) => "fileNotFound",
+            DtfError::ParseError { .. } => "parse",
+            DtfError::MissingArgument(_) => "missingArgument",
+            DtfError::HttpError(_) => "http",
+            DtfError::DiffError(_) => "diff",
+            DtfError::LimitError(_) => "limit",
+            DtfError::GeneralError(err) => err.kind(),
+        }
+    }
+
+    /// The error as a JSON object, written instead of the message in the machine-readable formats
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "kind": self.kind(),
+                "message": self.to_string(),
+            }
+        })
+    }
+}
+
+impl Error for DtfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DtfError::IoError(err) => Some(err),
+            DtfError::ParseError { source, .. } => Some(source.as_ref()),
+            DtfError::GeneralError(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_by_desc() {
+        let array_diff = |descriptor: ArrayDiffDesc, value: &str| ArrayDiff {
+            descriptor,
+            key: "tags".to_owned(),
+            value: value.to_owned(),
+        };
+        let array_diffs = vec![
+            array_diff(ArrayDiffDesc::AHas, "a"),
+            array_diff(ArrayDiffDesc::BMisses, "a"),
+            array_diff(ArrayDiffDesc::BHas, "b"),
+            array_diff(ArrayDiffDesc::AMisses, "b"),
+            array_diff(ArrayDiffDesc::BHas, "c"),
+        ];
+
+        assert_eq!(
+            filter_by_desc(&array_diffs, ArrayDiffDesc::BHas),
+            vec![&array_diffs[2], &array_diffs[4]]
+        );
+        assert_eq!(
+            filter_by_desc(&array_diffs, ArrayDiffDesc::AHas),
+            vec![&array_diffs[0]]
+        );
+        assert!(filter_by_desc(&array_diffs[..1], ArrayDiffDesc::AMisses).is_empty());
+        assert_eq!(
+            describe_array_diff_desc(&array_diffs[2].descriptor),
+            "Only B has"
+        );
+    }
+
+    #[test]
+    fn test_column_width() {
+        assert_eq!(column_width(Some(130)), 40);
+        assert_eq!(column_width(Some(100)), 30);
+        assert_eq!(column_width(Some(20)), MIN_COLUMN_WIDTH);
+        assert_eq!(column_width(None), DEFAULT_COLUMN_WIDTH);
+    }
+
+    #[test]
+    fn test_diff_results_from_collection() {
+        let diffs: DiffCollection = (
+            Some(vec![KeyDiff {
+                key: "user.email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "tags".to_owned(),
+                value: "\"admin\"".to_owned(),
+            }]),
+        );
+
+        let results = DiffResults::from(diffs);
+
+        assert_eq!(
+            results.categories().collect::<Vec<_>>(),
+            vec![DiffCategory::Key, DiffCategory::Value, DiffCategory::Array]
+        );
+        assert_eq!(results.is_checked(DiffCategory::Type), false);
+        assert!(results.type_diffs().is_none());
+        assert_eq!(results.key_diffs().unwrap()[0].key, "user.email");
+        assert_eq!(results.value_diffs().map(<[ValueDiff]>::len), Some(0));
+        assert_eq!(results.array_diffs().unwrap()[0].key, "tags");
+        assert_eq!(results.count(DiffCategory::Array), 1);
+        assert_eq!(results.count(DiffCategory::Type), 0);
+        assert_eq!(results.total(), 2);
+    }
+
+    #[test]
+    fn test_diff_results_into_collection() {
+        let mut results = DiffResults::new();
+        results.insert(CategoryDiffs::Value(vec![ValueDiff {
+            key: "config.port".to_owned(),
+            value1: "8080".to_owned(),
+            value2: "9090".to_owned(),
+        }]));
+        results.insert(CategoryDiffs::Type(vec![TypeDiff {
+            key: "config.debug".to_owned(),
+            type1: "bool".to_owned(),
+            type2: "string".to_owned(),
+        }]));
+        assert_eq!(
+            results.get(DiffCategory::Type).map(CategoryDiffs::category),
+            Some(DiffCategory::Type)
+        );
+
+        let (key_diffs, type_diffs, value_diffs, array_diffs): DiffCollection = results.into();
+
+        assert!(key_diffs.is_none());
+        assert_eq!(type_diffs.unwrap()[0].key, "config.debug");
+        assert_eq!(value_diffs.unwrap()[0].key, "config.port");
+        assert!(array_diffs.is_none());
+    }
+
+    #[test]
+    fn test_diff_results_insert_replaces_category() {
+        let key_diff = |key: &str| KeyDiff {
+            key: key.to_owned(),
+            has: "a.json".to_owned(),
+            misses: "b.json".to_owned(),
+        };
+        let mut results = DiffResults::new();
+
+        results.insert(CategoryDiffs::Key(vec![key_diff("a"), key_diff("b")]));
+        results.insert(CategoryDiffs::Key(vec![key_diff("c")]));
+
+        assert_eq!(results.count(DiffCategory::Key), 1);
+        assert_eq!(results.key_diffs().unwrap()[0].key, "c");
+        assert!(results.remove(DiffCategory::Key).is_some());
+        assert_eq!(results.is_checked(DiffCategory::Key), false);
+        assert_eq!(results.total(), 0);
+    }
+
+    #[test]
+    fn test_parse_severity_rule() {
+        let rule: SeverityRule = "array=info".parse().unwrap();
+        assert_eq!(rule.category, DiffCategory::Array);
+        assert_eq!(rule.path, None);
+        assert_eq!(rule.severity, Severity::Info);
+
+        let rule: SeverityRule = "value:pipeline.steps=Error".parse().unwrap();
+        assert_eq!(rule.category, DiffCategory::Value);
+        assert_eq!(
+            rule.path.as_ref().map(PathMatcher::pattern),
+            Some("pipeline.steps")
+        );
+        assert_eq!(rule.severity, Severity::Error);
+
+        let rule: SeverityRule = "value:filters.a=b=warning".parse().unwrap();
+        assert_eq!(rule.category, DiffCategory::Value);
+        assert_eq!(
+            rule.path.as_ref().map(PathMatcher::pattern),
+            Some("filters.a=b")
+        );
+        assert_eq!(rule.severity, Severity::Warning);
+
+        assert!("value".parse::<SeverityRule>().is_err());
+        assert!("colour=info".parse::<SeverityRule>().is_err());
+        assert!("value=fatal".parse::<SeverityRule>().is_err());
+    }
+
+    #[test]
+    fn test_parse_tolerance() {
+        let tolerance: Tolerance = "0.5".parse().unwrap();
+        assert_eq!(tolerance.path, None);
+        assert_eq!(tolerance.epsilon, 0.5);
+
+        let tolerance: Tolerance = "items[0].price=0.01".parse().unwrap();
+        assert_eq!(tolerance.path, Some("items[0].price".to_owned()));
+        assert_eq!(tolerance.epsilon, 0.01);
+
+        assert!("price=".parse::<Tolerance>().is_err());
+        assert!("price=-1".parse::<Tolerance>().is_err());
+        assert!("price=NaN".parse::<Tolerance>().is_err());
+    }
+
+    #[test]
+    fn test_diff_data_json() {
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "id".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![ValueDiff {
+                key: "name".to_owned(),
+                value1: "\"John\"".to_owned(),
+                value2: "\"Jane\"".to_owned(),
+            }]),
+            Some(vec![ArrayDiff {
+                key: "tags".to_owned(),
+                descriptor: ArrayDiffDesc::AHas,
+                value: "x".to_owned(),
+            }]),
+        );
+
+        let json = serde_json::to_value(DiffData::from(diffs)).unwrap();
+
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            vec!["arrayDiffs", "keyDiffs", "typeDiffs", "valueDiffs"]
+        );
+        assert_eq!(json["keyDiffs"][0]["key"], "id");
+        assert_eq!(json["typeDiffs"], serde_json::json!([]));
+
+        let data: DiffData = serde_json::from_value(json).unwrap();
+        assert_eq!(data.key_diffs[0].key_diff.has, "a.json");
+        assert!(data.type_diffs.is_empty());
+        assert_eq!(data.value_diffs[0].value2, "\"Jane\"");
+        assert_eq!(data.array_diffs[0].value, "x");
+    }
+
+    #[test]
+    fn test_identical_message() {
+        let config = ConfigBuilder::new().build();
+        assert_eq!(config.identical_message(), "The data is identical!");
+
+        let config = ConfigBuilder::new()
+            .identical_message(Some("No drift".to_owned()))
+            .build();
+        assert_eq!(config.identical_message(), "No drift");
+    }
+
+    #[test]
+    fn test_presence_severity_of_baseline() {
+        let config = |baseline| {
+            ConfigBuilder::new()
+                .file_a(Some("a.json".to_owned()))
+                .file_b(Some("b.json".to_owned()))
+                .baseline(baseline)
+                .build()
+        };
+
+        let baseline_b = config(Some(Baseline::B));
+        assert_eq!(baseline_b.baseline_has_key(Side::B), true);
+        assert_eq!(baseline_b.baseline_has_key(Side::A), false);
+        assert_eq!(
+            baseline_b.presence_severity_of(DiffCategory::Key, "extra", false),
+            Severity::Warning
+        );
+        assert_eq!(
+            baseline_b.presence_severity_of(DiffCategory::Key, "missing", true),
+            Severity::Error
+        );
+        assert_eq!(
+            baseline_b.baseline_has_array_item(&ArrayDiffDesc::BHas),
+            true
+        );
+
+        let baseline_a = config(Some(Baseline::A));
+        assert_eq!(baseline_a.baseline_has_key(Side::A), true);
+        assert_eq!(
+            baseline_a.baseline_has_array_item(&ArrayDiffDesc::BHas),
+            false
+        );
+        assert_eq!(
+            baseline_a.presence_severity_of(DiffCategory::Array, "tags", false),
+            Severity::Warning
+        );
+
+        let no_baseline = config(None);
+        assert_eq!(
+            no_baseline.presence_severity_of(DiffCategory::Key, "extra", false),
+            Severity::Error
+        );
+
+        let mut with_rule = config(Some(Baseline::B));
+        with_rule.severity_rules = vec!["key=info".parse().unwrap()];
+        assert_eq!(
+            with_rule.presence_severity_of(DiffCategory::Key, "missing", true),
+            Severity::Info
+        );
+    }
+
+    #[test]
+    fn test_severity_of() {
+        let config = ConfigBuilder::new()
+            .severity_rules(vec![
+                "value=warning".parse().unwrap(),
+                "array=info".parse().unwrap(),
+                "value:pipeline.steps=error".parse().unwrap(),
+            ])
+            .build();
+
+        assert_eq!(config.uses_severities(), true);
+        assert_eq!(
+            config.severity_of(DiffCategory::Value, "name"),
+            Severity::Warning
+        );
+        assert_eq!(
+            config.severity_of(DiffCategory::Value, "pipeline.steps[0]"),
+            Severity::Error
+        );
+        assert_eq!(
+            config.severity_of(DiffCategory::Array, "tags"),
+            Severity::Info
+        );
+        assert_eq!(
+            config.severity_of(DiffCategory::Key, "name"),
+            Severity::Error
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::DiffCollection,
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// Drops the key, type and value differences of the JSON data between keys that are unset in both files:
+/// missing, `null` or `""`. Other falsy values like `0`, `false` or `{}` are values like any other.
+pub fn ignore_json_empty_diffs(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> DiffCollection {
+    let is_unset = |value: Option<&Value>| match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        Some(_) => false,
+    };
+    ignore_empty_diffs(diffs, |key| {
+        is_unset(lookup_json(data1, key)) && is_unset(lookup_json(data2, key))
+    })
+}
+
+/// Drops the key, type and value differences of the YAML data between keys that are unset in both files:
+/// missing, `null`/`~` or `""`. Other falsy values like `0`, `false` or `{}` are values like any other.
+pub fn ignore_yaml_empty_diffs(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+) -> DiffCollection {
+    let is_unset = |value: Option<&YamlValue>| match value {
+        None | Some(YamlValue::Null) => true,
+        Some(YamlValue::String(s)) => s.is_empty(),
+        Some(_) => false,
+    };
+    ignore_empty_diffs(diffs, |key| {
+        is_unset(lookup_yaml(data1, key)) && is_unset(lookup_yaml(data2, key))
+    })
+}
+
+/// The array differences are kept, an item is never unset
+fn ignore_empty_diffs(diffs: DiffCollection, is_unset: impl Fn(&str) -> bool) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|kd| kd.into_iter().filter(|d| !is_unset(&d.key)).collect()),
+        type_diff.map(|td| td.into_iter().filter(|d| !is_unset(&d.key)).collect()),
+        value_diff.map(|vd| vd.into_iter().filter(|d| !is_unset(&d.key)).collect()),
+        array_diff,
+    )
+}
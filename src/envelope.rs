@@ -0,0 +1,80 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::DtfError,
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// Navigates the JSON data to the object at the path, so payloads wrapped differently can be compared.
+/// The data is returned as it is without a path.
+pub fn unwrap_json(
+    data: Map<String, Value>,
+    path: Option<&str>,
+    file_name: &str,
+) -> Result<Map<String, Value>, DtfError> {
+    let path = match path {
+        Some(path) if !path.is_empty() => path,
+        _ => return Ok(data),
+    };
+
+    match lookup_json(&data, path) {
+        Some(Value::Object(object)) => Ok(object.clone()),
+        _ => Err(missing_object(file_name, path)),
+    }
+}
+
+/// Navigates the YAML data to the mapping at the path, so payloads wrapped differently can be compared.
+/// The data is returned as it is without a path.
+pub fn unwrap_yaml(
+    data: Mapping,
+    path: Option<&str>,
+    file_name: &str,
+) -> Result<Mapping, DtfError> {
+    let path = match path {
+        Some(path) if !path.is_empty() => path,
+        _ => return Ok(data),
+    };
+
+    match lookup_yaml(&data, path) {
+        Some(YamlValue::Mapping(mapping)) => Ok(mapping.clone()),
+        _ => Err(missing_object(file_name, path)),
+    }
+}
+
+fn missing_object(file_name: &str, path: &str) -> DtfError {
+    DtfError::DiffError(format!("{} has no object at {}", file_name, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unwrap_json() {
+        let data: Map<String, Value> =
+            serde_json::from_str(r#"{"data": {"items": [{"id": 1}]}, "status": "ok"}"#).unwrap();
+
+        let unwrapped = unwrap_json(data.clone(), Some("data.items[0]"), "a.json").unwrap();
+        assert_eq!(Value::Object(unwrapped), serde_json::json!({ "id": 1 }));
+
+        assert_eq!(unwrap_json(data.clone(), None, "a.json").unwrap(), data);
+        assert!(matches!(
+            unwrap_json(data.clone(), Some("status"), "a.json"),
+            Err(DtfError::DiffError(message)) if message == "a.json has no object at status"
+        ));
+        assert!(unwrap_json(data, Some("missing"), "a.json").is_err());
+    }
+
+    #[test]
+    fn test_unwrap_yaml() {
+        let data: Mapping = serde_yaml::from_str("data:\n  name: John\n").unwrap();
+
+        let unwrapped = unwrap_yaml(data, Some("data"), "a.yaml").unwrap();
+
+        assert_eq!(
+            unwrapped,
+            serde_yaml::from_str::<Mapping>("name: John\n").unwrap()
+        );
+    }
+}
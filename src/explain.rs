@@ -0,0 +1,257 @@
+use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc, KeyDiff, TypeDiff, ValueDiff};
+
+use crate::{
+    dtfterminal_types::{DiffCollection, WorkingContext},
+    side::Side,
+};
+
+/// Describes every difference to be rendered with a sentence, for readers not familiar with the tables.
+/// Differences are listed in the order of the tables: key, type, value and array differences.
+/// The items of the array differences are placed by their index, if `index_of` knows it.
+pub fn explain_diffs(
+    diffs: &DiffCollection,
+    context: &WorkingContext,
+    mut index_of: impl FnMut(&ArrayDiff) -> Option<usize>,
+) -> Vec<String> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let config = &context.config;
+    let (file_a, file_b) = labeled_file_names(context.get_file_names());
+    let file_names = (file_a.as_str(), file_b.as_str());
+    let mut sentences = vec![];
+
+    if config.render_key_diffs {
+        sentences.extend(
+            key_diff
+                .iter()
+                .flatten()
+                .map(|kd| explain_key_diff(kd, context.key_side(kd), file_names)),
+        );
+    }
+
+    if config.render_type_diffs {
+        sentences.extend(
+            type_diff
+                .iter()
+                .flatten()
+                .map(|td| explain_type_diff(td, file_names)),
+        );
+    }
+
+    if config.render_value_diffs {
+        sentences.extend(value_diff.iter().flatten().map(explain_value_diff));
+    }
+
+    if config.render_array_diffs {
+        sentences.extend(
+            array_diff
+                .iter()
+                .flatten()
+                .map(|ad| explain_array_diff(ad, file_names, index_of(ad))),
+        );
+    }
+
+    sentences
+}
+
+/// The file names to write in the sentences, with their sides if they are the same
+fn labeled_file_names((file_a, file_b): (&str, &str)) -> (String, String) {
+    if file_a == file_b {
+        (
+            format!("{} ({})", file_a, Side::A),
+            format!("{} ({})", file_b, Side::B),
+        )
+    } else {
+        (file_a.to_owned(), file_b.to_owned())
+    }
+}
+
+fn explain_key_diff(key_diff: &KeyDiff, side: Side, (file_a, file_b): (&str, &str)) -> String {
+    let (has, misses) = match side {
+        Side::A => (file_a, file_b),
+        Side::B => (file_b, file_a),
+    };
+    format!(
+        "Key `{}` exists in {} but not {}",
+        key_diff.key, has, misses
+    )
+}
+
+fn explain_type_diff(type_diff: &TypeDiff, (file_a, file_b): (&str, &str)) -> String {
+    format!(
+        "Type of `{}` changed from {} in {} to {} in {}",
+        type_diff.key, type_diff.type1, file_a, type_diff.type2, file_b
+    )
+}
+
+fn explain_value_diff(value_diff: &ValueDiff) -> String {
+    format!(
+        "Value of `{}` changed from {} to {}",
+        value_diff.key, value_diff.value1, value_diff.value2
+    )
+}
+
+fn explain_array_diff(
+    array_diff: &ArrayDiff,
+    (file_a, file_b): (&str, &str),
+    index: Option<usize>,
+) -> String {
+    let (has, misses) = match array_diff.descriptor {
+        ArrayDiffDesc::AHas | ArrayDiffDesc::BMisses => (file_a, file_b),
+        ArrayDiffDesc::BHas | ArrayDiffDesc::AMisses => (file_b, file_a),
+    };
+    let position = index.map_or(String::new(), |index| format!(" at index {}", index));
+    format!(
+        "Array `{}` contains {}{} in {} but not in {}",
+        array_diff.key, array_diff.value, position, has, misses
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dtfterminal_types::ConfigBuilder, utils::create_working_context};
+
+    use super::*;
+
+    const FILE_NAMES: (&str, &str) = ("a.json", "b.json");
+
+    #[test]
+    fn test_explain_key_diff() {
+        let key_diff = KeyDiff {
+            key: "user.email".to_owned(),
+            has: "a.json".to_owned(),
+            misses: "b.json".to_owned(),
+        };
+
+        assert_eq!(
+            explain_key_diff(&key_diff, Side::A, FILE_NAMES),
+            "Key `user.email` exists in a.json but not b.json"
+        );
+        assert_eq!(
+            explain_key_diff(&key_diff, Side::B, FILE_NAMES),
+            "Key `user.email` exists in b.json but not a.json"
+        );
+    }
+
+    #[test]
+    fn test_explain_diffs_same_file_names() {
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_type_diffs(true)
+            .file_a(Some("config.json".to_owned()))
+            .file_b(Some("config.json".to_owned()))
+            .build();
+        let mut context = create_working_context(&config).unwrap();
+        context.key_sides.insert("city".to_owned(), Side::B);
+        let key_diff = |key: &str| KeyDiff {
+            key: key.to_owned(),
+            has: "config.json".to_owned(),
+            misses: "config.json".to_owned(),
+        };
+        let diffs = (
+            Some(vec![key_diff("age"), key_diff("city")]),
+            Some(vec![TypeDiff {
+                key: "port".to_owned(),
+                type1: "number".to_owned(),
+                type2: "string".to_owned(),
+            }]),
+            None,
+            None,
+        );
+
+        assert_eq!(
+            explain_diffs(&diffs, &context, |_| None),
+            vec![
+                "Key `age` exists in config.json (A) but not config.json (B)",
+                "Key `city` exists in config.json (B) but not config.json (A)",
+                "Type of `port` changed from number in config.json (A) to string in config.json (B)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_type_diff() {
+        let type_diff = TypeDiff {
+            key: "config.port".to_owned(),
+            type1: "number".to_owned(),
+            type2: "string".to_owned(),
+        };
+
+        assert_eq!(
+            explain_type_diff(&type_diff, FILE_NAMES),
+            "Type of `config.port` changed from number in a.json to string in b.json"
+        );
+    }
+
+    #[test]
+    fn test_explain_value_diff() {
+        let value_diff = ValueDiff {
+            key: "config.port".to_owned(),
+            value1: "8080".to_owned(),
+            value2: "9090".to_owned(),
+        };
+
+        assert_eq!(
+            explain_value_diff(&value_diff),
+            "Value of `config.port` changed from 8080 to 9090"
+        );
+    }
+
+    #[test]
+    fn test_explain_array_diff() {
+        let array_diff = |descriptor: ArrayDiffDesc| ArrayDiff {
+            descriptor,
+            key: "tags".to_owned(),
+            value: "\"admin\"".to_owned(),
+        };
+
+        assert_eq!(
+            explain_array_diff(&array_diff(ArrayDiffDesc::AHas), FILE_NAMES, None),
+            "Array `tags` contains \"admin\" in a.json but not in b.json"
+        );
+        assert_eq!(
+            explain_array_diff(&array_diff(ArrayDiffDesc::BHas), FILE_NAMES, None),
+            "Array `tags` contains \"admin\" in b.json but not in a.json"
+        );
+        assert_eq!(
+            explain_array_diff(&array_diff(ArrayDiffDesc::AHas), FILE_NAMES, Some(4)),
+            "Array `tags` contains \"admin\" at index 4 in a.json but not in b.json"
+        );
+    }
+
+    #[test]
+    fn test_explain_diffs_only_rendered_categories() {
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .build();
+        let context = create_working_context(&config).unwrap();
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "user.email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![ValueDiff {
+                key: "config.port".to_owned(),
+                value1: "8080".to_owned(),
+                value2: "9090".to_owned(),
+            }]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "tags".to_owned(),
+                value: "\"admin\"".to_owned(),
+            }]),
+        );
+
+        assert_eq!(
+            explain_diffs(&diffs, &context, |_| None),
+            vec![
+                "Key `user.email` exists in a.json but not b.json",
+                "Value of `config.port` changed from 8080 to 9090",
+            ]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! C-compatible bindings for the core comparison, so it can be used from other languages.
+//! Enabled by the `ffi` feature. The crate only builds an rlib by default, build the shared library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    dtfterminal_types::{ConfigBuilder, DtfError},
+    json_app::compare_json_str,
+    utils::create_working_context,
+};
+
+/// Check for Key differences
+pub const DATADIFF_KEY_DIFFS: u32 = 1;
+/// Check for Type differences
+pub const DATADIFF_TYPE_DIFFS: u32 = 1 << 1;
+/// Check for Value differences
+pub const DATADIFF_VALUE_DIFFS: u32 = 1 << 2;
+/// Check for Array differences
+pub const DATADIFF_ARRAY_DIFFS: u32 = 1 << 3;
+/// Arrays should be in the same order
+pub const DATADIFF_ARRAY_SAME_ORDER: u32 = 1 << 4;
+
+/// Compares two JSON documents and returns the differences as a JSON string:
+/// `{"key_diffs": [...], "type_diffs": [...], "value_diffs": [...], "array_diffs": [...]}`,
+/// the key differences with the `side` having the key, `A` or `B`.
+/// On failure `{"error": "..."}` is returned instead.
+/// `flags` is a combination of the `DATADIFF_*` constants.
+/// The returned string must be released with `datadiff_free`.
+///
+/// # Safety
+/// `a_json` and `b_json` must be null or point to null-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn datadiff_compare(
+    a_json: *const c_char,
+    b_json: *const c_char,
+    flags: u32,
+) -> *mut c_char {
+    let result = read_c_str(a_json, "a_json")
+        .and_then(|a| read_c_str(b_json, "b_json").map(|b| (a, b)))
+        .and_then(|(a, b)| compare(a, b, flags))
+        .unwrap_or_else(|e| json!({ "error": e.to_string() }));
+
+    // A JSON string can't contain a nul byte, as those are escaped
+    CString::new(result.to_string())
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Releases a string returned by `datadiff_compare`
+///
+/// # Safety
+/// `result` must be null or a pointer returned by `datadiff_compare` that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn datadiff_free(result: *mut c_char) {
+    if !result.is_null() {
+        drop(CString::from_raw(result));
+    }
+}
+
+/// Reads a C string, returning an error if it's null or not valid UTF-8
+///
+/// # Safety
+/// `value` must be null or point to a null-terminated string.
+unsafe fn read_c_str<'a>(value: *const c_char, name: &str) -> Result<&'a str, DtfError> {
+    if value.is_null() {
+        return Err(DtfError::DiffError(format!("{} is null", name)));
+    }
+
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|e| DtfError::DiffError(format!("{} is not valid UTF-8: {}", name, e)))
+}
+
+/// Compares the documents with the options defined by the flags
+fn compare(a: &str, b: &str, flags: u32) -> Result<Value, DtfError> {
+    let has_flag = |flag: u32| flags & flag != 0;
+    let config = ConfigBuilder::new()
+        .check_for_key_diffs(has_flag(DATADIFF_KEY_DIFFS))
+        .check_for_type_diffs(has_flag(DATADIFF_TYPE_DIFFS))
+        .check_for_value_diffs(has_flag(DATADIFF_VALUE_DIFFS))
+        .check_for_array_diffs(has_flag(DATADIFF_ARRAY_DIFFS))
+        .file_a(Some("a".to_owned()))
+        .file_b(Some("b".to_owned()))
+        .array_same_order(has_flag(DATADIFF_ARRAY_SAME_ORDER))
+        .build();
+
+    let context = create_working_context(&config)?;
+    let (key_diff, type_diff, value_diff, array_diff) = compare_json_str(a, b, &context)?;
+
+    Ok(json!({
+        "key_diffs": context.sided_key_diffs(&key_diff.unwrap_or_default()),
+        "type_diffs": type_diff.unwrap_or_default(),
+        "value_diffs": value_diff.unwrap_or_default(),
+        "array_diffs": array_diff.unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datadiff_compare() {
+        let a = CString::new(r#"{"name": "John", "age": 30}"#).unwrap();
+        let b = CString::new(r#"{"name": "Jane"}"#).unwrap();
+
+        let result = call_compare(
+            a.as_ptr(),
+            b.as_ptr(),
+            DATADIFF_KEY_DIFFS | DATADIFF_VALUE_DIFFS,
+        );
+
+        assert_eq!(result["key_diffs"][0]["key"], "age");
+        assert_eq!(result["value_diffs"][0]["key"], "name");
+        assert_eq!(result["value_diffs"][0]["value1"], "\"John\"");
+        assert_eq!(result["type_diffs"], json!([]));
+        assert!(result.get("error").is_none());
+    }
+
+    #[test]
+    fn test_datadiff_compare_null_input() {
+        let b = CString::new("{}").unwrap();
+
+        let result = call_compare(ptr::null(), b.as_ptr(), DATADIFF_KEY_DIFFS);
+
+        assert_eq!(result["error"], "Diff error: a_json is null");
+    }
+
+    #[test]
+    fn test_datadiff_compare_invalid_input() {
+        let a = CString::new("{}").unwrap();
+        let invalid_utf8: [u8; 3] = [0xff, 0xfe, 0];
+        let not_json = CString::new("not json").unwrap();
+
+        let result = call_compare(
+            a.as_ptr(),
+            invalid_utf8.as_ptr() as *const c_char,
+            DATADIFF_KEY_DIFFS,
+        );
+        assert!(result["error"]
+            .as_str()
+            .unwrap()
+            .contains("b_json is not valid UTF-8"));
+
+        let result = call_compare(a.as_ptr(), not_json.as_ptr(), DATADIFF_KEY_DIFFS);
+        assert!(result["error"]
+            .as_str()
+            .unwrap()
+            .contains("Could not parse JSON"));
+    }
+
+    fn call_compare(a: *const c_char, b: *const c_char, flags: u32) -> Value {
+        unsafe {
+            let result = datadiff_compare(a, b, flags);
+            assert!(!result.is_null());
+            let json = CStr::from_ptr(result).to_str().unwrap().to_owned();
+            datadiff_free(result);
+            serde_json::from_str(&json).unwrap()
+        }
+    }
+}
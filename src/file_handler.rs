@@ -1,12 +1,29 @@
-use std::{error::Error, fs::File, io::BufReader};
+use std::{
+    fs,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+};
+
+use flate2::read::GzDecoder;
 
-use libdtf::{core::diff_types::WorkingFile, json::read_json_file, yaml::read_yaml_file};
+use libdtf::core::diff_types::WorkingFile;
 
-use crate::dtfterminal_types::{
-    Config, ConfigBuilder, DiffCollection, DtfError, LibConfig, LibWorkingContext, SavedConfig,
-    SavedContext, WorkingContext,
+#[cfg(feature = "http")]
+use crate::http;
+use crate::{
+    compare::DataFormat,
+    depth::{check_json_map_depth, check_text_depth, check_toml_depth, check_yaml_depth},
+    dtfterminal_types::{
+        Config, ConfigBuilder, DiffCollection, DtfError, LibConfig, LibWorkingContext, SavedConfig,
+        SavedContext, WorkingContext,
+    },
+    normalize::{toml_to_json, yaml_to_json},
+    utils::{is_json_file, is_jsonc_file, is_toml_file, is_yaml_file},
 };
 
+/// The first bytes of every gzipped file
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Responsible for reading and writing files
 pub struct FileHandler {
     user_config: Config,
@@ -21,34 +38,275 @@ impl FileHandler {
         }
     }
 
-    /// Reads a JSON file and returns a map of the data
+    /// Reads a JSON file and returns a map of the data.
+    /// JSONC and JSON5 files are recognized by their extension.
     pub fn read_json_file(
         file_path: &str,
-    ) -> Result<serde_json::Map<String, serde_json::Value>, serde_json::Error> {
-        read_json_file(file_path)
+        max_size: Option<u64>,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, DtfError> {
+        FileHandler::read_jsonc_file(file_path, max_size, is_jsonc_file(file_path))
+    }
+
+    /// Reads a JSON file, allowing comments and trailing commas if `jsonc` is set.
+    /// The comments are dropped while parsing, so they are never compared.
+    fn read_jsonc_file(
+        file_path: &str,
+        max_size: Option<u64>,
+        jsonc: bool,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, DtfError> {
+        let content = FileHandler::read_non_empty_file(file_path, max_size)?;
+        let parse_error = |source| DtfError::ParseError {
+            path: file_path.to_owned(),
+            source,
+        };
+        if jsonc {
+            // The JSON5 parser has no recursion limit, unlike serde_json
+            check_text_depth(&content)?;
+            let data = json5::from_str(&content).map_err(|e| parse_error(Box::new(e)))?;
+            check_json_map_depth(&data)?;
+            return Ok(data);
+        }
+
+        let data = serde_json::from_str(&content).map_err(|e| {
+            // Point out the flag if the file would be fine as JSONC
+            if check_text_depth(&content).is_ok()
+                && json5::from_str::<serde_json::Value>(&content).is_ok()
+            {
+                parse_error(
+                    format!(
+                        "{}, the file seems to contain comments or trailing commas, read it with --jsonc",
+                        e
+                    )
+                    .into(),
+                )
+            } else {
+                parse_error(Box::new(e))
+            }
+        })?;
+        check_json_map_depth(&data)?;
+        Ok(data)
     }
 
     /// Reads a YAML file and returns a map of the data
-    pub fn read_yaml_file(file_path: &str) -> Result<serde_yaml::Mapping, serde_yaml::Error> {
-        read_yaml_file(file_path)
+    pub fn read_yaml_file(
+        file_path: &str,
+        max_size: Option<u64>,
+    ) -> Result<serde_yaml::Mapping, DtfError> {
+        let content = FileHandler::read_non_empty_file(file_path, max_size)?;
+        let data = serde_yaml::from_str(&content).map_err(|e| DtfError::ParseError {
+            path: file_path.to_owned(),
+            source: Box::new(e),
+        })?;
+        check_yaml_depth(&data)?;
+        Ok(data)
+    }
+
+    /// Reads a JSON file, or a TOML or YAML file normalized into JSON values.
+    /// TOML files are always read this way, YAML files when they are validated against a schema.
+    /// JSON files are read as JSONC if `jsonc` is set or their extension is .jsonc or .json5.
+    pub fn read_as_json(
+        file_path: &str,
+        max_size: Option<u64>,
+        jsonc: bool,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, DtfError> {
+        if is_toml_file(file_path) {
+            return FileHandler::read_toml_file(file_path, max_size);
+        }
+
+        match FileHandler::detect_format(file_path, max_size) {
+            Some(DataFormat::Yaml) => {
+                let mapping = FileHandler::read_yaml_file(file_path, max_size)?;
+                yaml_to_json(mapping).map_err(|e| DtfError::ParseError {
+                    path: file_path.to_owned(),
+                    source: e.into(),
+                })
+            }
+            _ => {
+                FileHandler::read_jsonc_file(file_path, max_size, jsonc || is_jsonc_file(file_path))
+            }
+        }
+    }
+
+    /// Reads a TOML file, normalized into JSON values
+    fn read_toml_file(
+        file_path: &str,
+        max_size: Option<u64>,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, DtfError> {
+        let content = FileHandler::read_non_empty_file(file_path, max_size)?;
+        let parse_error = |source: String| DtfError::ParseError {
+            path: file_path.to_owned(),
+            source: source.into(),
+        };
+        let table: toml::Table = content
+            .parse()
+            .map_err(|e: toml::de::Error| parse_error(e.to_string()))?;
+        // Dotted keys and table headers can nest tables without limit
+        check_toml_depth(&table)?;
+        toml_to_json(table).map_err(parse_error)
+    }
+
+    /// Reads the content of a JSON or YAML file as text, without parsing it
+    pub fn read_text(file_path: &str, max_size: Option<u64>) -> Result<String, DtfError> {
+        FileHandler::read_non_empty_file(file_path, max_size)
+    }
+
+    /// Reads the bytes of a file as they are on the disk or downloaded, without decompressing them
+    pub fn read_bytes(file_path: &str) -> Result<Vec<u8>, DtfError> {
+        match FileHandler::read_remote(file_path, None)? {
+            Some(content) => Ok(content.into_bytes()),
+            None => fs::read(file_path).map_err(|e| FileHandler::open_error(file_path, e)),
+        }
+    }
+
+    /// Reads the content of a file. Files containing nothing but whitespace are treated as an error.
+    /// Files larger than `max_size` bytes are rejected before being read.
+    /// Gzipped files are decompressed, recognized by their extension or header.
+    fn read_non_empty_file(file_path: &str, max_size: Option<u64>) -> Result<String, DtfError> {
+        let content = match FileHandler::read_remote(file_path, max_size)? {
+            Some(content) => content,
+            None => FileHandler::read_local(file_path, max_size)?,
+        };
+        if content.trim().is_empty() {
+            return Err(DtfError::DiffError(format!("File is empty: {}", file_path)));
+        }
+
+        Ok(content)
     }
 
-    /// Writes the diff results to a JSON file
-    pub fn write_to_file(&self, diffs: DiffCollection) -> Result<(), DtfError> {
+    /// Reads the content of a file from the disk
+    fn read_local(file_path: &str, max_size: Option<u64>) -> Result<String, DtfError> {
+        if let Some(max_size) = max_size {
+            let size = fs::metadata(file_path)
+                .map_err(|e| FileHandler::open_error(file_path, e))?
+                .len();
+            if size > max_size {
+                return Err(DtfError::LimitError(format!(
+                    "{} is {} bytes, the maximum size is {} bytes",
+                    file_path, size, max_size
+                )));
+            }
+        }
+
+        FileHandler::read_content(file_path, max_size)
+    }
+
+    /// Downloads the content of a document if the path is a URL
+    #[cfg(feature = "http")]
+    fn read_remote(path: &str, max_size: Option<u64>) -> Result<Option<String>, DtfError> {
+        if !http::is_url(path) {
+            return Ok(None);
+        }
+
+        http::fetch_kept(path, max_size).map(|document| Some(document.body))
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn read_remote(_path: &str, _max_size: Option<u64>) -> Result<Option<String>, DtfError> {
+        Ok(None)
+    }
+
+    /// Detects the format of a file by its extension.
+    /// TOML files are compared as JSON, they are normalized into JSON values when they are read.
+    /// The format of URLs without a known extension is detected by downloading them, at most `max_size` bytes.
+    /// The downloaded document is kept, so reading it doesn't download it again.
+    pub fn detect_format(path: &str, max_size: Option<u64>) -> Option<DataFormat> {
+        if is_json_file(path) || is_toml_file(path) {
+            Some(DataFormat::Json)
+        } else if is_yaml_file(path) {
+            Some(DataFormat::Yaml)
+        } else {
+            FileHandler::detect_remote_format(path, max_size)
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn detect_remote_format(path: &str, max_size: Option<u64>) -> Option<DataFormat> {
+        if !http::is_url(path) {
+            return None;
+        }
+
+        http::detect_remote_format(path, max_size).ok()
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn detect_remote_format(_path: &str, _max_size: Option<u64>) -> Option<DataFormat> {
+        None
+    }
+
+    /// Reads the content of a file, decompressing it if it's gzipped.
+    /// The decompressed content is also limited to `max_size` bytes.
+    fn read_content(file_path: &str, max_size: Option<u64>) -> Result<String, DtfError> {
+        let file = File::open(file_path).map_err(|e| FileHandler::open_error(file_path, e))?;
+        let mut reader = BufReader::new(file);
+        let is_gzipped = file_path.ends_with(".gz")
+            || reader
+                .fill_buf()
+                .map_err(DtfError::IoError)?
+                .starts_with(&GZIP_MAGIC);
+
+        if !is_gzipped {
+            let mut content = String::new();
+            reader
+                .read_to_string(&mut content)
+                .map_err(DtfError::IoError)?;
+            return Ok(content);
+        }
+
+        // Reading one byte over the limit is enough to tell if it's exceeded
+        let limit = max_size.map_or(u64::MAX, |max_size| max_size.saturating_add(1));
+        let mut decompressed = vec![];
+        GzDecoder::new(reader)
+            .take(limit)
+            .read_to_end(&mut decompressed)
+            .map_err(DtfError::IoError)?;
+
+        if let Some(max_size) = max_size.filter(|&max_size| decompressed.len() as u64 > max_size) {
+            return Err(DtfError::LimitError(format!(
+                "{} is larger than {} bytes when decompressed",
+                file_path, max_size
+            )));
+        }
+
+        String::from_utf8(decompressed)
+            .map_err(|e| DtfError::DiffError(format!("{} is not valid UTF-8: {}", file_path, e)))
+    }
+
+    /// Writes the diff results to a JSON file, with the sides of the key differences told by the context
+    pub fn write_to_file(
+        &self,
+        diffs: DiffCollection,
+        context: &WorkingContext,
+    ) -> Result<(), DtfError> {
         let (key_diff_option, type_diff_option, value_diff_option, array_diff_option) = diffs;
-        let key_diff = key_diff_option.unwrap_or_default();
+        let key_diff = context.sided_key_diffs(&key_diff_option.unwrap_or_default());
         let type_diff = type_diff_option.unwrap_or_default();
         let value_diff = value_diff_option.unwrap_or_default();
         let array_diff = array_diff_option.unwrap_or_default();
 
         let config = &self.user_config;
-        if config.write_to_file.is_none() {
-            panic!("File write path is missing!")
+        let write_path = config.write_to_file.as_ref().ok_or_else(|| {
+            DtfError::MissingArgument("the file to write the results to".to_owned())
+        })?;
+        let (file_a, file_b) = match (&config.file_a, &config.file_b) {
+            (Some(file_a), Some(file_b)) => (file_a.clone(), file_b.clone()),
+            _ => {
+                return Err(DtfError::MissingArgument(
+                    "the names of the checked files".to_owned(),
+                ))
+            }
+        };
+        let mut file = if config.append {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(write_path)
+        } else {
+            File::create(write_path)
         }
-        let file = File::create(config.write_to_file.as_ref().unwrap());
+        .map_err(DtfError::IoError)?;
 
-        match serde_json::to_writer(
-            &mut file.unwrap(),
+        serde_json::to_writer(
+            &mut file,
             &SavedContext::new(
                 key_diff,
                 type_diff,
@@ -59,46 +317,51 @@ impl FileHandler {
                     config.check_for_type_diffs,
                     config.check_for_value_diffs,
                     config.check_for_array_diffs,
-                    config.file_a.clone().unwrap(),
-                    config.file_b.clone().unwrap(),
+                    file_a,
+                    file_b,
                     config.array_same_order,
-                ),
+                )
+                .with_ordered_array_paths(config.ordered_array_paths.clone()),
             ),
-        ) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(DtfError::IoError(e.into())),
+        )
+        .map_err(|e| DtfError::IoError(e.into()))?;
+        // Appended results are separated by new lines, one per line
+        if config.append {
+            writeln!(file).map_err(DtfError::IoError)?;
         }
+        Ok(())
     }
 
     /// Loads the saved results from a JSON file
-    pub fn load_saved_results(
-        &mut self,
-    ) -> Result<(DiffCollection, WorkingContext), Box<dyn Error>> {
-        let saved_data = match self.read_from_file(&self.user_config.read_from_file) {
-            Ok(data) => data,
-            Err(e) => return Err(Box::new(DtfError::IoError(e.into()))),
-        };
+    pub fn load_saved_results(&mut self) -> Result<(DiffCollection, WorkingContext), DtfError> {
+        let saved_data = FileHandler::read_saved_context(&self.user_config.read_from_file)?;
+        let mut working_context = self.build_working_context_from_loaded_data(&saved_data.config);
         self.saved_config = Some(saved_data.config);
+        // Results saved without the sides are left to tell them by the file names
+        working_context.key_sides = saved_data
+            .key_diff
+            .iter()
+            .filter_map(|kd| Some((kd.key_diff.key.clone(), kd.side?)))
+            .collect();
 
         let diff_collection = (
-            Some(saved_data.key_diff),
+            Some(
+                saved_data
+                    .key_diff
+                    .into_iter()
+                    .map(|kd| kd.key_diff)
+                    .collect(),
+            ),
             Some(saved_data.type_diff),
             Some(saved_data.value_diff),
             Some(saved_data.array_diff),
         );
 
-        let working_context = self.build_working_context_from_loaded_data();
-
         Ok((diff_collection, working_context))
     }
 
     /// Builds a working context object based on the loaded data
-    fn build_working_context_from_loaded_data(&self) -> WorkingContext {
-        if self.saved_config.is_none() {
-            panic!("Saved data is corrupted! Config options not present!")
-        }
-
-        let saved_config = self.saved_config.as_ref().unwrap();
+    fn build_working_context_from_loaded_data(&self, saved_config: &SavedConfig) -> WorkingContext {
         let user_config = &self.user_config;
 
         let file_a = WorkingFile::new(saved_config.file_a.clone());
@@ -124,6 +387,28 @@ impl FileHandler {
                 .file_a(Some(saved_config.file_a.clone()))
                 .file_b(Some(saved_config.file_b.clone()))
                 .array_same_order(saved_config.array_same_order)
+                .ordered_array_paths(saved_config.ordered_array_paths.clone())
+                .severity_rules(user_config.severity_rules.clone())
+                .min_severity(user_config.min_severity)
+                .fail_on_severity(user_config.fail_on_severity)
+                .array_key(user_config.array_key.clone())
+                .emit_header(user_config.emit_header)
+                .baseline(user_config.baseline)
+                .output_format(user_config.output_format)
+                .value_truncate_len(user_config.value_truncate_len)
+                .max_array_diffs_per_key(user_config.max_array_diffs_per_key)
+                .explain(user_config.explain)
+                .badge(user_config.badge.clone())
+                .embed_data(user_config.embed_data)
+                .sort_array_values(user_config.sort_array_values)
+                .side_by_side(user_config.side_by_side)
+                .ascii(user_config.ascii)
+                .show_empty_tables(user_config.show_empty_tables)
+                .report_title(user_config.report_title.clone())
+                .report_description(user_config.report_description.clone())
+                .redact_patterns(user_config.redact_patterns.clone())
+                .baseline_diffs(user_config.baseline_diffs.clone())
+                .positions(user_config.positions)
                 .browser_view(user_config.browser_view.clone())
                 .printer_friendly(user_config.printer_friendly)
                 .no_browser_show(user_config.no_browser_show)
@@ -132,10 +417,233 @@ impl FileHandler {
     }
 
     /// Reads the saved results from a JSON file
-    fn read_from_file(&self, file_path: &str) -> serde_json::Result<SavedContext> {
-        let file =
-            File::open(file_path).unwrap_or_else(|_| panic!("Could not open file {}", file_path));
+    pub fn read_saved_context(file_path: &str) -> Result<SavedContext, DtfError> {
+        FileHandler::read_saved_contexts(file_path)?
+            .pop()
+            .ok_or_else(|| DtfError::DiffError(format!("No saved results in {}", file_path)))
+    }
+
+    /// Reads every saved result of a file, in the order they were written.
+    /// Files written with `--append` contain a result per line, others a single one.
+    pub fn read_saved_contexts(file_path: &str) -> Result<Vec<SavedContext>, DtfError> {
+        let file = File::open(file_path).map_err(|e| FileHandler::open_error(file_path, e))?;
         let reader = BufReader::new(file);
-        serde_json::from_reader(reader)
+        serde_json::Deserializer::from_reader(reader)
+            .into_iter::<SavedContext>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| DtfError::ParseError {
+                path: file_path.to_owned(),
+                source: Box::new(e),
+            })
+    }
+
+    /// Converts an error of opening a file, so missing files are reported by name
+    pub(crate) fn open_error(file_path: &str, error: io::Error) -> DtfError {
+        match error.kind() {
+            ErrorKind::NotFound => DtfError::FileNotFound(file_path.to_owned()),
+            _ => DtfError::IoError(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::KeyDiff;
+
+    use crate::side::{KeySides, Side};
+
+    use super::*;
+
+    #[test]
+    fn test_read_file_within_max_size() {
+        let result = FileHandler::read_json_file("test_data/json/person1.json", Some(1_000_000));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_read_file_over_max_size() {
+        let result = FileHandler::read_json_file("test_data/json/person1.json", Some(10));
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+
+        let result = FileHandler::read_yaml_file("test_data/yaml/person1.yaml", Some(10));
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+    }
+
+    #[test]
+    fn test_read_missing_file() {
+        let result = FileHandler::read_json_file("test_data/json/missing.json", None);
+        assert!(
+            matches!(result, Err(DtfError::FileNotFound(path)) if path == "test_data/json/missing.json")
+        );
+
+        let result = FileHandler::read_yaml_file("test_data/yaml/missing.yaml", Some(10));
+        assert!(matches!(result, Err(DtfError::FileNotFound(_))));
+
+        let result = FileHandler::read_saved_context("test_data/missing_results.json");
+        assert!(matches!(result, Err(DtfError::FileNotFound(_))));
+    }
+
+    #[test]
+    fn test_read_invalid_file() {
+        let result = FileHandler::read_json_file("test_data/json/invalid.json", None);
+        assert!(
+            matches!(result, Err(DtfError::ParseError { path, .. }) if path == "test_data/json/invalid.json")
+        );
+
+        let result = FileHandler::read_yaml_file("test_data/yaml/invalid.yaml", None);
+        assert!(matches!(result, Err(DtfError::ParseError { .. })));
+
+        let result = FileHandler::read_saved_context("test_data/json/person1.json");
+        assert!(matches!(result, Err(DtfError::ParseError { .. })));
+    }
+
+    #[test]
+    fn test_read_jsonc_file() {
+        let result = FileHandler::read_json_file("test_data/json/person1.jsonc", None);
+        assert_eq!(
+            result.unwrap(),
+            FileHandler::read_json_file("test_data/json/person1.json", None).unwrap()
+        );
+
+        let result = FileHandler::read_json_file("test_data/json/comments.json", None);
+        assert!(
+            matches!(result, Err(DtfError::ParseError { source, .. }) if source.to_string().contains("--jsonc"))
+        );
+
+        let result = FileHandler::read_as_json("test_data/json/comments.json", None, true);
+        assert_eq!(result.unwrap()["name"], "John Doe");
+    }
+
+    #[test]
+    fn test_write_to_file_without_path() {
+        let config = ConfigBuilder::new().build();
+        let context = working_context(&config);
+        let file_handler = FileHandler::new(config, None);
+        let result = file_handler.write_to_file((None, None, None, None), &context);
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+    }
+
+    #[test]
+    fn test_append_to_file() {
+        let path = std::env::temp_dir().join(format!("dtf_append_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let _ = fs::remove_file(&path);
+        let config = ConfigBuilder::new()
+            .write_to_file(Some(path.clone()))
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .append(true)
+            .build();
+        let context = working_context(&config);
+        let file_handler = FileHandler::new(config, None);
+        let value_diff = |key: &str| libdtf::core::diff_types::ValueDiff {
+            key: key.to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        };
+
+        file_handler
+            .write_to_file(
+                (None, None, Some(vec![value_diff("first")]), None),
+                &context,
+            )
+            .unwrap();
+        file_handler
+            .write_to_file(
+                (None, None, Some(vec![value_diff("second")]), None),
+                &context,
+            )
+            .unwrap();
+
+        let saved_contexts = FileHandler::read_saved_contexts(&path).unwrap();
+        assert_eq!(saved_contexts.len(), 2);
+        assert_eq!(saved_contexts[0].value_diff[0].key, "first");
+        assert_eq!(saved_contexts[1].value_diff[0].key, "second");
+        let last = FileHandler::read_saved_context(&path).unwrap();
+        assert_eq!(last.value_diff[0].key, "second");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_ordered_array_paths() {
+        let path = std::env::temp_dir().join(format!("dtf_ordered_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let config = ConfigBuilder::new()
+            .write_to_file(Some(path.clone()))
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .ordered_array_paths(vec!["steps".to_owned()])
+            .build();
+        let context = working_context(&config);
+        FileHandler::new(config, None)
+            .write_to_file((None, None, Some(vec![]), None), &context)
+            .unwrap();
+
+        let config = ConfigBuilder::new().read_from_file(path.clone()).build();
+        let (_, working_context) = FileHandler::new(config, None).load_saved_results().unwrap();
+
+        assert_eq!(working_context.config.ordered_array_paths, vec!["steps"]);
+        assert!(working_context.has_ordered_array_paths());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_gzipped_files() {
+        let gzipped = FileHandler::read_json_file("test_data/json/person1.json.gz", None).unwrap();
+        let plain = FileHandler::read_json_file("test_data/json/person1.json", None).unwrap();
+        assert_eq!(gzipped, plain);
+
+        let gzipped = FileHandler::read_yaml_file("test_data/yaml/person1.yaml.gz", None).unwrap();
+        let plain = FileHandler::read_yaml_file("test_data/yaml/person1.yaml", None).unwrap();
+        assert_eq!(gzipped, plain);
+    }
+
+    #[test]
+    fn test_read_gzipped_file_over_max_size_when_decompressed() {
+        let compressed_size = fs::metadata("test_data/json/person1.json.gz")
+            .unwrap()
+            .len();
+        let result =
+            FileHandler::read_json_file("test_data/json/person1.json.gz", Some(compressed_size));
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+    }
+
+    #[test]
+    fn test_saved_key_sides() {
+        let path = std::env::temp_dir().join(format!("dtf_sides_{}.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let config = ConfigBuilder::new()
+            .write_to_file(Some(path.clone()))
+            .file_a(Some("config.json".to_owned()))
+            .file_b(Some("config.json".to_owned()))
+            .build();
+        let mut context = working_context(&config);
+        context.key_sides = KeySides::from([("city".to_owned(), Side::B)]);
+        let key_diff = KeyDiff {
+            key: "city".to_owned(),
+            has: "config.json".to_owned(),
+            misses: "config.json".to_owned(),
+        };
+        FileHandler::new(config, None)
+            .write_to_file((Some(vec![key_diff.clone()]), None, None, None), &context)
+            .unwrap();
+
+        let config = ConfigBuilder::new().read_from_file(path.clone()).build();
+        let (diffs, working_context) = FileHandler::new(config, None).load_saved_results().unwrap();
+
+        assert_eq!(diffs.0.unwrap(), vec![key_diff.clone()]);
+        assert_eq!(working_context.key_side(&key_diff), Side::B);
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn working_context(config: &Config) -> WorkingContext {
+        WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config.clone(),
+        )
     }
 }
@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use glob::glob;
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table, TableStyle,
+};
+
+use crate::dtfterminal_types::DtfError;
+
+/// Files matched by two glob patterns, paired by their file names
+#[derive(Debug, Default, PartialEq)]
+pub struct FilePairs {
+    pub pairs: Vec<(String, String)>,
+    pub unmatched: Vec<String>,
+}
+
+impl FilePairs {
+    /// Renders the files without a pair as a terminal table
+    pub fn render_unmatched(&self) -> String {
+        let mut table = Table::new();
+        table.max_column_width = 80;
+        table.style = TableStyle::extended();
+        table.add_row(Row::new(vec![
+            TableCell::builder("Unmatched Files").alignment(Alignment::Center)
+        ]));
+        for file in &self.unmatched {
+            table.add_row(Row::new(vec![TableCell::new(file)]));
+        }
+        table.render()
+    }
+}
+
+/// Checks if the path should be expanded as a glob pattern.
+/// URLs are never expanded, as their query strings may contain the same characters.
+pub fn has_glob_metacharacters(path: &str) -> bool {
+    !path.contains("://") && path.contains(['*', '?', '['])
+}
+
+/// Expands both glob patterns and pairs the matched files by their file names.
+/// A single first file is paired with every file matched by the second pattern, as their baseline.
+pub fn pair_files(pattern_a: &str, pattern_b: &str) -> Result<FilePairs, DtfError> {
+    if !has_glob_metacharacters(pattern_a) {
+        let pairs = expand_glob(pattern_b)?
+            .into_iter()
+            .map(|file_b| (pattern_a.to_owned(), file_b))
+            .collect();
+        return Ok(FilePairs {
+            pairs,
+            unmatched: vec![],
+        });
+    }
+
+    let files_a = expand_glob(pattern_a)?;
+    let mut files_b = expand_glob(pattern_b)?;
+    let mut file_pairs = FilePairs::default();
+
+    for file_a in files_a {
+        match files_b
+            .iter()
+            .position(|file_b| file_name(file_b) == file_name(&file_a))
+        {
+            Some(index) => file_pairs.pairs.push((file_a, files_b.remove(index))),
+            None => file_pairs.unmatched.push(file_a),
+        }
+    }
+    file_pairs.unmatched.extend(files_b);
+
+    Ok(file_pairs)
+}
+
+/// Get the sorted list of files matching the pattern
+fn expand_glob(pattern: &str) -> Result<Vec<String>, DtfError> {
+    let paths = glob(pattern)
+        .map_err(|e| DtfError::DiffError(format!("Invalid pattern {}: {}", pattern, e)))?;
+
+    let mut files = vec![];
+    for path in paths {
+        let path = path.map_err(|e| DtfError::IoError(e.into_error()))?;
+        if path.is_file() {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn file_name(path: &str) -> Option<&str> {
+    Path::new(path).file_name().and_then(|name| name.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    #[test]
+    fn test_has_glob_metacharacters() {
+        assert_eq!(has_glob_metacharacters("configs/*.json"), true);
+        assert_eq!(has_glob_metacharacters("configs/file?.json"), true);
+        assert_eq!(has_glob_metacharacters("configs/[ab].json"), true);
+        assert_eq!(has_glob_metacharacters("configs/file.json"), false);
+        assert_eq!(
+            has_glob_metacharacters("https://example.com/data.json?version=2"),
+            false
+        );
+    }
+
+    #[test]
+    fn test_pair_files() {
+        let dir = create_test_dir("pair_files", &["x.json", "y.json"], &["x.json", "z.json"]);
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+
+        let file_pairs = pair_files(&path("a/*.json"), &path("b/*.json")).unwrap();
+
+        assert_eq!(file_pairs.pairs, vec![(path("a/x.json"), path("b/x.json"))]);
+        assert_eq!(
+            file_pairs.unmatched,
+            vec![path("a/y.json"), path("b/z.json")]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pair_files_with_baseline() {
+        let dir = create_test_dir("pair_baseline", &["base.json"], &["x.json", "y.json"]);
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+
+        let file_pairs = pair_files(&path("a/base.json"), &path("b/*.json")).unwrap();
+
+        assert_eq!(
+            file_pairs.pairs,
+            vec![
+                (path("a/base.json"), path("b/x.json")),
+                (path("a/base.json"), path("b/y.json")),
+            ]
+        );
+        assert!(file_pairs.unmatched.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn create_test_dir(name: &str, files_a: &[&str], files_b: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dtf_{}_{}", name, std::process::id()));
+        for (sub_dir, files) in [("a", files_a), ("b", files_b)] {
+            fs::create_dir_all(dir.join(sub_dir)).unwrap();
+            for file in files {
+                fs::write(dir.join(sub_dir).join(file), "{}").unwrap();
+            }
+        }
+        dir
+    }
+}
@@ -0,0 +1,195 @@
+//! Finds the first difference of two documents, for `--fail-fast`.
+//!
+//! The full check compares the whole documents before anything can be reported. Here they are checked key by key
+//! instead, so the comparison can stop at the first key with a difference. Each key is checked and its differences
+//! are rewritten like in the full check, so both fail on the same files and report the differences the same way.
+
+use std::ops::ControlFlow;
+
+use log::debug;
+
+use crate::{
+    checked_data::{walk_chunks, CheckedData},
+    dtfterminal_types::{Config, DiffCollection, DtfError},
+    utils::count_diffs,
+};
+
+/// Finds the first difference of the data without checking the rest of it.
+/// `check` is the full check with the steps after it, run on a chunk of the data: the entry of a single key.
+pub fn find_first_diff<D: CheckedData>(
+    data1: &D,
+    data2: &D,
+    config: &Config,
+    mut check: impl FnMut(D, D) -> Result<DiffCollection, DtfError>,
+) -> Result<DiffCollection, DtfError> {
+    let mut checked = 0;
+    let mut first = None;
+    walk_chunks(data1, data2, &mut |chunk1, chunk2| {
+        checked += 1;
+        let diffs = check(chunk1, chunk2)?;
+        if count_diffs(&diffs) == 0 {
+            return Ok(ControlFlow::Continue(()));
+        }
+        first = Some(diffs);
+        Ok(ControlFlow::Break(()))
+    })?;
+    debug!("Checked {} keys for --fail-fast", checked);
+    Ok(first.map(first_only).unwrap_or_else(|| no_diffs(config)))
+}
+
+/// Keeps only the first difference, the other checked categories are left empty
+fn first_only(diffs: DiffCollection) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let mut found = false;
+    (
+        keep_first(key_diff, &mut found),
+        keep_first(type_diff, &mut found),
+        keep_first(value_diff, &mut found),
+        keep_first(array_diff, &mut found),
+    )
+}
+
+fn keep_first<T>(diffs: Option<Vec<T>>, found: &mut bool) -> Option<Vec<T>> {
+    diffs.map(|diffs| {
+        if *found {
+            return vec![];
+        }
+        let first: Vec<T> = diffs.into_iter().take(1).collect();
+        *found = !first.is_empty();
+        first
+    })
+}
+
+/// The result of checking the categories without finding anything.
+/// Key differences aren't checked in intersection mode.
+fn no_diffs(config: &Config) -> DiffCollection {
+    (
+        (config.check_for_key_diffs && !config.intersection).then(Vec::new),
+        config.check_for_type_diffs.then(Vec::new),
+        config.check_for_value_diffs.then(Vec::new),
+        config.check_for_array_diffs.then(Vec::new),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{TypeDiff, ValueDiff};
+    use serde_json::{json, Map, Value};
+
+    use crate::dtfterminal_types::ConfigBuilder;
+
+    use super::*;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn get_config() -> Config {
+        ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .check_for_type_diffs(true)
+            .check_for_value_diffs(true)
+            .check_for_array_diffs(true)
+            .build()
+    }
+
+    /// Reports a value difference for every chunk differing in the two files
+    fn check_chunk(chunk1: Map<String, Value>, chunk2: Map<String, Value>) -> DiffCollection {
+        let value_diff = (chunk1 != chunk2).then(|| ValueDiff {
+            key: String::new(),
+            value1: Value::Object(chunk1).to_string(),
+            value2: Value::Object(chunk2).to_string(),
+        });
+        (
+            Some(vec![]),
+            Some(vec![]),
+            Some(value_diff.into_iter().collect()),
+            Some(vec![]),
+        )
+    }
+
+    #[test]
+    fn test_stops_at_first_diff() {
+        let large: Map<String, Value> = (0..1000)
+            .map(|i| (format!("key{}", i), json!({ "value": i })))
+            .collect();
+        let data1 = object(json!({ "a": 1, "b": large }));
+        let data2 = object(json!({ "a": 2, "b": large }));
+        let mut checked = 0;
+
+        let diffs = find_first_diff(&data1, &data2, &get_config(), |chunk1, chunk2| {
+            checked += 1;
+            Ok(check_chunk(chunk1, chunk2))
+        })
+        .unwrap();
+
+        assert_eq!(checked, 1);
+        assert_eq!(diffs.2.unwrap()[0].value1, r#"{"a":1}"#);
+
+        // Every key is checked without a difference: a and the values of the 1000 objects in b
+        let mut checked = 0;
+        let diffs = find_first_diff(&data1, &data1, &get_config(), |chunk1, chunk2| {
+            checked += 1;
+            Ok(check_chunk(chunk1, chunk2))
+        })
+        .unwrap();
+        assert_eq!(checked, 1001);
+        assert_eq!(count_diffs(&diffs), 0);
+    }
+
+    #[test]
+    fn test_chunks_are_at_their_paths() {
+        let data1 = object(json!({ "a": { "b": { "c": 1 }, "d": [1] }, "e": 1 }));
+        let data2 = object(json!({ "a": { "b": 2, "d": [1] }, "f": 1 }));
+        let mut chunks = vec![];
+
+        find_first_diff(&data1, &data2, &get_config(), |chunk1, chunk2| {
+            chunks.push((Value::Object(chunk1), Value::Object(chunk2)));
+            Ok((None, None, None, None))
+        })
+        .unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                (
+                    json!({ "a": { "b": { "c": 1 } } }),
+                    json!({ "a": { "b": 2 } })
+                ),
+                (json!({ "a": { "d": [1] } }), json!({ "a": { "d": [1] } })),
+                (json!({ "e": 1 }), json!({})),
+                (json!({}), json!({ "f": 1 })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keeps_only_the_first_diff() {
+        let type_diff = |key: &str| TypeDiff {
+            key: key.to_owned(),
+            type1: "string".to_owned(),
+            type2: "number".to_owned(),
+        };
+        let data1 = object(json!({ "a": "1" }));
+        let data2 = object(json!({ "a": 1 }));
+
+        let diffs = find_first_diff(&data1, &data2, &get_config(), |_, _| {
+            Ok((
+                Some(vec![]),
+                Some(vec![type_diff("a"), type_diff("a.b")]),
+                Some(vec![ValueDiff {
+                    key: "a".to_owned(),
+                    value1: "\"1\"".to_owned(),
+                    value2: "1".to_owned(),
+                }]),
+                None,
+            ))
+        })
+        .unwrap();
+
+        assert_eq!(
+            diffs,
+            (Some(vec![]), Some(vec![type_diff("a")]), Some(vec![]), None)
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// Flattens the JSON data into a single-level map keyed by the paths of its values, e.g. `{"a": {"b": 1}}` becomes `{"a.b": 1}`.
+/// Arrays are only flattened by their indexes, like `tags[0]`, if `flatten_arrays` is set, otherwise they are values themselves.
+pub fn flatten_json(data: Map<String, Value>, flatten_arrays: bool) -> Map<String, Value> {
+    let mut flat = Map::new();
+    for (key, value) in data {
+        flatten_json_value(key, value, flatten_arrays, &mut flat);
+    }
+    flat
+}
+
+/// Flattens the YAML data into a single-level mapping keyed by the paths of its values, e.g. `a: {b: 1}` becomes `a.b: 1`.
+/// Sequences are only flattened by their indexes, like `tags[0]`, if `flatten_arrays` is set, otherwise they are values themselves.
+pub fn flatten_yaml(data: Mapping, flatten_arrays: bool) -> Mapping {
+    let mut flat = Mapping::new();
+    for (key, value) in data {
+        flatten_yaml_value(yaml_key(&key), value, flatten_arrays, &mut flat);
+    }
+    flat
+}
+
+/// Lists the path of every leaf value of a JSON document in document order, in the style of the differences,
+/// e.g. `servers[0].port`. These are the keys `flatten_json` gives with `flatten_arrays`, so empty objects and arrays are leaves too.
+/// A document that isn't an object is listed under `$`, like `compare_json_values` compares it.
+pub fn collect_paths(doc: &Value) -> Vec<String> {
+    let mut paths = vec![];
+    match doc {
+        Value::Object(object) => {
+            for (key, value) in object {
+                collect_value_paths(key.clone(), value, &mut paths);
+            }
+        }
+        other => collect_value_paths("$".to_owned(), other, &mut paths),
+    }
+    paths
+}
+
+fn collect_value_paths(path: String, value: &Value, paths: &mut Vec<String>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                collect_value_paths(format!("{}.{}", path, key), value, paths);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, item) in array.iter().enumerate() {
+                collect_value_paths(format!("{}[{}]", path, index), item, paths);
+            }
+        }
+        _ => paths.push(path),
+    }
+}
+
+fn flatten_json_value(
+    path: String,
+    value: Value,
+    flatten_arrays: bool,
+    flat: &mut Map<String, Value>,
+) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                flatten_json_value(format!("{}.{}", path, key), value, flatten_arrays, flat);
+            }
+        }
+        Value::Array(array) if flatten_arrays && !array.is_empty() => {
+            for (index, item) in array.into_iter().enumerate() {
+                flatten_json_value(format!("{}[{}]", path, index), item, flatten_arrays, flat);
+            }
+        }
+        value => {
+            flat.insert(path, value);
+        }
+    }
+}
+
+fn flatten_yaml_value(path: String, value: YamlValue, flatten_arrays: bool, flat: &mut Mapping) {
+    match value {
+        YamlValue::Mapping(mapping) if !mapping.is_empty() => {
+            for (key, value) in mapping {
+                let path = format!("{}.{}", path, yaml_key(&key));
+                flatten_yaml_value(path, value, flatten_arrays, flat);
+            }
+        }
+        YamlValue::Sequence(sequence) if flatten_arrays && !sequence.is_empty() => {
+            for (index, item) in sequence.into_iter().enumerate() {
+                flatten_yaml_value(format!("{}[{}]", path, index), item, flatten_arrays, flat);
+            }
+        }
+        value => {
+            flat.insert(YamlValue::String(path), value);
+        }
+    }
+}
+
+/// Writes a YAML key as a path segment
+fn yaml_key(key: &YamlValue) -> String {
+    match key {
+        YamlValue::String(s) => s.clone(),
+        YamlValue::Number(number) => number.to_string(),
+        YamlValue::Bool(b) => b.to_string(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim_end()
+            .to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_flatten_json() {
+        let data: Map<String, Value> = serde_json::from_str(
+            r#"{"user": {"name": "John", "address": {"city": "Budapest"}, "meta": {}}, "tags": ["a", {"b": 1}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Value::Object(flatten_json(data.clone(), false)),
+            json!({
+                "user.name": "John",
+                "user.address.city": "Budapest",
+                "user.meta": {},
+                "tags": ["a", {"b": 1}]
+            })
+        );
+        assert_eq!(
+            Value::Object(flatten_json(data, true)),
+            json!({
+                "user.name": "John",
+                "user.address.city": "Budapest",
+                "user.meta": {},
+                "tags[0]": "a",
+                "tags[1].b": 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten_yaml() {
+        let data: Mapping =
+            serde_yaml::from_str("server:\n  port: 80\n  1: one\nlist: [1, 2]\n").unwrap();
+
+        let flat: Mapping =
+            serde_yaml::from_str("server.port: 80\nserver.1: one\nlist[0]: 1\nlist[1]: 2\n")
+                .unwrap();
+        assert_eq!(flatten_yaml(data, true), flat);
+    }
+
+    #[test]
+    fn test_collect_paths() {
+        let doc = json!({
+            "name": "app",
+            "servers": [
+                {"host": "a", "ports": [80, 443]},
+                {"host": "b", "ports": []}
+            ],
+            "matrix": [[1, 2], [3]],
+            "meta": {}
+        });
+
+        let mut paths = collect_paths(&doc);
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                "matrix[0][0]",
+                "matrix[0][1]",
+                "matrix[1][0]",
+                "meta",
+                "name",
+                "servers[0].host",
+                "servers[0].ports[0]",
+                "servers[0].ports[1]",
+                "servers[1].host",
+                "servers[1].ports",
+            ]
+        );
+        assert_eq!(collect_paths(&json!([1, [2]])), vec!["$[0]", "$[1][0]"]);
+        assert_eq!(collect_paths(&json!(5)), vec!["$"]);
+        assert!(collect_paths(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "cli")]
+use crate::{dtfterminal_types::DtfError, file_handler::FileHandler};
+
+/// The SHA-256 hashes of the two files, confirming if they are identical byte for byte.
+/// The structural comparison ignores formatting and key order, the hashes don't.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHashes {
+    pub sha256_a: String,
+    pub sha256_b: String,
+    pub byte_identical: bool,
+}
+
+impl FileHashes {
+    pub fn new(bytes_a: &[u8], bytes_b: &[u8]) -> FileHashes {
+        let (sha256_a, sha256_b) = (sha256_hex(bytes_a), sha256_hex(bytes_b));
+        FileHashes {
+            byte_identical: sha256_a == sha256_b,
+            sha256_a,
+            sha256_b,
+        }
+    }
+
+    /// Hashes the files as they are on the disk, gzipped files without decompressing them
+    #[cfg(feature = "cli")]
+    pub fn of_files(path_a: &str, path_b: &str) -> Result<FileHashes, DtfError> {
+        Ok(FileHashes::new(
+            &FileHandler::read_bytes(path_a)?,
+            &FileHandler::read_bytes(path_b)?,
+        ))
+    }
+
+    /// Lists the hashes and tells how the bytes relate to the structural comparison
+    pub fn render(&self, file_names: (&str, &str), structurally_identical: bool) -> String {
+        let (file_a, file_b) = file_names;
+        let verdict = match (self.byte_identical, structurally_identical) {
+            (true, _) => "The files are identical byte for byte",
+            (false, true) => "The files are structurally identical, but their bytes differ",
+            (false, false) => "The files differ both structurally and byte for byte",
+        };
+        format!(
+            "SHA-256 {}: {}\nSHA-256 {}: {}\n{}",
+            file_a, self.sha256_a, file_b, self.sha256_b, verdict
+        )
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_hashes() {
+        let hashes = FileHashes::new(b"abc", b"abc");
+
+        assert_eq!(
+            hashes.sha256_a,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(hashes.byte_identical);
+        assert!(!FileHashes::new(b"abc", b"abd").byte_identical);
+    }
+
+    #[test]
+    fn test_render_structurally_identical() {
+        let hashes = FileHashes::new(b"{\"a\": 1, \"b\": 2}", b"{\n  \"b\": 2,\n  \"a\": 1\n}\n");
+
+        let rendered = hashes.render(("a.json", "b.json"), true);
+
+        assert!(rendered.starts_with(&format!("SHA-256 a.json: {}\n", hashes.sha256_a)));
+        assert!(rendered.ends_with("The files are structurally identical, but their bytes differ"));
+    }
+}
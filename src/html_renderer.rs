@@ -1,11 +1,13 @@
 use std::fmt::Write;
 
 use html_builder::{Buffer, Html5};
-use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+use libdtf::core::diff_types::ArrayDiff;
 
 use crate::{
-    dtfterminal_types::{DtfError, WorkingContext},
-    utils::{get_display_values_by_column, group_by_key, is_yaml_file},
+    dtfterminal_types::{DiffCategory, DiffCollection, DiffData, DtfError, WorkingContext},
+    side::Side,
+    utils::{cap_array_rows, get_aligned_display_values, group_by_key, shown_diffs},
+    value_types::annotate_type,
 };
 
 struct Classes {
@@ -17,6 +19,8 @@ struct Classes {
     original: &'static str,
     checkmark: &'static str,
     multiply: &'static str,
+    line_diff: &'static str,
+    notice: &'static str,
 }
 
 struct Ids {
@@ -24,6 +28,7 @@ struct Ids {
     type_diff: &'static str,
     value_diff: &'static str,
     array_diff: &'static str,
+    diff_data: &'static str,
 }
 
 struct DisplayText {
@@ -39,6 +44,7 @@ struct DisplayText {
     array_diff_title: &'static str,
     only: &'static str,
     has: &'static str,
+    no_differences: &'static str,
 }
 
 /// Collection of CSS classes used in the HTML output.
@@ -51,6 +57,8 @@ const CLASSES: Classes = Classes {
     original: "original",
     checkmark: "checkmark",
     multiply: "multiply",
+    line_diff: "line-diff",
+    notice: "notice",
 };
 
 /// Collection of HTML IDs used in the HTML output.
@@ -59,6 +67,7 @@ const IDS: Ids = Ids {
     type_diff: "type_diff",
     value_diff: "value_diff",
     array_diff: "array_diff",
+    diff_data: "diff_data",
 };
 
 /// Collection of text displayed in the HTML output.
@@ -75,8 +84,39 @@ const DISPLAY_TEXT: DisplayText = DisplayText {
     array_diff_title: "Array Differences",
     only: "Only",
     has: "has",
+    no_differences: "(no differences)",
 };
 
+/// Marks the rows of the tables by the severity of the difference, if severities are configured.
+const SEVERITY_CSS: &str = "
+        tr.severity-error th {
+            border-left: 4px solid #e06c75;
+        }
+
+        tr.severity-warning th {
+            border-left: 4px solid #e5c07b;
+        }
+
+        tr.severity-info th {
+            border-left: 4px solid #61afef;
+        }";
+
+/// Keeps the line breaks of line differences of multi-line strings.
+const LINE_DIFF_CSS: &str = "
+        td.line-diff {
+            white-space: pre-wrap;
+            font-family: monospace;
+        }";
+
+/// Sets the notice of identical data apart from the rest of the page.
+const NOTICE_CSS: &str = "
+        div.notice {
+            margin: 2em 0;
+            padding: 1em;
+            border-left: 4px solid #98c379;
+            font-size: 1.25em;
+        }";
+
 /// The `HtmlRenderer` struct is responsible for rendering the HTML output.
 pub struct HtmlRenderer<'a> {
     context: &'a WorkingContext,
@@ -87,10 +127,64 @@ impl<'a> HtmlRenderer<'a> {
     pub fn new(context: &'a WorkingContext) -> HtmlRenderer<'a> {
         HtmlRenderer {
             context,
-            css: HtmlRenderer::create_css(context.config.printer_friendly),
+            css: HtmlRenderer::create_css(context.config.printer_friendly)
+                + SEVERITY_CSS
+                + LINE_DIFF_CSS
+                + NOTICE_CSS,
         }
     }
 
+    /// Renders the whole HTML document for the given differences and returns it as a string.
+    /// Only the difference types enabled for rendering and having any entries get a section.
+    pub fn render_document(&mut self, diffs: &DiffCollection) -> Result<String, DtfError> {
+        let mut buf = Buffer::new();
+        let config = &self.context.config;
+        let key_diffs = shown_diffs(&diffs.0, config.render_key_diffs, config);
+        let type_diffs = shown_diffs(&diffs.1, config.render_type_diffs, config);
+        let value_diffs = shown_diffs(&diffs.2, config.render_value_diffs, config);
+        let array_diffs = shown_diffs(&diffs.3, config.render_array_diffs, config);
+
+        self.init_document(
+            &mut buf,
+            (
+                key_diffs.is_some(),
+                type_diffs.is_some(),
+                value_diffs.is_some(),
+                array_diffs.is_some(),
+            ),
+        )?;
+
+        if let Some(key_diffs) = key_diffs {
+            self.render_key_diff_table(&mut buf, key_diffs)?;
+        }
+
+        if let Some(type_diffs) = type_diffs {
+            self.render_type_diff_table(&mut buf, type_diffs)?;
+        }
+
+        if let Some(value_diffs) = value_diffs {
+            self.render_value_diff_table(&mut buf, value_diffs)?;
+        }
+
+        if let Some(array_diffs) = array_diffs {
+            self.render_array_diff_table(&mut buf, array_diffs)?;
+        }
+
+        if key_diffs.is_none()
+            && type_diffs.is_none()
+            && value_diffs.is_none()
+            && array_diffs.is_none()
+        {
+            self.render_identical(&mut buf)?;
+        }
+
+        if config.embed_data {
+            self.render_data_island(&mut buf, diffs)?;
+        }
+
+        Ok(buf.finish())
+    }
+
     /// Initializes the HTML document.
     /// This function writes the doctype, html, head, and body tags to the buffer.
     /// # Arguments
@@ -115,16 +209,19 @@ impl<'a> HtmlRenderer<'a> {
         Ok(())
     }
 
-    /// Writes the title of the HTML document.
+    /// Writes the title of the HTML document, the custom title of the report if there is one.
     fn write_title(&mut self, head: &mut html_builder::Node) -> Result<(), DtfError> {
-        let (file_a, file_b) = self.context.get_file_names();
-        self.write_line(
-            &mut head.title(),
-            &format!(
-                "{} {} {} {}",
-                DISPLAY_TEXT.comparing, file_a, DISPLAY_TEXT.against, file_b
-            ),
-        )
+        let title = match &self.context.config.report_title {
+            Some(report_title) => escape_attribute(report_title),
+            None => {
+                let (file_a, file_b) = self.context.get_file_names();
+                format!(
+                    "{} {} {} {}",
+                    DISPLAY_TEXT.comparing, file_a, DISPLAY_TEXT.against, file_b
+                )
+            }
+        };
+        self.write_line(&mut head.title(), &title)
     }
 
     /// Writes the meta tags of the HTML document.
@@ -138,9 +235,19 @@ impl<'a> HtmlRenderer<'a> {
     }
 
     /// Writes the header of the HTML document including a title a small lead paragraph.
+    /// The custom title and description of the report are used if there are any.
     fn write_header(&mut self, lead: &mut html_builder::Node) -> Result<(), DtfError> {
         let (file_name1, file_name2) = self.context.get_file_names();
-        self.write_line(&mut lead.h1(), DISPLAY_TEXT.title)?;
+        let config = &self.context.config;
+        let title = match &config.report_title {
+            Some(report_title) => escape_attribute(report_title),
+            None => DISPLAY_TEXT.title.to_owned(),
+        };
+        let description = config.report_description.as_deref().map(escape_attribute);
+        self.write_line(&mut lead.h1(), &title)?;
+        if let Some(description) = description {
+            self.write_line(&mut lead.p(), &description)?;
+        }
         let mut lead_p = lead.p();
         self.write_line(&mut lead_p, DISPLAY_TEXT.lead)?;
         self.write_line(
@@ -197,11 +304,53 @@ impl<'a> HtmlRenderer<'a> {
         Ok(())
     }
 
+    /// Renders a notice, that no differences were found.
+    fn render_identical(&mut self, buf: &mut Buffer) -> Result<(), DtfError> {
+        let mut html = buf.html();
+        let mut body = html.body();
+        let message = self.context.config.identical_message();
+        self.write_line(
+            &mut body
+                .div()
+                .attr(&format!("class='{}'", CLASSES.notice))
+                .attr("role='status'"),
+            message,
+        )
+    }
+
+    /// Writes a row noting that a table has no differences, for the tables shown even if empty
+    fn write_no_differences(&mut self, tbody: &mut html_builder::Node) -> Result<(), DtfError> {
+        self.write_line(
+            &mut tbody.tr().td().attr("colspan='3'"),
+            DISPLAY_TEXT.no_differences,
+        )
+    }
+
+    /// Embeds the differences and their summary as JSON, so other tools can read them from the report too.
+    /// `</` is escaped, so values can't close the script element.
+    fn render_data_island(
+        &mut self,
+        buf: &mut Buffer,
+        diffs: &DiffCollection,
+    ) -> Result<(), DtfError> {
+        let data = serde_json::to_string(&DiffData::summarized(diffs, self.context))
+            .map_err(|e| DtfError::DiffError(e.to_string()))?;
+        let mut html = buf.html();
+        let mut body = html.body();
+        self.write_line(
+            &mut body
+                .script()
+                .attr("type='application/json'")
+                .attr(&format!("id='{}'", IDS.diff_data)),
+            &data.replace("</", "<\\/"),
+        )
+    }
+
     /// Renders the key differences table.
     pub fn render_key_diff_table(
         &mut self,
         buf: &mut Buffer,
-        diffs: &Vec<libdtf::core::diff_types::KeyDiff>,
+        diffs: &[libdtf::core::diff_types::KeyDiff],
     ) -> Result<(), DtfError> {
         let mut html = buf.html();
         let mut body = html.body();
@@ -220,20 +369,23 @@ impl<'a> HtmlRenderer<'a> {
         self.write_line(&mut tr1.th().attr("scope='col'"), file_b)?;
 
         let mut tbody = table.tbody();
+        if diffs.is_empty() {
+            self.write_no_differences(&mut tbody)?;
+        }
         for diff in diffs {
             let key = &diff.key;
-            let get_class = |file| {
-                if diff.has.eq(file) {
+            let get_class = |side| {
+                if self.context.key_side(diff) == side {
                     CLASSES.checkmark
                 } else {
                     CLASSES.multiply
                 }
             };
 
-            let class1 = get_class(file_a);
-            let class2 = get_class(file_b);
+            let class1 = get_class(Side::A);
+            let class2 = get_class(Side::B);
 
-            let mut tr = tbody.tr();
+            let mut tr = self.severity_row(tbody.tr(), DiffCategory::Key, key);
             self.write_line(
                 &mut tr
                     .th()
@@ -252,7 +404,7 @@ impl<'a> HtmlRenderer<'a> {
     pub fn render_type_diff_table(
         &mut self,
         buf: &mut Buffer,
-        diffs: &Vec<libdtf::core::diff_types::TypeDiff>,
+        diffs: &[libdtf::core::diff_types::TypeDiff],
     ) -> Result<(), DtfError> {
         let mut html = buf.html();
         let mut body = html.body();
@@ -271,12 +423,15 @@ impl<'a> HtmlRenderer<'a> {
         self.write_line(&mut tr1.th().attr("scope='col'"), file_b)?;
 
         let mut tbody = table.tbody();
+        if diffs.is_empty() {
+            self.write_no_differences(&mut tbody)?;
+        }
         for diff in diffs {
             let key = &diff.key;
             let val1 = &diff.type1;
             let val2 = &diff.type2;
 
-            let mut tr = tbody.tr();
+            let mut tr = self.severity_row(tbody.tr(), DiffCategory::Type, key);
             self.write_line(
                 &mut tr
                     .th()
@@ -294,7 +449,7 @@ impl<'a> HtmlRenderer<'a> {
     pub fn render_value_diff_table(
         &mut self,
         buf: &mut Buffer,
-        diffs: &Vec<libdtf::core::diff_types::ValueDiff>,
+        diffs: &[libdtf::core::diff_types::ValueDiff],
     ) -> Result<(), DtfError> {
         let mut html = buf.html();
         let mut body = html.body();
@@ -313,12 +468,15 @@ impl<'a> HtmlRenderer<'a> {
         self.write_line(&mut tr1.th().attr("scope='col'"), file_b)?;
 
         let mut tbody = table.tbody();
+        if diffs.is_empty() {
+            self.write_no_differences(&mut tbody)?;
+        }
         for diff in diffs {
             let key = &diff.key;
             let val1 = &diff.value1;
             let val2 = &diff.value2;
 
-            let mut tr = tbody.tr();
+            let mut tr = self.severity_row(tbody.tr(), DiffCategory::Value, key);
             self.write_line(
                 &mut tr
                     .th()
@@ -326,8 +484,17 @@ impl<'a> HtmlRenderer<'a> {
                     .attr("scope='row'"),
                 key,
             )?;
-            self.write_line(&mut tr.td(), val1)?;
-            self.write_line(&mut tr.td(), val2)?;
+            let (type1, type2) = self.value_types(key);
+            let mut td1 = self.value_cell(tr.td(), key, val1);
+            self.write_line(
+                &mut td1,
+                &annotate_type(self.display_diff_value(key, val1), type1),
+            )?;
+            let mut td2 = self.value_cell(tr.td(), key, val2);
+            self.write_line(
+                &mut td2,
+                &annotate_type(self.display_diff_value(key, val2), type2),
+            )?;
         }
         Ok(())
     }
@@ -359,37 +526,67 @@ impl<'a> HtmlRenderer<'a> {
             &self.format_array_diff_table_header(false),
         )?;
         let map = group_by_key(diffs);
-        let join_str = if is_yaml_file(self.context.get_file_names().0) {
-            ""
-        } else {
-            ",\n"
-        };
 
         let mut tbody = table.tbody();
+        if diffs.is_empty() {
+            self.write_no_differences(&mut tbody)?;
+        }
         for (key, values) in map {
-            let val1 = get_display_values_by_column(self.context, &values, ArrayDiffDesc::AHas);
-            let val2 = get_display_values_by_column(self.context, &values, ArrayDiffDesc::BHas);
+            let mut rows = get_aligned_display_values(self.context, &values);
+            let summary = cap_array_rows(&mut rows, self.context.config.max_array_diffs_per_key);
+            let value_row_count = rows.len();
+            let row_count = value_row_count + usize::from(summary.is_some());
 
-            let mut tr = tbody.tr();
-            self.write_line(
-                &mut tr
-                    .th()
-                    .attr(&format!("class='{}'", CLASSES.code))
-                    .attr("scope='row'"),
-                key,
-            )?;
-            self.write_line(
-                &mut tr.td().pre().attr(&format!("class='{}'", CLASSES.original)),
-                &val1.join(join_str),
-            )?;
-            self.write_line(
-                &mut tr.td().pre().attr(&format!("class='{}'", CLASSES.original)),
-                &val2.join(join_str),
-            )?;
+            for (index, (val1, val2)) in rows.into_iter().enumerate() {
+                let mut tr = self.severity_row(tbody.tr(), DiffCategory::Array, key);
+                // The key cell spans every row of the group
+                if index == 0 {
+                    self.write_array_key_cell(&mut tr, key, row_count)?;
+                }
+                self.write_line(
+                    &mut self
+                        .full_value_title(tr.td(), &val1)
+                        .pre()
+                        .attr(&format!("class='{}'", CLASSES.original)),
+                    &self.display_value(&val1),
+                )?;
+                self.write_line(
+                    &mut self
+                        .full_value_title(tr.td(), &val2)
+                        .pre()
+                        .attr(&format!("class='{}'", CLASSES.original)),
+                    &self.display_value(&val2),
+                )?;
+            }
+
+            if let Some(summary) = summary {
+                let mut tr = self.severity_row(tbody.tr(), DiffCategory::Array, key);
+                if value_row_count == 0 {
+                    self.write_array_key_cell(&mut tr, key, row_count)?;
+                }
+                self.write_line(&mut tr.td().attr("colspan='2'"), &summary)?;
+            }
         }
         Ok(())
     }
 
+    /// Writes the key cell of an array key's group of rows
+    fn write_array_key_cell(
+        &mut self,
+        tr: &mut html_builder::Node,
+        key: &str,
+        row_count: usize,
+    ) -> Result<(), DtfError> {
+        self.write_line(
+            &mut tr
+                .th()
+                .attr(&format!("class='{}'", CLASSES.code))
+                .attr("scope='row'")
+                .attr(&format!("rowspan='{}'", row_count)),
+            key,
+        )
+    }
+
     /// Creates a column header for the array differences table.
     /// # Arguments
     /// * `is_file_a`: A boolean that determines if the column header is for file A. If false, the column header is for file B.
@@ -400,6 +597,79 @@ impl<'a> HtmlRenderer<'a> {
         format!("{} {} {}", DISPLAY_TEXT.only, file_name, DISPLAY_TEXT.has)
     }
 
+    /// Marks a table cell holding line differences of multi-line strings, so their line breaks are kept.
+    /// Other values get their full value as title if they are truncated.
+    fn value_cell<'b>(
+        &self,
+        td: html_builder::Node<'b>,
+        key: &str,
+        value: &str,
+    ) -> html_builder::Node<'b> {
+        if self.context.is_line_diff(key) {
+            td.attr(&format!("class='{}'", CLASSES.line_diff))
+        } else {
+            self.full_value_title(td, value)
+        }
+    }
+
+    /// Adds the full value as the title of a cell, if the displayed value is truncated.
+    fn full_value_title<'b>(
+        &self,
+        td: html_builder::Node<'b>,
+        value: &str,
+    ) -> html_builder::Node<'b> {
+        match truncate_value(value, self.context.config.value_truncate_len) {
+            Some(_) => td.attr(&format!("title='{}'", escape_attribute(value))),
+            None => td,
+        }
+    }
+
+    /// Get the value of a value difference as displayed in a cell, like `display_value`.
+    /// Line differences of multi-line strings are never truncated.
+    fn display_diff_value(&self, key: &str, value: &str) -> String {
+        if self.context.is_line_diff(key) {
+            return value.to_owned();
+        }
+
+        self.display_value(value)
+    }
+
+    /// Get the value as displayed in a cell: truncated to the configured length.
+    fn display_value(&self, value: &str) -> String {
+        truncate_value(value, self.context.config.value_truncate_len)
+            .unwrap_or_else(|| value.to_owned())
+    }
+
+    /// The types of the values in the two files, if they are to be shown
+    fn value_types(&self, key: &str) -> (Option<&str>, Option<&str>) {
+        if !self.context.config.show_types_in_values {
+            return (None, None);
+        }
+
+        match self.context.value_types_of(key) {
+            Some((type1, type2)) => (Some(type1.as_str()), Some(type2.as_str())),
+            None => (None, None),
+        }
+    }
+
+    /// Marks a table row with the severity of its difference, if severities are configured.
+    fn severity_row<'b>(
+        &self,
+        tr: html_builder::Node<'b>,
+        category: DiffCategory,
+        key: &str,
+    ) -> html_builder::Node<'b> {
+        let config = &self.context.config;
+        if config.uses_severities() {
+            tr.attr(&format!(
+                "class='severity-{}'",
+                config.severity_of(category, key)
+            ))
+        } else {
+            tr
+        }
+    }
+
     /// Writes a line of text to the buffer.
     /// If an error occurs, it's mapped to a `DtfError`.
     fn write_line(&mut self, node: &mut html_builder::Node, text: &str) -> Result<(), DtfError> {
@@ -713,9 +983,34 @@ impl<'a> HtmlRenderer<'a> {
     }
 }
 
+/// Shortens a value to `max_len` characters, the last of them being an ellipsis.
+/// Returns `None` if the value doesn't have to be shortened.
+fn truncate_value(value: &str, max_len: Option<usize>) -> Option<String> {
+    let max_len = max_len?;
+    if value.chars().count() <= max_len {
+        return None;
+    }
+
+    let truncated: String = value.chars().take(max_len.saturating_sub(1)).collect();
+    Some(format!("{}…", truncated))
+}
+
+/// Escapes a value to be used in a single or double quoted HTML attribute.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::dtfterminal_types::ConfigBuilder;
+    use crate::{
+        dtfterminal_types::{ConfigBuilder, DiffStats, DEFAULT_IDENTICAL_MESSAGE},
+        side::SidedKeyDiff,
+    };
 
     use super::*;
 
@@ -733,6 +1028,181 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_truncate_value() {
+        assert_eq!(
+            truncate_value("abcdefghijkl", Some(5)),
+            Some("abcd…".to_owned())
+        );
+        assert_eq!(truncate_value("abcde", Some(5)), None);
+        assert_eq!(truncate_value("abcdefghijkl", None), None);
+        assert_eq!(
+            truncate_value("árvíztűrő", Some(4)),
+            Some("árv…".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_render_value_diff_table_truncated() {
+        let mut working_context = get_working_context();
+        working_context.config.value_truncate_len = Some(10);
+        let mut renderer = HtmlRenderer::new(&working_context);
+        let diffs = vec![libdtf::core::diff_types::ValueDiff {
+            key: "description".to_owned(),
+            value1: "a very long description".to_owned(),
+            value2: "short".to_owned(),
+        }];
+        let mut buf = html_builder::Buffer::new();
+
+        renderer.render_value_diff_table(&mut buf, &diffs).unwrap();
+
+        let html = buf.finish();
+        assert!(html.contains("a very lo…"));
+        assert!(html.contains("title='a very long description'"));
+        assert!(html.contains("short"));
+        assert!(!html.contains("title='short'"));
+    }
+
+    #[test]
+    fn test_render_document_embed_data() {
+        let mut working_context = get_working_context();
+        working_context.config.embed_data = true;
+        let mut renderer = HtmlRenderer::new(&working_context);
+        let value_diffs = vec![libdtf::core::diff_types::ValueDiff {
+            key: "script".to_owned(),
+            value1: "</script>".to_owned(),
+            value2: "none".to_owned(),
+        }];
+        let key_diffs = vec![libdtf::core::diff_types::KeyDiff {
+            key: "name".to_owned(),
+            has: "FileA.yaml".to_owned(),
+            misses: "FileB.yaml".to_owned(),
+        }];
+
+        let html = renderer
+            .render_document(&(
+                Some(key_diffs.clone()),
+                None,
+                Some(value_diffs.clone()),
+                None,
+            ))
+            .unwrap();
+
+        let start_tag = "<script type='application/json' id='diff_data'>";
+        let start = html.find(start_tag).unwrap() + start_tag.len();
+        let end = start + html[start..].find("</script>").unwrap();
+        let data: DiffData = serde_json::from_str(&html[start..end]).unwrap();
+        let sided: Vec<SidedKeyDiff> = key_diffs
+            .into_iter()
+            .map(|key_diff| SidedKeyDiff {
+                key_diff,
+                side: Some(Side::A),
+            })
+            .collect();
+        assert_eq!(data.key_diffs, sided);
+        assert!(data.type_diffs.is_empty());
+        assert_eq!(data.value_diffs, value_diffs);
+        assert!(data.array_diffs.is_empty());
+
+        let summary = data.summary.unwrap();
+        assert_eq!(
+            summary.stats,
+            DiffStats {
+                key_diffs: 1,
+                type_diffs: 0,
+                value_diffs: 1,
+                array_diffs: 0,
+            }
+        );
+        assert_eq!(summary.total, 2);
+        assert_eq!(summary.file_a, "FileA.yaml");
+        assert_eq!(summary.config.file_b, "FileB.yaml");
+        assert!(chrono::DateTime::parse_from_rfc3339(&summary.timestamp).is_ok());
+    }
+
+    #[test]
+    fn test_render_document_empty_tables() {
+        let mut working_context = get_working_context();
+        working_context.config.render_key_diffs = true;
+        working_context.config.render_value_diffs = true;
+        let diffs = (
+            Some(vec![]),
+            None,
+            Some(vec![libdtf::core::diff_types::ValueDiff {
+                key: "name".to_owned(),
+                value1: "a".to_owned(),
+                value2: "b".to_owned(),
+            }]),
+            None,
+        );
+
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&diffs)
+            .unwrap();
+        assert!(!html.contains("id='key_diff'"));
+        assert!(!html.contains(DISPLAY_TEXT.no_differences));
+
+        working_context.config.show_empty_tables = true;
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&diffs)
+            .unwrap();
+        assert!(html.contains("id='key_diff'"));
+        assert!(html.contains(DISPLAY_TEXT.no_differences));
+        assert!(html.contains("id='value_diff'"));
+        // Only the requested categories are shown
+        assert!(!html.contains("id='type_diff'"));
+    }
+
+    #[test]
+    fn test_render_document_identical() {
+        let mut working_context = get_working_context();
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&(None, None, None, None))
+            .unwrap();
+        assert!(html.contains("<div class='notice' role='status'>"));
+        assert!(html.contains(DEFAULT_IDENTICAL_MESSAGE));
+
+        working_context.config.identical_message = Some("No drift".to_owned());
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&(None, None, None, None))
+            .unwrap();
+        assert!(html.contains("No drift"));
+        assert!(!html.contains(DEFAULT_IDENTICAL_MESSAGE));
+    }
+
+    #[test]
+    fn test_render_document_report_title() {
+        let mut working_context = get_working_context();
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&(None, None, None, None))
+            .unwrap();
+        assert!(html.contains("Comparing FileA.yaml against FileB.yaml"));
+        assert!(html.contains("Data Differences"));
+
+        working_context.config.report_title = Some("Staging drift".to_owned());
+        working_context.config.report_description = Some("Nightly check".to_owned());
+        let html = HtmlRenderer::new(&working_context)
+            .render_document(&(None, None, None, None))
+            .unwrap();
+        let text_of = |tag: &str| {
+            let start = html.find(&format!("<{}>", tag)).unwrap() + tag.len() + 2;
+            let end = start + html[start..].find(&format!("</{}>", tag)).unwrap();
+            html[start..end].trim().to_owned()
+        };
+        assert_eq!(text_of("title"), "Staging drift");
+        assert_eq!(text_of("h1"), "Staging drift");
+        assert!(html.contains("Nightly check"));
+        assert!(!html.contains("Data Differences"));
+    }
+
+    #[test]
+    fn test_escape_attribute() {
+        assert_eq!(
+            escape_attribute(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
     #[test]
     fn test_write_line() {
         let working_context = get_working_context();
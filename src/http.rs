@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{compare::DataFormat, dtfterminal_types::DtfError};
+
+/// The documents downloaded to detect their format, kept until they are read
+static DOWNLOADED: Mutex<BTreeMap<String, RemoteDocument>> = Mutex::new(BTreeMap::new());
+
+/// A document downloaded from a URL
+pub struct RemoteDocument {
+    pub body: String,
+    pub format: DataFormat,
+}
+
+/// Checks if a path points to a document on the web
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Downloads a document. Any response other than `200 OK` is an error.
+/// The body is limited to `max_size` bytes.
+pub fn fetch(url: &str, max_size: Option<u64>) -> Result<RemoteDocument, DtfError> {
+    let response = ureq::get(url).call().map_err(|e| match e {
+        ureq::Error::Status(status, _) => {
+            DtfError::HttpError(format!("{} responded with status {}", url, status))
+        }
+        e => DtfError::HttpError(format!("Could not fetch {}: {}", url, e)),
+    })?;
+    if response.status() != 200 {
+        return Err(DtfError::HttpError(format!(
+            "{} responded with status {}",
+            url,
+            response.status()
+        )));
+    }
+
+    let content_type = response.content_type().to_owned();
+    // Reading one byte over the limit is enough to tell if it's exceeded
+    let limit = max_size.map_or(u64::MAX, |max_size| max_size.saturating_add(1));
+    let mut body = vec![];
+    response
+        .into_reader()
+        .take(limit)
+        .read_to_end(&mut body)
+        .map_err(DtfError::IoError)?;
+
+    if let Some(max_size) = max_size.filter(|&max_size| body.len() as u64 > max_size) {
+        return Err(DtfError::LimitError(format!(
+            "{} is larger than {} bytes",
+            url, max_size
+        )));
+    }
+
+    let body = String::from_utf8(body).map_err(|e| DtfError::ParseError {
+        path: url.to_owned(),
+        source: Box::new(e),
+    })?;
+    let format = detect_format(&content_type, &body);
+
+    Ok(RemoteDocument { body, format })
+}
+
+/// Downloads a document to detect its format. The body is kept, so `fetch_kept` doesn't download it again.
+pub fn detect_remote_format(url: &str, max_size: Option<u64>) -> Result<DataFormat, DtfError> {
+    let mut downloaded = DOWNLOADED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(document) = downloaded.get(url) {
+        return Ok(document.format);
+    }
+
+    let document = fetch(url, max_size)?;
+    let format = document.format;
+    downloaded.insert(url.to_owned(), document);
+    Ok(format)
+}
+
+/// Takes the document downloaded by `detect_remote_format`, or downloads it if it wasn't
+pub fn fetch_kept(url: &str, max_size: Option<u64>) -> Result<RemoteDocument, DtfError> {
+    let kept = DOWNLOADED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(url);
+    match kept {
+        Some(document) => Ok(document),
+        None => fetch(url, max_size),
+    }
+}
+
+/// Infers the format of a document from its content type.
+/// If the content type is not specific, JSON is recognized by its first character.
+fn detect_format(content_type: &str, body: &str) -> DataFormat {
+    if content_type.contains("json") {
+        DataFormat::Json
+    } else if content_type.contains("yaml") {
+        DataFormat::Yaml
+    } else if body.trim_start().starts_with(['{', '[']) {
+        DataFormat::Json
+    } else {
+        DataFormat::Yaml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert_eq!(is_url("https://example.com/data.json"), true);
+        assert_eq!(is_url("http://localhost:8080/data"), true);
+        assert_eq!(is_url("test_data/json/person1.json"), false);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            detect_format("application/json; charset=utf-8", "a: 1"),
+            DataFormat::Json
+        );
+        assert_eq!(detect_format("application/yaml", "{}"), DataFormat::Yaml);
+        assert_eq!(detect_format("text/plain", " {\"a\": 1}"), DataFormat::Json);
+        assert_eq!(detect_format("text/plain", "a: 1"), DataFormat::Yaml);
+    }
+
+    #[test]
+    fn test_fetch() {
+        let (url, server) = serve_once("200 OK", "text/plain", r#"{"name": "John"}"#);
+
+        let document = fetch(&url, None).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(document.body, r#"{"name": "John"}"#);
+        assert_eq!(document.format, DataFormat::Json);
+    }
+
+    #[test]
+    fn test_fetch_not_found() {
+        let (url, server) = serve_once("404 Not Found", "text/plain", "Not Found");
+
+        let result = fetch(&url, None);
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(DtfError::HttpError(msg)) if msg.contains("404")));
+    }
+
+    #[test]
+    fn test_fetch_over_max_size() {
+        let (url, server) = serve_once("200 OK", "application/yaml", "name: John\n");
+
+        let result = fetch(&url, Some(5));
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+    }
+
+    #[test]
+    fn test_fetch_kept_after_detecting_format() {
+        let (url, server) = serve_once("200 OK", "application/yaml", "name: John\n");
+
+        let format = detect_remote_format(&url, Some(100)).unwrap();
+        server.join().unwrap();
+        // The server is gone, so the body can only come from the first download
+        let document = fetch_kept(&url, Some(100)).unwrap();
+
+        assert_eq!(format, DataFormat::Yaml);
+        assert_eq!(document.body, "name: John\n");
+    }
+
+    #[test]
+    fn test_detect_remote_format_over_max_size() {
+        let (url, server) = serve_once("200 OK", "application/yaml", "name: John\n");
+
+        let result = detect_remote_format(&url, Some(5));
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+    }
+
+    /// Starts a server answering a single request with the given response
+    fn serve_once(
+        status: &'static str,
+        content_type: &'static str,
+        body: &'static str,
+    ) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        (url, server)
+    }
+}
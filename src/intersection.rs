@@ -0,0 +1,128 @@
+use libdtf::core::diff_types::ArrayDiff;
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    array_indexes::{
+        find_json_array_indexes, find_yaml_array_indexes, is_in_first_file, ArrayIndexes,
+        IndexCursor,
+    },
+    dtfterminal_types::DiffCollection,
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// Drops the array differences of the JSON items that are only there because one array is longer:
+/// the ones past the end of the other array, or in an array the other file doesn't have.
+/// Items at an index both arrays have are changed, not added or removed, so they are kept.
+pub fn drop_json_presence_only_items(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let array_diff = array_diff.map(|array_diffs| {
+        let array_indexes = find_json_array_indexes(&array_diffs, data1, data2);
+        drop_presence_only_items(array_diffs, &array_indexes, |key, in_a| {
+            let other = if in_a { data2 } else { data1 };
+            lookup_json(other, key)
+                .and_then(Value::as_array)
+                .map(Vec::len)
+        })
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// Drops the array differences of the YAML items that are only there because one sequence is longer,
+/// like `drop_json_presence_only_items`
+pub fn drop_yaml_presence_only_items(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let array_diff = array_diff.map(|array_diffs| {
+        let array_indexes = find_yaml_array_indexes(&array_diffs, data1, data2);
+        drop_presence_only_items(array_diffs, &array_indexes, |key, in_a| {
+            let other = if in_a { data2 } else { data1 };
+            lookup_yaml(other, key)
+                .and_then(YamlValue::as_sequence)
+                .map(Vec::len)
+        })
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// Keeps the items at an index the other array has too. Items with an unknown index are kept as well.
+fn drop_presence_only_items(
+    array_diffs: Vec<ArrayDiff>,
+    array_indexes: &ArrayIndexes,
+    other_len: impl Fn(&str, bool) -> Option<usize>,
+) -> Vec<ArrayDiff> {
+    let mut cursor = IndexCursor::default();
+    array_diffs
+        .into_iter()
+        .filter(|ad| {
+            let in_a = is_in_first_file(&ad.descriptor);
+            match cursor.next(array_indexes, &ad.key, in_a, &ad.value) {
+                Some(index) => other_len(&ad.key, in_a).is_some_and(|len| index < len),
+                None => true,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::ArrayDiffDesc;
+
+    use super::*;
+
+    fn array_diff(descriptor: ArrayDiffDesc, value: &str) -> ArrayDiff {
+        ArrayDiff {
+            descriptor,
+            key: "tags".to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_drop_json_presence_only_items() {
+        let data1: Map<String, Value> = serde_json::from_str(r#"{"tags": ["a", "x"]}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"tags": ["b", "x", "c", "d"]}"#).unwrap();
+        let diffs = (
+            None,
+            None,
+            None,
+            Some(vec![
+                array_diff(ArrayDiffDesc::AHas, "a"),
+                array_diff(ArrayDiffDesc::BHas, "b"),
+                array_diff(ArrayDiffDesc::BHas, "c"),
+                array_diff(ArrayDiffDesc::BHas, "d"),
+            ]),
+        );
+
+        let (_, _, _, array_diff) = drop_json_presence_only_items(diffs, &data1, &data2);
+
+        let values: Vec<String> = array_diff.unwrap().into_iter().map(|ad| ad.value).collect();
+        assert_eq!(values, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_drop_yaml_presence_only_items_missing_array() {
+        let data1: Mapping = serde_yaml::from_str("tags:\n  - a\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("name: dtf\n").unwrap();
+        let diffs = (
+            None,
+            None,
+            None,
+            Some(vec![array_diff(ArrayDiffDesc::AHas, "a")]),
+        );
+
+        let (_, _, _, array_diff) = drop_yaml_presence_only_items(diffs, &data1, &data2);
+
+        assert!(array_diff.unwrap().is_empty());
+    }
+}
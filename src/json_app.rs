@@ -1,18 +1,50 @@
+use std::rc::Rc;
+
+#[cfg(feature = "cli")]
+use crate::key_order::{find_json_key_order_diffs, KeyOrderDiff};
+#[cfg(feature = "schema")]
+use crate::schema::validate;
+use crate::{
+    array_indexes::{find_json_array_indexes, ArrayIndexes},
+    canonical::canonicalize_json,
+    checked_data::{check_in_chunks, process_diffs},
+    common::common_json,
+    depth::{check_json_depth, check_json_map_depth},
+    dtfterminal_types::{Config, DiffCollection, DtfError, LibWorkingContext, WorkingContext},
+    envelope::unwrap_json,
+    flatten::flatten_json,
+    key_walk::find_json_key_diffs,
+    line_diff::{find_json_line_diff_keys, LineDiffKeys},
+    matrix::compare_json_matrices,
+    redact::redact_json,
+    sample::{sample_json_data, SampledArray, SampledData},
+    side::{find_json_key_sides, KeySides},
+    unicode::{normalize_json, restore_originals},
+    utils::{check_diff_limit, log_diffs, merge_ordered_array_diffs, DiffLimit},
+    value_types::{find_json_value_types, ValueTypes},
+};
+#[cfg(feature = "cli")]
 use crate::{
-    dtfterminal_types::{DiffCollection, WorkingContext},
-    file_handler::FileHandler,
+    defaults::apply_json_defaults, document_cache::DocumentCache, file_handler::FileHandler,
+    first_diff::find_first_diff,
 };
 
 use libdtf::{
     core::diff_types::{ArrayDiff, Checker, KeyDiff, TypeDiff, ValueDiff},
     json::diff_types::CheckingData,
 };
+use log::debug;
 use serde_json::{Map, Value};
 
+/// The key the roots other than objects are compared under
+const ROOT_KEY: &str = "$";
+
 /// Responsible for the main functionality of the app. Makes sure everything runs in the correct order.
 pub struct JsonApp {
-    data1: Map<String, Value>,
-    data2: Map<String, Value>,
+    data1: Rc<Map<String, Value>>,
+    data2: Rc<Map<String, Value>>,
+    /// The data with the long arrays sampled, if only a sample of them is compared
+    sampled: Option<SampledData<Map<String, Value>>>,
     context: WorkingContext,
 }
 
@@ -20,67 +52,431 @@ impl JsonApp {
     /// Creates a new App instance
     /// 1. Parses the command line arguments
     /// 2. Checks for differences and stores them
-    pub fn new(path1: String, path2: String, context: WorkingContext) -> JsonApp {
-        let data1 = FileHandler::read_json_file(&path1).expect("Could not read JSON file");
-        let data2 = FileHandler::read_json_file(&path2).expect("Could not read JSON file");
+    /// If a defaults file is configured, its values fill in the keys missing from both files.
+    /// TOML files are normalized into JSON values, so they can be compared against JSON files.
+    #[cfg(feature = "cli")]
+    pub fn new(path1: String, path2: String, context: WorkingContext) -> Result<JsonApp, DtfError> {
+        JsonApp::new_cached(path1, path2, context, &mut DocumentCache::default())
+    }
+
+    /// Creates a new App instance, reading the files through the cache
+    #[cfg(feature = "cli")]
+    pub fn new_cached(
+        path1: String,
+        path2: String,
+        context: WorkingContext,
+        cache: &mut DocumentCache,
+    ) -> Result<JsonApp, DtfError> {
+        let (max_size, jsonc) = (context.config.max_size, context.config.jsonc);
+        let (mut data1, mut data2) = prepare_data(
+            cache.read_as_json(&path1, max_size, jsonc)?,
+            cache.read_as_json(&path2, max_size, jsonc)?,
+            &context,
+        )?;
+        if let Some(defaults_path) = &context.config.defaults {
+            let defaults = FileHandler::read_as_json(defaults_path, max_size, jsonc)?;
+            apply_json_defaults(Rc::make_mut(&mut data1), &defaults);
+            apply_json_defaults(Rc::make_mut(&mut data2), &defaults);
+        }
+        Ok(JsonApp::from_shared(data1, data2, context))
+    }
+
+    /// Creates a new App instance from already parsed data
+    pub fn from_data(
+        data1: Map<String, Value>,
+        data2: Map<String, Value>,
+        context: WorkingContext,
+    ) -> JsonApp {
+        JsonApp::from_shared(Rc::new(data1), Rc::new(data2), context)
+    }
+
+    /// Creates a new App instance from parsed data shared with others, like the cached baseline file
+    pub fn from_shared(
+        data1: Rc<Map<String, Value>>,
+        data2: Rc<Map<String, Value>>,
+        context: WorkingContext,
+    ) -> JsonApp {
+        let sampled = context
+            .config
+            .sample
+            .map(|size| sample_json_data(&data1, &data2, size, context.config.sample_seed));
         JsonApp {
             data1,
             data2,
+            sampled,
             context,
         }
     }
 
+    /// Finds the objects having the same keys in a different order in the two files
+    #[cfg(feature = "cli")]
+    pub fn find_key_order_diffs(&self) -> Vec<KeyOrderDiff> {
+        find_json_key_order_diffs(&self.data1, &self.data2)
+    }
+
+    /// Validates the first file against the JSON Schema in the second one
+    #[cfg(feature = "schema")]
+    pub fn validate_against_schema(&self) -> Result<DiffCollection, DtfError> {
+        validate(&self.data1, &self.data2, &self.context)
+    }
+
+    /// Looks up where the items of the array differences are in the two files
+    pub fn find_array_indexes(&self, array_diffs: &[ArrayDiff]) -> ArrayIndexes {
+        find_json_array_indexes(array_diffs, &self.data1, &self.data2)
+    }
+
+    /// Adds which file has the key of each key difference to the differences of the two files.
+    /// The sides are looked up in the data the differences were found in, so files with the same name are told apart.
+    pub fn with_key_sides(&self, diffs: DiffCollection) -> (DiffCollection, KeySides) {
+        let key_diffs = diffs.0.as_deref().unwrap_or_default();
+        let key_sides = find_json_key_sides(key_diffs, &self.data1, &self.data2);
+        (diffs, key_sides)
+    }
+
+    /// Looks up the types of the differing values in the two files
+    pub fn find_value_types(&self, value_diffs: &[ValueDiff]) -> ValueTypes {
+        find_json_value_types(value_diffs, &self.data1, &self.data2)
+    }
+
+    /// Looks up which value differences are of multi-line strings
+    pub fn find_line_diff_keys(&self, value_diffs: &[ValueDiff]) -> LineDiffKeys {
+        find_json_line_diff_keys(value_diffs, &self.data1, &self.data2)
+    }
+
+    /// Finds only the first difference, without comparing the rest of the files.
+    /// The files are checked key by key like in `perform_new_check`, stopping at the first key with a difference.
+    #[cfg(feature = "cli")]
+    pub fn find_first_diff(&self) -> Result<DiffCollection, DtfError> {
+        check_json_map_depth(&self.data1)?;
+        check_json_map_depth(&self.data2)?;
+        let (data1, data2) = self.compared_data();
+        let mut limit = DiffLimit::new(&self.context.config);
+        find_first_diff(data1, data2, &self.context.config, |chunk1, chunk2| {
+            let diffs = self.check_compared_data(&chunk1, &chunk2, &mut limit)?;
+            Ok(process_diffs(
+                diffs,
+                self.data1.as_ref(),
+                self.data2.as_ref(),
+                &self.context,
+            ))
+        })
+    }
+
+    /// The arrays of which only a sample of the items was compared
+    pub fn sampled_arrays(&self) -> Vec<SampledArray> {
+        self.sampled
+            .as_ref()
+            .map(SampledData::sampled_arrays)
+            .unwrap_or_default()
+    }
+
+    /// Serializes the structure the two files agree on, redacted like the differences
+    pub fn common(&self) -> Result<String, DtfError> {
+        let common = Value::Object(common_json(&self.data1, &self.data2));
+        let common = redact_json(common, &self.context.config.redact_patterns);
+        serde_json::to_string_pretty(&common).map_err(|e| DtfError::DiffError(e.to_string()))
+    }
+
+    /// Checks for differences between the two files, with the file having the key of each key difference
+    pub fn perform_sided_check(&self) -> Result<(DiffCollection, KeySides), DtfError> {
+        Ok(self.with_key_sides(self.perform_new_check()?))
+    }
+
     /// Checks for differences between the two files
-    pub fn perform_new_check(&self) -> DiffCollection {
-        self.check_for_diffs(&self.data1, &self.data2)
+    pub fn perform_new_check(&self) -> Result<DiffCollection, DtfError> {
+        check_json_map_depth(&self.data1)?;
+        check_json_map_depth(&self.data2)?;
+        let (data1, data2) = self.compared_data();
+        let mut limit = DiffLimit::new(&self.context.config);
+        // With a maximum the files are checked key by key, so the check stops at the key exceeding it
+        let diffs = if self.context.config.max_diffs.is_some() {
+            check_in_chunks(data1, data2, |chunk1, chunk2| {
+                self.check_compared_data(&chunk1, &chunk2, &mut limit)
+            })?
+        } else {
+            self.check_compared_data(data1, data2, &mut limit)?
+        };
+        Ok(process_diffs(
+            diffs,
+            self.data1.as_ref(),
+            self.data2.as_ref(),
+            &self.context,
+        ))
+    }
+
+    /// The data the check compares: the files, or their samples if only a sample of the long arrays is compared
+    fn compared_data(&self) -> (&Map<String, Value>, &Map<String, Value>) {
+        match &self.sampled {
+            Some(sampled) => (&sampled.data1, &sampled.data2),
+            None => (self.data1.as_ref(), self.data2.as_ref()),
+        }
+    }
+
+    /// Checks the compared data, or a chunk of it, for differences.
+    /// The keys of the differences are of the files, with the original strings and array indexes.
+    /// The differences are counted by the limit shared by the chunks.
+    fn check_compared_data(
+        &self,
+        data1: &Map<String, Value>,
+        data2: &Map<String, Value>,
+        limit: &mut DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = if self.context.config.normalize_unicode {
+            let (data1, originals1) = normalize_json(data1);
+            let (data2, originals2) = normalize_json(data2);
+            let diffs = self.check_with_matrices(&data1, &data2, limit)?;
+            restore_originals(diffs, &originals1, &originals2)
+        } else {
+            self.check_with_matrices(data1, data2, limit)?
+        };
+        let diffs = match &self.sampled {
+            Some(sampled) => sampled.restore_indexes(diffs),
+            None => diffs,
+        };
+        limit.add_diffs(&diffs)?;
+        Ok(diffs)
+    }
+
+    /// Checks for differences between the two files, comparing the matrices cell by cell if asked to.
+    /// The matrices are looked up in the compared data, so their cells are normalized and sampled like the rest.
+    fn check_with_matrices(
+        &self,
+        data1: &Map<String, Value>,
+        data2: &Map<String, Value>,
+        limit: &DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = self.check_with_ordered_arrays(data1, data2, limit)?;
+        if self.context.config.matrices {
+            Ok(compare_json_matrices(
+                diffs,
+                data1,
+                data2,
+                self.context.config.intersection,
+            ))
+        } else {
+            Ok(diffs)
+        }
+    }
+
+    /// Checks for differences between the two files, checking the arrays under the ordered paths as ordered.
+    /// Both checks count their differences on top of those found so far, the merged ones are counted by the caller.
+    fn check_with_ordered_arrays(
+        &self,
+        data1: &Map<String, Value>,
+        data2: &Map<String, Value>,
+        limit: &DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = self.check_for_diffs(
+            data1,
+            data2,
+            &self.context.lib_working_context,
+            &mut limit.clone(),
+        )?;
+        log_diffs(&diffs);
+        if self.context.has_ordered_array_paths() {
+            let ordered_diffs = self.check_for_diffs(
+                data1,
+                data2,
+                &self.context.ordered_lib_working_context(),
+                &mut limit.clone(),
+            )?;
+            debug!(
+                "Checked the arrays under {:?} as ordered",
+                self.context.config.ordered_array_paths
+            );
+            Ok(merge_ordered_array_diffs(
+                diffs,
+                ordered_diffs,
+                &self.context.config.ordered_array_paths,
+            ))
+        } else {
+            Ok(diffs)
+        }
     }
 
     /// Checks for differences between the two files
+    /// In intersection mode key differences are not checked, they are caused by presence only.
+    /// Stops with an error as soon as a category exceeds the maximum number of differences.
     fn check_for_diffs(
         &self,
         data1: &Map<String, Value>,
         data2: &Map<String, Value>,
-    ) -> DiffCollection {
-        let key_diff = if self.context.config.check_for_key_diffs {
+        lib_working_context: &LibWorkingContext,
+        limit: &mut DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let config = &self.context.config;
+        let key_diff = if config.check_for_key_diffs && config.intersection {
+            None
+        } else if config.check_for_key_diffs && config.compare_keys_only {
+            let file_names = (
+                lib_working_context.file_a.name.as_str(),
+                lib_working_context.file_b.name.as_str(),
+            );
+            Some(find_json_key_diffs(data1, data2, file_names, limit)?)
+        } else if config.check_for_key_diffs {
             let mut checking_data: CheckingData<KeyDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let type_diff = if self.context.config.check_for_type_diffs {
             let mut checking_data: CheckingData<TypeDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let value_diff = if self.context.config.check_for_value_diffs {
             let mut checking_data: CheckingData<ValueDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let array_diff = if self.context.config.check_for_array_diffs {
+            let lib_working_context = self
+                .context
+                .array_diff_lib_working_context(lib_working_context);
             let mut checking_data: CheckingData<ArrayDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, &lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
 
-        (key_diff, type_diff, value_diff, array_diff)
+        Ok((key_diff, type_diff, value_diff, array_diff))
+    }
+}
+
+/// Checks two JSON strings for differences without touching the filesystem
+pub fn compare_json_str(
+    a: &str,
+    b: &str,
+    context: &WorkingContext,
+) -> Result<DiffCollection, DtfError> {
+    compare_json_str_sided(a, b, context).map(|(diffs, _)| diffs)
+}
+
+/// Checks two JSON strings for differences, with the file having the key of each key difference
+pub(crate) fn compare_json_str_sided(
+    a: &str,
+    b: &str,
+    context: &WorkingContext,
+) -> Result<(DiffCollection, KeySides), DtfError> {
+    compare_json_data(parse_json_str(a)?, parse_json_str(b)?, context)
+}
+
+/// Checks two JSON values of any type for differences, without unwrapping them into objects first.
+/// If either of them isn't an object, both are compared under the `$` key,
+/// so e.g. the differing items of two arrays are reported at `$` and two different numbers at `$`.
+pub fn compare_json_values(
+    a: &Value,
+    b: &Value,
+    context: &WorkingContext,
+) -> Result<DiffCollection, DtfError> {
+    check_json_depth(a)?;
+    check_json_depth(b)?;
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => compare_json_data(a.clone(), b.clone(), context),
+        _ => compare_json_data(wrap_root(a), wrap_root(b), context),
     }
+    .map(|(diffs, _)| diffs)
+}
+
+/// Checks two JSON or TOML files for differences, reading them through the cache.
+/// Comparing a single baseline file against many others only parses it once this way.
+#[cfg(feature = "cli")]
+pub fn compare_json_files(
+    path1: &str,
+    path2: &str,
+    context: &WorkingContext,
+    cache: &mut DocumentCache,
+) -> Result<DiffCollection, DtfError> {
+    let json_app = JsonApp::new_cached(path1.to_owned(), path2.to_owned(), context.clone(), cache)?;
+    let diffs = json_app.perform_new_check()?;
+    check_diff_limit(&diffs, &context.config)?;
+    Ok(diffs)
+}
+
+fn compare_json_data(
+    data1: Map<String, Value>,
+    data2: Map<String, Value>,
+    context: &WorkingContext,
+) -> Result<(DiffCollection, KeySides), DtfError> {
+    let (data1, data2) = prepare_data(Rc::new(data1), Rc::new(data2), context)?;
+    let (diffs, key_sides) =
+        JsonApp::from_shared(data1, data2, context.clone()).perform_sided_check()?;
+    check_diff_limit(&diffs, &context.config)?;
+    Ok((diffs, key_sides))
+}
+
+/// Puts a value under the root key, so it can be compared like an object
+fn wrap_root(value: &Value) -> Map<String, Value> {
+    let mut data = Map::new();
+    data.insert(ROOT_KEY.to_owned(), value.clone());
+    data
+}
+
+/// Navigates both files to the objects to compare and rewrites them into their canonical form, if they are configured
+fn prepare_data(
+    data1: Rc<Map<String, Value>>,
+    data2: Rc<Map<String, Value>>,
+    context: &WorkingContext,
+) -> Result<(Rc<Map<String, Value>>, Rc<Map<String, Value>>), DtfError> {
+    let config = &context.config;
+    let (file_name_a, file_name_b) = context.get_file_names();
+    Ok((
+        prepare_file(data1, config.unwrap_a.as_deref(), file_name_a, config)?,
+        prepare_file(data2, config.unwrap_b.as_deref(), file_name_b, config)?,
+    ))
+}
+
+/// Navigates a file to the object to compare and rewrites it, if it's configured.
+/// The data is only copied if it's rewritten, so a cached file stays shared otherwise.
+fn prepare_file(
+    data: Rc<Map<String, Value>>,
+    unwrap: Option<&str>,
+    file_name: &str,
+    config: &Config,
+) -> Result<Rc<Map<String, Value>>, DtfError> {
+    check_json_map_depth(&data)?;
+    let unwraps = unwrap.is_some_and(|path| !path.is_empty());
+    if !unwraps && !config.canonicalize && !config.flatten {
+        return Ok(data);
+    }
+    let data = unwrap_json(Rc::unwrap_or_clone(data), unwrap, file_name)?;
+    let data = if config.canonicalize {
+        canonicalize_json(data, config.trim_strings)
+    } else {
+        data
+    };
+    let data = if config.flatten {
+        flatten_json(data, config.flatten_arrays)
+    } else {
+        data
+    };
+    Ok(Rc::new(data))
+}
+
+/// Parses a JSON string into a map of the data
+fn parse_json_str(json_str: &str) -> Result<Map<String, Value>, DtfError> {
+    serde_json::from_str(json_str)
+        .map_err(|e| DtfError::DiffError(format!("Could not parse JSON: {}", e)))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::dtfterminal_types::ConfigBuilder;
+    use crate::{
+        depth::MAX_DEPTH,
+        dtfterminal_types::{ArrayDiffDesc, ConfigBuilder, TypeNaming},
+        sample::sampling_note,
+        utils::count_diffs,
+    };
 
     use super::*;
 
@@ -91,8 +487,9 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_some(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -106,8 +503,9 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_some(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -121,8 +519,9 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_some(), true);
@@ -136,8 +535,9 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -151,8 +551,9 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_some(), true);
         assert_eq!(diffs.1.is_some(), true);
         assert_eq!(diffs.2.is_some(), true);
@@ -167,14 +568,727 @@ mod tests {
             "test_data/json/person3.json".to_string(),
             "test_data/json/person4.json".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
         assert_eq!(diffs.3.is_none(), true);
     }
 
+    #[test]
+    fn test_jsonc_against_json() {
+        let json_app = JsonApp::new(
+            "test_data/json/person1.jsonc".to_string(),
+            "test_data/json/person1.json".to_string(),
+            get_working_context(true, true, true, true),
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
+        assert_eq!(count_diffs(&diffs), 0);
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let working_context = get_working_context(true, true, true, true);
+        let result = JsonApp::new(
+            "test_data/json/empty.json".to_string(),
+            "test_data/json/person1.json".to_string(),
+            working_context,
+        );
+        assert!(matches!(result, Err(DtfError::DiffError(msg)) if msg.contains("empty")));
+    }
+
+    #[test]
+    fn test_empty_object_against_non_empty_object() {
+        let working_context = get_working_context(true, false, false, false);
+        let json_app = JsonApp::new(
+            "test_data/json/empty_object.json".to_string(),
+            "test_data/json/person1.json".to_string(),
+            working_context,
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
+        assert_eq!(diffs.0.unwrap().is_empty(), false);
+    }
+
+    #[test]
+    fn test_empty_objects_are_identical() {
+        let working_context = get_working_context(true, true, true, true);
+        let json_app = JsonApp::new(
+            "test_data/json/empty_object.json".to_string(),
+            "test_data/json/empty_object.json".to_string(),
+            working_context,
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
+        assert!(diffs.0.unwrap().is_empty());
+        assert!(diffs.1.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+        assert!(diffs.3.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compare_json_str() {
+        let working_context = get_working_context(true, false, true, false);
+        let diffs = compare_json_str(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "Jane", "city": "Budapest"}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let key_diffs = diffs.0.unwrap();
+        assert_eq!(key_diffs.len(), 2);
+        assert!(key_diffs
+            .iter()
+            .any(|kd| kd.key == "age" && kd.has == "FileA.yaml"));
+        assert!(key_diffs
+            .iter()
+            .any(|kd| kd.key == "city" && kd.has == "FileB.yaml"));
+
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "name");
+    }
+
+    #[test]
+    fn test_ordered_array_paths() {
+        let mut working_context = get_working_context(false, false, true, true);
+        working_context.config.ordered_array_paths = vec!["steps".to_owned()];
+        let diffs = compare_json_str(
+            r#"{"steps": ["build", "test"], "tags": ["x", "y"]}"#,
+            r#"{"steps": ["test", "build"], "tags": ["y", "z"]}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert!(value_diffs.iter().any(|vd| vd.key.starts_with("steps")));
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.is_empty(), false);
+        assert!(array_diffs.iter().all(|ad| ad.key == "tags"));
+    }
+
+    #[test]
+    fn test_equivalent_objects_in_arrays() {
+        let working_context = get_working_context(false, false, false, true);
+        let diffs = compare_json_str(
+            r#"{"users": [{"id": 1.0, "name": "a"}, {"id": 2, "name": "b"}]}"#,
+            r#"{"users": [{"name": "a", "id": 1}, {"id": 2, "name": "c"}]}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.len(), 2);
+        assert!(array_diffs.iter().all(|ad| !ad.value.contains("\"a\"")));
+    }
+
+    #[test]
+    fn test_set_array_diffs() {
+        let mut working_context = get_working_context(false, false, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+        working_context.config.set_array_diffs = true;
+        let diffs = compare_json_str(
+            r#"{"tags": ["a", "b", "c"]}"#,
+            r#"{"tags": ["a", "c", "d"]}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert!(value_diffs.iter().any(|vd| vd.key.starts_with("tags")));
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.len(), 2);
+        assert!(array_diffs
+            .iter()
+            .any(|ad| ad.descriptor == ArrayDiffDesc::AHas && ad.value == "\"b\""));
+        assert!(array_diffs
+            .iter()
+            .any(|ad| ad.descriptor == ArrayDiffDesc::BHas && ad.value == "\"d\""));
+    }
+
+    #[test]
+    fn test_compare_json_str_max_diffs() {
+        let mut working_context = get_working_context(true, false, true, false);
+        working_context.config.max_diffs = Some(2);
+        let result = compare_json_str(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "Jane", "city": "Budapest"}"#,
+            &working_context,
+        );
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+
+        working_context.config.max_diffs = Some(3);
+        let result = compare_json_str(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "Jane", "city": "Budapest"}"#,
+            &working_context,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_max_diffs_stops_checking() {
+        let mut working_context = get_working_context(true, false, true, false);
+        working_context.config.max_diffs = Some(1);
+        let result = compare_json_str(
+            r#"{"name": "John", "age": 30}"#,
+            r#"{"name": "Jane", "city": "Budapest"}"#,
+            &working_context,
+        );
+
+        // The value differences aren't checked once the key differences are over the limit
+        assert!(
+            matches!(result, Err(DtfError::LimitError(message)) if message.starts_with("2 differences found"))
+        );
+    }
+
+    #[test]
+    fn test_max_diffs_stops_at_the_key_exceeding_it() {
+        let mut working_context = get_working_context(false, false, true, false);
+        working_context.config.max_diffs = Some(2);
+        let result = compare_json_str(
+            r#"{"a": 1, "b": {"c": 1}, "d": 1, "e": 1}"#,
+            r#"{"a": 2, "b": {"c": 2}, "d": 2, "e": 2}"#,
+            &working_context,
+        );
+
+        // The keys are checked one by one, sharing the limit, so e isn't checked
+        assert!(
+            matches!(result, Err(DtfError::LimitError(message)) if message.starts_with("3 differences found"))
+        );
+    }
+
+    #[test]
+    fn test_compare_json_str_parse_dates() {
+        let mut working_context = get_working_context(false, false, true, false);
+        working_context.config.parse_dates = true;
+        let diffs = compare_json_str(
+            r#"{"created": "2024-01-01T00:00:00Z", "updated": "2024-01-01T00:00:00Z"}"#,
+            r#"{"created": "2024-01-01T00:00:00+00:00", "updated": "2024-01-02T00:00:00Z"}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "updated");
+    }
+
+    #[test]
+    fn test_compare_json_str_parse_dates_in_arrays() {
+        let mut working_context = get_working_context(false, false, true, true);
+        working_context.config.parse_dates = true;
+        let diffs = compare_json_str(
+            r#"{"events": ["2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"]}"#,
+            r#"{"events": ["2024-01-01T00:00:00+00:00", "2024-01-03T00:00:00Z"]}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.len(), 2);
+        assert!(array_diffs.iter().all(|ad| !ad.value.contains("01-01")));
+    }
+
+    #[test]
+    fn test_compare_json_str_max_value_len() {
+        let mut working_context = get_working_context(false, false, true, false);
+        working_context.config.max_value_len = Some(10);
+        // The values only differ after the cap
+        let blob = "x".repeat(100_000);
+        let diffs = compare_json_str(
+            &format!(r#"{{"blob": "{}a"}}"#, blob),
+            &format!(r#"{{"blob": "{}b"}}"#, blob),
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert!(value_diffs[0].value1.ends_with("…(truncated)"));
+        assert!(value_diffs[0].value1.chars().count() < 100);
+    }
+
+    #[test]
+    fn test_compare_json_str_normalize_unicode() {
+        // The first file has the decomposed form of the key, the second one the composed form
+        let a = "{\"cafe\u{301}\": 1, \"name\": \"Zoe\u{308}\"}";
+        let b = "{\"caf\u{e9}\": 2, \"name\": \"Zo\u{eb}\"}";
+
+        let working_context = get_working_context(true, false, true, false);
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+        assert_eq!(diffs.0.unwrap().len(), 2);
+
+        let mut working_context = get_working_context(true, false, true, false);
+        working_context.config.normalize_unicode = true;
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert!(diffs.0.unwrap().is_empty());
+        assert_eq!(
+            diffs.2.unwrap(),
+            vec![ValueDiff {
+                key: "cafe\u{301}".to_owned(),
+                value1: "1".to_owned(),
+                value2: "2".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compare_json_str_unwrap() {
+        let a = r#"{"data": {"name": "John", "age": 30}, "status": "ok"}"#;
+        let b = r#"{"name": "John", "age": 31}"#;
+
+        let mut working_context = get_working_context(true, false, true, false);
+        working_context.config.unwrap_a = Some("data".to_owned());
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert!(diffs.0.unwrap().is_empty());
+        assert_eq!(
+            diffs.2.unwrap(),
+            vec![ValueDiff {
+                key: "age".to_owned(),
+                value1: "30".to_owned(),
+                value2: "31".to_owned(),
+            }]
+        );
+
+        // Each side is navigated to its own path
+        let b = r#"{"response": {"payload": {"name": "John", "age": 30}}}"#;
+        working_context.config.unwrap_b = Some("response.payload".to_owned());
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert!(diffs.0.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+
+        working_context.config.unwrap_b = Some("response.missing".to_owned());
+        assert!(matches!(
+            compare_json_str(a, b, &working_context),
+            Err(DtfError::DiffError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_json_str_canonicalize() {
+        let a = r#"{"price": 1.0, "size": 1e2, "tags": ["b", "a"], "name": "John "}"#;
+        let b = r#"{"name": " John", "tags": ["a", "b"], "size": 100, "price": 1}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+        assert_ne!(count_diffs(&diffs), 0);
+
+        working_context.config.canonicalize = true;
+        working_context.config.trim_strings = true;
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+        assert_eq!(count_diffs(&diffs), 0);
+    }
+
+    #[test]
+    fn test_compare_json_str_flatten() {
+        let a =
+            r#"{"user": {"name": "John", "address": {"city": "Budapest"}}, "tags": ["a", "b"]}"#;
+        let b = r#"{"user": {"name": "Jane", "address": {"zip": "1011"}}, "tags": ["a", "c"]}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.flatten = true;
+        working_context.config.flatten_arrays = true;
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        let mut keys: Vec<&str> = diffs.0.iter().flatten().map(|kd| kd.key.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["user.address.city", "user.address.zip"]);
+        let value_diffs = diffs.2.unwrap();
+        let mut keys: Vec<&str> = value_diffs.iter().map(|vd| vd.key.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["tags[1]", "user.name"]);
+        assert_eq!(diffs.3.iter().flatten().count(), 0);
+    }
+
+    #[test]
+    fn test_compare_json_str_ignore_array_tail() {
+        let a = r#"{"steps": ["build", "test"], "tags": ["x", "y"]}"#;
+        let b = r#"{"steps": ["build", "test", "deploy"], "tags": ["x", "z", "w"]}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+        let under_steps = |diffs: &DiffCollection| {
+            let keys = diffs.0.iter().flatten().map(|kd| kd.key.as_str());
+            let keys = keys.chain(diffs.1.iter().flatten().map(|td| td.key.as_str()));
+            let keys = keys.chain(diffs.2.iter().flatten().map(|vd| vd.key.as_str()));
+            let keys = keys.chain(diffs.3.iter().flatten().map(|ad| ad.key.as_str()));
+            keys.filter(|key| key.starts_with("steps")).count()
+        };
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+        assert_ne!(under_steps(&diffs), 0);
+
+        working_context.config.ignore_array_tail = true;
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+        assert_eq!(under_steps(&diffs), 0);
+        // The tags diverge before the end of the shorter array, so they are still reported
+        assert!(diffs
+            .2
+            .iter()
+            .flatten()
+            .any(|vd| vd.key.starts_with("tags")));
+    }
+
+    #[test]
+    fn test_sample() {
+        let a = Value::from_iter((0..100).map(|i| serde_json::json!({"id": i, "v": 0})));
+        let b = Value::from_iter((0..100).map(|i| serde_json::json!({"id": i, "v": 1})));
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+        working_context.config.sample = Some(10);
+        let json_app = JsonApp::from_data(wrap_root(&a), wrap_root(&b), working_context);
+
+        let value_diffs = json_app.perform_new_check().unwrap().2.unwrap();
+
+        assert_eq!(value_diffs.len(), 10);
+        // The first and the last items are always compared, by their indexes in the files
+        let keys: Vec<&str> = value_diffs.iter().map(|vd| vd.key.as_str()).collect();
+        assert!(keys.contains(&"$[0].v"));
+        assert!(keys.contains(&"$[99].v"));
+        let sampled_arrays = json_app.sampled_arrays();
+        assert_eq!(
+            sampling_note(&sampled_arrays).unwrap(),
+            "Only a sample of the items was compared in these arrays: $ (10 of 100)"
+        );
+    }
+
+    #[test]
+    fn test_type_naming_json_schema() {
+        let a = r#"{"active": true, "count": 1, "ratio": 0.5}"#;
+        let b = r#"{"active": "yes", "count": "1", "ratio": "half"}"#;
+        let mut working_context = get_working_context(false, true, false, false);
+        working_context.config.type_naming = TypeNaming::JsonSchema;
+
+        let type_diffs = compare_json_str(a, b, &working_context).unwrap().1.unwrap();
+
+        let mut types: Vec<(&str, &str, &str)> = type_diffs
+            .iter()
+            .map(|td| (td.key.as_str(), td.type1.as_str(), td.type2.as_str()))
+            .collect();
+        types.sort();
+        assert_eq!(
+            types,
+            vec![
+                ("active", "boolean", "string"),
+                ("count", "integer", "string"),
+                ("ratio", "number", "string"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_naming_json_schema_of_keys_with_dots() {
+        let a = r#"{"limits.max": 1, "hosts[eu]": {"ratio": 0.5}}"#;
+        let b = r#"{"limits.max": "1", "hosts[eu]": {"ratio": "half"}}"#;
+        let mut working_context = get_working_context(false, true, false, false);
+        working_context.config.type_naming = TypeNaming::JsonSchema;
+
+        let type_diffs = compare_json_str(a, b, &working_context).unwrap().1.unwrap();
+
+        // The keys are found in the data even though they contain the separators of the path
+        let mut types: Vec<(&str, &str, &str)> = type_diffs
+            .iter()
+            .map(|td| (td.key.as_str(), td.type1.as_str(), td.type2.as_str()))
+            .collect();
+        types.sort();
+        assert_eq!(
+            types,
+            vec![
+                ("hosts[eu].ratio", "number", "string"),
+                ("limits.max", "integer", "string"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matrices_cell() {
+        let a = r#"{"grid": [[1, 2, 3], [4, 5, 6], [7, 8, 9]]}"#;
+        let b = r#"{"grid": [[1, 2, 3], [4, 0, 6], [7, 8, 9]]}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.matrices = true;
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert_eq!(count_diffs(&diffs), 1);
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs[0].key, "grid[1][1]");
+        assert_eq!(
+            (
+                value_diffs[0].value1.as_str(),
+                value_diffs[0].value2.as_str()
+            ),
+            ("5", "0")
+        );
+    }
+
+    #[test]
+    fn test_matrices_inserted_row() {
+        let a = r#"{"grid": [[1, 2], [3, 4], [5, 6]]}"#;
+        let b = r#"{"grid": [[1, 2], [0, 0], [3, 4], [5, 6]]}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+        working_context.config.matrices = true;
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert_eq!(count_diffs(&diffs), 1);
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs[0].key, "grid");
+        assert_eq!(array_diffs[0].descriptor, ArrayDiffDesc::BHas);
+        assert_eq!(array_diffs[0].value, "[0,0]");
+    }
+
+    #[test]
+    fn test_matrices_normalized_intersection() {
+        let a = r#"{"grid": [["e\u0301", 1], [2, 3]]}"#;
+        let b = r#"{"grid": [["\u00e9", 9], [2, 3, 4], [5]]}"#;
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.lib_working_context.config.array_same_order = true;
+        working_context.config.matrices = true;
+        working_context.config.normalize_unicode = true;
+        working_context.config.intersection = true;
+
+        let diffs = compare_json_str(a, b, &working_context).unwrap();
+
+        assert_eq!(count_diffs(&diffs), 1);
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs[0].key, "grid[0][1]");
+    }
+
+    #[test]
+    fn test_compare_json_str_empty_equivalence() {
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.empty_equivalence = true;
+        let compare =
+            |a: &str, b: &str| count_diffs(&compare_json_str(a, b, &working_context).unwrap());
+
+        assert_eq!(compare(r#"{"x": null}"#, r#"{"x": ""}"#), 0);
+        assert_eq!(compare(r#"{"x": ""}"#, r#"{"x": null}"#), 0);
+        assert_eq!(compare(r#"{"x": null}"#, r#"{}"#), 0);
+        assert_eq!(compare(r#"{}"#, r#"{"x": null}"#), 0);
+        assert_eq!(compare(r#"{"x": ""}"#, r#"{}"#), 0);
+        assert_eq!(compare(r#"{}"#, r#"{"x": ""}"#), 0);
+        assert_eq!(compare(r#"{"a": {"x": ""}}"#, r#"{"a": {}}"#), 0);
+        // 0 and false are set
+        assert_ne!(compare(r#"{"x": 0}"#, r#"{}"#), 0);
+        assert_ne!(compare(r#"{"x": false}"#, r#"{"x": null}"#), 0);
+        assert_ne!(compare(r#"{"x": 0}"#, r#"{"x": ""}"#), 0);
+        assert_ne!(compare(r#"{"x": "a"}"#, r#"{"x": ""}"#), 0);
+
+        working_context.config.empty_equivalence = false;
+        let diffs = compare_json_str(r#"{"x": null}"#, r#"{"x": ""}"#, &working_context).unwrap();
+        assert_ne!(count_diffs(&diffs), 0);
+    }
+
+    #[test]
+    fn test_compare_json_str_coerce_numeric_strings() {
+        let mut working_context = get_working_context(false, true, true, false);
+        working_context.config.coerce_numeric_strings = true;
+        let diffs = compare_json_str(
+            r#"{"port": "8080", "host": "abc"}"#,
+            r#"{"port": 8080, "host": 8080}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        let type_diffs = diffs.1.unwrap();
+        assert_eq!(type_diffs.len(), 1);
+        assert_eq!(type_diffs[0].key, "host");
+        assert!(diffs.2.unwrap().iter().all(|vd| vd.key == "host"));
+
+        working_context.config.coerce_numeric_strings = false;
+        let diffs =
+            compare_json_str(r#"{"port": "8080"}"#, r#"{"port": 8080}"#, &working_context).unwrap();
+        assert_eq!(diffs.1.unwrap()[0].key, "port");
+    }
+
+    #[test]
+    fn test_compare_json_values_object_root() {
+        let working_context = get_working_context(true, true, true, true);
+        let diffs = compare_json_values(
+            &serde_json::json!({"name": "John", "age": 30}),
+            &serde_json::json!({"name": "Jane"}),
+            &working_context,
+        )
+        .unwrap();
+
+        assert_eq!(diffs.0.unwrap()[0].key, "age");
+        assert_eq!(diffs.2.unwrap()[0].key, "name");
+    }
+
+    #[test]
+    fn test_compare_json_values_array_root() {
+        let working_context = get_working_context(true, true, true, true);
+        let diffs = compare_json_values(
+            &serde_json::json!(["a", "b"]),
+            &serde_json::json!(["b", "c"]),
+            &working_context,
+        )
+        .unwrap();
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.len(), 2);
+        assert!(array_diffs.iter().all(|ad| ad.key == ROOT_KEY));
+        assert_eq!(count_diffs(&(diffs.0, diffs.1, diffs.2, None)), 0);
+    }
+
+    #[test]
+    fn test_compare_json_values_scalar_root() {
+        let working_context = get_working_context(true, true, true, true);
+        let compare = |a: Value, b: Value| compare_json_values(&a, &b, &working_context).unwrap();
+
+        let diffs = compare(serde_json::json!(1), serde_json::json!(2));
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, ROOT_KEY);
+
+        let diffs = compare(serde_json::json!("1"), serde_json::json!(1));
+        assert_eq!(diffs.1.unwrap()[0].key, ROOT_KEY);
+
+        assert_eq!(
+            count_diffs(&compare(serde_json::json!(true), serde_json::json!(true))),
+            0
+        );
+        // An object compared to a scalar is a type difference at the root as well
+        let diffs = compare(serde_json::json!({"a": 1}), serde_json::json!(null));
+        assert_eq!(diffs.1.unwrap()[0].key, ROOT_KEY);
+    }
+
+    #[test]
+    fn test_compare_json_values_too_deep() {
+        let working_context = get_working_context(true, true, true, true);
+        let nested = |depth: usize| {
+            (0..depth).fold(
+                serde_json::json!(1),
+                |value, _| serde_json::json!({ "a": value }),
+            )
+        };
+
+        let result = compare_json_values(&nested(MAX_DEPTH + 10), &nested(1), &working_context);
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+
+        let diffs = compare_json_values(&nested(MAX_DEPTH), &nested(MAX_DEPTH), &working_context);
+        assert_eq!(count_diffs(&diffs.unwrap()), 0);
+
+        // Data built in code is checked before it's compared, whichever way it gets to the checkers
+        let json_app = JsonApp::from_data(
+            wrap_root(&nested(MAX_DEPTH + 10)),
+            wrap_root(&nested(1)),
+            working_context.clone(),
+        );
+        assert!(matches!(
+            json_app.perform_new_check(),
+            Err(DtfError::LimitError(_))
+        ));
+
+        // Parsed data is limited by the parser
+        let deep = format!("{{\"a\": {}1{}}}", "[".repeat(1000), "]".repeat(1000));
+        assert!(compare_json_str(&deep, "{}", &working_context).is_err());
+    }
+
+    #[test]
+    fn test_compare_json_str_intersection() {
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.intersection = true;
+        let diffs = compare_json_str(
+            r#"{"name": "John", "age": 30, "tags": ["a"]}"#,
+            r#"{"name": "Jane", "age": 30, "city": "Budapest", "tags": ["b", "c"]}"#,
+            &working_context,
+        )
+        .unwrap();
+
+        assert!(diffs.0.is_none());
+        assert!(diffs.1.unwrap().is_empty());
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "name");
+        // "c" is only added, but "a" and "b" are at the same index
+        let array_values: Vec<String> = diffs.3.unwrap().into_iter().map(|ad| ad.value).collect();
+        assert_eq!(array_values, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_gzipped_file() {
+        let working_context = get_working_context(true, true, true, true);
+        let gzipped = JsonApp::new(
+            "test_data/json/person1.json.gz".to_string(),
+            "test_data/json/person3.json".to_string(),
+            working_context.clone(),
+        )
+        .unwrap()
+        .perform_new_check()
+        .unwrap();
+        let plain = JsonApp::new(
+            "test_data/json/person1.json".to_string(),
+            "test_data/json/person3.json".to_string(),
+            working_context,
+        )
+        .unwrap()
+        .perform_new_check()
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&gzipped).unwrap(),
+            serde_json::to_string(&plain).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compare_json_str_invalid_input() {
+        let working_context = get_working_context(true, false, false, false);
+        let result = compare_json_str("{", "{}", &working_context);
+        assert!(matches!(result, Err(DtfError::DiffError(_))));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_find_first_diff_agrees_with_the_full_check() {
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.empty_equivalence = true;
+        working_context.config.coerce_numeric_strings = true;
+        working_context.config.normalize_unicode = true;
+        let data1: Map<String, Value> = serde_json::from_str(
+            r#"{"server": {"port": "8080", "note": null}, "name": "Caf\u00e9", "tags": ["a", "b"]}"#,
+        )
+        .unwrap();
+        let data2: Map<String, Value> = serde_json::from_str(
+            r#"{"server": {"port": 8080}, "name": "Cafe\u0301", "tags": ["b", "a"]}"#,
+        )
+        .unwrap();
+        let json_app = JsonApp::from_data(data1, data2, working_context);
+
+        // The files are the same after the normalization, so neither of the checks fails
+        assert_eq!(count_diffs(&json_app.perform_new_check().unwrap()), 0);
+        assert_eq!(count_diffs(&json_app.find_first_diff().unwrap()), 0);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_find_first_diff_reports_like_the_full_check() {
+        let working_context = get_working_context(false, true, false, false);
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"name": "a", "server": {"enabled": true}}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"name": "b", "server": {"enabled": "yes"}}"#).unwrap();
+        let json_app = JsonApp::from_data(data1, data2, working_context);
+
+        // The type names are those of the full check
+        assert_eq!(
+            json_app.find_first_diff().unwrap(),
+            json_app.perform_new_check().unwrap()
+        );
+    }
+
     fn get_working_context(
         key_diffs: bool,
         type_diffs: bool,
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// An object having the same keys in both files, but in a different order
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyOrderDiff {
+    pub key: String,
+    pub order1: Vec<String>,
+    pub order2: Vec<String>,
+}
+
+/// Finds the objects of the JSON data having the same keys in a different order.
+/// serde_json only keeps the order of the keys with its `preserve_order` feature, enabled by the `key-order` feature.
+pub fn find_json_key_order_diffs(
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> Vec<KeyOrderDiff> {
+    let mut diffs = vec![];
+    check_json_object("", data1, data2, &mut diffs);
+    diffs
+}
+
+/// Finds the mappings of the YAML data having the same keys in a different order
+pub fn find_yaml_key_order_diffs(data1: &Mapping, data2: &Mapping) -> Vec<KeyOrderDiff> {
+    let mut diffs = vec![];
+    check_yaml_mapping("", data1, data2, &mut diffs);
+    diffs
+}
+
+fn check_json_object(
+    key: &str,
+    object1: &Map<String, Value>,
+    object2: &Map<String, Value>,
+    diffs: &mut Vec<KeyOrderDiff>,
+) {
+    let order1: Vec<String> = object1.keys().cloned().collect();
+    let order2: Vec<String> = object2.keys().cloned().collect();
+    check_order(key, order1, order2, diffs);
+
+    for (child_key, value1) in object1 {
+        if let Some(value2) = object2.get(child_key) {
+            check_json_value(&join_key(key, child_key), value1, value2, diffs);
+        }
+    }
+}
+
+fn check_json_value(key: &str, value1: &Value, value2: &Value, diffs: &mut Vec<KeyOrderDiff>) {
+    match (value1, value2) {
+        (Value::Object(object1), Value::Object(object2)) => {
+            check_json_object(key, object1, object2, diffs)
+        }
+        (Value::Array(array1), Value::Array(array2)) => {
+            for (index, (item1, item2)) in array1.iter().zip(array2).enumerate() {
+                check_json_value(&format!("{}[{}]", key, index), item1, item2, diffs);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn check_yaml_mapping(
+    key: &str,
+    mapping1: &Mapping,
+    mapping2: &Mapping,
+    diffs: &mut Vec<KeyOrderDiff>,
+) {
+    let order1: Vec<String> = mapping1.keys().map(yaml_key_name).collect();
+    let order2: Vec<String> = mapping2.keys().map(yaml_key_name).collect();
+    check_order(key, order1, order2, diffs);
+
+    for (child_key, value1) in mapping1 {
+        if let Some(value2) = mapping2.get(child_key) {
+            check_yaml_value(
+                &join_key(key, &yaml_key_name(child_key)),
+                value1,
+                value2,
+                diffs,
+            );
+        }
+    }
+}
+
+fn check_yaml_value(
+    key: &str,
+    value1: &YamlValue,
+    value2: &YamlValue,
+    diffs: &mut Vec<KeyOrderDiff>,
+) {
+    match (value1, value2) {
+        (YamlValue::Mapping(mapping1), YamlValue::Mapping(mapping2)) => {
+            check_yaml_mapping(key, mapping1, mapping2, diffs)
+        }
+        (YamlValue::Sequence(sequence1), YamlValue::Sequence(sequence2)) => {
+            for (index, (item1, item2)) in sequence1.iter().zip(sequence2).enumerate() {
+                check_yaml_value(&format!("{}[{}]", key, index), item1, item2, diffs);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Records a difference if the objects have the same keys in a different order.
+/// Objects with different keys are left to the key differences.
+fn check_order(key: &str, order1: Vec<String>, order2: Vec<String>, diffs: &mut Vec<KeyOrderDiff>) {
+    if order1 == order2 {
+        return;
+    }
+
+    let mut sorted1 = order1.clone();
+    let mut sorted2 = order2.clone();
+    sorted1.sort();
+    sorted2.sort();
+    if sorted1 == sorted2 {
+        diffs.push(KeyOrderDiff {
+            key: key.to_owned(),
+            order1,
+            order2,
+        });
+    }
+}
+
+fn join_key(key: &str, child_key: &str) -> String {
+    if key.is_empty() {
+        child_key.to_owned()
+    } else {
+        format!("{}.{}", key, child_key)
+    }
+}
+
+fn yaml_key_name(key: &YamlValue) -> String {
+    match key {
+        YamlValue::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_yaml_key_order_diffs() {
+        let data1: Mapping = serde_yaml::from_str(
+            "a: 1\nb: 2\nnested:\n  x: 1\n  y: 2\nlist:\n  - p: 1\n    q: 2\n",
+        )
+        .unwrap();
+        let data2: Mapping = serde_yaml::from_str(
+            "b: 2\na: 1\nnested:\n  y: 2\n  x: 1\nlist:\n  - p: 1\n    q: 2\n",
+        )
+        .unwrap();
+
+        let diffs = find_yaml_key_order_diffs(&data1, &data2);
+
+        assert_eq!(
+            diffs,
+            vec![
+                KeyOrderDiff {
+                    key: String::new(),
+                    order1: vec!["a", "b", "nested", "list"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    order2: vec!["b", "a", "nested", "list"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                },
+                KeyOrderDiff {
+                    key: "nested".to_owned(),
+                    order1: vec!["x".to_owned(), "y".to_owned()],
+                    order2: vec!["y".to_owned(), "x".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_yaml_key_order_diffs_ignores_different_keys() {
+        let data1: Mapping = serde_yaml::from_str("a: 1\nb: 2\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("c: 3\na: 1\n").unwrap();
+
+        assert!(find_yaml_key_order_diffs(&data1, &data2).is_empty());
+    }
+
+    #[cfg(feature = "key-order")]
+    #[test]
+    fn test_find_json_key_order_diffs() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"a": 1, "b": 2, "list": [{"x": 1, "y": 2}]}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"a": 1, "b": 2, "list": [{"y": 2, "x": 1}]}"#).unwrap();
+
+        let diffs = find_json_key_order_diffs(&data1, &data2);
+
+        assert_eq!(
+            diffs,
+            vec![KeyOrderDiff {
+                key: "list[0]".to_owned(),
+                order1: vec!["x".to_owned(), "y".to_owned()],
+                order2: vec!["y".to_owned(), "x".to_owned()],
+            }]
+        );
+    }
+}
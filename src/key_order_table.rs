@@ -0,0 +1,66 @@
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+};
+
+use crate::dtfterminal_types::{DiffCategory, TableContext, TermTable, WorkingContext};
+use crate::key_order::KeyOrderDiff;
+
+/// How the key of the top level object is displayed
+const ROOT_KEY: &str = "(root)";
+
+/// Table to display key order differences in the terminal
+pub struct KeyOrderTable<'a> {
+    context: TableContext<'a>,
+}
+
+impl<'a> TermTable<KeyOrderDiff> for KeyOrderTable<'a> {
+    fn render(&self) -> String {
+        self.context.render()
+    }
+
+    fn create_table(&mut self, data: &[KeyOrderDiff]) {
+        self.add_header();
+        self.add_rows(data);
+    }
+
+    fn add_header(&mut self) {
+        let (file_name_a_str, file_name_b_str) = self.context.working_context().get_file_names();
+        let file_name_a = file_name_a_str.to_owned();
+        let file_name_b = file_name_b_str.to_owned();
+        self.context
+            .add_row(Row::new(vec![TableCell::builder("Key Order Differences")
+                .col_span(3)
+                .alignment(Alignment::Center)]));
+        self.context.add_row(Row::new(vec![
+            TableCell::new("Key"),
+            TableCell::new(file_name_a),
+            TableCell::new(file_name_b),
+        ]));
+    }
+
+    fn add_rows(&mut self, data: &[KeyOrderDiff]) {
+        for kod in data {
+            let key = if kod.key.is_empty() {
+                ROOT_KEY
+            } else {
+                &kod.key
+            };
+            self.context.add_row(Row::new(vec![
+                TableCell::new(self.context.format_key(DiffCategory::Key, key)),
+                TableCell::new(kod.order1.join(", ")),
+                TableCell::new(kod.order2.join(", ")),
+            ]));
+        }
+    }
+}
+
+impl<'a> KeyOrderTable<'a> {
+    pub fn new(data: &[KeyOrderDiff], working_context: &'a WorkingContext) -> KeyOrderTable<'a> {
+        let mut table = KeyOrderTable {
+            context: TableContext::new(working_context),
+        };
+        table.create_table(data);
+        table
+    }
+}
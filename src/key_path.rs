@@ -0,0 +1,172 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+/// A step of the path to a value: the key of an object or the index of an array
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A value the rendered keys of the differences can point into
+trait PathNode {
+    fn child(&self, key: &str) -> Option<&Self>;
+    fn item(&self, index: usize) -> Option<&Self>;
+}
+
+impl PathNode for Value {
+    fn child(&self, key: &str) -> Option<&Value> {
+        self.get(key)
+    }
+
+    fn item(&self, index: usize) -> Option<&Value> {
+        self.get(index)
+    }
+}
+
+impl PathNode for YamlValue {
+    fn child(&self, key: &str) -> Option<&YamlValue> {
+        self.get(key)
+    }
+
+    fn item(&self, index: usize) -> Option<&YamlValue> {
+        self.get(index)
+    }
+}
+
+/// Finds the path of a rendered key like `friends[0].name` in the JSON data.
+/// The keys are matched against the keys the data has, so keys containing `.` or `[` are found too.
+pub fn find_json_path(data: &Map<String, Value>, key: &str) -> Option<Vec<Segment>> {
+    let mut path = vec![];
+    find_entry(|key| data.get(key), key, &mut path).then_some(path)
+}
+
+/// Finds the path of a rendered key like `friends[0].name` in the YAML data.
+/// The keys are matched against the keys the data has, so keys containing `.` or `[` are found too.
+pub fn find_yaml_path(data: &Mapping, key: &str) -> Option<Vec<Segment>> {
+    let mut path = vec![];
+    find_entry(|key| data.get(key), key, &mut path).then_some(path)
+}
+
+/// Get the value at the path in the JSON data
+pub fn follow_json<'a>(data: &'a Map<String, Value>, path: &[Segment]) -> Option<&'a Value> {
+    let (first, rest) = path.split_first()?;
+    match first {
+        Segment::Key(key) => follow(data.get(key)?, rest),
+        Segment::Index(_) => None,
+    }
+}
+
+/// Get the value at the path in the YAML data
+pub fn follow_yaml<'a>(data: &'a Mapping, path: &[Segment]) -> Option<&'a YamlValue> {
+    let (first, rest) = path.split_first()?;
+    match first {
+        Segment::Key(key) => follow(data.get(key.as_str())?, rest),
+        Segment::Index(_) => None,
+    }
+}
+
+fn follow<'a, N: PathNode>(value: &'a N, path: &[Segment]) -> Option<&'a N> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        Segment::Key(key) => value.child(key),
+        Segment::Index(index) => value.item(*index),
+    })
+}
+
+/// Matches the start of the rendered key against the keys of an object, trying the shorter keys first.
+/// A key is followed by the end of the rendered key, a `.` or a `[`.
+fn find_entry<'a, N: PathNode + 'a>(
+    get: impl Fn(&str) -> Option<&'a N>,
+    rendered: &str,
+    path: &mut Vec<Segment>,
+) -> bool {
+    let ends = rendered
+        .match_indices(|c| c == '.' || c == '[')
+        .map(|(end, _)| end)
+        .chain([rendered.len()]);
+    for end in ends {
+        let (key, rest) = rendered.split_at(end);
+        if let Some(value) = get(key) {
+            path.push(Segment::Key(key.to_owned()));
+            if find_in_value(value, rest, path) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+    false
+}
+
+fn find_in_value<N: PathNode>(value: &N, rendered: &str, path: &mut Vec<Segment>) -> bool {
+    if rendered.is_empty() {
+        return true;
+    }
+    if let Some(rest) = rendered.strip_prefix('.') {
+        return find_entry(|key| value.child(key), rest, path);
+    }
+    let item = rendered
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .and_then(|(index, rest)| Some((index.parse().ok()?, rest)))
+        .and_then(|(index, rest)| Some((index, value.item(index)?, rest)));
+    match item {
+        Some((index, item, rest)) => {
+            path.push(Segment::Index(index));
+            if find_in_value(item, rest, path) {
+                return true;
+            }
+            path.pop();
+            false
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn key(key: &str) -> Segment {
+        Segment::Key(key.to_owned())
+    }
+
+    #[test]
+    fn test_find_json_path() {
+        let data = json!({"friends": [{"name": "a"}], "a.b": {"c[0]": 1}, "a": {"d": 2}});
+        let data = data.as_object().unwrap();
+
+        assert_eq!(
+            find_json_path(data, "friends[0].name"),
+            Some(vec![key("friends"), Segment::Index(0), key("name")])
+        );
+        assert_eq!(
+            find_json_path(data, "a.b.c[0]"),
+            Some(vec![key("a.b"), key("c[0]")])
+        );
+        assert_eq!(find_json_path(data, "a.d"), Some(vec![key("a"), key("d")]));
+        assert_eq!(find_json_path(data, "friends[1]"), None);
+        assert_eq!(find_json_path(data, "missing"), None);
+        assert_eq!(
+            follow_json(data, &find_json_path(data, "a.b.c[0]").unwrap()),
+            Some(&json!(1))
+        );
+    }
+
+    #[test]
+    fn test_find_yaml_path() {
+        let data: Mapping = serde_yaml::from_str("servers:\n  - host.name: a\n").unwrap();
+
+        let path = find_yaml_path(&data, "servers[0].host.name").unwrap();
+
+        assert_eq!(
+            path,
+            vec![key("servers"), Segment::Index(0), key("host.name")]
+        );
+        assert_eq!(
+            follow_yaml(&data, &path),
+            Some(&YamlValue::String("a".to_owned()))
+        );
+    }
+}
@@ -1,7 +1,5 @@
-use crate::{
-    dtfterminal_types::{TableContext, TermTable, WorkingContext},
-    utils::{CHECKMARK, MULTIPLY},
-};
+use crate::dtfterminal_types::{DiffCategory, TableContext, TermTable, WorkingContext};
+use crate::side::Side;
 use colored::{Color, ColoredString, Colorize};
 use libdtf::core::diff_types::KeyDiff;
 use term_table::{
@@ -21,7 +19,11 @@ impl<'a> TermTable<KeyDiff> for KeyTable<'a> {
 
     fn create_table(&mut self, data: &[KeyDiff]) {
         self.add_header();
-        self.add_rows(data);
+        if data.is_empty() {
+            self.context.add_no_differences_row();
+        } else {
+            self.add_rows(data);
+        }
     }
 
     fn add_header(&mut self) {
@@ -33,14 +35,11 @@ impl<'a> TermTable<KeyDiff> for KeyTable<'a> {
     }
 
     fn add_rows(&mut self, data: &[KeyDiff]) {
-        let (file_name_a_str, file_name_b_str) = self.context.working_context().get_file_names();
-        let file_name_a = file_name_a_str.to_owned();
-        let file_name_b = file_name_b_str.to_owned();
         for kd in data {
-            let a_has = self.check_has(file_name_a.as_str(), kd);
-            let b_has = self.check_has(file_name_b.as_str(), kd);
+            let a_has = self.check_has(Side::A, kd);
+            let b_has = self.check_has(Side::B, kd);
             self.context.add_row(Row::new(vec![
-                TableCell::new(&kd.key),
+                TableCell::new(self.context.format_key(DiffCategory::Key, &kd.key)),
                 TableCell::new(a_has),
                 TableCell::new(b_has),
             ]));
@@ -57,13 +56,25 @@ impl<'a> KeyTable<'a> {
         table
     }
 
-    /// Check if the key is present in the file
-    fn check_has(&self, file_name: &str, key_diff: &KeyDiff) -> ColoredString {
-        if key_diff.has == file_name {
-            CHECKMARK.color(Color::Green)
-        } else {
-            MULTIPLY.color(Color::Red)
-        }
+    /// Check if the key is present in the file.
+    /// With a baseline the marks are colored by the severity of the difference instead.
+    fn check_has(&self, side: Side, key_diff: &KeyDiff) -> ColoredString {
+        let key_side = self.context.working_context().key_side(key_diff);
+        let has = key_side == side;
+        let mark = self.context.presence_mark(has);
+        let config = &self.context.working_context().config;
+        let color = match config.baseline {
+            Some(_) => config
+                .presence_severity_of(
+                    DiffCategory::Key,
+                    &key_diff.key,
+                    config.baseline_has_key(key_side),
+                )
+                .color(),
+            None if has => Color::Green,
+            None => Color::Red,
+        };
+        mark.color(color)
     }
 
     /// Adds the header row to the table
@@ -86,7 +97,10 @@ impl<'a> KeyTable<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::dtfterminal_types::ConfigBuilder;
+    use crate::{
+        dtfterminal_types::{Baseline, ConfigBuilder},
+        utils::{with_colors, ASCII_CHECKMARK, ASCII_MULTIPLY, CHECKMARK, MULTIPLY},
+    };
 
     use super::*;
 
@@ -99,10 +113,104 @@ mod tests {
             misses: "file_b.json".to_owned(),
         };
         let key_table = KeyTable::new(&[], &working_context);
-        let result = key_table.check_has("file_a.json", &key_diff);
+        let result = key_table.check_has(Side::A, &key_diff);
         assert_eq!(result, CHECKMARK.color(Color::Green));
     }
 
+    #[test]
+    fn test_check_has_with_baseline() {
+        let key_diff = KeyDiff {
+            key: "key1".to_owned(),
+            has: "file_a.json".to_owned(),
+            misses: "file_b.json".to_owned(),
+        };
+
+        // B is expected, so the key A has in addition is extra
+        let working_context = get_working_context_with_baseline(Baseline::B);
+        let key_table = KeyTable::new(&[], &working_context);
+        assert_eq!(
+            key_table.check_has(Side::A, &key_diff),
+            CHECKMARK.color(Color::Yellow)
+        );
+        assert_eq!(
+            key_table.check_has(Side::B, &key_diff),
+            MULTIPLY.color(Color::Yellow)
+        );
+
+        // A is expected, so the key B misses is missing
+        let working_context = get_working_context_with_baseline(Baseline::A);
+        let key_table = KeyTable::new(&[], &working_context);
+        assert_eq!(
+            key_table.check_has(Side::A, &key_diff),
+            CHECKMARK.color(Color::Red)
+        );
+        assert_eq!(
+            key_table.check_has(Side::B, &key_diff),
+            MULTIPLY.color(Color::Red)
+        );
+    }
+
+    #[test]
+    fn test_check_has_same_file_names() {
+        let mut working_context = get_working_context();
+        working_context.lib_working_context.file_b.name = "file_a.json".to_owned();
+        working_context.key_sides.insert("key1".to_owned(), Side::B);
+        let key_diff = KeyDiff {
+            key: "key1".to_owned(),
+            has: "file_a.json".to_owned(),
+            misses: "file_a.json".to_owned(),
+        };
+
+        let key_table = KeyTable::new(&[], &working_context);
+
+        assert_eq!(
+            key_table.check_has(Side::A, &key_diff),
+            MULTIPLY.color(Color::Red)
+        );
+        assert_eq!(
+            key_table.check_has(Side::B, &key_diff),
+            CHECKMARK.color(Color::Green)
+        );
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mut working_context = get_working_context();
+        working_context.config.ascii = true;
+        let key_diff = KeyDiff {
+            key: "key1".to_owned(),
+            has: "file_a.json".to_owned(),
+            misses: "file_b.json".to_owned(),
+        };
+
+        let rendered = with_colors(|| KeyTable::new(&[key_diff], &working_context).render());
+
+        assert!(rendered.contains(&format!("\u{1b}[32m{}\u{1b}[0m", ASCII_CHECKMARK)));
+        assert!(rendered.contains(&format!("\u{1b}[31m{}\u{1b}[0m", ASCII_MULTIPLY)));
+        assert!(rendered.starts_with('+'));
+        assert!(rendered.contains("| Key"));
+        assert!(rendered.is_ascii());
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let working_context = get_working_context();
+
+        let key_table = KeyTable::new(&[], &working_context);
+
+        let rendered = key_table.render();
+        assert!(rendered.contains("Key Differences"));
+        assert!(rendered.contains("(no differences)"));
+    }
+
+    fn get_working_context_with_baseline(baseline: Baseline) -> WorkingContext {
+        let mut working_context = get_working_context();
+        working_context.config.file_a = Some("file_a.json".to_owned());
+        working_context.config.file_b = Some("file_b.json".to_owned());
+        working_context.config.baseline = Some(baseline);
+        working_context
+    }
+
     fn get_working_context() -> WorkingContext {
         let working_file_a = libdtf::core::diff_types::WorkingFile::new("file_a.json".to_string());
         let working_file_b = libdtf::core::diff_types::WorkingFile::new("file_b.json".to_string());
@@ -0,0 +1,191 @@
+use libdtf::core::diff_types::KeyDiff;
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{dtfterminal_types::DtfError, utils::DiffLimit};
+
+/// Finds the keys only one of the JSON documents has, walking nothing but their keys.
+/// Objects under the same key are walked into, the values are never compared,
+/// and no other kind of difference is collected.
+/// The path of the keys is built in a single buffer instead of formatting it for every key.
+/// Each key difference is counted by the limit as it's found, so the walk stops as soon as there are too many.
+pub fn find_json_key_diffs(
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+    file_names: (&str, &str),
+    limit: &mut DiffLimit,
+) -> Result<Vec<KeyDiff>, DtfError> {
+    let mut walk = KeyWalk::new(file_names, limit);
+    walk.json(data1, data2)?;
+    Ok(walk.key_diffs)
+}
+
+/// Finds the keys only one of the YAML documents has, like `find_json_key_diffs`
+pub fn find_yaml_key_diffs(
+    data1: &Mapping,
+    data2: &Mapping,
+    file_names: (&str, &str),
+    limit: &mut DiffLimit,
+) -> Result<Vec<KeyDiff>, DtfError> {
+    let mut walk = KeyWalk::new(file_names, limit);
+    walk.yaml(data1, data2)?;
+    Ok(walk.key_diffs)
+}
+
+struct KeyWalk<'a, 'l> {
+    file_names: (&'a str, &'a str),
+    limit: &'l mut DiffLimit,
+    path: String,
+    key_diffs: Vec<KeyDiff>,
+}
+
+impl<'a, 'l> KeyWalk<'a, 'l> {
+    fn new(file_names: (&'a str, &'a str), limit: &'l mut DiffLimit) -> KeyWalk<'a, 'l> {
+        KeyWalk {
+            file_names,
+            limit,
+            path: String::new(),
+            key_diffs: vec![],
+        }
+    }
+
+    fn json(&mut self, a: &Map<String, Value>, b: &Map<String, Value>) -> Result<(), DtfError> {
+        for (key, value_a) in a {
+            let parent_len = self.enter(key);
+            match (value_a, b.get(key)) {
+                (_, None) => self.push(true)?,
+                (Value::Object(a), Some(Value::Object(b))) => self.json(a, b)?,
+                _ => {}
+            }
+            self.path.truncate(parent_len);
+        }
+        for key in b.keys().filter(|key| !a.contains_key(*key)) {
+            let parent_len = self.enter(key);
+            self.push(false)?;
+            self.path.truncate(parent_len);
+        }
+        Ok(())
+    }
+
+    fn yaml(&mut self, a: &Mapping, b: &Mapping) -> Result<(), DtfError> {
+        for (key, value_a) in a {
+            let parent_len = self.enter(&yaml_key(key));
+            match (value_a, b.get(key)) {
+                (_, None) => self.push(true)?,
+                (YamlValue::Mapping(a), Some(YamlValue::Mapping(b))) => self.yaml(a, b)?,
+                _ => {}
+            }
+            self.path.truncate(parent_len);
+        }
+        for key in b.keys().filter(|key| !a.contains_key(*key)) {
+            let parent_len = self.enter(&yaml_key(key));
+            self.push(false)?;
+            self.path.truncate(parent_len);
+        }
+        Ok(())
+    }
+
+    /// Appends the key to the path, returning the length of the parent path to go back to
+    fn enter(&mut self, key: &str) -> usize {
+        let parent_len = self.path.len();
+        if parent_len > 0 {
+            self.path.push('.');
+        }
+        self.path.push_str(key);
+        parent_len
+    }
+
+    fn push(&mut self, in_a: bool) -> Result<(), DtfError> {
+        self.limit.count()?;
+        let (file_a, file_b) = self.file_names;
+        let (has, misses) = if in_a {
+            (file_a, file_b)
+        } else {
+            (file_b, file_a)
+        };
+        self.key_diffs.push(KeyDiff {
+            key: self.path.clone(),
+            has: has.to_owned(),
+            misses: misses.to_owned(),
+        });
+        Ok(())
+    }
+}
+
+fn yaml_key(key: &YamlValue) -> String {
+    match key {
+        YamlValue::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dtfterminal_types::ConfigBuilder;
+
+    use super::*;
+
+    fn no_limit() -> DiffLimit {
+        DiffLimit::new(&ConfigBuilder::new().build())
+    }
+
+    #[test]
+    fn test_find_json_key_diffs() {
+        let data1: Map<String, Value> = serde_json::from_str(
+            r#"{"name": "dtf", "age": 3, "nested": {"a": 1, "deeper": {"x": 1}}, "list": [{"a": 1}]}"#,
+        )
+        .unwrap();
+        let data2: Map<String, Value> = serde_json::from_str(
+            r#"{"name": "other", "city": "Budapest", "nested": {"b": 2, "deeper": {"x": 2, "y": 3}}, "list": [{"b": 1}]}"#,
+        )
+        .unwrap();
+
+        let key_diffs =
+            find_json_key_diffs(&data1, &data2, ("a.json", "b.json"), &mut no_limit()).unwrap();
+
+        let keys: Vec<(&str, &str)> = key_diffs
+            .iter()
+            .map(|kd| (kd.key.as_str(), kd.has.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("age", "a.json"),
+                ("nested.a", "a.json"),
+                ("nested.deeper.y", "b.json"),
+                ("nested.b", "b.json"),
+                ("city", "b.json"),
+            ]
+        );
+        assert_eq!(key_diffs[0].misses, "b.json");
+    }
+
+    #[test]
+    fn test_find_yaml_key_diffs() {
+        let data1: Mapping = serde_yaml::from_str("name: dtf\nnested:\n  a: 1\n1: one\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("name: dtf\nnested:\n  b: 2\n").unwrap();
+
+        let key_diffs =
+            find_yaml_key_diffs(&data1, &data2, ("a.yaml", "b.yaml"), &mut no_limit()).unwrap();
+
+        let keys: Vec<&str> = key_diffs.iter().map(|kd| kd.key.as_str()).collect();
+        assert_eq!(keys, vec!["nested.a", "nested.b", "1"]);
+    }
+
+    #[test]
+    fn test_limit_stops_the_walk() {
+        let data1: Map<String, Value> = (0..1000)
+            .map(|i| (format!("key{}", i), Value::from(i)))
+            .collect();
+        let mut limit = DiffLimit::new(&ConfigBuilder::new().max_diffs(Some(10)).build());
+
+        let result = find_json_key_diffs(&data1, &Map::new(), ("a.json", "b.json"), &mut limit);
+
+        // The walk stops at the first key over the limit instead of collecting all 1000
+        assert!(
+            matches!(result, Err(DtfError::LimitError(message)) if message.starts_with("11 differences found"))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::cmp::Reverse;
+
+use libdtf::core::diff_types::ValueDiff;
+
+/// The size a value difference is ranked by: the combined length of its two values in bytes
+pub fn value_diff_size(value_diff: &ValueDiff) -> usize {
+    value_diff.value1.len() + value_diff.value2.len()
+}
+
+/// The `n` value differences with the largest values, the largest first.
+/// Differences of the same size keep their order.
+pub fn largest_value_diffs(value_diffs: &[ValueDiff], n: usize) -> Vec<&ValueDiff> {
+    let mut value_diffs: Vec<&ValueDiff> = value_diffs.iter().collect();
+    value_diffs.sort_by_key(|value_diff| Reverse(value_diff_size(value_diff)));
+    value_diffs.truncate(n);
+    value_diffs
+}
+
+/// Lists the `n` largest value differences under a title, a line each
+pub fn render_largest_value_diffs(value_diffs: &[ValueDiff], n: usize) -> Vec<String> {
+    let largest = largest_value_diffs(value_diffs, n);
+    if largest.is_empty() {
+        return vec![];
+    }
+
+    let mut lines = vec![format!("The {} largest value differences:", largest.len())];
+    lines.extend(largest.into_iter().enumerate().map(|(index, value_diff)| {
+        format!(
+            "{}. {} ({} bytes)",
+            index + 1,
+            value_diff.key,
+            value_diff_size(value_diff)
+        )
+    }));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_diff(key: &str, value1: &str, value2: &str) -> ValueDiff {
+        ValueDiff {
+            key: key.to_owned(),
+            value1: value1.to_owned(),
+            value2: value2.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_largest_value_diffs() {
+        let value_diffs = vec![
+            value_diff("small", "1", "2"),
+            value_diff("large", &"x".repeat(100), "\"y\""),
+            value_diff("medium", "\"hello\"", "\"world\""),
+            value_diff("same", "3", "4"),
+        ];
+
+        let keys: Vec<&str> = largest_value_diffs(&value_diffs, 3)
+            .iter()
+            .map(|vd| vd.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["large", "medium", "small"]);
+        assert_eq!(largest_value_diffs(&value_diffs, 10).len(), 4);
+        assert!(largest_value_diffs(&value_diffs, 0).is_empty());
+    }
+
+    #[test]
+    fn test_render_largest_value_diffs() {
+        let value_diffs = vec![
+            value_diff("name", "\"John\"", "\"Jane\""),
+            value_diff("bio", "\"A long biography\"", "\"\""),
+        ];
+
+        assert_eq!(
+            render_largest_value_diffs(&value_diffs, 5),
+            vec![
+                "The 2 largest value differences:",
+                "1. bio (20 bytes)",
+                "2. name (12 bytes)",
+            ]
+        );
+        assert!(render_largest_value_diffs(&[], 5).is_empty());
+    }
+}
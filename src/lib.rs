@@ -1,21 +1,114 @@
+#[cfg(feature = "cli")]
 use app::App;
+#[cfg(feature = "cli")]
 use clap::{ArgGroup, Parser};
-use dtfterminal_types::DtfError;
+#[cfg(feature = "cli")]
+use diff_of_diffs::DiffOfDiffsApp;
+#[cfg(feature = "cli")]
+pub use document_cache::DocumentCache;
+#[cfg(feature = "cli")]
+use dtfterminal_types::{
+    ArrayMatchStrategy, Baseline, DtfError, NumberFormat, Outcome, OutputFormat, Severity,
+    SeverityRule, Tolerance, TypeNaming,
+};
+#[cfg(feature = "cli")]
+use file_pairs::{has_glob_metacharacters, pair_files};
+#[cfg(feature = "cli")]
+use regex::Regex;
+#[cfg(feature = "cli")]
+use three_way::ThreeWayApp;
 
+#[cfg(feature = "cli")]
 mod app;
+mod array_indexes;
+mod array_items;
+mod array_match;
 mod array_table;
+mod array_tail;
+#[cfg(feature = "cli")]
+mod badge;
+mod block_scalars;
+mod canonical;
+mod checked_data;
+mod common;
+pub mod comparator;
+pub mod compare;
+#[cfg(feature = "cli")]
+mod defaults;
+mod depth;
+#[cfg(feature = "cli")]
+mod diff_of_diffs;
+pub mod diff_tree;
+#[cfg(feature = "cli")]
+mod document_cache;
 pub mod dtfterminal_types;
+mod empty_values;
+mod envelope;
+mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "cli")]
 mod file_handler;
+#[cfg(feature = "cli")]
+mod file_pairs;
+#[cfg(feature = "cli")]
+mod first_diff;
+mod flatten;
+mod hash;
 mod html_renderer;
+#[cfg(feature = "http")]
+mod http;
+mod intersection;
 mod json_app;
+#[cfg(feature = "cli")]
+mod key_order;
+#[cfg(feature = "cli")]
+mod key_order_table;
+mod key_path;
 mod key_table;
+mod key_walk;
+mod largest;
+mod line_diff;
+mod matrix;
+#[cfg(feature = "cli")]
+mod normalize;
+mod numeric_strings;
+#[cfg(feature = "parquet")]
+mod parquet_app;
+mod path_filters;
+pub mod path_matcher;
+#[cfg(feature = "cli")]
+mod plan;
+mod positions;
+mod redact;
+mod render;
+mod sample;
+#[cfg(feature = "schema")]
+mod schema;
+pub mod semantic;
+mod side;
+mod stream;
+#[cfg(feature = "cli")]
+mod three_way;
 mod type_table;
+mod unicode;
 mod utils;
+mod value_filters;
 mod value_table;
+mod value_types;
 mod yaml_app;
+mod yaml_tags;
+
+pub use compare::diff_to_string;
+pub use flatten::collect_paths;
+#[cfg(feature = "cli")]
+pub use json_app::compare_json_files;
+pub use json_app::{compare_json_str, compare_json_values};
+pub use yaml_app::compare_yaml_str;
 
 /// Command line arguments are handled here by clap
-#[derive(Default, Parser, Debug)]
+#[cfg(feature = "cli")]
+#[derive(Clone, Default, Parser, Debug)]
 #[clap(
     version,
     about,
@@ -23,12 +116,12 @@ mod yaml_app;
         ArgGroup::new("diff-options")
             .required(true)
             .multiple(true)
-            .args(&["key_diffs", "type_diffs", "value_diffs", "array_diffs"]),
+            .args(&["key_diffs", "type_diffs", "value_diffs", "array_diffs", "compare_keys_only", "arrays_only", "key_out", "type_out", "value_out", "array_out", "semantic_equal"]),
     ),
     group(
         ArgGroup::new("file-options")
         .required(true)
-        .args(&["check_files", "read_from_file"])
+        .args(&["check_files", "read_from_file", "diff_of_diffs", "three_way"])
     ),
     group(
         ArgGroup::new("browser-options")
@@ -40,13 +133,21 @@ mod yaml_app;
 )]
 /// Find the difference in your data structures
 struct Arguments {
-    /// The files to check if not reading from saved check
+    /// The files to check if not reading from saved check. Glob patterns (e.g. 'configs/*.json') check every pair of files with the same name
     #[clap(short, value_delimiter = ' ', num_args = 2)]
     check_files: Vec<String>,
-    /// Read from a JSON file created on previous check instead of checking again
+    /// Read from a JSON file created on previous check instead of checking again. The files aren't read, so the array indexes, key positions and value types looked up in them aren't shown
     #[clap(short, default_value_t = String::new())]
     read_from_file: String,
 
+    /// Compare two results saved with -w and list which differences are new, resolved or persisting
+    #[clap(long, value_delimiter = ' ', num_args = 2, value_names = ["OLD", "NEW"])]
+    diff_of_diffs: Vec<String>,
+
+    /// Compare two files against the base they were both changed from, listing the changes of each side and where they conflict
+    #[clap(long, value_delimiter = ' ', num_args = 3, value_names = ["BASE", "OURS", "THEIRS"])]
+    three_way: Vec<String>,
+
     /// Output to json file instead of rendering tables in the terminal
     #[clap(short)]
     write_to_file: Option<String>,
@@ -63,6 +164,10 @@ struct Arguments {
     #[clap(short, default_value_t = false)]
     no_browser_show: bool,
 
+    /// Don't color the output in the terminal
+    #[clap(long, default_value_t = false)]
+    no_color: bool,
+
     /// Check for Key differences
     #[clap(short, default_value_t = false)]
     key_diffs: bool,
@@ -76,12 +181,410 @@ struct Arguments {
     #[clap(short, default_value_t = false)]
     array_diffs: bool,
 
+    /// Only check if the data is equal ignoring the order of keys and arrays at every level. Exits with 1 if it's not.
+    #[clap(long, default_value_t = false, requires = "check_files")]
+    semantic_equal: bool,
+
+    /// Display array differences of objects with the same value in this field on the same row
+    #[clap(long, value_name = "FIELD")]
+    array_key: Option<String>,
+
+    /// Compare ISO-8601 timestamps as instants, so different representations of the same time are equal
+    #[clap(long, default_value_t = false)]
+    parse_dates: bool,
+
+    /// Swap the roles of the two files, as if they were given in the opposite order
+    #[clap(long, default_value_t = false)]
+    invert: bool,
+
+    /// Print a machine-parseable line with the file names and the number of differences before the output
+    #[clap(long, default_value_t = false)]
+    emit_header: bool,
+
+    /// Only compare the keys present in both files: Key and Array differences caused by presence are not reported
+    #[clap(long, default_value_t = false)]
+    intersection: bool,
+
+    /// A file of default values filling in the keys missing from the checked files, in the same format as them
+    #[clap(long, value_name = "FILE")]
+    defaults: Option<String>,
+
+    /// Show only the differing lines of multi-line string values, instead of the whole values
+    #[clap(long, default_value_t = false)]
+    multiline_string_diff: bool,
+
+    /// The file (a or b) that is the expected state: keys and array items the other file misses are errors, the ones it has in addition are warnings
+    #[clap(long, value_name = "FILE")]
+    baseline: Option<Baseline>,
+
+    /// How the differences are written to the terminal: table, json (only the differences, without the configuration saved by -w) paths (the differing key paths, one per line), ndjson (a JSON line per difference and a summary line, written once the check is finished), markdown (a table per category, e.g. for pull request comments) or html (the document of -b, written to the terminal)
+    #[clap(long, value_name = "FORMAT", default_value = "table")]
+    format: OutputFormat,
+
+    /// Truncate values longer than this many characters in the browser view. The full value is shown when hovering over them.
+    #[clap(long, value_name = "N")]
+    value_truncate_len: Option<usize>,
+
+    /// Show at most this many rows of Array differences per key, followed by the number of the ones left out
+    #[clap(long, value_name = "N")]
+    max_array_diffs_per_key: Option<usize>,
+
+    /// Describe each difference with a sentence after the tables, for readers not familiar with them
+    #[clap(long, default_value_t = false)]
+    explain: bool,
+
+    /// Report objects having the same keys in a different order. JSON files are only supported when built with the `key-order` feature.
+    #[clap(long, default_value_t = false)]
+    check_key_order: bool,
+
+    /// Treat the second file as a JSON Schema and report where the first file doesn't conform to it. Requires the `schema` feature.
+    #[clap(long, default_value_t = false)]
+    schema: bool,
+
+    /// Show the type of the values next to them in the Value differences table
+    #[clap(long, default_value_t = false)]
+    show_types_in_values: bool,
+
+    /// Only report differences at the given path, inside it or in its parents. `*` matches any key and `[*]` any array index. Can be used multiple times.
+    #[clap(long = "only-path", value_name = "PATH")]
+    only_path: Vec<String>,
+
+    /// Don't report differences at the given path or inside it, even if allowed by `--only-path`. `*` matches any key and `[*]` any array index. Can be used multiple times.
+    #[clap(long = "ignore-path", value_name = "PATH")]
+    ignore_path: Vec<String>,
+
+    /// Write an SVG badge summarizing the result to the given file, e.g. for dashboards
+    #[clap(long, value_name = "FILE")]
+    badge: Option<String>,
+
+    /// Output at most this many characters of the differing values, e.g. to keep huge values out of the file written by -w
+    #[clap(long, value_name = "N")]
+    max_value_len: Option<usize>,
+
+    /// Log the steps of the comparison to stderr, like the skipped paths and matched array items. Once for debug, twice for trace.
+    #[clap(long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Embed the differences as JSON in the HTML output, so other tools can read them from the same file
+    #[clap(long, default_value_t = false)]
+    embed_data: bool,
+
+    /// Treat numbers not further apart than the epsilon as equal, optionally only under a path: `[path=]epsilon`. Can be used multiple times, the most specific path wins over the global one.
+    #[clap(long, value_name = "TOLERANCE")]
+    epsilon: Vec<Tolerance>,
+
+    /// Precede the paths of `--format paths` with the tag of their category: K, T, V or A
+    #[clap(long, default_value_t = false)]
+    tag_paths: bool,
+
+    /// Normalize keys and string values to Unicode NFC before comparing them, so composed and decomposed characters are equal
+    #[clap(long, default_value_t = false)]
+    normalize_unicode: bool,
+
+    /// Print which files would be compared, with which checks and path filters, then exit without comparing them
+    #[clap(long, default_value_t = false)]
+    plan: bool,
+
+    /// Compare the object at the path of the first file instead of the whole file, e.g. `data` for `{"data": {...}}`
+    #[clap(long, value_name = "PATH")]
+    unwrap_a: Option<String>,
+
+    /// Compare the object at the path of the second file instead of the whole file
+    #[clap(long, value_name = "PATH")]
+    unwrap_b: Option<String>,
+
+    /// Sort the values of the array differences of each key, numerically if all of them are numbers
+    #[clap(long, default_value_t = false)]
+    sort_array_values: bool,
+
+    /// Rewrite both files into a canonical form before comparing them: sorted keys, integral numbers as integers and sorted arrays of scalars
+    #[clap(long, default_value_t = false)]
+    canonicalize: bool,
+
+    /// Also trim the leading and trailing whitespace of strings with --canonicalize
+    #[clap(long, default_value_t = false)]
+    trim_strings: bool,
+
+    /// Wrap long value differences within their columns and line up the lines of the two versions
+    #[clap(long, default_value_t = false)]
+    side_by_side: bool,
+
+    /// How array items are matched: positional (like -o), key (by --array-key, falling back to similarity), similarity (pair objects sharing most fields) or set. Defaults to positional with -o and set otherwise, so --array-key alone only displays the objects with the same key on the same row.
+    #[clap(long, value_name = "STRATEGY")]
+    array_match: Option<ArrayMatchStrategy>,
+
+    /// Only write ASCII characters to the terminal: [x] and [ ] instead of the check marks and crosses, -> instead of the arrows and plain table borders
+    #[clap(long, default_value_t = false)]
+    ascii: bool,
+
+    /// Ignore Value differences of strings more similar than this, from 0 (anything) to 1 (equal), by their normalized Levenshtein distance
+    #[clap(long, value_name = "THRESHOLD")]
+    string_similarity: Option<f64>,
+
+    /// Flatten both files into single-level maps keyed by the paths of their values, like `a.b.c`, before comparing them
+    #[clap(long, default_value_t = false)]
+    flatten: bool,
+
+    /// Also flatten arrays by their indexes with --flatten, like `tags[0]`
+    #[clap(long, default_value_t = false, requires = "flatten")]
+    flatten_arrays: bool,
+
+    /// Write the keys present in both files with equal values to this file, in the format of the files
+    #[clap(long, value_name = "FILE")]
+    common: Option<String>,
+
+    /// Append the results to the file of -w as a new line instead of overwriting it. -r reads the last appended results.
+    #[clap(long, default_value_t = false, requires = "write_to_file")]
+    append: bool,
+
+    /// Show a table for every checked category, noting "(no differences)" in the empty ones, in the terminal and the browser view
+    #[clap(long, default_value_t = false, conflicts_with = "no_empty_tables")]
+    show_empty_tables: bool,
+
+    /// Leave out the tables of the categories without differences. This is the default.
+    #[clap(long, default_value_t = false)]
+    no_empty_tables: bool,
+
+    /// The title of the browser view, instead of "Data Differences"
+    #[clap(long, value_name = "TITLE")]
+    report_title: Option<String>,
+
+    /// A description shown under the title of the browser view
+    #[clap(long, value_name = "TEXT")]
+    report_description: Option<String>,
+
+    /// Don't report the items appended to the end of an ordered array, if the rest of it is the same in both files
+    #[clap(long, default_value_t = false)]
+    ignore_array_tail: bool,
+
+    /// Replace the parts of the shown keys and values matching the regular expression with ***. The files are still compared by their real values. Can be used multiple times.
+    #[clap(long = "redact", value_name = "REGEX")]
+    redact: Vec<Regex>,
+
+    /// Only check which keys were added or removed by walking nothing but the keys of the objects, without comparing any values. Objects inside arrays aren't walked into. Can't be used with -t, -v or -a.
+    #[clap(long, default_value_t = false, conflicts_with_all = ["type_diffs", "value_diffs", "array_diffs"])]
+    compare_keys_only: bool,
+
+    /// Leave out the differences already in a file written by -w, and fail only if there are others. For acknowledging known differences in CI.
+    #[clap(long, value_name = "FILE")]
+    baseline_diffs: Option<String>,
+
+    /// Add the line and column of the differing keys in both files to the output of --format json and ndjson, for jumping to them in an editor
+    #[clap(long, default_value_t = false)]
+    positions: bool,
+
+    /// Treat null, "" and missing keys as the same, unset value. 0 and false are still values.
+    #[clap(long, default_value_t = false)]
+    empty_equivalence: bool,
+
+    /// Only compare the contents of arrays. The key, type and value differences are left out even if -k, -t or -v is given.
+    #[clap(long, default_value_t = false, conflicts_with = "compare_keys_only")]
+    arrays_only: bool,
+
+    /// Compare tagged YAML values like `!Color red` as if they had no tags. By default the tag is part of the value.
+    #[clap(long, default_value_t = false)]
+    ignore_yaml_tags: bool,
+
+    /// Print only the number of differences and nothing else, for shell scripts
+    #[clap(long, default_value_t = false, conflicts_with_all = ["emit_header", "write_to_file", "browser_view"])]
+    count: bool,
+
+    /// Write the key differences to a file of their own. The format follows the extension: .html, .md, .json, .ndjson or tables for anything else.
+    #[clap(long, value_name = "FILE")]
+    key_out: Option<String>,
+
+    /// Write the type differences to a file of their own, in the format of its extension like --key-out
+    #[clap(long, value_name = "FILE")]
+    type_out: Option<String>,
+
+    /// Write the value differences to a file of their own, in the format of its extension like --key-out
+    #[clap(long, value_name = "FILE")]
+    value_out: Option<String>,
+
+    /// Write the array differences to a file of their own, in the format of its extension like --key-out
+    #[clap(long, value_name = "FILE")]
+    array_out: Option<String>,
+
+    /// Compare strings holding numbers with these separators as numbers: the thousands separator followed by the decimal one, e.g. ',.' for "1,234.56" or '.,' for "1.234,56"
+    #[clap(long, value_name = "SEPARATORS")]
+    number_format: Option<NumberFormat>,
+
+    /// Ignore the trailing line breaks of YAML strings, so block scalars differing only in their chomping indicator (|, |- or |+) are equal
+    #[clap(long, default_value_t = false)]
+    ignore_chomping: bool,
+
+    /// After the tables, list the N value differences with the largest values, by the combined size of the two values in bytes
+    #[clap(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// Read the JSON files as JSONC/JSON5, allowing comments and trailing commas. Files ending in .jsonc or .json5 are always read like this
+    #[clap(long, default_value_t = false)]
+    jsonc: bool,
+
+    /// With -a, report the items only one of the arrays contains even if the arrays are ordered (-o), next to their positional Value differences
+    #[clap(long, default_value_t = false)]
+    set_array_diffs: bool,
+
+    /// Treat a string holding a number and a number with the same value as equal, e.g. "8080" and 8080. Strings not holding a number still differ from numbers
+    #[clap(long, default_value_t = false)]
+    coerce_numeric_strings: bool,
+
+    /// Stop comparing at the first difference of the checked categories, show only that one and exit with 1. For quick gating, when it only matters if the files differ
+    #[clap(long, default_value_t = false, conflicts_with = "baseline_diffs")]
+    fail_fast: bool,
+
+    /// The column identifying the rows of Parquet files, so rows are compared by it instead of their position
+    #[clap(long, value_name = "COLUMN")]
+    primary_key: Option<String>,
+
+    /// The message shown instead of the differences if there are none, in the terminal, Markdown and HTML outputs
+    #[clap(long, value_name = "MESSAGE")]
+    identical_message: Option<String>,
+
+    /// Compare only a sample of this many items (at least 2) of longer arrays: the first, the last and evenly spread ones between them, at the same indexes in both files
+    #[clap(long, value_name = "N")]
+    sample: Option<usize>,
+
+    /// The seed picking the items of the sampled arrays, the same seed always picks the same items
+    #[clap(long, value_name = "SEED", default_value_t = 0, requires = "sample")]
+    sample_seed: u64,
+
+    /// How the types of the type differences are named: default (like bool and number) or json-schema (like boolean and integer)
+    #[clap(long, value_name = "NAMING", default_value = "default")]
+    type_naming: TypeNaming,
+
+    /// Also compute the SHA-256 hash of both files and report if they are identical byte for byte, independently of the structural comparison
+    #[clap(long, default_value_t = false)]
+    hash: bool,
+
+    /// Compare arrays of arrays as matrices: rows are aligned so inserted rows are reported once, and the differing cells at `[row][column]`
+    #[clap(long, default_value_t = false)]
+    matrices: bool,
+
+    /// Show the runs of consecutive array items only one file has as a single row, like "indices 10–42 added in B"
+    #[clap(long, default_value_t = false)]
+    collapse_ranges: bool,
+
+    /// Refuse to read input files larger than this many bytes
+    #[clap(long, value_name = "BYTES")]
+    max_size: Option<u64>,
+
+    /// Stop with an error if more than this many differences are found. The files are checked key by key, walking into the objects both have, and the check stops at the first key over the limit. With --compare-keys-only every key is counted as it's walked. A single value or array is still compared in full, so the differences under one key can exceed this in memory
+    #[clap(long, value_name = "N")]
+    max_diffs_hard: Option<usize>,
+
     /// Do you want arrays to be the same order? If defined you will get Value differences with indexes, otherwise you will get array differences, that tell you which object contains or misses values.
     #[clap(short = 'o', default_value_t = false)]
     array_same_order: bool,
+
+    /// Treat the array at the given path as ordered even if `-o` is not defined. Can be used multiple times.
+    #[clap(long = "ordered-array", value_name = "PATH")]
+    ordered_array: Vec<String>,
+
+    /// Assign a severity (info, warning or error) to a difference category, optionally under a path: `category[:path]=severity`. Can be used multiple times, the last matching one wins.
+    #[clap(long, value_name = "RULE")]
+    severity: Vec<SeverityRule>,
+
+    /// Only report differences with at least this severity (info, warning or error)
+    #[clap(long, value_name = "SEVERITY", default_value = "info")]
+    min_severity: Severity,
+
+    /// Exit with 1 if a reported difference has at least this severity (info, warning or error), for failing CI only on the important ones
+    #[clap(long, value_name = "SEVERITY")]
+    fail_on_severity: Option<Severity>,
 }
 
 /// Runs the application
-pub fn run() -> Result<(), DtfError> {
-    App::new().execute()
+/// In the machine-readable formats errors are written to the standard output as JSON, then the process exits with 1.
+/// A failed outcome is not an error: its results are already written, so it only sets the exit code.
+#[cfg(feature = "cli")]
+pub fn run() -> Result<Outcome, DtfError> {
+    let args = Arguments::parse();
+    let format = args.format;
+    match run_with_args(args) {
+        Err(error) if format.is_machine_readable() => {
+            println!("{}", error.to_json());
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+/// Runs the application with the parsed arguments
+#[cfg(feature = "cli")]
+fn run_with_args(args: Arguments) -> Result<Outcome, DtfError> {
+    if args.no_color {
+        colored::control::set_override(false);
+    }
+    if args.verbose > 0 {
+        let level = if args.verbose == 1 {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Trace
+        };
+        env_logger::Builder::new().filter_level(level).init();
+    }
+
+    if args.semantic_equal {
+        if !semantic::check_files(&args.check_files[0], &args.check_files[1])? {
+            std::process::exit(1);
+        }
+        return Ok(Outcome::Passed);
+    }
+
+    if args.plan {
+        println!("{}", App::plan(args)?);
+        return Ok(Outcome::Passed);
+    }
+
+    if !args.diff_of_diffs.is_empty() {
+        DiffOfDiffsApp::new(args)?.execute()?;
+        return Ok(Outcome::Passed);
+    }
+
+    if !args.three_way.is_empty() {
+        ThreeWayApp::new(args)?.execute()?;
+        return Ok(Outcome::Passed);
+    }
+
+    if args
+        .check_files
+        .iter()
+        .any(|path| has_glob_metacharacters(path))
+    {
+        return run_file_pairs(args);
+    }
+
+    App::new(args)?.execute()
+}
+
+/// Runs the application for every pair of files matching the glob patterns
+/// Every pair is compared, the run fails with the first failed outcome.
+#[cfg(feature = "cli")]
+fn run_file_pairs(args: Arguments) -> Result<Outcome, DtfError> {
+    let (path_a, path_b) = (&args.check_files[0], &args.check_files[1]);
+    let file_pairs = pair_files(path_a, path_b)?;
+    // A single first file is the baseline of all the pairs, so it's only parsed once
+    let mut cache = if has_glob_metacharacters(path_a) {
+        DocumentCache::default()
+    } else {
+        DocumentCache::new(path_a.clone())
+    };
+    let mut outcome = Outcome::Passed;
+    // The progress goes to the standard error, so the output of every pair stays parseable, e.g. with `--format json`
+    for (file_a, file_b) in &file_pairs.pairs {
+        eprintln!("Comparing {} against {}", file_a, file_b);
+        let mut pair_args = args.clone();
+        pair_args.check_files = vec![file_a.clone(), file_b.clone()];
+        let pair_outcome = App::new_cached(pair_args, &mut cache)?.execute()?;
+        if outcome == Outcome::Passed {
+            outcome = pair_outcome;
+        }
+    }
+
+    if !file_pairs.unmatched.is_empty() {
+        eprintln!("{}", file_pairs.render_unmatched());
+    }
+
+    Ok(outcome)
 }
@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+
+use libdtf::core::diff_types::ValueDiff;
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::{Config, DiffCollection},
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// The keys of the value differences replaced by the lines that differ
+pub type LineDiffKeys = HashSet<String>;
+
+/// Replaces the values of differing multi-line strings with the lines that differ, if enabled.
+/// The first value gets the lines only A has as `-{line number}: {line}`,
+/// the second one the lines only B has as `+{line number}: {line}`.
+pub fn apply_line_diffs(diffs: DiffCollection, config: &Config) -> DiffCollection {
+    if !config.multiline_string_diff {
+        return diffs;
+    }
+
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let value_diff = value_diff.map(|value_diffs| {
+        value_diffs
+            .into_iter()
+            .map(
+                |vd| match (parse_string(&vd.value1), parse_string(&vd.value2)) {
+                    (Some(value1), Some(value2)) if is_multiline(&value1, &value2) => {
+                        let (lines_a, lines_b) = diff_lines(&value1, &value2);
+                        ValueDiff {
+                            key: vd.key,
+                            value1: format_lines('-', &lines_a),
+                            value2: format_lines('+', &lines_b),
+                        }
+                    }
+                    _ => vd,
+                },
+            )
+            .collect()
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// Finds the value differences `apply_line_diffs` replaces, by the strings in the JSON data,
+/// so they are displayed as is
+pub fn find_json_line_diff_keys(
+    value_diffs: &[ValueDiff],
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> LineDiffKeys {
+    find_line_diff_keys(value_diffs, |key| {
+        (
+            lookup_json(data1, key).and_then(Value::as_str),
+            lookup_json(data2, key).and_then(Value::as_str),
+        )
+    })
+}
+
+/// Finds the value differences `apply_line_diffs` replaces, by the strings in the YAML data
+pub fn find_yaml_line_diff_keys(
+    value_diffs: &[ValueDiff],
+    data1: &Mapping,
+    data2: &Mapping,
+) -> LineDiffKeys {
+    find_line_diff_keys(value_diffs, |key| {
+        (
+            lookup_yaml(data1, key).and_then(YamlValue::as_str),
+            lookup_yaml(data2, key).and_then(YamlValue::as_str),
+        )
+    })
+}
+
+fn find_line_diff_keys<'a>(
+    value_diffs: &[ValueDiff],
+    strings: impl Fn(&str) -> (Option<&'a str>, Option<&'a str>),
+) -> LineDiffKeys {
+    value_diffs
+        .iter()
+        .filter(|vd| match strings(&vd.key) {
+            (Some(value1), Some(value2)) => is_multiline(value1, value2),
+            _ => false,
+        })
+        .map(|vd| vd.key.clone())
+        .collect()
+}
+
+/// Differing strings are compared line by line if either of them has more than one line
+fn is_multiline(value1: &str, value2: &str) -> bool {
+    value1.contains('\n') || value2.contains('\n')
+}
+
+/// Parses a serialized string value. Anything else is ignored.
+fn parse_string(value: &str) -> Option<String> {
+    serde_json::from_str::<String>(value)
+        .ok()
+        .or_else(|| serde_yaml::from_str::<String>(value).ok())
+}
+
+/// Finds the lines only one of the texts has, using the longest common subsequence of lines.
+/// Returns the lines with their line numbers, starting from 1.
+fn diff_lines<'a>(a: &'a str, b: &'a str) -> (Vec<(usize, &'a str)>, Vec<(usize, &'a str)>) {
+    let lines_a: Vec<&str> = a.lines().collect();
+    let lines_b: Vec<&str> = b.lines().collect();
+
+    // common[i][j] is the length of the longest common subsequence of lines_a[i..] and lines_b[j..]
+    let mut common = vec![vec![0; lines_b.len() + 1]; lines_a.len() + 1];
+    for i in (0..lines_a.len()).rev() {
+        for j in (0..lines_b.len()).rev() {
+            common[i][j] = if lines_a[i] == lines_b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut only_a, mut only_b) = (vec![], vec![]);
+    let (mut i, mut j) = (0, 0);
+    while i < lines_a.len() || j < lines_b.len() {
+        if i < lines_a.len() && j < lines_b.len() && lines_a[i] == lines_b[j] {
+            i += 1;
+            j += 1;
+        } else if j == lines_b.len() || (i < lines_a.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            only_a.push((i + 1, lines_a[i]));
+            i += 1;
+        } else {
+            only_b.push((j + 1, lines_b[j]));
+            j += 1;
+        }
+    }
+
+    (only_a, only_b)
+}
+
+/// Formats the numbered lines, one per line, marked by the prefix
+fn format_lines(prefix: char, lines: &[(usize, &str)]) -> String {
+    lines
+        .iter()
+        .map(|(line_number, line)| format!("{}{}: {}", prefix, line_number, line))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dtfterminal_types::ConfigBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let (only_a, only_b) = diff_lines("a\nb\nc\nd", "a\nc\nx\nd\ne");
+        assert_eq!(only_a, vec![(2, "b")]);
+        assert_eq!(only_b, vec![(3, "x"), (5, "e")]);
+    }
+
+    #[test]
+    fn test_apply_line_diffs() {
+        let lines_a: Vec<String> = (1..=10).map(|n| format!("line {}", n)).collect();
+        let mut lines_b = lines_a.clone();
+        lines_b[5] = "changed line".to_owned();
+        let value_diff = ValueDiff {
+            key: "script".to_owned(),
+            value1: serde_json::to_string(&lines_a.join("\n")).unwrap(),
+            value2: serde_json::to_string(&lines_b.join("\n")).unwrap(),
+        };
+        let config = ConfigBuilder::new().multiline_string_diff(true).build();
+
+        let (_, _, value_diff, _) =
+            apply_line_diffs((None, None, Some(vec![value_diff]), None), &config);
+
+        let value_diffs = value_diff.unwrap();
+        assert_eq!(value_diffs[0].key, "script");
+        assert_eq!(value_diffs[0].value1, "-6: line 6");
+        assert_eq!(value_diffs[0].value2, "+6: changed line");
+    }
+
+    #[test]
+    fn test_apply_line_diffs_single_line_and_disabled() {
+        let value_diff = ValueDiff {
+            key: "name".to_owned(),
+            value1: "\"John\"".to_owned(),
+            value2: "\"Jane\"".to_owned(),
+        };
+        let diffs = (None, None, Some(vec![value_diff]), None);
+
+        let enabled = ConfigBuilder::new().multiline_string_diff(true).build();
+        let (_, _, value_diff, _) = apply_line_diffs(diffs.clone(), &enabled);
+        assert_eq!(value_diff.unwrap()[0].value1, "\"John\"");
+
+        let multiline = (
+            None,
+            None,
+            Some(vec![ValueDiff {
+                key: "script".to_owned(),
+                value1: "\"a\\nb\"".to_owned(),
+                value2: "\"a\\nc\"".to_owned(),
+            }]),
+            None,
+        );
+        let (_, _, value_diff, _) = apply_line_diffs(multiline, &ConfigBuilder::new().build());
+        assert_eq!(value_diff.unwrap()[0].value1, "\"a\\nb\"");
+    }
+
+    #[test]
+    fn test_find_json_line_diff_keys() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"script": "a\nb", "note": "-1: a", "count": 1}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"script": "a\nc", "note": "+1: b", "count": "1\n2"}"#)
+                .unwrap();
+        let value_diffs: Vec<ValueDiff> = ["script", "note", "count"]
+            .iter()
+            .map(|key| ValueDiff {
+                key: key.to_string(),
+                value1: String::new(),
+                value2: String::new(),
+            })
+            .collect();
+
+        let line_diff_keys = find_json_line_diff_keys(&value_diffs, &data1, &data2);
+
+        // Only looks like a line difference, and a number isn't diffed by lines
+        assert_eq!(line_diff_keys, LineDiffKeys::from(["script".to_owned()]));
+    }
+}
@@ -1,5 +1,17 @@
-use dtfterminal::{dtfterminal_types::DtfError, run};
+use std::process;
 
-fn main() -> Result<(), DtfError> {
-    run()
+use dtfterminal::{dtfterminal_types::Outcome, run};
+
+fn main() {
+    match run() {
+        Ok(Outcome::Passed) => {}
+        Ok(Outcome::Failed(reason)) => {
+            eprintln!("{}", reason);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,364 @@
+//! Compares arrays of arrays as matrices, cell by cell.
+//!
+//! The rows of the two matrices are aligned by their longest common subsequence, so an inserted or removed
+//! row is reported once as an array difference instead of shifting every row after it. Rows left between
+//! the aligned ones are paired in order and compared cell by cell, reported at `matrix[row][column]`
+//! by the row index of the first file. With `--intersection` the rows and cells only one matrix has are not reported.
+
+use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc, TypeDiff, ValueDiff};
+use serde_json::{Map, Value};
+use serde_yaml::Mapping;
+
+use crate::{
+    array_tail::array_paths,
+    dtfterminal_types::DiffCollection,
+    value_types::{json_type_name, lookup_json, lookup_yaml},
+};
+
+/// Replaces the differences found in the matrices of the JSON data with their cell by cell differences
+pub fn compare_json_matrices(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+    intersection: bool,
+) -> DiffCollection {
+    compare_matrices(diffs, intersection, |path| {
+        Some((
+            lookup_json(data1, path)?.clone(),
+            lookup_json(data2, path)?.clone(),
+        ))
+    })
+}
+
+/// Replaces the differences found in the matrices of the YAML data with their cell by cell differences
+pub fn compare_yaml_matrices(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+    intersection: bool,
+) -> DiffCollection {
+    compare_matrices(diffs, intersection, |path| {
+        let to_json = |value| serde_json::to_value(value).ok();
+        Some((
+            to_json(lookup_yaml(data1, path)?)?,
+            to_json(lookup_yaml(data2, path)?)?,
+        ))
+    })
+}
+
+fn compare_matrices(
+    diffs: DiffCollection,
+    intersection: bool,
+    lookup: impl Fn(&str) -> Option<(Value, Value)>,
+) -> DiffCollection {
+    let matrices = find_matrices(&diffs, &lookup);
+    if matrices.is_empty() {
+        return diffs;
+    }
+
+    let in_matrix = |key: &str| {
+        matrices.iter().any(|(path, ..)| {
+            key == path
+                || key
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('['))
+        })
+    };
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let mut type_diff = type_diff.map(|td| {
+        td.into_iter()
+            .filter(|d| !in_matrix(&d.key))
+            .collect::<Vec<_>>()
+    });
+    let mut value_diff = value_diff.map(|vd| {
+        vd.into_iter()
+            .filter(|d| !in_matrix(&d.key))
+            .collect::<Vec<_>>()
+    });
+    let mut array_diff = array_diff.map(|ad| {
+        ad.into_iter()
+            .filter(|d| !in_matrix(&d.key))
+            .collect::<Vec<_>>()
+    });
+    let key_diff = key_diff.map(|kd| kd.into_iter().filter(|d| !in_matrix(&d.key)).collect());
+
+    for (path, rows1, rows2) in &matrices {
+        let found = matrix_diffs(path, rows1, rows2, intersection);
+        if let Some(type_diff) = type_diff.as_mut() {
+            type_diff.extend(found.type_diffs);
+        }
+        if let Some(value_diff) = value_diff.as_mut() {
+            value_diff.extend(found.value_diffs);
+        }
+        if let Some(array_diff) = array_diff.as_mut() {
+            array_diff.extend(found.array_diffs);
+        }
+    }
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// The outermost matrices the differences are in, with their rows in both files
+fn find_matrices(
+    diffs: &DiffCollection,
+    lookup: &impl Fn(&str) -> Option<(Value, Value)>,
+) -> Vec<(String, Vec<Value>, Vec<Value>)> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let keys = key_diff.iter().flatten().map(|d| d.key.as_str());
+    let keys = keys.chain(type_diff.iter().flatten().map(|d| d.key.as_str()));
+    let keys = keys.chain(value_diff.iter().flatten().map(|d| d.key.as_str()));
+    let keys = keys.chain(array_diff.iter().flatten().map(|d| d.key.as_str()));
+
+    let mut matrices: Vec<(String, Vec<Value>, Vec<Value>)> = vec![];
+    for key in keys {
+        for path in array_paths(key) {
+            if matrices.iter().any(|(matrix, ..)| matrix == path) {
+                break;
+            }
+            if let Some((Value::Array(rows1), Value::Array(rows2))) = lookup(path) {
+                if is_matrix(&rows1) && is_matrix(&rows2) {
+                    matrices.push((path.to_owned(), rows1, rows2));
+                    break;
+                }
+            }
+        }
+    }
+    matrices
+}
+
+fn is_matrix(rows: &[Value]) -> bool {
+    !rows.is_empty() && rows.iter().all(Value::is_array)
+}
+
+#[derive(Debug, Default)]
+struct MatrixDiffs {
+    type_diffs: Vec<TypeDiff>,
+    value_diffs: Vec<ValueDiff>,
+    array_diffs: Vec<ArrayDiff>,
+}
+
+/// Aligns the rows of the matrices and compares the paired ones cell by cell.
+/// In intersection mode the rows and cells only one of the matrices has are left out.
+fn matrix_diffs(path: &str, rows1: &[Value], rows2: &[Value], intersection: bool) -> MatrixDiffs {
+    let mut found = MatrixDiffs::default();
+    let (mut start1, mut start2) = (0, 0);
+    let ends = common_rows(rows1, rows2)
+        .into_iter()
+        .chain(std::iter::once((rows1.len(), rows2.len())));
+    for (end1, end2) in ends {
+        let paired = (end1 - start1).min(end2 - start2);
+        for offset in 0..paired {
+            let index = start1 + offset;
+            compare_rows(
+                &format!("{}[{}]", path, index),
+                &rows1[index],
+                &rows2[start2 + offset],
+                intersection,
+                &mut found,
+            );
+        }
+        if !intersection {
+            for row in &rows1[start1 + paired..end1] {
+                found
+                    .array_diffs
+                    .push(array_diff(ArrayDiffDesc::AHas, path, row));
+            }
+            for row in &rows2[start2 + paired..end2] {
+                found
+                    .array_diffs
+                    .push(array_diff(ArrayDiffDesc::BHas, path, row));
+            }
+        }
+        (start1, start2) = (end1 + 1, end2 + 1);
+    }
+    found
+}
+
+fn compare_rows(
+    row_path: &str,
+    row1: &Value,
+    row2: &Value,
+    intersection: bool,
+    found: &mut MatrixDiffs,
+) {
+    let empty = vec![];
+    let cells1 = row1.as_array().unwrap_or(&empty);
+    let cells2 = row2.as_array().unwrap_or(&empty);
+    for (column, (cell1, cell2)) in cells1.iter().zip(cells2).enumerate() {
+        let key = format!("{}[{}]", row_path, column);
+        if json_type_name(cell1) != json_type_name(cell2) {
+            found.type_diffs.push(TypeDiff {
+                key,
+                type1: json_type_name(cell1).to_owned(),
+                type2: json_type_name(cell2).to_owned(),
+            });
+        } else if cell1 != cell2 {
+            found.value_diffs.push(ValueDiff {
+                key,
+                value1: cell1.to_string(),
+                value2: cell2.to_string(),
+            });
+        }
+    }
+    if intersection {
+        return;
+    }
+    for cell in cells1.iter().skip(cells2.len()) {
+        found
+            .array_diffs
+            .push(array_diff(ArrayDiffDesc::AHas, row_path, cell));
+    }
+    for cell in cells2.iter().skip(cells1.len()) {
+        found
+            .array_diffs
+            .push(array_diff(ArrayDiffDesc::BHas, row_path, cell));
+    }
+}
+
+fn array_diff(descriptor: ArrayDiffDesc, key: &str, value: &Value) -> ArrayDiff {
+    ArrayDiff {
+        descriptor,
+        key: key.to_owned(),
+        value: value.to_string(),
+    }
+}
+
+/// The indexes of the rows of the longest common subsequence of the two matrices, in order.
+/// Found by Hirschberg's algorithm, so only a row of lengths is kept in memory instead of the whole table.
+fn common_rows(rows1: &[Value], rows2: &[Value]) -> Vec<(usize, usize)> {
+    let mut common = vec![];
+    add_common_rows(rows1, rows2, (0, 0), &mut common);
+    common
+}
+
+fn add_common_rows(
+    rows1: &[Value],
+    rows2: &[Value],
+    offsets: (usize, usize),
+    common: &mut Vec<(usize, usize)>,
+) {
+    if rows1.is_empty() || rows2.is_empty() {
+        return;
+    }
+    if let [row] = rows1 {
+        if let Some(index) = rows2.iter().position(|other| other == row) {
+            common.push((offsets.0, offsets.1 + index));
+        }
+        return;
+    }
+
+    // Split rows2 where the subsequences of the two halves of rows1 are the longest together
+    let middle = rows1.len() / 2;
+    let forward = common_lengths(rows1[..middle].iter(), rows2.iter());
+    let backward = common_lengths(rows1[middle..].iter().rev(), rows2.iter().rev());
+    let split = (0..=rows2.len())
+        .max_by_key(|&index| forward[index] + backward[rows2.len() - index])
+        .unwrap_or_default();
+    add_common_rows(&rows1[..middle], &rows2[..split], offsets, common);
+    add_common_rows(
+        &rows1[middle..],
+        &rows2[split..],
+        (offsets.0 + middle, offsets.1 + split),
+        common,
+    );
+}
+
+/// The lengths of the longest common subsequences of rows1 and each prefix of rows2
+fn common_lengths<'a>(
+    rows1: impl Iterator<Item = &'a Value>,
+    rows2: impl Iterator<Item = &'a Value> + Clone,
+) -> Vec<usize> {
+    let mut lengths = vec![0; rows2.clone().count() + 1];
+    for row1 in rows1 {
+        // The length for the previous prefix of rows2, before the current row of rows1
+        let mut previous = 0;
+        for (index, row2) in rows2.clone().enumerate() {
+            let above = lengths[index + 1];
+            lengths[index + 1] = if row1 == row2 {
+                previous + 1
+            } else {
+                above.max(lengths[index])
+            };
+            previous = above;
+        }
+    }
+    lengths
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rows(value: Value) -> Vec<Value> {
+        value.as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn test_common_rows() {
+        let rows1 = rows(json!([[1], [2], [3]]));
+        let rows2 = rows(json!([[1], [9], [2], [3]]));
+
+        assert_eq!(common_rows(&rows1, &rows2), vec![(0, 0), (1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn test_common_rows_repeated() {
+        let rows1 = rows(json!([[1], [2], [1], [2], [3]]));
+        let rows2 = rows(json!([[2], [1], [2], [1], [3], [2]]));
+
+        let common = common_rows(&rows1, &rows2);
+
+        assert_eq!(common.len(), 4);
+        assert!(common
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1));
+        assert!(common.iter().all(|&(i, j)| rows1[i] == rows2[j]));
+    }
+
+    #[test]
+    fn test_matrix_diffs_cell() {
+        let rows1 = rows(json!([[1, 2], [3, 4]]));
+        let rows2 = rows(json!([[1, 2], [3, "4"]]));
+
+        let found = matrix_diffs("grid", &rows1, &rows2, false);
+
+        assert_eq!(found.type_diffs.len(), 1);
+        assert_eq!(found.type_diffs[0].key, "grid[1][1]");
+        assert!(found.value_diffs.is_empty());
+        assert!(found.array_diffs.is_empty());
+    }
+
+    #[test]
+    fn test_matrix_diffs_inserted_row() {
+        let rows1 = rows(json!([[1, 2], [3, 4], [5, 6]]));
+        let rows2 = rows(json!([[1, 2], [0, 0], [3, 4], [5, 7]]));
+
+        let found = matrix_diffs("grid", &rows1, &rows2, false);
+
+        assert_eq!(found.array_diffs.len(), 1);
+        assert_eq!(found.array_diffs[0].descriptor, ArrayDiffDesc::BHas);
+        assert_eq!(found.array_diffs[0].value, "[0,0]");
+        assert_eq!(found.value_diffs.len(), 1);
+        assert_eq!(found.value_diffs[0].key, "grid[2][1]");
+        assert_eq!(
+            (
+                found.value_diffs[0].value1.as_str(),
+                found.value_diffs[0].value2.as_str()
+            ),
+            ("6", "7")
+        );
+    }
+
+    #[test]
+    fn test_matrix_diffs_intersection() {
+        let rows1 = rows(json!([[1, 2], [3, 4]]));
+        let rows2 = rows(json!([[1, 2], [3, 5, 6], [7, 8]]));
+
+        let found = matrix_diffs("grid", &rows1, &rows2, true);
+
+        assert!(found.array_diffs.is_empty());
+        assert_eq!(found.value_diffs.len(), 1);
+        assert_eq!(found.value_diffs[0].key, "grid[1][1]");
+    }
+}
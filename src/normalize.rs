@@ -0,0 +1,172 @@
+//! Normalizes documents of different formats into JSON values, so they can be compared with each other.
+//!
+//! The rules of the normalization:
+//! * Mapping keys have to be strings in JSON. Number and boolean keys are converted to their string form
+//!   (`1: a` becomes `"1": "a"`), other keys are rejected.
+//! * Integers stay integers and floats stay floats. Like between two JSON files, `1` and `1.0` are different values.
+//!   A TOML integer only equals a JSON number written without a fraction or exponent.
+//! * TOML datetimes, dates and times become strings in their RFC 3339 form, e.g. `2024-01-01T12:00:00Z`,
+//!   so they equal the same JSON string. With `--parse-dates` they are compared as instants.
+//! * YAML tags are dropped, only the tagged values are kept.
+//! * `.nan` and `.inf` (`nan` and `inf` in TOML) have no JSON representation, so documents containing them are rejected.
+
+use serde_json::{Map, Number, Value};
+
+/// Converts a YAML mapping into a JSON object, following the rules of the module
+pub fn yaml_to_json(mapping: serde_yaml::Mapping) -> Result<Map<String, Value>, String> {
+    mapping
+        .into_iter()
+        .map(|(key, value)| Ok((yaml_key_to_json(key)?, yaml_value_to_json(value)?)))
+        .collect()
+}
+
+fn yaml_value_to_json(value: serde_yaml::Value) -> Result<Value, String> {
+    match value {
+        serde_yaml::Value::Null => Ok(Value::Null),
+        serde_yaml::Value::Bool(b) => Ok(Value::Bool(b)),
+        serde_yaml::Value::Number(n) => yaml_number_to_json(&n).map(Value::Number),
+        serde_yaml::Value::String(s) => Ok(Value::String(s)),
+        serde_yaml::Value::Sequence(sequence) => sequence
+            .into_iter()
+            .map(yaml_value_to_json)
+            .collect::<Result<Vec<Value>, String>>()
+            .map(Value::Array),
+        serde_yaml::Value::Mapping(mapping) => yaml_to_json(mapping).map(Value::Object),
+        serde_yaml::Value::Tagged(tagged) => yaml_value_to_json(tagged.value),
+    }
+}
+
+fn yaml_number_to_json(number: &serde_yaml::Number) -> Result<Number, String> {
+    if let Some(i) = number.as_i64() {
+        Ok(Number::from(i))
+    } else if let Some(u) = number.as_u64() {
+        Ok(Number::from(u))
+    } else {
+        number
+            .as_f64()
+            .and_then(Number::from_f64)
+            .ok_or_else(|| format!("{} can't be represented in JSON", number))
+    }
+}
+
+/// Converts a parsed TOML document into a JSON object, following the rules of the module
+pub fn toml_to_json(table: toml::Table) -> Result<Map<String, Value>, String> {
+    table
+        .into_iter()
+        .map(|(key, value)| Ok((key, toml_value_to_json(value)?)))
+        .collect()
+}
+
+fn toml_value_to_json(value: toml::Value) -> Result<Value, String> {
+    match value {
+        toml::Value::String(s) => Ok(Value::String(s)),
+        toml::Value::Integer(i) => Ok(Value::Number(Number::from(i))),
+        toml::Value::Float(f) => Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{} can't be represented in JSON", f)),
+        toml::Value::Boolean(b) => Ok(Value::Bool(b)),
+        toml::Value::Datetime(datetime) => Ok(Value::String(datetime.to_string())),
+        toml::Value::Array(array) => array
+            .into_iter()
+            .map(toml_value_to_json)
+            .collect::<Result<Vec<Value>, String>>()
+            .map(Value::Array),
+        toml::Value::Table(table) => toml_to_json(table).map(Value::Object),
+    }
+}
+
+fn yaml_key_to_json(key: serde_yaml::Value) -> Result<String, String> {
+    match key {
+        serde_yaml::Value::String(s) => Ok(s),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        serde_yaml::Value::Tagged(tagged) => yaml_key_to_json(tagged.value),
+        other => Err(format!("Unsupported mapping key: {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_yaml_to_json() {
+        let mapping: serde_yaml::Mapping = serde_yaml::from_str(
+            "name: John\nage: 42\nheight: 1.8\n1: one\ntrue: yes\ntags: [a, !custom b]\nnothing: null\n",
+        )
+        .unwrap();
+
+        let object = yaml_to_json(mapping).unwrap();
+
+        assert_eq!(
+            Value::Object(object),
+            json!({
+                "name": "John",
+                "age": 42,
+                "height": 1.8,
+                "1": "one",
+                "true": "yes",
+                "tags": ["a", "b"],
+                "nothing": null
+            })
+        );
+    }
+
+    #[test]
+    fn test_yaml_to_json_keeps_integers_and_floats_apart() {
+        let mapping: serde_yaml::Mapping = serde_yaml::from_str("a: 1\nb: 1.0\n").unwrap();
+
+        let object = yaml_to_json(mapping).unwrap();
+
+        assert_eq!(object["a"], json!(1));
+        assert_ne!(object["b"], json!(1));
+    }
+
+    #[test]
+    fn test_yaml_to_json_rejects_unrepresentable_values() {
+        let nan: serde_yaml::Mapping = serde_yaml::from_str("a: .nan\n").unwrap();
+        let sequence_key: serde_yaml::Mapping = serde_yaml::from_str("[a, b]: c\n").unwrap();
+
+        assert!(yaml_to_json(nan).is_err());
+        assert!(yaml_to_json(sequence_key).is_err());
+    }
+
+    #[test]
+    fn test_toml_to_json() {
+        let object = toml_to_json(
+            "name = \"John\"\nage = 42\nheight = 1.8\nborn = 1979-05-27T07:32:00-08:00\n\
+             day = 1979-05-27\ntags = [\"a\", \"b\"]\n\n[address]\ncity = \"Anytown\"\n"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Value::Object(object),
+            json!({
+                "name": "John",
+                "age": 42,
+                "height": 1.8,
+                "born": "1979-05-27T07:32:00-08:00",
+                "day": "1979-05-27",
+                "tags": ["a", "b"],
+                "address": {"city": "Anytown"}
+            })
+        );
+    }
+
+    #[test]
+    fn test_toml_to_json_keeps_integers_and_floats_apart() {
+        let object = toml_to_json("a = 1\nb = 1.0\n".parse().unwrap()).unwrap();
+
+        assert_eq!(object["a"], json!(1));
+        assert_ne!(object["b"], json!(1));
+    }
+
+    #[test]
+    fn test_toml_to_json_rejects_unrepresentable_values() {
+        assert!(toml_to_json("a = nan\n".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::DiffCollection,
+    value_types::{lookup_json, lookup_yaml},
+};
+
+/// Drops the type and value differences of the JSON data between a number and a string holding the same number,
+/// like `8080` and `"8080"`. Strings not holding a number, like `"abc"`, still differ from numbers.
+pub fn coerce_json_numeric_strings(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> DiffCollection {
+    let number = |value: Option<&Value>| match value {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => parse_number(s),
+        _ => None,
+    };
+    let is_string = |value: Option<&Value>| matches!(value, Some(Value::String(_)));
+    drop_same_number_diffs(diffs, |key| {
+        let (value1, value2) = (lookup_json(data1, key), lookup_json(data2, key));
+        is_string(value1) != is_string(value2) && same_number(number(value1), number(value2))
+    })
+}
+
+/// Drops the type and value differences of the YAML data between a number and a string holding the same number,
+/// like `8080` and `"8080"`. Strings not holding a number, like `"abc"`, still differ from numbers.
+pub fn coerce_yaml_numeric_strings(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+) -> DiffCollection {
+    let number = |value: Option<&YamlValue>| match value {
+        Some(YamlValue::Number(n)) => n.as_f64(),
+        Some(YamlValue::String(s)) => parse_number(s),
+        _ => None,
+    };
+    let is_string = |value: Option<&YamlValue>| matches!(value, Some(YamlValue::String(_)));
+    drop_same_number_diffs(diffs, |key| {
+        let (value1, value2) = (lookup_yaml(data1, key), lookup_yaml(data2, key));
+        is_string(value1) != is_string(value2) && same_number(number(value1), number(value2))
+    })
+}
+
+/// Parses a string holding a finite number, e.g. `"8080"` or `"1.5e3"`
+fn parse_number(s: &str) -> Option<f64> {
+    s.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn same_number(number1: Option<f64>, number2: Option<f64>) -> bool {
+    matches!((number1, number2), (Some(n1), Some(n2)) if n1 == n2)
+}
+
+/// The key and array differences are kept, the key is present in both files anyway
+fn drop_same_number_diffs(
+    diffs: DiffCollection,
+    is_same_number: impl Fn(&str) -> bool,
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff,
+        type_diff.map(|td| td.into_iter().filter(|d| !is_same_number(&d.key)).collect()),
+        value_diff.map(|vd| vd.into_iter().filter(|d| !is_same_number(&d.key)).collect()),
+        array_diff,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{TypeDiff, ValueDiff};
+    use serde_json::json;
+
+    use super::*;
+
+    fn diffs(key: &str) -> DiffCollection {
+        (
+            None,
+            Some(vec![TypeDiff {
+                key: key.to_owned(),
+                type1: "string".to_owned(),
+                type2: "number".to_owned(),
+            }]),
+            Some(vec![ValueDiff {
+                key: key.to_owned(),
+                value1: "a".to_owned(),
+                value2: "b".to_owned(),
+            }]),
+            None,
+        )
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_coerce_json_numeric_strings() {
+        let data1 = object(json!({"port": "8080", "name": "abc", "ratio": "0.5"}));
+        let data2 = object(json!({"port": 8080, "name": 8080, "ratio": 0.25}));
+
+        let (_, type_diff, value_diff, _) =
+            coerce_json_numeric_strings(diffs("port"), &data1, &data2);
+        assert!(type_diff.unwrap().is_empty());
+        assert!(value_diff.unwrap().is_empty());
+
+        let (_, type_diff, value_diff, _) =
+            coerce_json_numeric_strings(diffs("name"), &data1, &data2);
+        assert_eq!(type_diff.unwrap().len(), 1);
+        assert_eq!(value_diff.unwrap().len(), 1);
+
+        let (_, type_diff, _, _) = coerce_json_numeric_strings(diffs("ratio"), &data1, &data2);
+        assert_eq!(type_diff.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_coerce_yaml_numeric_strings() {
+        let data1: Mapping = serde_yaml::from_str("port: '8080'\nname: abc\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("port: 8080\nname: 8080\n").unwrap();
+
+        let (_, type_diff, value_diff, _) =
+            coerce_yaml_numeric_strings(diffs("port"), &data1, &data2);
+        assert!(type_diff.unwrap().is_empty());
+        assert!(value_diff.unwrap().is_empty());
+
+        let (_, type_diff, _, _) = coerce_yaml_numeric_strings(diffs("name"), &data1, &data2);
+        assert_eq!(type_diff.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+//! Compares Parquet files row by row.
+//!
+//! The rows are read into JSON objects keyed by their primary key column, or their index if there is none,
+//! so the standard checkers report added and removed rows as Key differences and changed cells as Value differences
+//! at paths like `42.price`. Columns having a different type in the schemas are Type differences at `$schema.<column>`.
+
+use std::fs::File;
+
+use libdtf::core::diff_types::TypeDiff;
+use parquet::{
+    basic::ConvertedType,
+    errors::ParquetError,
+    file::reader::{FileReader, SerializedFileReader},
+    schema::types::SchemaDescriptor,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    dtfterminal_types::{DiffCollection, DtfError, WorkingContext},
+    file_handler::FileHandler,
+    json_app::JsonApp,
+};
+
+/// The columns of a schema by their paths, with the names of their types
+type ColumnTypes = Vec<(String, String)>;
+
+/// Responsible for comparing two Parquet files, using the JSON checkers for their rows
+pub struct ParquetApp {
+    json_app: JsonApp,
+    schema_diffs: Vec<TypeDiff>,
+}
+
+impl ParquetApp {
+    /// Reads the rows and schemas of both files
+    pub fn new(path1: &str, path2: &str, context: WorkingContext) -> Result<ParquetApp, DtfError> {
+        let primary_key = context.config.primary_key.clone();
+        let (rows1, columns1) = read_parquet_file(path1, primary_key.as_deref())?;
+        let (rows2, columns2) = read_parquet_file(path2, primary_key.as_deref())?;
+        Ok(ParquetApp {
+            json_app: JsonApp::from_data(rows1, rows2, context),
+            schema_diffs: schema_type_diffs(&columns1, &columns2),
+        })
+    }
+
+    /// Checks the rows for differences. The columns of different types are reported before the cells.
+    pub fn perform_new_check(&self) -> Result<DiffCollection, DtfError> {
+        let (key_diff, type_diff, value_diff, array_diff) = self.json_app.perform_new_check()?;
+        let type_diff = type_diff.map(|type_diffs| {
+            self.schema_diffs
+                .iter()
+                .cloned()
+                .chain(type_diffs)
+                .collect()
+        });
+        Ok((key_diff, type_diff, value_diff, array_diff))
+    }
+}
+
+/// Reads the rows of a Parquet file keyed by their primary key, and the types of its columns
+fn read_parquet_file(
+    path: &str,
+    primary_key: Option<&str>,
+) -> Result<(Map<String, Value>, ColumnTypes), DtfError> {
+    let parse_error = |e: ParquetError| DtfError::ParseError {
+        path: path.to_owned(),
+        source: Box::new(e),
+    };
+    let file = File::open(path).map_err(|e| FileHandler::open_error(path, e))?;
+    let reader = SerializedFileReader::new(file).map_err(parse_error)?;
+    let columns = column_types(reader.metadata().file_metadata().schema_descr());
+
+    let mut rows = Map::new();
+    for (index, row) in reader.get_row_iter(None).map_err(parse_error)?.enumerate() {
+        let row = row.map_err(parse_error)?.to_json_value();
+        let key = match primary_key {
+            Some(column) => match row.get(column) {
+                Some(Value::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+                None => {
+                    return Err(DtfError::DiffError(format!(
+                        "{} has no primary key column {}",
+                        path, column
+                    )))
+                }
+            },
+            None => index.to_string(),
+        };
+        if rows.insert(key.clone(), row).is_some() {
+            return Err(DtfError::DiffError(format!(
+                "The primary key {} is not unique in {}",
+                key, path
+            )));
+        }
+    }
+
+    Ok((rows, columns))
+}
+
+/// The types of the leaf columns, like `INT64` or `BYTE_ARRAY (UTF8)`
+fn column_types(schema: &SchemaDescriptor) -> ColumnTypes {
+    schema
+        .columns()
+        .iter()
+        .map(|column| {
+            let type_name = match column.converted_type() {
+                ConvertedType::NONE => column.physical_type().to_string(),
+                converted_type => format!("{} ({})", column.physical_type(), converted_type),
+            };
+            (column.path().string(), type_name)
+        })
+        .collect()
+}
+
+/// The columns both schemas have, but with different types.
+/// The columns only one of them has show up as Key differences of the rows.
+fn schema_type_diffs(columns1: &ColumnTypes, columns2: &ColumnTypes) -> Vec<TypeDiff> {
+    columns1
+        .iter()
+        .filter_map(|(column, type1)| {
+            let (_, type2) = columns2.iter().find(|(other, _)| other == column)?;
+            (type1 != type2).then(|| TypeDiff {
+                key: format!("$schema.{}", column),
+                type1: type1.clone(),
+                type2: type2.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use parquet::{
+        data_type::{ByteArray, ByteArrayType, Int64Type},
+        file::{properties::WriterProperties, writer::SerializedFileWriter},
+        schema::parser::parse_message_type,
+    };
+
+    use crate::{dtfterminal_types::ConfigBuilder, utils::create_working_context};
+
+    use super::*;
+
+    const PEOPLE_SCHEMA: &str =
+        "message people { REQUIRED INT64 id; REQUIRED BYTE_ARRAY name (UTF8); }";
+
+    /// Writes a Parquet file of people in a single row group
+    fn write_people(path: &Path, ids: &[i64], names: &[&str]) {
+        let schema = Arc::new(parse_message_type(PEOPLE_SCHEMA).unwrap());
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(path).unwrap(), schema, properties).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(ids, None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        let names: Vec<ByteArray> = names.iter().map(|name| ByteArray::from(*name)).collect();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&names, None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        row_group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_parquet_app() {
+        let dir = std::env::temp_dir().join(format!("dtf_parquet_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (path1, path2) = (dir.join("a.parquet"), dir.join("b.parquet"));
+        write_people(&path1, &[1, 2], &["Ann", "Bob"]);
+        write_people(&path2, &[1, 2, 3], &["Ann", "Rob", "Cid"]);
+        let config = ConfigBuilder::new()
+            .check_for_key_diffs(true)
+            .check_for_type_diffs(true)
+            .check_for_value_diffs(true)
+            .primary_key(Some("id".to_owned()))
+            .file_a(Some("a.parquet".to_owned()))
+            .file_b(Some("b.parquet".to_owned()))
+            .build();
+
+        let app = ParquetApp::new(
+            path1.to_str().unwrap(),
+            path2.to_str().unwrap(),
+            create_working_context(&config).unwrap(),
+        )
+        .unwrap();
+        let (key_diff, type_diff, value_diff, _) = app.perform_new_check().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let key_diffs = key_diff.unwrap();
+        assert_eq!(key_diffs.len(), 1);
+        assert_eq!(key_diffs[0].key, "3");
+        assert_eq!(key_diffs[0].has, "b.parquet");
+        assert!(type_diff.unwrap().is_empty());
+        let value_diffs = value_diff.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "2.name");
+        assert_eq!(value_diffs[0].value1, "\"Bob\"");
+        assert_eq!(value_diffs[0].value2, "\"Rob\"");
+    }
+
+    #[test]
+    fn test_schema_type_diffs() {
+        let columns = |message: &str| {
+            let schema = SchemaDescriptor::new(Arc::new(parse_message_type(message).unwrap()));
+            column_types(&schema)
+        };
+        let columns1 = columns("message a { REQUIRED INT64 id; REQUIRED DOUBLE price; }");
+        let columns2 =
+            columns("message b { REQUIRED INT64 id; REQUIRED BYTE_ARRAY price (UTF8); }");
+
+        let type_diffs = schema_type_diffs(&columns1, &columns2);
+
+        assert_eq!(
+            type_diffs,
+            vec![TypeDiff {
+                key: "$schema.price".to_owned(),
+                type1: "DOUBLE".to_owned(),
+                type2: "BYTE_ARRAY (UTF8)".to_owned(),
+            }]
+        );
+    }
+}
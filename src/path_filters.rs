@@ -0,0 +1,142 @@
+use log::debug;
+
+use crate::{
+    dtfterminal_types::{Config, DiffCollection},
+    path_matcher::PathMatcher,
+};
+
+/// Drops the differences under the ignored paths and, if any paths are allowed, the ones outside them.
+/// Ignored paths win over allowed ones.
+pub fn filter_paths(diffs: DiffCollection, config: &Config) -> DiffCollection {
+    if config.only_paths.is_empty() && config.ignore_paths.is_empty() {
+        return diffs;
+    }
+
+    let only_paths = PathMatcher::compile_all(&config.only_paths);
+    let ignore_paths = PathMatcher::compile_all(&config.ignore_paths);
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let reported = |key: &str| is_reported(key, &only_paths, &ignore_paths);
+    (
+        key_diff.map(|diffs| diffs.into_iter().filter(|d| reported(&d.key)).collect()),
+        type_diff.map(|diffs| diffs.into_iter().filter(|d| reported(&d.key)).collect()),
+        value_diff.map(|diffs| diffs.into_iter().filter(|d| reported(&d.key)).collect()),
+        array_diff.map(|diffs| diffs.into_iter().filter(|d| reported(&d.key)).collect()),
+    )
+}
+
+/// Checks if a difference at the key should be reported.
+/// Differences of the parents of an allowed path are reported too, as they affect the allowed path.
+fn is_reported(key: &str, only_paths: &[PathMatcher], ignore_paths: &[PathMatcher]) -> bool {
+    if let Some(matcher) = ignore_paths.iter().find(|matcher| matcher.matches(key)) {
+        debug!("Skipped {}: ignored by {}", key, matcher.pattern());
+        return false;
+    }
+
+    if only_paths.is_empty()
+        || only_paths
+            .iter()
+            .any(|matcher| matcher.matches_or_contains(key))
+    {
+        return true;
+    }
+
+    debug!("Skipped {}: outside the allowed paths", key);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use libdtf::core::diff_types::ValueDiff;
+
+    use crate::dtfterminal_types::ConfigBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_only_paths() {
+        let only_paths = [
+            PathMatcher::new("user.email"),
+            PathMatcher::new("servers[*].port"),
+        ];
+
+        assert!(is_reported("user.email", &only_paths, &[]));
+        assert!(is_reported("user", &only_paths, &[]));
+        assert!(is_reported("servers[1].port", &only_paths, &[]));
+        assert!(!is_reported("user.name", &only_paths, &[]));
+        assert!(!is_reported("servers[1].host", &only_paths, &[]));
+        assert!(!is_reported("version", &only_paths, &[]));
+    }
+
+    #[test]
+    fn test_ignore_paths() {
+        let ignore_paths = [
+            PathMatcher::new("metadata"),
+            PathMatcher::new("*.updated_at"),
+        ];
+
+        assert!(!is_reported("metadata", &[], &ignore_paths));
+        assert!(!is_reported("metadata.labels[0]", &[], &ignore_paths));
+        assert!(!is_reported("user.updated_at", &[], &ignore_paths));
+        assert!(is_reported("user", &[], &ignore_paths));
+        assert!(is_reported("user.name", &[], &ignore_paths));
+    }
+
+    #[test]
+    fn test_ignore_wins_over_only() {
+        let config = ConfigBuilder::new()
+            .only_paths(vec!["user".to_owned()])
+            .ignore_paths(vec!["user.password".to_owned()])
+            .build();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        };
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff("user.email"),
+                value_diff("user.password"),
+                value_diff("version"),
+            ]),
+            None,
+        );
+
+        let (_, _, value_diff, _) = filter_paths(diffs, &config);
+
+        let keys: Vec<String> = value_diff.unwrap().into_iter().map(|vd| vd.key).collect();
+        assert_eq!(keys, vec!["user.email"]);
+    }
+
+    #[test]
+    fn test_skipped_paths_are_logged() {
+        static LOGGER: TestLogger = TestLogger(Mutex::new(vec![]));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        is_reported("metadata.labels", &[], &[PathMatcher::new("metadata")]);
+        is_reported("version", &[PathMatcher::new("user")], &[]);
+
+        let messages = LOGGER.0.lock().unwrap();
+        assert!(messages.contains(&"Skipped metadata.labels: ignored by metadata".to_owned()));
+        assert!(messages.contains(&"Skipped version: outside the allowed paths".to_owned()));
+    }
+
+    /// Collects the logged messages, so the tests can check them
+    struct TestLogger(Mutex<Vec<String>>);
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+}
@@ -0,0 +1,126 @@
+use crate::diff_tree::split_key_path;
+
+/// Matches any key in a path pattern
+const ANY_KEY: &str = "*";
+/// Matches any array index in a path pattern
+const ANY_INDEX: &str = "[*]";
+
+/// A path pattern split into its segments once, to match the keys of the differences against.
+/// `*` matches any key and `[*]` any array index, e.g. `servers[*].port` or `*.updated_at`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathMatcher {
+    pattern: String,
+    segments: Vec<String>,
+}
+
+impl PathMatcher {
+    pub fn new(pattern: &str) -> PathMatcher {
+        PathMatcher {
+            pattern: pattern.to_owned(),
+            segments: split_key_path(pattern),
+        }
+    }
+
+    /// Compiles each of the patterns
+    pub fn compile_all(patterns: &[String]) -> Vec<PathMatcher> {
+        patterns
+            .iter()
+            .map(|pattern| PathMatcher::new(pattern))
+            .collect()
+    }
+
+    /// The pattern the matcher was created from
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The number of segments of the pattern, the more the more specific it is
+    pub fn specificity(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Checks if the path is the one the pattern points to or anything inside it
+    pub fn matches(&self, path: &str) -> bool {
+        let path_segments = split_key_path(path);
+        self.segments.len() <= path_segments.len() && self.matches_prefix(&path_segments)
+    }
+
+    /// Checks if the path matches the pattern or is a parent of the paths it points to
+    pub fn matches_or_contains(&self, path: &str) -> bool {
+        self.matches_prefix(&split_key_path(path))
+    }
+
+    fn matches_prefix(&self, path_segments: &[String]) -> bool {
+        path_segments
+            .iter()
+            .zip(&self.segments)
+            .all(|(path_segment, pattern_segment)| segment_matches(path_segment, pattern_segment))
+    }
+}
+
+fn segment_matches(path_segment: &str, pattern_segment: &str) -> bool {
+    let is_index = path_segment.starts_with('[');
+    match pattern_segment {
+        ANY_KEY => !is_index,
+        ANY_INDEX => is_index,
+        _ => path_segment == pattern_segment,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_pattern() {
+        let matcher = PathMatcher::new("steps");
+
+        assert!(matcher.matches("steps"));
+        assert!(matcher.matches("steps[1]"));
+        assert!(matcher.matches("steps.name"));
+        assert!(!matcher.matches("steps_old"));
+        assert!(!matcher.matches("tags"));
+    }
+
+    #[test]
+    fn test_key_wildcard_pattern() {
+        let matcher = PathMatcher::new("*.updated_at");
+
+        assert!(matcher.matches("user.updated_at"));
+        assert!(matcher.matches("order.updated_at.zone"));
+        assert!(!matcher.matches("updated_at"));
+        assert!(!matcher.matches("user.created_at"));
+        assert!(!matcher.matches("[0].updated_at"));
+    }
+
+    #[test]
+    fn test_index_wildcard_pattern() {
+        let matcher = PathMatcher::new("servers[*].port");
+
+        assert!(matcher.matches("servers[0].port"));
+        assert!(matcher.matches("servers[12].port"));
+        assert!(!matcher.matches("servers.main.port"));
+        assert!(!matcher.matches("servers[0].host"));
+    }
+
+    #[test]
+    fn test_multi_segment_pattern() {
+        let matcher = PathMatcher::new("pipeline.*[*].name");
+
+        assert!(matcher.matches("pipeline.steps[0].name"));
+        assert!(matcher.matches("pipeline.jobs[3].name"));
+        assert!(!matcher.matches("pipeline.steps.name"));
+        assert!(!matcher.matches("pipeline.steps[0]"));
+        assert_eq!(matcher.specificity(), 4);
+    }
+
+    #[test]
+    fn test_matches_or_contains() {
+        let matcher = PathMatcher::new("user.email");
+
+        assert!(matcher.matches_or_contains("user"));
+        assert!(matcher.matches_or_contains("user.email"));
+        assert!(!matcher.matches_or_contains("user.name"));
+        assert!(!matcher.matches("user"));
+    }
+}
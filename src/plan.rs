@@ -0,0 +1,91 @@
+use crate::{
+    dtfterminal_types::{Config, OutputFormat},
+    file_pairs::FilePairs,
+};
+
+/// Describes what a comparison would do: the paired files, the checks and the filtered paths
+pub fn render_plan(file_pairs: &FilePairs, config: &Config) -> String {
+    let mut lines = vec![];
+    if !config.read_from_file.is_empty() {
+        lines.push(format!(
+            "Load the differences saved to {}",
+            config.read_from_file
+        ));
+    } else {
+        lines.push("Compare:".to_owned());
+        lines.extend(
+            file_pairs
+                .pairs
+                .iter()
+                .map(|(file_a, file_b)| format!("  {} against {}", file_a, file_b)),
+        );
+        if !file_pairs.unmatched.is_empty() {
+            lines.push("Skip without a pair:".to_owned());
+            lines.extend(
+                file_pairs
+                    .unmatched
+                    .iter()
+                    .map(|file| format!("  {}", file)),
+            );
+        }
+    }
+
+    lines.push(format!("Check for: {}", checked_categories(config)));
+    lines.push(format!("Ordered arrays: {}", ordered_arrays(config)));
+    lines.push(format!(
+        "Only paths: {}",
+        list_or(&config.only_paths, "all")
+    ));
+    lines.push(format!(
+        "Ignored paths: {}",
+        list_or(&config.ignore_paths, "none")
+    ));
+    lines.push(format!("Output: {}", output(config)));
+    lines.join("\n")
+}
+
+fn checked_categories(config: &Config) -> String {
+    let categories: Vec<&str> = [
+        (config.check_for_key_diffs, "key"),
+        (config.check_for_type_diffs, "type"),
+        (config.check_for_value_diffs, "value"),
+        (config.check_for_array_diffs, "array"),
+    ]
+    .into_iter()
+    .filter_map(|(checked, category)| checked.then_some(category))
+    .collect();
+    categories.join(", ")
+}
+
+fn ordered_arrays(config: &Config) -> String {
+    if config.array_same_order {
+        "all".to_owned()
+    } else {
+        list_or(&config.ordered_array_paths, "none")
+    }
+}
+
+fn output(config: &Config) -> String {
+    if let Some(write_to_file) = &config.write_to_file {
+        format!("save to {}", write_to_file)
+    } else if let Some(browser_view) = &config.browser_view {
+        format!("HTML report {}", browser_view)
+    } else {
+        match config.output_format {
+            OutputFormat::Table => "tables".to_owned(),
+            OutputFormat::Json => "JSON".to_owned(),
+            OutputFormat::Paths => "paths".to_owned(),
+            OutputFormat::Ndjson => "a JSON line per difference".to_owned(),
+            OutputFormat::Markdown => "Markdown".to_owned(),
+            OutputFormat::Html => "an HTML document".to_owned(),
+        }
+    }
+}
+
+fn list_or(items: &[String], empty: &str) -> String {
+    if items.is_empty() {
+        empty.to_owned()
+    } else {
+        items.join(", ")
+    }
+}
@@ -0,0 +1,385 @@
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use serde::Serialize;
+use serde_json::Value;
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+/// A place in a source file, both counted from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The positions of the keys of a file by their paths, e.g. `servers[0].port`
+pub type SourcePositions = HashMap<String, Position>;
+
+/// Where the keys are in the two files, only looked up if they are to be shown
+#[derive(Clone, Debug, Default)]
+pub struct KeyPositions {
+    pub a: SourcePositions,
+    pub b: SourcePositions,
+}
+
+impl KeyPositions {
+    /// Adds the positions of the key to a serialized difference as `positionA` and `positionB`,
+    /// for the files having the key
+    pub fn annotate(&self, diff: &mut Value, key: &str) {
+        if let Some(object) = diff.as_object_mut() {
+            for (field, positions) in [("positionA", &self.a), ("positionB", &self.b)] {
+                if let Some(position) = positions.get(key) {
+                    object.insert(field.to_owned(), serde_json::json!(position));
+                }
+            }
+        }
+    }
+}
+
+/// Finds where the keys and array items of a JSON document start: the opening quote of keys and the first character of items
+pub fn find_json_positions(source: &str) -> SourcePositions {
+    let mut scanner = JsonScanner {
+        chars: source.chars().peekable(),
+        position: Position { line: 1, column: 1 },
+        positions: SourcePositions::new(),
+    };
+    scanner.value("");
+    scanner.positions
+}
+
+/// Finds where the keys and sequence items of a YAML document start, by the marks of the YAML parser.
+/// Only the first document is looked into. If the document can't be parsed, the positions found before the error are kept.
+pub fn find_yaml_positions(source: &str) -> SourcePositions {
+    let mut receiver = YamlPositions::default();
+    let _ = Parser::new_from_str(source).load(&mut receiver, false);
+    receiver.positions
+}
+
+/// Keeps the positions of the keys inside the object at the path, relative to it, like the keys of an unwrapped object
+pub fn relative_to(positions: SourcePositions, path: &str) -> SourcePositions {
+    let prefix = format!("{}.", path);
+    positions
+        .into_iter()
+        .filter_map(|(key, position)| Some((key.strip_prefix(&prefix)?.to_owned(), position)))
+        .collect()
+}
+
+/// A mapping or sequence the YAML parser is in, with its path.
+/// The path is `None` inside the keys that aren't scalars, as their contents can't be addressed.
+enum YamlFrame {
+    /// The key of the value coming next, or `None` if a key comes next
+    Mapping {
+        path: Option<String>,
+        key: Option<Option<String>>,
+    },
+    Sequence {
+        path: Option<String>,
+        index: usize,
+    },
+}
+
+/// Collects the positions of the keys and items from the events of the YAML parser
+#[derive(Default)]
+struct YamlPositions {
+    frames: Vec<YamlFrame>,
+    positions: SourcePositions,
+}
+
+impl YamlPositions {
+    /// Records the position of a node if it's a key or an item, returning its path if it's a value
+    fn node(&mut self, mark: Marker, scalar: Option<&str>) -> Option<String> {
+        let position = Position {
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+        match self.frames.last_mut() {
+            None => Some(String::new()),
+            Some(YamlFrame::Mapping { path, key }) => match key.take() {
+                Some(key_path) => key_path,
+                None => {
+                    let key_path = path.as_deref().zip(scalar).map(|(parent, key)| {
+                        if parent.is_empty() {
+                            key.to_owned()
+                        } else {
+                            format!("{}.{}", parent, key)
+                        }
+                    });
+                    if let Some(key_path) = &key_path {
+                        self.positions.insert(key_path.clone(), position);
+                    }
+                    *key = Some(key_path);
+                    None
+                }
+            },
+            Some(YamlFrame::Sequence { path, index }) => {
+                let item_path = path.as_ref().map(|parent| format!("{}[{}]", parent, index));
+                *index += 1;
+                if let Some(item_path) = &item_path {
+                    self.positions.insert(item_path.clone(), position);
+                }
+                item_path
+            }
+        }
+    }
+}
+
+impl MarkedEventReceiver for YamlPositions {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(value, ..) => {
+                self.node(mark, Some(&value));
+            }
+            Event::Alias(_) => {
+                self.node(mark, None);
+            }
+            Event::MappingStart(..) => {
+                let path = self.node(mark, None);
+                self.frames.push(YamlFrame::Mapping { path, key: None });
+            }
+            Event::SequenceStart(..) => {
+                let path = self.node(mark, None);
+                self.frames.push(YamlFrame::Sequence { path, index: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.frames.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Walks a JSON document character by character, keeping track of the position
+struct JsonScanner<'s> {
+    chars: Peekable<Chars<'s>>,
+    position: Position,
+    positions: SourcePositions,
+}
+
+impl JsonScanner<'_> {
+    fn advance(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) -> Option<char> {
+        while self.chars.peek()?.is_whitespace() {
+            self.advance();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn value(&mut self, path: &str) -> Option<()> {
+        match self.skip_whitespace()? {
+            '{' => self.object(path),
+            '[' => self.array(path),
+            '"' => self.string().map(|_| ()),
+            _ => {
+                while !matches!(self.chars.peek()?, ',' | ']' | '}') {
+                    self.advance();
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn object(&mut self, path: &str) -> Option<()> {
+        self.advance();
+        loop {
+            if self.skip_whitespace()? == '}' {
+                self.advance();
+                return Some(());
+            }
+            let position = self.position;
+            let key = self.string()?;
+            let key_path = if path.is_empty() {
+                key
+            } else {
+                format!("{}.{}", path, key)
+            };
+            self.positions.insert(key_path.clone(), position);
+            self.skip_whitespace()?;
+            self.advance();
+            self.value(&key_path)?;
+            if self.skip_whitespace()? == ',' {
+                self.advance();
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str) -> Option<()> {
+        self.advance();
+        let mut index = 0;
+        loop {
+            if self.skip_whitespace()? == ']' {
+                self.advance();
+                return Some(());
+            }
+            let item_path = format!("{}[{}]", path, index);
+            self.positions.insert(item_path.clone(), self.position);
+            self.value(&item_path)?;
+            index += 1;
+            if self.skip_whitespace()? == ',' {
+                self.advance();
+            }
+        }
+    }
+
+    /// Reads a string including its quotes and unescapes it
+    fn string(&mut self) -> Option<String> {
+        let mut raw = String::from(self.advance()?);
+        loop {
+            let c = self.advance()?;
+            raw.push(c);
+            match c {
+                '\\' => raw.push(self.advance()?),
+                '"' => return serde_json::from_str(&raw).ok(),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_json_positions() {
+        let source = "{\n  \"name\": \"John\",\n  \"address\": {\"city\": \"A, \\\"B\\\"\"},\n  \"tags\": [\n    \"x\",\n    {\"id\": 1}\n  ]\n}";
+
+        let positions = find_json_positions(source);
+
+        assert_eq!(positions["name"], Position { line: 2, column: 3 });
+        assert_eq!(positions["address"], Position { line: 3, column: 3 });
+        assert_eq!(
+            positions["address.city"],
+            Position {
+                line: 3,
+                column: 15
+            }
+        );
+        assert_eq!(positions["tags[0]"], Position { line: 5, column: 5 });
+        assert_eq!(positions["tags[1].id"], Position { line: 6, column: 6 });
+        assert_eq!(positions.len(), 7);
+    }
+
+    #[test]
+    fn test_positions_of_keys_with_dots() {
+        // The paths are built from the keys as they are, like the keys of the differences
+        let json_positions = find_json_positions("{\"a.b\": {\"c[0]\": 1}}");
+        let yaml_positions = find_yaml_positions("a.b:\n  c[0]: 1\n");
+
+        assert_eq!(
+            json_positions["a.b.c[0]"],
+            Position {
+                line: 1,
+                column: 10
+            }
+        );
+        assert_eq!(yaml_positions["a.b.c[0]"], Position { line: 2, column: 3 });
+    }
+
+    #[test]
+    fn test_find_yaml_positions() {
+        let source = "name: John # the name\naddress:\n  city: A\nnote: |\n  not: a key\ndescription: a plain\n  - scalar\nflow: {a: 1, b: [x, y]}\nbase: &base\n  port: 80\ncopy: *base\nservers:\n- host: a\n  port: 80\n-   host: b\ntags:\n  - x\n  - y\n";
+
+        let positions = find_yaml_positions(source);
+
+        assert_eq!(positions["name"], Position { line: 1, column: 1 });
+        assert_eq!(positions["address.city"], Position { line: 3, column: 3 });
+        assert!(!positions.contains_key("note.not"));
+        assert!(!positions.contains_key("not"));
+        assert!(!positions.contains_key("description[0]"));
+        assert_eq!(
+            positions["flow.b"],
+            Position {
+                line: 8,
+                column: 14
+            }
+        );
+        assert_eq!(
+            positions["flow.b[1]"],
+            Position {
+                line: 8,
+                column: 21
+            }
+        );
+        assert_eq!(
+            positions["base.port"],
+            Position {
+                line: 10,
+                column: 3
+            }
+        );
+        assert_eq!(
+            positions["copy"],
+            Position {
+                line: 11,
+                column: 1
+            }
+        );
+        assert_eq!(
+            positions["servers[0].port"],
+            Position {
+                line: 14,
+                column: 3
+            }
+        );
+        assert_eq!(
+            positions["servers[1].host"],
+            Position {
+                line: 15,
+                column: 5
+            }
+        );
+        assert_eq!(
+            positions["tags[1]"],
+            Position {
+                line: 18,
+                column: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let positions = find_json_positions(r#"{"id": 1, "data": {"name": "a", "tags": [1]}}"#);
+
+        let relative = relative_to(positions, "data");
+
+        assert_eq!(relative.len(), 3);
+        assert_eq!(
+            relative["name"],
+            Position {
+                line: 1,
+                column: 20
+            }
+        );
+        assert!(relative.contains_key("tags[0]"));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let key_positions = KeyPositions {
+            a: SourcePositions::from([("name".to_owned(), Position { line: 2, column: 3 })]),
+            b: SourcePositions::new(),
+        };
+        let mut diff = serde_json::json!({"key": "name", "value1": "a", "value2": "b"});
+
+        key_positions.annotate(&mut diff, "name");
+
+        assert_eq!(
+            diff["positionA"],
+            serde_json::json!({"line": 2, "column": 3})
+        );
+        assert!(diff.get("positionB").is_none());
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use regex::Regex;
+use serde_json::Value;
+use serde_yaml::{value::TaggedValue, Value as YamlValue};
+
+use crate::dtfterminal_types::DiffCollection;
+
+/// What the parts of the keys and values matching a redaction pattern are replaced with
+pub const REDACTED: &str = "***";
+
+/// Replaces the parts of the text matching any of the patterns
+pub fn redact(text: &str, patterns: &[Regex]) -> String {
+    patterns.iter().fold(text.to_owned(), |text, pattern| {
+        pattern.replace_all(&text, REDACTED).into_owned()
+    })
+}
+
+/// Redacts the keys and values of the differences.
+/// Meant to be used after the comparison, so the real values decide what differs.
+pub fn redact_diffs(diffs: DiffCollection, patterns: &[Regex]) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|mut kd| {
+                    kd.key = redact(&kd.key, patterns);
+                    kd
+                })
+                .collect()
+        }),
+        type_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|mut td| {
+                    td.key = redact(&td.key, patterns);
+                    td
+                })
+                .collect()
+        }),
+        value_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|mut vd| {
+                    vd.key = redact(&vd.key, patterns);
+                    vd.value1 = redact(&vd.value1, patterns);
+                    vd.value2 = redact(&vd.value2, patterns);
+                    vd
+                })
+                .collect()
+        }),
+        array_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|mut ad| {
+                    ad.key = redact(&ad.key, patterns);
+                    ad.value = redact(&ad.value, patterns);
+                    ad
+                })
+                .collect()
+        }),
+    )
+}
+
+/// Redacts the keys and the strings of a JSON document
+pub fn redact_json(value: Value, patterns: &[Regex]) -> Value {
+    match value {
+        Value::String(text) => Value::String(redact(&text, patterns)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| redact_json(item, patterns))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (redact(&key, patterns), redact_json(value, patterns)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Redacts the string keys and the strings of a YAML document
+pub fn redact_yaml(value: YamlValue, patterns: &[Regex]) -> YamlValue {
+    match value {
+        YamlValue::String(text) => YamlValue::String(redact(&text, patterns)),
+        YamlValue::Sequence(items) => YamlValue::Sequence(
+            items
+                .into_iter()
+                .map(|item| redact_yaml(item, patterns))
+                .collect(),
+        ),
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (redact_yaml(key, patterns), redact_yaml(value, patterns)))
+                .collect(),
+        ),
+        YamlValue::Tagged(tagged) => {
+            let TaggedValue { tag, value } = *tagged;
+            YamlValue::Tagged(Box::new(TaggedValue {
+                tag,
+                value: redact_yaml(value, patterns),
+            }))
+        }
+        value => value,
+    }
+}
+
+/// The original keys and array items of the redacted differences, by what they were redacted to.
+/// What's looked up for the differences, like the types of the values, stays by the original keys
+/// and is found through these. Text several originals were redacted to has no original,
+/// so nothing is found for it instead of the data of another key.
+#[derive(Clone, Debug, Default)]
+pub struct RedactedTexts {
+    keys: HashMap<String, Option<String>>,
+    items: HashMap<String, Option<String>>,
+}
+
+impl RedactedTexts {
+    /// Redacts the keys and array items of the differences, keeping what they were redacted from
+    pub fn of_diffs(diffs: &DiffCollection, patterns: &[Regex]) -> RedactedTexts {
+        let (key_diff, type_diff, value_diff, array_diff) = diffs;
+        let keys = key_diff
+            .iter()
+            .flatten()
+            .map(|kd| &kd.key)
+            .chain(type_diff.iter().flatten().map(|td| &td.key))
+            .chain(value_diff.iter().flatten().map(|vd| &vd.key))
+            .chain(array_diff.iter().flatten().map(|ad| &ad.key));
+
+        let mut redacted = RedactedTexts::default();
+        for key in keys {
+            add_original(&mut redacted.keys, key, patterns);
+        }
+        for ad in array_diff.iter().flatten() {
+            add_original(&mut redacted.items, &ad.value, patterns);
+        }
+        redacted
+    }
+
+    /// Get the original of a shown key, which is the key itself if nothing was redacted to it
+    pub fn original_key<'a>(&'a self, key: &'a str) -> Option<&'a str> {
+        original(&self.keys, key)
+    }
+
+    /// Get the original of a shown array item, which is the item itself if nothing was redacted to it
+    pub fn original_item<'a>(&'a self, item: &'a str) -> Option<&'a str> {
+        original(&self.items, item)
+    }
+}
+
+fn add_original(originals: &mut HashMap<String, Option<String>>, text: &str, patterns: &[Regex]) {
+    match originals.entry(redact(text, patterns)) {
+        Entry::Vacant(entry) => {
+            entry.insert(Some(text.to_owned()));
+        }
+        Entry::Occupied(mut entry) => {
+            if entry.get().as_deref() != Some(text) {
+                entry.insert(None);
+            }
+        }
+    }
+}
+
+fn original<'a>(originals: &'a HashMap<String, Option<String>>, text: &'a str) -> Option<&'a str> {
+    match originals.get(text) {
+        Some(original) => original.as_deref(),
+        None => Some(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::ValueDiff;
+
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let patterns = vec![
+            Regex::new(r"sk-\w+").unwrap(),
+            Regex::new("password").unwrap(),
+        ];
+
+        assert_eq!(redact("key: sk-abc123", &patterns), "key: ***");
+        assert_eq!(redact("db.password", &patterns), "db.***");
+        assert_eq!(redact("name", &patterns), "name");
+        assert_eq!(redact("sk-1 sk-2", &[]), "sk-1 sk-2");
+    }
+
+    #[test]
+    fn test_redact_diffs() {
+        let patterns = vec![Regex::new(r"sk-\w+").unwrap()];
+        let diffs = (
+            None,
+            None,
+            Some(vec![ValueDiff {
+                key: "api.token".to_owned(),
+                value1: "sk-abc".to_owned(),
+                value2: "sk-abd".to_owned(),
+            }]),
+            None,
+        );
+
+        let value_diffs = redact_diffs(diffs, &patterns).2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "api.token");
+        assert_eq!(value_diffs[0].value1, REDACTED);
+        assert_eq!(value_diffs[0].value2, REDACTED);
+    }
+
+    #[test]
+    fn test_redacted_texts_collisions() {
+        let patterns = vec![Regex::new(r"sk-\w+").unwrap()];
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        };
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff("tokens.sk-abc"),
+                value_diff("tokens.sk-abd"),
+                value_diff("keys.sk-xyz"),
+                value_diff("name"),
+            ]),
+            None,
+        );
+
+        let redacted = RedactedTexts::of_diffs(&diffs, &patterns);
+
+        assert_eq!(redacted.original_key("tokens.***"), None);
+        assert_eq!(redacted.original_key("keys.***"), Some("keys.sk-xyz"));
+        assert_eq!(redacted.original_key("name"), Some("name"));
+        assert_eq!(redacted.original_item("sk-1"), Some("sk-1"));
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let patterns = vec![Regex::new(r"sk-\w+").unwrap()];
+        let document = serde_json::json!({"sk-key": {"tokens": ["sk-abc", 1]}});
+
+        assert_eq!(
+            redact_json(document, &patterns),
+            serde_json::json!({"***": {"tokens": ["***", 1]}})
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use libdtf::core::diff_types::{KeyDiff, TypeDiff, ValueDiff};
+use serde_json::Value;
+
+use crate::{
+    array_table::ArrayTable,
+    dtfterminal_types::{
+        DiffCollection, DiffData, DtfError, OutputFormat, TermTable, WorkingContext,
+    },
+    html_renderer::HtmlRenderer,
+    key_table::KeyTable,
+    side::Side,
+    stream::{diff_records, write_stream},
+    type_table::TypeTable,
+    utils::{
+        diff_paths, get_aligned_display_values, group_by_key, shown_diffs, ASCII_CHECKMARK,
+        ASCII_MULTIPLY, CHECKMARK, MULTIPLY,
+    },
+    value_table::ValueTable,
+};
+
+/// Renders the differences in the output format into a string, without printing or writing anything
+pub fn render_diffs(
+    diffs: &DiffCollection,
+    context: &WorkingContext,
+    format: OutputFormat,
+) -> Result<String, DtfError> {
+    match format {
+        OutputFormat::Table => {
+            let tables = diff_tables(diffs, context);
+            if tables.is_empty() {
+                return Ok(format!("{}\n", context.config.identical_message()));
+            }
+            Ok(tables.join("\n"))
+        }
+        OutputFormat::Json => diffs_json(diffs, context),
+        OutputFormat::Paths => Ok(diff_paths(diffs, &context.config)
+            .into_iter()
+            .map(|path| path + "\n")
+            .collect()),
+        OutputFormat::Ndjson => {
+            let mut buffer = vec![];
+            write_stream(diff_records(diffs, context), context, &mut buffer)
+                .map_err(DtfError::IoError)?;
+            Ok(String::from_utf8_lossy(&buffer).into_owned())
+        }
+        OutputFormat::Markdown => Ok(render_markdown(diffs, context)),
+        OutputFormat::Html => HtmlRenderer::new(context).render_document(diffs),
+    }
+}
+
+/// Renders a table for each category of the differences to be shown
+pub fn diff_tables(diffs: &DiffCollection, context: &WorkingContext) -> Vec<String> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let config = &context.config;
+
+    let mut rendered_tables = vec![];
+    if let Some(diffs) = shown_diffs(key_diff, config.render_key_diffs, config) {
+        let table = KeyTable::new(diffs, context);
+        rendered_tables.push(table.render());
+    }
+
+    if let Some(diffs) = shown_diffs(type_diff, config.render_type_diffs, config) {
+        let table = TypeTable::new(diffs, context);
+        rendered_tables.push(table.render());
+    }
+
+    if let Some(diffs) = shown_diffs(value_diff, config.render_value_diffs, config) {
+        let table = ValueTable::new(diffs, context);
+        rendered_tables.push(table.render());
+    }
+
+    if let Some(diffs) = shown_diffs(array_diff, config.render_array_diffs, config) {
+        let table = ArrayTable::new(diffs, context);
+        rendered_tables.push(table.render());
+    }
+
+    rendered_tables
+}
+
+/// Renders the differences as JSON with a summary of the comparison
+pub fn diffs_json(diffs: &DiffCollection, context: &WorkingContext) -> Result<String, DtfError> {
+    let data = DiffData::summarized(diffs, context);
+    let rendered = if context.config.positions {
+        serde_json::to_value(data).map(|mut data| {
+            let diffs = data
+                .as_object_mut()
+                .into_iter()
+                .flat_map(|d| d.values_mut());
+            for diff in diffs.filter_map(Value::as_array_mut).flatten() {
+                context.annotate_positions(diff);
+            }
+            data
+        })
+    } else {
+        serde_json::to_value(data)
+    };
+    rendered
+        .and_then(|data| serde_json::to_string_pretty(&data))
+        .map_err(|e| DtfError::DiffError(e.to_string()))
+}
+
+/// Renders a Markdown section with a table for each category of the differences to be shown, e.g. for pull request comments
+pub fn render_markdown(diffs: &DiffCollection, context: &WorkingContext) -> String {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let config = &context.config;
+    let (file_a, file_b) = context.get_file_names();
+    let header =
+        |first: &str, second: &str| vec!["Key".to_owned(), first.to_owned(), second.to_owned()];
+
+    let mut sections = vec![];
+    if let Some(diffs) = shown_diffs(key_diff, config.render_key_diffs, config) {
+        let mark = |kd: &KeyDiff, side: Side| match (config.ascii, context.key_side(kd) == side) {
+            (true, true) => ASCII_CHECKMARK,
+            (true, false) => ASCII_MULTIPLY,
+            (false, true) => CHECKMARK,
+            (false, false) => MULTIPLY,
+        };
+        let rows = diffs.iter().map(|kd| {
+            [
+                kd.key.clone(),
+                mark(kd, Side::A).to_owned(),
+                mark(kd, Side::B).to_owned(),
+            ]
+        });
+        sections.push(markdown_section(
+            "Key Differences",
+            header(file_a, file_b),
+            rows,
+        ));
+    }
+
+    if let Some(diffs) = shown_diffs(type_diff, config.render_type_diffs, config) {
+        let rows = diffs
+            .iter()
+            .map(|td: &TypeDiff| [td.key.clone(), td.type1.clone(), td.type2.clone()]);
+        sections.push(markdown_section(
+            "Type Differences",
+            header(file_a, file_b),
+            rows,
+        ));
+    }
+
+    if let Some(diffs) = shown_diffs(value_diff, config.render_value_diffs, config) {
+        let rows = diffs
+            .iter()
+            .map(|vd: &ValueDiff| [vd.key.clone(), vd.value1.clone(), vd.value2.clone()]);
+        sections.push(markdown_section(
+            "Value Differences",
+            header(file_a, file_b),
+            rows,
+        ));
+    }
+
+    if let Some(diffs) = shown_diffs(array_diff, config.render_array_diffs, config) {
+        let mut groups: Vec<_> = group_by_key(diffs).into_iter().collect();
+        groups.sort_by_key(|(key, _)| *key);
+        let rows = groups.into_iter().flat_map(|(key, values)| {
+            get_aligned_display_values(context, &values)
+                .into_iter()
+                .map(move |(value_a, value_b)| [key.to_owned(), value_a, value_b])
+        });
+        sections.push(markdown_section(
+            "Array Differences",
+            header(
+                &format!("Only {} contains", file_a),
+                &format!("Only {} contains", file_b),
+            ),
+            rows,
+        ));
+    }
+
+    if sections.is_empty() {
+        return format!("{}\n", config.identical_message());
+    }
+    sections.join("\n")
+}
+
+/// A heading followed by a table of the rows
+fn markdown_section(
+    title: &str,
+    header: Vec<String>,
+    rows: impl Iterator<Item = [String; 3]>,
+) -> String {
+    let mut section = format!("## {}\n\n", title);
+    section += &markdown_row(&header);
+    section += "| --- | --- | --- |\n";
+    for row in rows {
+        section += &markdown_row(&row);
+    }
+    section
+}
+
+fn markdown_row(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|cell| escape_markdown_cell(cell))
+        .collect();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+/// Keeps a value in its cell: pipes would end it and line breaks would end the table
+fn escape_markdown_cell(cell: &str) -> String {
+    cell.trim_end().replace('|', "\\|").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc};
+
+    use crate::{dtfterminal_types::ConfigBuilder, utils::create_working_context};
+
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .render_array_diffs(true)
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .build();
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![ValueDiff {
+                key: "motto".to_owned(),
+                value1: "\"a|b\"".to_owned(),
+                value2: "\"c\"".to_owned(),
+            }]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::BHas,
+                key: "tags".to_owned(),
+                value: "\"admin\"".to_owned(),
+            }]),
+        );
+
+        let markdown = render_markdown(&diffs, &create_working_context(&config).unwrap());
+
+        assert_eq!(
+            markdown,
+            "## Key Differences\n\n| Key | a.json | b.json |\n| --- | --- | --- |\n| email | \u{2713} | \u{00D7} |\n\
+             \n## Value Differences\n\n| Key | a.json | b.json |\n| --- | --- | --- |\n| motto | \"a\\|b\" | \"c\" |\n\
+             \n## Array Differences\n\n| Key | Only a.json contains | Only b.json contains |\n| --- | --- | --- |\n| tags |  | \"admin\" |\n"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown_identical() {
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .identical_message(Some("No drift".to_owned()))
+            .build();
+
+        let markdown = render_markdown(
+            &(Some(vec![]), None, None, None),
+            &create_working_context(&config).unwrap(),
+        );
+
+        assert_eq!(markdown, "No drift\n");
+    }
+
+    #[test]
+    fn test_diffs_json_identical() {
+        let config = ConfigBuilder::new().check_for_key_diffs(true).build();
+        let context = create_working_context(&config).unwrap();
+
+        let json = diffs_json(&(Some(vec![]), None, None, None), &context).unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["summary"]["identical"], true);
+
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            None,
+            None,
+        );
+        let json = diffs_json(&diffs, &context).unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["summary"]["identical"], false);
+    }
+
+    #[test]
+    fn test_diffs_json_summary() {
+        let config = ConfigBuilder::new()
+            .check_for_value_diffs(true)
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .build();
+        let diffs = (
+            None,
+            None,
+            Some(vec![ValueDiff {
+                key: "port".to_owned(),
+                value1: "80".to_owned(),
+                value2: "8080".to_owned(),
+            }]),
+            None,
+        );
+
+        let json = diffs_json(&diffs, &create_working_context(&config).unwrap()).unwrap();
+
+        let json: Value = serde_json::from_str(&json).unwrap();
+        let summary = &json["summary"];
+        assert_eq!(summary["keyDiffs"], 0);
+        assert_eq!(summary["valueDiffs"], 1);
+        assert_eq!(summary["total"], 1);
+        assert_eq!(summary["fileA"], "a.json");
+        assert_eq!(summary["fileB"], "b.json");
+        assert_eq!(summary["config"]["check_for_value_diffs"], true);
+        assert_eq!(summary["config"]["array_same_order"], false);
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(summary["timestamp"].as_str().unwrap()).is_ok()
+        );
+        assert_eq!(json["valueDiffs"][0]["key"], "port");
+    }
+}
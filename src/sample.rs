@@ -0,0 +1,633 @@
+//! Compares only a sample of the items of long arrays, trading completeness for speed on huge files.
+//!
+//! An array longer than the sample size keeps its first and last items, and one item from each of the
+//! equal buckets between them. The item of a bucket is picked by hashing the seed, the path of the array
+//! and the bucket, so the same files and seed always compare the same items.
+//! The arrays at the same path in the two files are sampled together, keeping the same indexes of both.
+//! If they differ in length, only the items both of them have are sampled, and the lengths are reported instead.
+
+use std::collections::HashMap;
+
+use libdtf::core::diff_types::{ArrayDiff, KeyDiff, TypeDiff, ValueDiff};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{diff_tree::split_key_path, dtfterminal_types::DiffCollection, unicode::join_key_path};
+
+/// An array of which only a sample of the items was compared
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampledArray {
+    pub path: String,
+    /// The length of the array, in the first file if both files have it
+    pub length: usize,
+    /// The length of the array in the second file, if it's different.
+    /// The items past the end of the shorter array aren't compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_length: Option<usize>,
+    pub sampled: usize,
+}
+
+/// The original indexes of the sampled items, by the path of their array in the sampled data
+#[derive(Debug, Default)]
+struct Samples {
+    indexes: HashMap<String, Vec<usize>>,
+    arrays: Vec<SampledArray>,
+}
+
+impl Samples {
+    /// Keeps the original indexes of a sampled array
+    fn record(&mut self, segments: &[String], array: SampledArray, indexes: Vec<usize>) {
+        self.arrays.push(array);
+        self.indexes.insert(join_key_path(segments), indexes);
+    }
+
+    /// The path of an array in the file, the arrays it's in may be sampled
+    fn original_path(&self, segments: &[String]) -> String {
+        let path: Vec<String> = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let array = join_key_path(&segments[..i]);
+                index(segment)
+                    .and_then(|index| self.indexes.get(&array)?.get(index))
+                    .map(|original| format!("[{}]", original))
+                    .unwrap_or_else(|| segment.clone())
+            })
+            .collect();
+        join_key_path(&path)
+    }
+}
+
+/// Picks the items of the arrays to compare, keeping the indexes of the ones it sampled
+struct Sampler {
+    size: usize,
+    seed: u64,
+    samples: Samples,
+}
+
+impl Sampler {
+    fn new(size: usize, seed: u64) -> Sampler {
+        Sampler {
+            size,
+            seed,
+            samples: Samples::default(),
+        }
+    }
+
+    /// The original indexes of the items to keep of the arrays at the path, the same in both files.
+    /// `None` if the arrays aren't longer than the sample size, so all their items are kept.
+    fn indexes(
+        &mut self,
+        segments: &[String],
+        length: usize,
+        other_length: Option<usize>,
+    ) -> Option<Vec<usize>> {
+        let longest = other_length.map_or(length, |other| length.max(other));
+        if longest <= self.size {
+            return None;
+        }
+        let path = self.samples.original_path(segments);
+        let common = other_length.map_or(length, |other| length.min(other));
+        let indexes = sample_indexes(common, self.size, self.seed, &path);
+        let array = SampledArray {
+            path,
+            length,
+            other_length: other_length.filter(|&other| other != length),
+            sampled: indexes.len(),
+        };
+        self.samples.record(segments, array, indexes.clone());
+        Some(indexes)
+    }
+}
+
+/// The data of both files with their long arrays sampled
+#[derive(Debug)]
+pub struct SampledData<T> {
+    pub data1: T,
+    pub data2: T,
+    samples: Samples,
+}
+
+impl<T> SampledData<T> {
+    /// Writes the indexes of the sampled items in the keys of the differences back to the indexes in the files
+    pub fn restore_indexes(&self, diffs: DiffCollection) -> DiffCollection {
+        restore_indexes(diffs, &self.samples)
+    }
+
+    /// The sampled arrays of both files
+    pub fn sampled_arrays(&self) -> Vec<SampledArray> {
+        self.samples.arrays.clone()
+    }
+}
+
+/// Samples the long arrays of the JSON data of both files
+pub fn sample_json_data(
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+    size: usize,
+    seed: u64,
+) -> SampledData<Map<String, Value>> {
+    let mut sampler = Sampler::new(size, seed);
+    let (data1, data2) = sample_json_objects(data1, data2, &[], &mut sampler);
+    SampledData {
+        data1,
+        data2,
+        samples: sampler.samples,
+    }
+}
+
+/// Samples the long sequences of the YAML data of both files
+pub fn sample_yaml_data(
+    data1: &Mapping,
+    data2: &Mapping,
+    size: usize,
+    seed: u64,
+) -> SampledData<Mapping> {
+    let mut sampler = Sampler::new(size, seed);
+    let (data1, data2) = sample_yaml_mappings(data1, data2, &[], &mut sampler);
+    SampledData {
+        data1,
+        data2,
+        samples: sampler.samples,
+    }
+}
+
+/// Writes the indexes of the sampled items in the keys of the differences back to the indexes in the files.
+/// The arrays at the same path are sampled together, so their items share their indexes.
+fn restore_indexes(diffs: DiffCollection, samples: &Samples) -> DiffCollection {
+    let restore_key = |key: String| {
+        let mut sampled_path = vec![];
+        let mut path = vec![];
+        for segment in split_key_path(&key) {
+            let original = index(&segment)
+                .and_then(|index| {
+                    let array = join_key_path(&sampled_path);
+                    samples
+                        .indexes
+                        .get(&array)?
+                        .get(index)
+                        .map(|original| format!("[{}]", original))
+                })
+                .unwrap_or_else(|| segment.clone());
+            sampled_path.push(segment);
+            path.push(original);
+        }
+        join_key_path(&path)
+    };
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|kd| KeyDiff {
+                    key: restore_key(kd.key),
+                    ..kd
+                })
+                .collect()
+        }),
+        type_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|td| TypeDiff {
+                    key: restore_key(td.key),
+                    ..td
+                })
+                .collect()
+        }),
+        value_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|vd| ValueDiff {
+                    key: restore_key(vd.key),
+                    ..vd
+                })
+                .collect()
+        }),
+        array_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|ad| ArrayDiff {
+                    key: restore_key(ad.key),
+                    ..ad
+                })
+                .collect()
+        }),
+    )
+}
+
+/// Tells that only a sample of the arrays was compared, None if nothing was sampled
+pub fn sampling_note(sampled_arrays: &[SampledArray]) -> Option<String> {
+    if sampled_arrays.is_empty() {
+        return None;
+    }
+    let arrays: Vec<String> = sampled_arrays
+        .iter()
+        .map(|array| match array.other_length {
+            Some(other_length) => format!(
+                "{} ({} of {} in the first file and {} in the second)",
+                array.path, array.sampled, array.length, other_length
+            ),
+            None => format!("{} ({} of {})", array.path, array.sampled, array.length),
+        })
+        .collect();
+    Some(format!(
+        "Only a sample of the items was compared in these arrays: {}",
+        arrays.join(", ")
+    ))
+}
+
+/// Samples the objects at the same path in the two files together
+fn sample_json_objects(
+    object1: &Map<String, Value>,
+    object2: &Map<String, Value>,
+    segments: &[String],
+    sampler: &mut Sampler,
+) -> (Map<String, Value>, Map<String, Value>) {
+    let mut sampled2 = HashMap::new();
+    let sampled1 = object1
+        .iter()
+        .map(|(key, value)| {
+            let key_segments = with_segment(segments, key.clone());
+            let (value1, value2) =
+                sample_json_values(Some(value), object2.get(key), key_segments, sampler);
+            if let Some(value2) = value2 {
+                sampled2.insert(key, value2);
+            }
+            (key.clone(), value1.unwrap_or_default())
+        })
+        .collect();
+    let sampled2 = object2
+        .iter()
+        .map(|(key, value)| {
+            let value = sampled2.remove(key).unwrap_or_else(|| {
+                sample_json_value(value, with_segment(segments, key.clone()), sampler)
+            });
+            (key.clone(), value)
+        })
+        .collect();
+    (sampled1, sampled2)
+}
+
+/// Samples the values at the same path in the two files, the ones both files have together
+fn sample_json_values(
+    value1: Option<&Value>,
+    value2: Option<&Value>,
+    segments: Vec<String>,
+    sampler: &mut Sampler,
+) -> (Option<Value>, Option<Value>) {
+    match (value1, value2) {
+        (Some(Value::Array(items1)), Some(Value::Array(items2))) => {
+            let longest = items1.len().max(items2.len());
+            let indexes = sampler
+                .indexes(&segments, items1.len(), Some(items2.len()))
+                .unwrap_or_else(|| (0..longest).collect());
+            let (sampled1, sampled2): (Vec<_>, Vec<_>) = indexes
+                .iter()
+                .enumerate()
+                .map(|(i, &original)| {
+                    let item_segments = with_segment(&segments, format!("[{}]", i));
+                    sample_json_values(
+                        items1.get(original),
+                        items2.get(original),
+                        item_segments,
+                        sampler,
+                    )
+                })
+                .unzip();
+            (
+                Some(Value::Array(sampled1.into_iter().flatten().collect())),
+                Some(Value::Array(sampled2.into_iter().flatten().collect())),
+            )
+        }
+        (Some(Value::Object(object1)), Some(Value::Object(object2))) => {
+            let (sampled1, sampled2) = sample_json_objects(object1, object2, &segments, sampler);
+            (Some(Value::Object(sampled1)), Some(Value::Object(sampled2)))
+        }
+        _ => (
+            value1.map(|value| sample_json_value(value, segments.clone(), sampler)),
+            value2.map(|value| sample_json_value(value, segments.clone(), sampler)),
+        ),
+    }
+}
+
+/// Samples a value the other file has nothing like at its path
+fn sample_json_value(value: &Value, segments: Vec<String>, sampler: &mut Sampler) -> Value {
+    match value {
+        Value::Array(items) => {
+            let indexes = sampler
+                .indexes(&segments, items.len(), None)
+                .unwrap_or_else(|| (0..items.len()).collect());
+            let sampled = indexes
+                .iter()
+                .enumerate()
+                .map(|(i, &original)| {
+                    let item_segments = with_segment(&segments, format!("[{}]", i));
+                    sample_json_value(&items[original], item_segments, sampler)
+                })
+                .collect();
+            Value::Array(sampled)
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let value =
+                        sample_json_value(value, with_segment(&segments, key.clone()), sampler);
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Samples the mappings at the same path in the two files together
+fn sample_yaml_mappings(
+    mapping1: &Mapping,
+    mapping2: &Mapping,
+    segments: &[String],
+    sampler: &mut Sampler,
+) -> (Mapping, Mapping) {
+    let mut sampled2 = HashMap::new();
+    let sampled1 = mapping1
+        .iter()
+        .map(|(key, value)| {
+            let key_segments = with_segment(segments, yaml_key(key));
+            let (value1, value2) =
+                sample_yaml_values(Some(value), mapping2.get(key), key_segments, sampler);
+            if let Some(value2) = value2 {
+                sampled2.insert(key, value2);
+            }
+            (key.clone(), value1.unwrap_or_default())
+        })
+        .collect();
+    let sampled2 = mapping2
+        .iter()
+        .map(|(key, value)| {
+            let value = sampled2.remove(key).unwrap_or_else(|| {
+                sample_yaml_value(value, with_segment(segments, yaml_key(key)), sampler)
+            });
+            (key.clone(), value)
+        })
+        .collect();
+    (sampled1, sampled2)
+}
+
+/// Samples the YAML values at the same path in the two files, like `sample_json_values`
+fn sample_yaml_values(
+    value1: Option<&YamlValue>,
+    value2: Option<&YamlValue>,
+    segments: Vec<String>,
+    sampler: &mut Sampler,
+) -> (Option<YamlValue>, Option<YamlValue>) {
+    match (value1, value2) {
+        (Some(YamlValue::Sequence(items1)), Some(YamlValue::Sequence(items2))) => {
+            let longest = items1.len().max(items2.len());
+            let indexes = sampler
+                .indexes(&segments, items1.len(), Some(items2.len()))
+                .unwrap_or_else(|| (0..longest).collect());
+            let (sampled1, sampled2): (Vec<_>, Vec<_>) = indexes
+                .iter()
+                .enumerate()
+                .map(|(i, &original)| {
+                    let item_segments = with_segment(&segments, format!("[{}]", i));
+                    sample_yaml_values(
+                        items1.get(original),
+                        items2.get(original),
+                        item_segments,
+                        sampler,
+                    )
+                })
+                .unzip();
+            (
+                Some(YamlValue::Sequence(
+                    sampled1.into_iter().flatten().collect(),
+                )),
+                Some(YamlValue::Sequence(
+                    sampled2.into_iter().flatten().collect(),
+                )),
+            )
+        }
+        (Some(YamlValue::Mapping(mapping1)), Some(YamlValue::Mapping(mapping2))) => {
+            let (sampled1, sampled2) = sample_yaml_mappings(mapping1, mapping2, &segments, sampler);
+            (
+                Some(YamlValue::Mapping(sampled1)),
+                Some(YamlValue::Mapping(sampled2)),
+            )
+        }
+        _ => (
+            value1.map(|value| sample_yaml_value(value, segments.clone(), sampler)),
+            value2.map(|value| sample_yaml_value(value, segments.clone(), sampler)),
+        ),
+    }
+}
+
+/// Samples a YAML value the other file has nothing like at its path
+fn sample_yaml_value(value: &YamlValue, segments: Vec<String>, sampler: &mut Sampler) -> YamlValue {
+    match value {
+        YamlValue::Sequence(items) => {
+            let indexes = sampler
+                .indexes(&segments, items.len(), None)
+                .unwrap_or_else(|| (0..items.len()).collect());
+            let sampled = indexes
+                .iter()
+                .enumerate()
+                .map(|(i, &original)| {
+                    let item_segments = with_segment(&segments, format!("[{}]", i));
+                    sample_yaml_value(&items[original], item_segments, sampler)
+                })
+                .collect();
+            YamlValue::Sequence(sampled)
+        }
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(
+            mapping
+                .iter()
+                .map(|(key, value)| {
+                    let value =
+                        sample_yaml_value(value, with_segment(&segments, yaml_key(key)), sampler);
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// The indexes of the items to compare: the first and the last one and one from each bucket between them.
+/// The sample size is at least 2, so both ends are kept.
+fn sample_indexes(length: usize, size: usize, seed: u64, path: &str) -> Vec<usize> {
+    if length <= size {
+        return (0..length).collect();
+    }
+    let buckets = size.saturating_sub(2);
+    let inner = length - 2;
+    let mut indexes = vec![0];
+    for bucket in 0..buckets {
+        let start = 1 + bucket * inner / buckets;
+        let end = 1 + (bucket + 1) * inner / buckets;
+        indexes.push(start + (hash(seed, path, bucket) % (end - start) as u64) as usize);
+    }
+    indexes.push(length - 1);
+    indexes
+}
+
+/// FNV-1a, so the samples don't change between platforms and Rust versions
+fn hash(seed: u64, path: &str, bucket: usize) -> u64 {
+    let bytes = seed
+        .to_le_bytes()
+        .into_iter()
+        .chain(path.bytes())
+        .chain((bucket as u64).to_le_bytes());
+    bytes.fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn with_segment(segments: &[String], segment: String) -> Vec<String> {
+    let mut segments = segments.to_vec();
+    segments.push(segment);
+    segments
+}
+
+/// Parses an array index segment like `[0]`
+fn index(segment: &str) -> Option<usize> {
+    segment.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+fn yaml_key(key: &YamlValue) -> String {
+    match key {
+        YamlValue::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::ArrayDiffDesc;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_sample_indexes() {
+        assert_eq!(sample_indexes(3, 5, 0, "items"), vec![0, 1, 2]);
+
+        let indexes = sample_indexes(100, 5, 0, "items");
+        assert_eq!(indexes.len(), 5);
+        assert_eq!((indexes[0], indexes[4]), (0, 99));
+        assert!(indexes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(indexes, sample_indexes(100, 5, 0, "items"));
+        assert_ne!(
+            (0..10)
+                .map(|seed| sample_indexes(100, 5, seed, "items"))
+                .collect::<Vec<_>>(),
+            vec![indexes; 10]
+        );
+    }
+
+    #[test]
+    fn test_sample_json() {
+        let data = json!({"items": (0..100).collect::<Vec<_>>(), "tags": ["a", "b"]});
+        let data = data.as_object().unwrap();
+
+        let sampled = sample_json_data(data, data, 4, 0);
+
+        assert_eq!(sampled.data1["items"].as_array().unwrap().len(), 4);
+        assert_eq!(sampled.data1["tags"], json!(["a", "b"]));
+        assert_eq!(sampled.data1, sampled.data2);
+        assert_eq!(
+            sampled.sampled_arrays(),
+            [SampledArray {
+                path: "items".to_owned(),
+                length: 100,
+                other_length: None,
+                sampled: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sample_json_different_lengths() {
+        let data1 = json!({"items": (0..100).collect::<Vec<_>>(), "tags": ["a", "b", "c"]});
+        let data2 = json!({"items": (0..101).collect::<Vec<_>>(), "tags": ["a", "b", "c", "d"]});
+
+        let sampled = sample_json_data(
+            data1.as_object().unwrap(),
+            data2.as_object().unwrap(),
+            10,
+            0,
+        );
+
+        // The same items of both arrays are compared, so there is nothing to report about them
+        assert_eq!(sampled.data1["items"], sampled.data2["items"]);
+        assert_eq!(sampled.data1["items"].as_array().unwrap().len(), 10);
+        assert_eq!(sampled.data1["items"][9], json!(99));
+        // Arrays no longer than the sample size keep all their items
+        assert_eq!(sampled.data2["tags"], json!(["a", "b", "c", "d"]));
+        let sampled_arrays = sampled.sampled_arrays();
+        assert_eq!(sampled_arrays[0].other_length, Some(101));
+        assert_eq!(
+            sampling_note(&sampled_arrays).unwrap(),
+            "Only a sample of the items was compared in these arrays: items (10 of 100 in the first file and 101 in the second)"
+        );
+    }
+
+    #[test]
+    fn test_restore_indexes() {
+        let rows: Vec<Value> = (0..10)
+            .map(|i| json!({"cells": (0..10).collect::<Vec<_>>(), "id": i}))
+            .collect();
+        let data = json!({ "rows": rows });
+        let data = data.as_object().unwrap();
+        let sampled = sample_json_data(data, data, 3, 0);
+        let row = sampled.data1["rows"][1]["id"].as_u64().unwrap();
+        let cell = sampled.data1["rows"][1]["cells"][1].as_u64().unwrap();
+        let diffs = (
+            None,
+            None,
+            Some(vec![ValueDiff {
+                key: "rows[1].cells[1]".to_owned(),
+                value1: "1".to_owned(),
+                value2: "2".to_owned(),
+            }]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "rows[2].cells".to_owned(),
+                value: "9".to_owned(),
+            }]),
+        );
+
+        let (_, _, value_diffs, array_diffs) = sampled.restore_indexes(diffs);
+
+        assert_eq!(
+            value_diffs.unwrap()[0].key,
+            format!("rows[{}].cells[{}]", row, cell)
+        );
+        assert_eq!(array_diffs.unwrap()[0].key, "rows[9].cells");
+        assert!(sampled
+            .sampled_arrays()
+            .iter()
+            .any(|array| array.path == format!("rows[{}].cells", row)));
+    }
+
+    #[test]
+    fn test_sampling_note() {
+        assert_eq!(sampling_note(&[]), None);
+        assert_eq!(
+            sampling_note(&[SampledArray {
+                path: "items".to_owned(),
+                length: 100,
+                other_length: None,
+                sampled: 4,
+            }]),
+            Some(
+                "Only a sample of the items was compared in these arrays: items (4 of 100)"
+                    .to_owned()
+            )
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use jsonschema::{error::ValidationErrorKind, JSONSchema, ValidationError};
+use libdtf::core::diff_types::{KeyDiff, TypeDiff, ValueDiff};
+use serde_json::{Map, Value};
+
+use crate::{
+    dtfterminal_types::{DiffCollection, DtfError, WorkingContext},
+    value_types::json_schema_type_name,
+};
+
+/// Validates the document against the JSON Schema and reports the validation errors as differences,
+/// so they can be displayed the same way:
+/// * Missing required properties are Key differences, the schema has them, the document misses them.
+/// * Values of the wrong type are Type differences, with the expected type of the schema.
+/// * Every other error is a Value difference, with the error message as the expectation of the schema.
+pub fn validate(
+    document: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    context: &WorkingContext,
+) -> Result<DiffCollection, DtfError> {
+    let (document_name, schema_name) = context.get_file_names();
+    let schema = Value::Object(schema.clone());
+    let compiled = JSONSchema::compile(&schema)
+        .map_err(|e| DtfError::DiffError(format!("Invalid schema {}: {}", schema_name, e)))?;
+
+    let document = Value::Object(document.clone());
+    let mut key_diffs = vec![];
+    let mut type_diffs = vec![];
+    let mut value_diffs = vec![];
+    if let Err(errors) = compiled.validate(&document) {
+        for error in errors {
+            let key = key_path(&document, &error.instance_path.to_string());
+            match &error.kind {
+                ValidationErrorKind::Required { property } => key_diffs.push(KeyDiff {
+                    key: join_key(&key, property.as_str().unwrap_or_default()),
+                    has: schema_name.to_owned(),
+                    misses: document_name.to_owned(),
+                }),
+                ValidationErrorKind::Type { .. } => type_diffs.push(TypeDiff {
+                    key,
+                    type1: json_schema_type_name(&error.instance).to_owned(),
+                    type2: expected_types(&schema, &error),
+                }),
+                _ => value_diffs.push(value_diff(key, &error)),
+            }
+        }
+    }
+
+    let config = &context.config;
+    Ok((
+        config.check_for_key_diffs.then_some(key_diffs),
+        config.check_for_type_diffs.then_some(type_diffs),
+        config.check_for_value_diffs.then_some(value_diffs),
+        config.check_for_array_diffs.then_some(vec![]),
+    ))
+}
+
+fn value_diff(key: String, error: &ValidationError) -> ValueDiff {
+    ValueDiff {
+        key,
+        value1: error.instance.to_string(),
+        value2: error.to_string(),
+    }
+}
+
+/// Converts a JSON pointer like `/friends/0/name` to a key path like `friends[0].name`.
+/// Segments are only treated as indexes if they point into an array of the document.
+fn key_path(document: &Value, pointer: &str) -> String {
+    let mut key = String::new();
+    let mut current = Some(document);
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        match current {
+            Some(Value::Array(array)) => {
+                let index = segment.parse::<usize>().ok();
+                current = index.and_then(|index| array.get(index));
+                key.push_str(&format!("[{}]", segment));
+            }
+            Some(value) => {
+                current = value.get(&segment);
+                key = join_key(&key, &segment);
+            }
+            None => key = join_key(&key, &segment),
+        }
+    }
+    key
+}
+
+fn join_key(key: &str, child_key: &str) -> String {
+    if key.is_empty() {
+        child_key.to_owned()
+    } else {
+        format!("{}.{}", key, child_key)
+    }
+}
+
+/// The types the schema allows at the place of a type error, read from its `type` keyword
+fn expected_types(schema: &Value, error: &ValidationError) -> String {
+    match schema.pointer(&error.schema_path.to_string()) {
+        Some(Value::String(expected)) => expected.clone(),
+        Some(Value::Array(expected)) => expected
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<&str>>()
+            .join(" or "),
+        _ => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::WorkingFile;
+    use serde_json::json;
+
+    use crate::dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext};
+
+    use super::*;
+
+    #[test]
+    fn test_validate_missing_required_field() {
+        let schema = object(json!({
+            "type": "object",
+            "properties": {
+                "user": {
+                    "type": "object",
+                    "required": ["email"]
+                }
+            }
+        }));
+        let document = object(json!({ "user": { "name": "John" } }));
+
+        let (key_diffs, type_diffs, value_diffs, _) =
+            validate(&document, &schema, &get_working_context()).unwrap();
+
+        assert_eq!(
+            key_diffs.unwrap(),
+            vec![KeyDiff {
+                key: "user.email".to_owned(),
+                has: "schema.json".to_owned(),
+                misses: "document.json".to_owned(),
+            }]
+        );
+        assert!(type_diffs.unwrap().is_empty());
+        assert!(value_diffs.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_type_mismatch() {
+        let schema = object(json!({
+            "type": "object",
+            "properties": {
+                "ports": {
+                    "type": "array",
+                    "items": { "type": "integer" }
+                }
+            }
+        }));
+        let document = object(json!({ "ports": [8080, "9090"] }));
+
+        let (key_diffs, type_diffs, _, _) =
+            validate(&document, &schema, &get_working_context()).unwrap();
+
+        assert!(key_diffs.unwrap().is_empty());
+        assert_eq!(
+            type_diffs.unwrap(),
+            vec![TypeDiff {
+                key: "ports[1]".to_owned(),
+                type1: "string".to_owned(),
+                type2: "integer".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_invalid_schema() {
+        let schema = object(json!({ "type": "unknown" }));
+
+        let result = validate(&Map::new(), &schema, &get_working_context());
+
+        assert!(matches!(result, Err(DtfError::DiffError(_))));
+    }
+
+    #[test]
+    fn test_key_path() {
+        let document = json!({ "friends": [{ "name": "Jane" }], "0": { "a/b": 1 } });
+
+        assert_eq!(key_path(&document, ""), "");
+        assert_eq!(key_path(&document, "/friends/0/name"), "friends[0].name");
+        assert_eq!(key_path(&document, "/0/a~1b"), "0.a/b");
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("Not an object"),
+        }
+    }
+
+    fn get_working_context() -> WorkingContext {
+        WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("document.json".to_owned()),
+                WorkingFile::new("schema.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new()
+                .check_for_key_diffs(true)
+                .check_for_type_diffs(true)
+                .check_for_value_diffs(true)
+                .build(),
+        )
+    }
+}
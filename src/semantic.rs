@@ -0,0 +1,119 @@
+use serde_json::Value;
+
+#[cfg(feature = "cli")]
+use crate::{
+    depth::check_json_depth, dtfterminal_types::DtfError, file_handler::FileHandler,
+    utils::is_yaml_file,
+};
+
+/// Checks if two values are equal, ignoring the order of object keys and array elements at every level.
+/// Arrays are compared as multisets, so repeated elements have to be repeated as many times in both.
+pub fn semantic_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, value_a)| {
+                    b.get(key)
+                        .map_or(false, |value_b| semantic_equal(value_a, value_b))
+                })
+        }
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && same_elements(a, b),
+        _ => a == b,
+    }
+}
+
+/// Checks if every element of `a` has a semantically equal element in `b`, each element of `b` used once
+fn same_elements(a: &[Value], b: &[Value]) -> bool {
+    let mut used = vec![false; b.len()];
+    a.iter().all(|value_a| {
+        let matched = (0..b.len()).find(|&i| !used[i] && semantic_equal(value_a, &b[i]));
+        match matched {
+            Some(i) => {
+                used[i] = true;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Reads the two files and checks if they are semantically equal.
+/// Prints the result to the terminal.
+#[cfg(feature = "cli")]
+pub fn check_files(path1: &str, path2: &str) -> Result<bool, DtfError> {
+    let equal = semantic_equal(&read_file(path1)?, &read_file(path2)?);
+    if equal {
+        println!("The data is semantically equal!");
+    } else {
+        println!("The data is not semantically equal!");
+    }
+
+    Ok(equal)
+}
+
+/// Reads a JSON, TOML or YAML file into a JSON value, failing if it's nested too deep to be compared
+#[cfg(feature = "cli")]
+fn read_file(path: &str) -> Result<Value, DtfError> {
+    let value = if is_yaml_file(path) {
+        let data = FileHandler::read_yaml_file(path, None)?;
+        serde_json::to_value(serde_yaml::Value::Mapping(data)).map_err(|e| {
+            DtfError::DiffError(format!("Could not convert YAML file {}: {}", path, e))
+        })?
+    } else {
+        Value::Object(FileHandler::read_as_json(path, None, false)?)
+    };
+    check_json_depth(&value)?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_reordered_keys_and_arrays_are_equal() {
+        let a = json!({
+            "name": "John",
+            "tags": ["a", "b", "c"],
+            "friends": [{"name": "Jane", "ids": [1, 2]}, {"name": "Joe", "ids": [3]}]
+        });
+        let b = json!({
+            "friends": [{"ids": [3], "name": "Joe"}, {"ids": [2, 1], "name": "Jane"}],
+            "tags": ["c", "a", "b"],
+            "name": "John"
+        });
+
+        assert_eq!(semantic_equal(&a, &b), true);
+    }
+
+    #[test]
+    fn test_arrays_are_compared_as_multisets() {
+        assert_eq!(semantic_equal(&json!([1, 1, 2]), &json!([2, 1, 1])), true);
+        assert_eq!(semantic_equal(&json!([1, 1, 2]), &json!([2, 1])), false);
+        assert_eq!(semantic_equal(&json!([1, 1, 2]), &json!([1, 2, 2])), false);
+    }
+
+    #[test]
+    fn test_genuine_differences_are_not_equal() {
+        let a = json!({"name": "John", "tags": ["a", "b"]});
+
+        assert_eq!(
+            semantic_equal(&a, &json!({"name": "John", "tags": ["a", "c"]})),
+            false
+        );
+        assert_eq!(
+            semantic_equal(&a, &json!({"name": "Jane", "tags": ["a", "b"]})),
+            false
+        );
+        assert_eq!(
+            semantic_equal(&a, &json!({"name": "John", "tags": ["a", "b"], "age": 30})),
+            false
+        );
+        assert_eq!(
+            semantic_equal(&json!([{"a": 1}]), &json!([{"a": "1"}])),
+            false
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, fmt};
+
+use libdtf::core::diff_types::KeyDiff;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_yaml::Mapping;
+
+use crate::value_types::{lookup_json, lookup_yaml};
+
+/// One of the two files being compared
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    /// The file having the key of the difference, judged by the file names it was reported with.
+    /// Results saved without the sides only have the names to go by.
+    /// If the names are the same, the side can't be told and it's the first file.
+    pub fn of(key_diff: &KeyDiff, file_names: (&str, &str)) -> Side {
+        let (_, file_b) = file_names;
+        if key_diff.has == file_b && key_diff.misses != file_b {
+            Side::B
+        } else {
+            Side::A
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::A => write!(f, "A"),
+            Side::B => write!(f, "B"),
+        }
+    }
+}
+
+/// The file having the key of each key difference, by the key
+pub type KeySides = HashMap<String, Side>;
+
+/// A key difference with the file having the key, as it's written to saved results and JSON output.
+/// The side is optional, so results saved without it still load.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SidedKeyDiff {
+    #[serde(flatten)]
+    pub key_diff: KeyDiff,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+}
+
+impl SidedKeyDiff {
+    /// The file having the key, by the file names if the side wasn't saved
+    pub fn side(&self, file_names: (&str, &str)) -> Side {
+        self.side
+            .unwrap_or_else(|| Side::of(&self.key_diff, file_names))
+    }
+}
+
+impl From<KeyDiff> for SidedKeyDiff {
+    fn from(key_diff: KeyDiff) -> SidedKeyDiff {
+        SidedKeyDiff {
+            key_diff,
+            side: None,
+        }
+    }
+}
+
+/// Looks up which file has the keys of the key differences in the JSON data
+pub fn find_json_key_sides(
+    key_diffs: &[KeyDiff],
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> KeySides {
+    find_key_sides(key_diffs, |key| {
+        (
+            lookup_json(data1, key).is_some(),
+            lookup_json(data2, key).is_some(),
+        )
+    })
+}
+
+/// Looks up which file has the keys of the key differences in the YAML data
+pub fn find_yaml_key_sides(key_diffs: &[KeyDiff], data1: &Mapping, data2: &Mapping) -> KeySides {
+    find_key_sides(key_diffs, |key| {
+        (
+            lookup_yaml(data1, key).is_some(),
+            lookup_yaml(data2, key).is_some(),
+        )
+    })
+}
+
+fn find_key_sides(key_diffs: &[KeyDiff], is_in: impl Fn(&str) -> (bool, bool)) -> KeySides {
+    key_diffs
+        .iter()
+        .filter_map(|kd| match is_in(&kd.key) {
+            (true, false) => Some((kd.key.clone(), Side::A)),
+            (false, true) => Some((kd.key.clone(), Side::B)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_diff(key: &str, has: &str, misses: &str) -> KeyDiff {
+        KeyDiff {
+            key: key.to_owned(),
+            has: has.to_owned(),
+            misses: misses.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_side_of() {
+        let file_names = ("a.json", "b.json");
+
+        assert_eq!(
+            Side::of(&key_diff("age", "a.json", "b.json"), file_names),
+            Side::A
+        );
+        assert_eq!(
+            Side::of(&key_diff("city", "b.json", "a.json"), file_names),
+            Side::B
+        );
+        assert_eq!(Side::B.to_string(), "B");
+    }
+
+    #[test]
+    fn test_sided_key_diff_serialization() {
+        let sided = SidedKeyDiff {
+            key_diff: key_diff("city", "config.json", "config.json"),
+            side: Some(Side::B),
+        };
+
+        let json = serde_json::to_value(&sided).unwrap();
+        assert_eq!(json["side"], "B");
+        let loaded: SidedKeyDiff = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, sided);
+        assert_eq!(loaded.side(("config.json", "config.json")), Side::B);
+
+        // Saved without the side, it's told by the names
+        let legacy: SidedKeyDiff = serde_json::from_value(
+            serde_json::to_value(key_diff("city", "b.json", "a.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(legacy.side, None);
+        assert_eq!(legacy.side(("a.json", "b.json")), Side::B);
+    }
+
+    #[test]
+    fn test_find_json_key_sides_same_file_names() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"name": "dtf", "age": 3, "nested": {"a": 1}}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"name": "dtf", "city": "Budapest", "nested": {"b": 2}}"#)
+                .unwrap();
+        let key_diffs = [
+            key_diff("age", "config.json", "config.json"),
+            key_diff("city", "config.json", "config.json"),
+            key_diff("nested.b", "config.json", "config.json"),
+        ];
+
+        let key_sides = find_json_key_sides(&key_diffs, &data1, &data2);
+
+        assert_eq!(key_sides.get("age"), Some(&Side::A));
+        assert_eq!(key_sides.get("city"), Some(&Side::B));
+        assert_eq!(key_sides.get("nested.b"), Some(&Side::B));
+    }
+}
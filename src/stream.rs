@@ -0,0 +1,153 @@
+use std::io::{self, Write};
+
+use libdtf::core::diff_types::{ArrayDiff, TypeDiff, ValueDiff};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    dtfterminal_types::{DiffCategory, DiffCollection, DiffStats, DiffSummary, WorkingContext},
+    side::SidedKeyDiff,
+};
+
+/// A single difference of any category, written as one line by `--format ndjson`
+#[derive(Serialize)]
+#[serde(tag = "category", rename_all = "camelCase")]
+pub enum DiffRecord<'a> {
+    Key(SidedKeyDiff),
+    Type(&'a TypeDiff),
+    Value(&'a ValueDiff),
+    Array(&'a ArrayDiff),
+}
+
+impl DiffRecord<'_> {
+    pub fn category(&self) -> DiffCategory {
+        match self {
+            DiffRecord::Key(_) => DiffCategory::Key,
+            DiffRecord::Type(_) => DiffCategory::Type,
+            DiffRecord::Value(_) => DiffCategory::Value,
+            DiffRecord::Array(_) => DiffCategory::Array,
+        }
+    }
+}
+
+/// Iterates over the differences of every category, in the order the tables are rendered in.
+/// The key differences come with the files having their keys, told by the context.
+pub fn diff_records<'a>(
+    diffs: &'a DiffCollection,
+    context: &WorkingContext,
+) -> impl Iterator<Item = DiffRecord<'a>> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    context
+        .sided_key_diffs(key_diff.as_deref().unwrap_or_default())
+        .into_iter()
+        .map(DiffRecord::Key)
+        .chain(type_diff.iter().flatten().map(DiffRecord::Type))
+        .chain(value_diff.iter().flatten().map(DiffRecord::Value))
+        .chain(array_diff.iter().flatten().map(DiffRecord::Array))
+}
+
+/// Writes each difference of the rendered categories as a JSON line, followed by a summary line
+/// with the number of differences written in each category, the files and the configuration like the summary of `--format json`.
+/// The differences are only known once the whole check is finished, so the lines are written after it.
+/// If the positions of the keys are to be shown, they are added to the lines of the differences.
+pub fn write_stream<'a, W: Write>(
+    records: impl IntoIterator<Item = DiffRecord<'a>>,
+    context: &WorkingContext,
+    writer: &mut W,
+) -> io::Result<()> {
+    let config = &context.config;
+    let mut stats = DiffStats::default();
+    for record in records {
+        let count = match record.category() {
+            DiffCategory::Key if config.render_key_diffs => &mut stats.key_diffs,
+            DiffCategory::Type if config.render_type_diffs => &mut stats.type_diffs,
+            DiffCategory::Value if config.render_value_diffs => &mut stats.value_diffs,
+            DiffCategory::Array if config.render_array_diffs => &mut stats.array_diffs,
+            _ => continue,
+        };
+        *count += 1;
+        if config.positions {
+            let mut line = serde_json::to_value(&record)?;
+            context.annotate_positions(&mut line);
+            writeln!(writer, "{}", line)?;
+        } else {
+            writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+        }
+    }
+
+    let summary = json!({ "summary": DiffSummary::with_stats(stats, context) });
+    writeln!(writer, "{}", summary)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::{ArrayDiffDesc, KeyDiff, WorkingFile};
+    use serde_json::Value;
+
+    use crate::dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext};
+
+    use super::*;
+
+    #[test]
+    fn test_write_stream() {
+        let diffs = (
+            Some(vec![KeyDiff {
+                key: "user.email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            None,
+            Some(vec![ValueDiff {
+                key: "version".to_owned(),
+                value1: "1".to_owned(),
+                value2: "2".to_owned(),
+            }]),
+            Some(vec![ArrayDiff {
+                key: "tags".to_owned(),
+                descriptor: ArrayDiffDesc::AHas,
+                value: "\"x\"".to_owned(),
+            }]),
+        );
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .render_array_diffs(true)
+            .build();
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config,
+        );
+        let mut output = vec![];
+
+        write_stream(diff_records(&diffs, &context), &context, &mut output).unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["category"], "key");
+        assert_eq!(lines[0]["key"], "user.email");
+        assert_eq!(lines[0]["side"], "A");
+        assert_eq!(lines[1]["category"], "value");
+        assert_eq!(lines[1]["key"], "version");
+        assert_eq!(lines[2]["category"], "array");
+        assert_eq!(lines[2]["key"], "tags");
+        let summary = &lines[3]["summary"];
+        assert_eq!(summary["keyDiffs"], 1);
+        assert_eq!(summary["typeDiffs"], 0);
+        assert_eq!(summary["valueDiffs"], 1);
+        assert_eq!(summary["arrayDiffs"], 1);
+        assert_eq!(summary["total"], 3);
+        assert_eq!(summary["fileA"], "a.json");
+        assert_eq!(summary["fileB"], "b.json");
+        assert!(summary["timestamp"].is_string());
+        assert!(summary["config"].is_object());
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use colored::{Color, Colorize};
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table, TableStyle,
+};
+
+use crate::{
+    app::App, diff_of_diffs::DiffEntry, document_cache::DocumentCache, dtfterminal_types::DtfError,
+    Arguments,
+};
+
+/// Which side of a three-way comparison changed a key compared to the base
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeSide {
+    Ours,
+    Theirs,
+    /// Both sides made the same change
+    Both,
+    /// Both sides changed the key, or something inside it, differently
+    Conflict,
+}
+
+impl ChangeSide {
+    /// The text and color the side is displayed with in the terminal
+    fn display(&self) -> (&'static str, Color) {
+        match self {
+            ChangeSide::Ours => ("Ours", Color::Cyan),
+            ChangeSide::Theirs => ("Theirs", Color::Magenta),
+            ChangeSide::Both => ("Both", Color::Green),
+            ChangeSide::Conflict => ("Conflict", Color::Red),
+        }
+    }
+}
+
+/// The changes made to a key by the two sides
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreeWayChange {
+    pub side: ChangeSide,
+    pub key: String,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+/// Compares two files against the base they were both changed from, like the sides of a merge
+pub struct ThreeWayApp {
+    changes: Vec<ThreeWayChange>,
+}
+
+impl ThreeWayApp {
+    /// Checks both sides against the base, defined by the command line arguments.
+    /// Each side is checked like two files given with `-c`, so the format and every option of the check apply.
+    pub fn new(args: Arguments) -> Result<ThreeWayApp, DtfError> {
+        let (base, ours, theirs) = match args.three_way.as_slice() {
+            [base, ours, theirs] => (base, ours, theirs),
+            _ => {
+                return Err(DtfError::MissingArgument(
+                    "a base and the two files changed from it".to_owned(),
+                ))
+            }
+        };
+        // The base is compared against both sides, so it's only parsed once
+        let mut cache = DocumentCache::new(base.clone());
+        let mut check = |side: &String| -> Result<Vec<DiffEntry>, DtfError> {
+            let mut side_args = args.clone();
+            side_args.three_way = vec![];
+            side_args.check_files = vec![base.clone(), side.clone()];
+            Ok(App::new_cached(side_args, &mut cache)?.diff_entries())
+        };
+
+        let changes = three_way_changes(&check(ours)?, &check(theirs)?);
+        Ok(ThreeWayApp { changes })
+    }
+
+    /// Renders the changes and the conflicts as separate tables to the terminal
+    pub fn execute(&self) -> Result<(), DtfError> {
+        if self.changes.is_empty() {
+            println!("Neither side changed anything!");
+            return Ok(());
+        }
+
+        let (conflicts, changes): (Vec<_>, Vec<_>) = self
+            .changes
+            .iter()
+            .partition(|change| change.side == ChangeSide::Conflict);
+        if !changes.is_empty() {
+            println!("{}", render_changes_table(&changes));
+        }
+        if !conflicts.is_empty() {
+            println!("{}", render_conflicts_table(&conflicts));
+        }
+
+        Ok(())
+    }
+}
+
+/// Sorts the differences of both sides from the base by key into the changes of one side, both sides and the conflicts.
+/// The differences are the entries of the checks of the base as A against each side as B.
+/// Sorted by side, then by key.
+pub fn three_way_changes(ours: &[DiffEntry], theirs: &[DiffEntry]) -> Vec<ThreeWayChange> {
+    let ours = entries_by_key(ours);
+    let theirs = entries_by_key(theirs);
+    // A change inside a key the other side changed conflicts with it, like removing an object and editing its field
+    let overlaps = |key: &str, other: &BTreeMap<String, BTreeSet<DiffEntry>>| {
+        other
+            .keys()
+            .any(|other_key| is_inside(other_key, key) || is_inside(key, other_key))
+    };
+
+    let keys: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+    let mut changes: Vec<ThreeWayChange> = keys
+        .into_iter()
+        .map(|key| {
+            let side = match (ours.get(key), theirs.get(key)) {
+                (Some(ours_entries), Some(theirs_entries)) if ours_entries == theirs_entries => {
+                    ChangeSide::Both
+                }
+                (Some(_), Some(_)) => ChangeSide::Conflict,
+                (Some(_), None) if overlaps(key, &theirs) => ChangeSide::Conflict,
+                (Some(_), None) => ChangeSide::Ours,
+                (None, _) if overlaps(key, &ours) => ChangeSide::Conflict,
+                (None, _) => ChangeSide::Theirs,
+            };
+            ThreeWayChange {
+                side,
+                key: key.clone(),
+                ours: describe(ours.get(key)),
+                theirs: describe(theirs.get(key)),
+            }
+        })
+        .collect();
+    changes.sort_by(|a, b| (a.side, &a.key).cmp(&(b.side, &b.key)));
+    changes
+}
+
+/// Whether the key is a key or item inside the parent key, like `db.host` or `tags[0]` inside `db` and `tags`
+fn is_inside(key: &str, parent: &str) -> bool {
+    key.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+fn entries_by_key(diff_entries: &[DiffEntry]) -> BTreeMap<String, BTreeSet<DiffEntry>> {
+    let mut entries: BTreeMap<String, BTreeSet<DiffEntry>> = BTreeMap::new();
+    for entry in diff_entries {
+        entries
+            .entry(entry.key.clone())
+            .or_default()
+            .insert(entry.clone());
+    }
+    entries
+}
+
+/// Describes the changes of a side to a key, the base being A and the side B
+fn describe(entries: Option<&BTreeSet<DiffEntry>>) -> Vec<String> {
+    entries
+        .into_iter()
+        .flatten()
+        .map(|entry| format!("{}: {}", entry.category, entry.value))
+        .collect()
+}
+
+/// Builds the terminal table of the changes made by one or both sides
+fn render_changes_table(changes: &[&ThreeWayChange]) -> String {
+    let mut table = Table::new();
+    table.max_column_width = 80;
+    table.style = TableStyle::extended();
+    table.add_row(Row::new(vec![TableCell::builder("Changes")
+        .col_span(3)
+        .alignment(Alignment::Center)]));
+    table.add_row(Row::new(vec![
+        TableCell::new("Side"),
+        TableCell::new("Key"),
+        TableCell::new("Change"),
+    ]));
+    for change in changes {
+        let (side_text, side_color) = change.side.display();
+        let description = match change.side {
+            ChangeSide::Theirs => &change.theirs,
+            _ => &change.ours,
+        };
+        table.add_row(Row::new(vec![
+            TableCell::new(side_text.color(side_color)),
+            TableCell::new(&change.key),
+            TableCell::new(description.join("\n")),
+        ]));
+    }
+    table.render()
+}
+
+/// Builds the terminal table of the keys both sides changed differently
+fn render_conflicts_table(conflicts: &[&ThreeWayChange]) -> String {
+    let mut table = Table::new();
+    table.max_column_width = 80;
+    table.style = TableStyle::extended();
+    table.add_row(Row::new(vec![TableCell::builder("Conflicts")
+        .col_span(3)
+        .alignment(Alignment::Center)]));
+    table.add_row(Row::new(vec![
+        TableCell::new("Key"),
+        TableCell::new("Ours"),
+        TableCell::new("Theirs"),
+    ]));
+    for conflict in conflicts {
+        table.add_row(Row::new(vec![
+            TableCell::new(conflict.key.color(Color::Red)),
+            TableCell::new(conflict.ours.join("\n")),
+            TableCell::new(conflict.theirs.join("\n")),
+        ]));
+    }
+    table.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use libdtf::core::diff_types::WorkingFile;
+
+    use crate::{
+        compare_json_str,
+        diff_of_diffs::diff_entries,
+        dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext, WorkingContext},
+    };
+
+    use super::*;
+
+    const BASE: &str = r#"{"name": "app", "port": 80, "db": {"host": "a", "user": "root"}}"#;
+
+    #[test]
+    fn test_three_way_changes_clean_merge() {
+        let ours = check(r#"{"name": "app", "port": 81, "db": {"host": "a", "user": "root"}}"#);
+        let theirs = check(
+            r#"{"name": "app", "port": 80, "db": {"host": "b", "user": "root"}, "debug": true}"#,
+        );
+
+        let changes = three_way_changes(&ours, &theirs);
+
+        let sides: Vec<(ChangeSide, &str)> = changes
+            .iter()
+            .map(|change| (change.side, change.key.as_str()))
+            .collect();
+        assert_eq!(
+            sides,
+            vec![
+                (ChangeSide::Ours, "port"),
+                (ChangeSide::Theirs, "db.host"),
+                (ChangeSide::Theirs, "debug"),
+            ]
+        );
+        assert_eq!(changes[0].ours, vec!["Value: 80 -> 81"]);
+        assert!(changes[0].theirs.is_empty());
+    }
+
+    #[test]
+    fn test_three_way_changes_conflict() {
+        let ours = check(r#"{"name": "api", "port": 81, "db": {"host": "a", "user": "root"}}"#);
+        let theirs = check(r#"{"name": "app", "port": 82, "db": {"host": "b", "user": "root"}}"#);
+        let ours_without_db = check(r#"{"name": "api", "port": 81}"#);
+
+        let changes = three_way_changes(&ours, &theirs);
+
+        let port = changes.iter().find(|change| change.key == "port").unwrap();
+        assert_eq!(port.side, ChangeSide::Conflict);
+        assert_eq!(port.ours, vec!["Value: 80 -> 81"]);
+        assert_eq!(port.theirs, vec!["Value: 80 -> 82"]);
+        let name = changes.iter().find(|change| change.key == "name").unwrap();
+        assert_eq!(name.side, ChangeSide::Ours);
+
+        // Removing the object conflicts with changing a field of it
+        let changes = three_way_changes(&ours_without_db, &theirs);
+        let db = changes.iter().find(|change| change.key == "db").unwrap();
+        assert_eq!(db.side, ChangeSide::Conflict);
+        let host = changes
+            .iter()
+            .find(|change| change.key == "db.host")
+            .unwrap();
+        assert_eq!(host.side, ChangeSide::Conflict);
+    }
+
+    #[test]
+    fn test_three_way_changes_same_change() {
+        let side = check(r#"{"name": "app", "port": 81, "db": {"host": "a", "user": "root"}}"#);
+
+        let changes = three_way_changes(&side, &side);
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].side, ChangeSide::Both);
+    }
+
+    #[test]
+    fn test_three_way_app_yaml() {
+        let args = Arguments {
+            three_way: vec![
+                "test_data/yaml/person1.yaml".to_owned(),
+                "test_data/yaml/person2.yaml".to_owned(),
+                "test_data/yaml/person1.yaml".to_owned(),
+            ],
+            value_diffs: true,
+            ignore_path: vec!["address".to_owned()],
+            ..Default::default()
+        };
+
+        let app = ThreeWayApp::new(args).unwrap();
+
+        assert!(app
+            .changes
+            .iter()
+            .all(|change| change.side == ChangeSide::Ours));
+        assert!(app.changes.iter().any(|change| change.key == "name"));
+        assert!(!app
+            .changes
+            .iter()
+            .any(|change| change.key.starts_with("address")));
+    }
+
+    #[test]
+    fn test_is_inside() {
+        assert!(is_inside("db.host", "db"));
+        assert!(is_inside("tags[0].id", "tags"));
+        assert!(!is_inside("dbx", "db"));
+        assert!(!is_inside("db", "db"));
+    }
+
+    fn check(side: &str) -> Vec<DiffEntry> {
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("base.json".to_owned()),
+                WorkingFile::new("side.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new()
+                .check_for_key_diffs(true)
+                .check_for_type_diffs(true)
+                .check_for_value_diffs(true)
+                .check_for_array_diffs(true)
+                .build(),
+        );
+        diff_entries(&compare_json_str(BASE, side, &context).unwrap(), &context)
+    }
+}
@@ -4,7 +4,7 @@ use term_table::{
     table_cell::{Alignment, TableCell},
 };
 
-use crate::dtfterminal_types::{TableContext, TermTable, WorkingContext};
+use crate::dtfterminal_types::{DiffCategory, TableContext, TermTable, WorkingContext};
 
 /// Table to display type differences in the terminal
 pub struct TypeTable<'a> {
@@ -18,7 +18,11 @@ impl<'a> TermTable<TypeDiff> for TypeTable<'a> {
 
     fn create_table(&mut self, data: &[TypeDiff]) {
         self.add_header();
-        self.add_rows(data);
+        if data.is_empty() {
+            self.context.add_no_differences_row();
+        } else {
+            self.add_rows(data);
+        }
     }
 
     fn add_header(&mut self) {
@@ -40,7 +44,7 @@ impl<'a> TermTable<TypeDiff> for TypeTable<'a> {
     fn add_rows(&mut self, data: &[TypeDiff]) {
         for td in data {
             self.context.add_row(Row::new(vec![
-                TableCell::new(&td.key),
+                TableCell::new(self.context.format_key(DiffCategory::Type, &td.key)),
                 TableCell::new(&td.type1),
                 TableCell::new(&td.type2),
             ]));
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashMap};
+
+use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc, KeyDiff, TypeDiff, ValueDiff};
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{diff_tree::split_key_path, dtfterminal_types::DiffCollection};
+
+/// The original forms of the keys and string values changed by the normalization, by their normalized paths
+#[derive(Debug, Default)]
+pub struct Originals {
+    /// The original keys, by the normalized path of the key
+    keys: HashMap<String, String>,
+    /// The original strings, by the normalized path of the string
+    values: HashMap<String, String>,
+    /// The normalized and original forms of the keys and strings changed inside the array items, in document order,
+    /// by the normalized path of the array and the index of the item
+    items: HashMap<String, BTreeMap<usize, Vec<(String, String)>>>,
+}
+
+/// Normalizes the keys and string values of the JSON data to NFC, so composed and decomposed characters are equal
+pub fn normalize_json(data: &Map<String, Value>) -> (Map<String, Value>, Originals) {
+    let mut normalizer = Normalizer::default();
+    let normalized = normalizer.normalize_json_object(data, "");
+    (normalized, normalizer.originals)
+}
+
+/// Normalizes the keys and string values of the YAML data to NFC, so composed and decomposed characters are equal
+pub fn normalize_yaml(data: &Mapping) -> (Mapping, Originals) {
+    let mut normalizer = Normalizer::default();
+    let normalized = normalizer.normalize_yaml_mapping(data, "");
+    (normalized, normalizer.originals)
+}
+
+/// Puts the original forms back into the differences found in the normalized data.
+/// Keys are restored as the first file has them, values as their own file has them.
+pub fn restore_originals(
+    diffs: DiffCollection,
+    originals1: &Originals,
+    originals2: &Originals,
+) -> DiffCollection {
+    let restore_key = |key: String| {
+        let mut path = String::new();
+        let segments: Vec<String> = split_key_path(&key)
+            .into_iter()
+            .map(|segment| {
+                path = child_path(&path, &segment);
+                originals1
+                    .keys
+                    .get(&path)
+                    .or_else(|| originals2.keys.get(&path))
+                    .cloned()
+                    .unwrap_or(segment)
+            })
+            .collect();
+        join_key_path(&segments)
+    };
+
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        key_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|kd| KeyDiff {
+                    key: restore_key(kd.key),
+                    ..kd
+                })
+                .collect()
+        }),
+        type_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|td| TypeDiff {
+                    key: restore_key(td.key),
+                    ..td
+                })
+                .collect()
+        }),
+        value_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|vd| ValueDiff {
+                    value1: restore_value(vd.value1, originals1.values.get(&vd.key)),
+                    value2: restore_value(vd.value2, originals2.values.get(&vd.key)),
+                    key: restore_key(vd.key),
+                })
+                .collect()
+        }),
+        array_diff.map(|diffs| {
+            diffs
+                .into_iter()
+                .map(|ad| {
+                    let originals = match ad.descriptor {
+                        ArrayDiffDesc::AHas | ArrayDiffDesc::BMisses => originals1,
+                        _ => originals2,
+                    };
+                    ArrayDiff {
+                        value: restore_item(ad.value, originals.items.get(&ad.key)),
+                        key: restore_key(ad.key),
+                        ..ad
+                    }
+                })
+                .collect()
+        }),
+    )
+}
+
+/// Normalizes the data while remembering the original forms by their paths
+#[derive(Default)]
+struct Normalizer {
+    originals: Originals,
+    /// The paths of the arrays and the indexes of the items the walk is in, outermost first
+    items: Vec<(String, usize)>,
+}
+
+impl Normalizer {
+    fn normalize_json_object(
+        &mut self,
+        object: &Map<String, Value>,
+        path: &str,
+    ) -> Map<String, Value> {
+        object
+            .iter()
+            .map(|(key, value)| {
+                let (key, path) = self.normalize_key(key, path);
+                let value = self.normalize_json_value(value, &path);
+                (key, value)
+            })
+            .collect()
+    }
+
+    fn normalize_json_value(&mut self, value: &Value, path: &str) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.normalize_string(s, path)),
+            Value::Array(array) => Value::Array(
+                array
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        self.in_item(path, index, |normalizer, item_path| {
+                            normalizer.normalize_json_value(item, item_path)
+                        })
+                    })
+                    .collect(),
+            ),
+            Value::Object(object) => Value::Object(self.normalize_json_object(object, path)),
+            other => other.clone(),
+        }
+    }
+
+    fn normalize_yaml_mapping(&mut self, mapping: &Mapping, path: &str) -> Mapping {
+        mapping
+            .iter()
+            .map(|(key, value)| {
+                let (key, path) = match key {
+                    YamlValue::String(s) => {
+                        let (key, path) = self.normalize_key(s, path);
+                        (YamlValue::String(key), path)
+                    }
+                    YamlValue::Number(n) => (key.clone(), child_path(path, &n.to_string())),
+                    YamlValue::Bool(b) => (key.clone(), child_path(path, &b.to_string())),
+                    other => (other.clone(), path.to_owned()),
+                };
+                let value = self.normalize_yaml_value(value, &path);
+                (key, value)
+            })
+            .collect()
+    }
+
+    fn normalize_yaml_value(&mut self, value: &YamlValue, path: &str) -> YamlValue {
+        match value {
+            YamlValue::String(s) => YamlValue::String(self.normalize_string(s, path)),
+            YamlValue::Sequence(sequence) => YamlValue::Sequence(
+                sequence
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        self.in_item(path, index, |normalizer, item_path| {
+                            normalizer.normalize_yaml_value(item, item_path)
+                        })
+                    })
+                    .collect(),
+            ),
+            YamlValue::Mapping(mapping) => {
+                YamlValue::Mapping(self.normalize_yaml_mapping(mapping, path))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Normalizes an item of the array at the path, so the changes inside it are remembered for the item
+    fn in_item<T>(
+        &mut self,
+        path: &str,
+        index: usize,
+        normalize: impl FnOnce(&mut Normalizer, &str) -> T,
+    ) -> T {
+        self.items.push((path.to_owned(), index));
+        let normalized = normalize(self, &format!("{}[{}]", path, index));
+        self.items.pop();
+        normalized
+    }
+
+    /// Normalizes a key of the object at the path. Returns it with its normalized path.
+    fn normalize_key(&mut self, key: &str, path: &str) -> (String, String) {
+        let normalized: String = key.nfc().collect();
+        let key_path = child_path(path, &normalized);
+        if normalized != key {
+            self.originals.keys.insert(key_path.clone(), key.to_owned());
+            self.remember_in_items(&normalized, key);
+        }
+        (normalized, key_path)
+    }
+
+    /// Normalizes the string at the path
+    fn normalize_string(&mut self, s: &str, path: &str) -> String {
+        let normalized: String = s.nfc().collect();
+        if normalized != s {
+            self.originals.values.insert(path.to_owned(), s.to_owned());
+            self.remember_in_items(&normalized, s);
+        }
+        normalized
+    }
+
+    fn remember_in_items(&mut self, normalized: &str, original: &str) {
+        for (path, index) in &self.items {
+            self.originals
+                .items
+                .entry(path.clone())
+                .or_default()
+                .entry(*index)
+                .or_default()
+                .push((normalized.to_owned(), original.to_owned()));
+        }
+    }
+}
+
+/// The path of a key or index under the path
+fn child_path(path: &str, segment: &str) -> String {
+    if path.is_empty() || segment.starts_with('[') {
+        format!("{}{}", path, segment)
+    } else {
+        format!("{}.{}", path, segment)
+    }
+}
+
+/// Restores a serialized string value to its original form
+fn restore_value(value: String, original: Option<&String>) -> String {
+    match original {
+        Some(original) => {
+            let normalized: String = original.nfc().collect();
+            value.replacen(&normalized, original, 1)
+        }
+        None => value,
+    }
+}
+
+/// Restores the keys and strings of a serialized array item to their original forms.
+/// The item is taken to be the first one of the array containing every changed form of it, in order.
+fn restore_item(value: String, items: Option<&BTreeMap<usize, Vec<(String, String)>>>) -> String {
+    items
+        .into_iter()
+        .flat_map(BTreeMap::values)
+        .find_map(|changes| replace_in_order(&value, changes))
+        .unwrap_or(value)
+}
+
+/// Replaces the normalized forms with the original ones one after the other, if the value has all of them in order
+fn replace_in_order(value: &str, changes: &[(String, String)]) -> Option<String> {
+    let mut restored = String::with_capacity(value.len());
+    let mut rest = value;
+    for (normalized, original) in changes {
+        let index = rest.find(normalized.as_str())?;
+        restored.push_str(&rest[..index]);
+        restored.push_str(original);
+        rest = &rest[index + normalized.len()..];
+    }
+    restored.push_str(rest);
+    Some(restored)
+}
+
+/// Joins the segments split by `split_key_path` back into a key path
+pub(crate) fn join_key_path(segments: &[String]) -> String {
+    let mut key = String::new();
+    for segment in segments {
+        if !key.is_empty() && !segment.starts_with('[') {
+            key.push('.');
+        }
+        key.push_str(segment);
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_json() {
+        let data: Map<String, Value> =
+            serde_json::from_str("{\"cafe\u{301}\": [\"nai\u{308}ve\", 1]}").unwrap();
+
+        let (normalized, originals) = normalize_json(&data);
+
+        assert_eq!(
+            Value::Object(normalized),
+            serde_json::json!({ "caf\u{e9}": ["na\u{ef}ve", 1] })
+        );
+        assert_eq!(
+            originals.keys.get("caf\u{e9}"),
+            Some(&"cafe\u{301}".to_owned())
+        );
+        assert_eq!(
+            originals.values.get("caf\u{e9}[0]"),
+            Some(&"nai\u{308}ve".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_restore_originals_by_path() {
+        let data: Map<String, Value> = serde_json::from_str(
+            "{\"a\": \"cafe\u{301}\", \"b\": \"caf\u{e9}\", \"tags\": [{\"name\": \"nai\u{308}ve\"}]}",
+        )
+        .unwrap();
+        let (_, originals) = normalize_json(&data);
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: "\"caf\u{e9}\"".to_owned(),
+            value2: "\"cafe\"".to_owned(),
+        };
+        let diffs = (
+            None,
+            None,
+            Some(vec![value_diff("a"), value_diff("b")]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "tags".to_owned(),
+                value: "{\"name\":\"na\u{ef}ve\"}".to_owned(),
+            }]),
+        );
+
+        let (_, _, value_diff, array_diff) =
+            restore_originals(diffs, &originals, &Originals::default());
+
+        let value_diffs = value_diff.unwrap();
+        assert_eq!(value_diffs[0].value1, "\"cafe\u{301}\"");
+        // Only the string at "a" was written decomposed
+        assert_eq!(value_diffs[1].value1, "\"caf\u{e9}\"");
+        assert_eq!(array_diff.unwrap()[0].value, "{\"name\":\"nai\u{308}ve\"}");
+    }
+
+    #[test]
+    fn test_join_key_path() {
+        assert_eq!(
+            join_key_path(&split_key_path("friends[0][1].name")),
+            "friends[0][1].name"
+        );
+    }
+}
@@ -1,9 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, VecDeque},
+};
 
-use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc, WorkingFile};
+use libdtf::core::diff_types::{ArrayDiff, ArrayDiffDesc, KeyDiff, WorkingFile};
+use log::{debug, log_enabled, trace, Level};
 use serde_yaml::Value;
 
-use crate::dtfterminal_types::{Config, LibConfig, LibWorkingContext, WorkingContext};
+use crate::array_indexes::{annotate_index, collapse_index_ranges, describe_range, IndexCursor};
+use crate::dtfterminal_types::{
+    Config, DiffCategory, DiffCollection, DiffStats, DtfError, LibConfig, LibWorkingContext,
+    Severity, WorkingContext,
+};
+use crate::path_matcher::PathMatcher;
 
 /// Unicode representation of a checkmark to render in the terminal
 pub const CHECKMARK: &str = "\u{2713}";
@@ -11,6 +20,18 @@ pub const CHECKMARK: &str = "\u{2713}";
 /// Unicode representation of a cross to render in the terminal
 pub const MULTIPLY: &str = "\u{00D7}";
 
+/// Unicode representation of a right arrow, pointing from the value in file A to the one in file B
+pub const RIGHT_ARROW: &str = "\u{2192}";
+
+/// ASCII replacement of the checkmark, used with `--ascii`
+pub const ASCII_CHECKMARK: &str = "[x]";
+
+/// ASCII replacement of the cross, used with `--ascii`
+pub const ASCII_MULTIPLY: &str = "[ ]";
+
+/// ASCII replacement of the right arrow, used with `--ascii`
+pub const ASCII_RIGHT_ARROW: &str = "->";
+
 /// Group array diffs by key
 pub fn group_by_key(data: &[ArrayDiff]) -> HashMap<&str, Vec<&ArrayDiff>> {
     let mut map = HashMap::new();
@@ -28,43 +49,574 @@ pub fn group_by_key(data: &[ArrayDiff]) -> HashMap<&str, Vec<&ArrayDiff>> {
     map
 }
 
-/// Get values to display in each column.
-/// Columns represent the files compared.
-pub fn get_display_values_by_column(
+/// Get the values to display for an array key, one row per entry.
+/// Columns represent the files compared. Objects having the same `array_key` field are displayed
+/// on the same row, everything else gets a row of its own with the other column left empty.
+/// With `sort_array_values` the values of both columns are sorted before they are paired.
+/// With `collapse_ranges` the runs of consecutive indexes get a single row first, like `indices 10–42 added in B`.
+pub fn get_aligned_display_values(
     context: &WorkingContext,
     values: &[&ArrayDiff],
-    diff_desc: ArrayDiffDesc,
-) -> Vec<String> {
+) -> Vec<(String, String)> {
     let file_names = context.get_file_names();
-    values
+    let raw_values = |diff_desc: ArrayDiffDesc| -> Vec<&str> {
+        let mut raw_values: Vec<&str> = values
+            .iter()
+            .filter(|ad| ad.descriptor == diff_desc)
+            .map(|ad| ad.value.as_str())
+            .collect();
+        if context.config.sort_array_values {
+            sort_array_values(&mut raw_values);
+        }
+        raw_values
+    };
+    let key = values.first().map_or("", |ad| ad.key.as_str());
+    let mut cursor = IndexCursor::default();
+    let mut with_indexes = |values: Vec<_>, in_a: bool| {
+        values
+            .into_iter()
+            .map(|v| (v, context.next_array_index(&mut cursor, key, in_a, v)))
+            .collect::<Vec<_>>()
+    };
+
+    let (values_a, ranges_a) = collapse_ranges(
+        context,
+        with_indexes(raw_values(ArrayDiffDesc::AHas), true),
+        true,
+    );
+    let (values_b, ranges_b) = collapse_ranges(
+        context,
+        with_indexes(raw_values(ArrayDiffDesc::BHas), false),
+        false,
+    );
+    let range_rows = ranges_a
+        .into_iter()
+        .map(|range| (range, String::new()))
+        .chain(ranges_b.into_iter().map(|range| (String::new(), range)));
+
+    let mut indexes_a = IndexQueues::new(&values_a);
+    let mut indexes_b = IndexQueues::new(&values_b);
+    let display_value = |value: Option<&str>, indexes: &mut IndexQueues| {
+        value.map_or(String::new(), |v| {
+            annotate_index(prettify_data(file_names, v), indexes.take(v))
+        })
+    };
+
+    let rows: Vec<(String, String)> = align_array_values(
+        values_a.into_iter().map(|(v, _)| v).collect(),
+        values_b.into_iter().map(|(v, _)| v).collect(),
+        context.config.array_key.as_deref(),
+    )
+    .into_iter()
+    .map(|(value_a, value_b)| {
+        (
+            display_value(value_a, &mut indexes_a),
+            display_value(value_b, &mut indexes_b),
+        )
+    })
+    .collect();
+    range_rows.chain(rows).collect()
+}
+
+/// The indexes of the displayed values of one file, by the values.
+/// Rows of equal values take them one after the other.
+struct IndexQueues(HashMap<String, VecDeque<Option<usize>>>);
+
+impl IndexQueues {
+    fn new(values: &[(&str, Option<usize>)]) -> IndexQueues {
+        let mut queues: HashMap<String, VecDeque<Option<usize>>> = HashMap::new();
+        for (value, index) in values {
+            queues
+                .entry(value.to_string())
+                .or_default()
+                .push_back(*index);
+        }
+        IndexQueues(queues)
+    }
+
+    fn take(&mut self, value: &str) -> Option<usize> {
+        self.0
+            .get_mut(value)
+            .and_then(VecDeque::pop_front)
+            .flatten()
+    }
+}
+
+/// Takes the runs of consecutive indexes out of the values of one file, described as ranges
+fn collapse_ranges<'a>(
+    context: &WorkingContext,
+    values: Vec<(&'a str, Option<usize>)>,
+    in_a: bool,
+) -> (Vec<(&'a str, Option<usize>)>, Vec<String>) {
+    if !context.config.collapse_ranges {
+        return (values, vec![]);
+    }
+
+    let (rest, ranges) = collapse_index_ranges(values);
+    let ranges = ranges
+        .into_iter()
+        .map(|range| describe_range(range, in_a, context.config.ascii))
+        .collect();
+    (rest, ranges)
+}
+
+/// Sorts the values numerically if all of them are numbers, lexically otherwise
+fn sort_array_values(values: &mut [&str]) {
+    let number = |value: &str| value.trim().parse::<f64>().ok();
+    if values.iter().all(|value| number(value).is_some()) {
+        values.sort_by(|a, b| number(a).unwrap().total_cmp(&number(b).unwrap()));
+    } else {
+        values.sort_unstable();
+    }
+}
+
+/// Limits the rows displayed for an array key to `max_rows`.
+/// Returns a summary of the values left out, if there are any.
+pub fn cap_array_rows(rows: &mut Vec<(String, String)>, max_rows: Option<usize>) -> Option<String> {
+    let max_rows = max_rows?;
+    if rows.len() <= max_rows {
+        return None;
+    }
+
+    let omitted = rows.split_off(max_rows);
+    let more_in_a = omitted.iter().filter(|(a, _)| !a.is_empty()).count();
+    let more_in_b = omitted.iter().filter(|(_, b)| !b.is_empty()).count();
+    Some(format!(
+        "+{} more in A, +{} more in B",
+        more_in_a, more_in_b
+    ))
+}
+
+/// Pairs the values only one of the files contains.
+/// Values are only paired if they are objects with an equal `array_key` field, otherwise they are padded with `None`.
+pub fn align_array_values<'a>(
+    values_a: Vec<&'a str>,
+    values_b: Vec<&'a str>,
+    array_key: Option<&str>,
+) -> Vec<(Option<&'a str>, Option<&'a str>)> {
+    let mut unmatched_b: Vec<Option<&str>> = values_b.into_iter().map(Some).collect();
+    let mut rows = vec![];
+
+    for value_a in values_a {
+        let matched_b = array_key.and_then(|array_key| {
+            let id = get_array_key_value(value_a, array_key)?;
+            unmatched_b
+                .iter_mut()
+                .find(|value_b| {
+                    value_b
+                        .and_then(|b| get_array_key_value(b, array_key))
+                        .as_ref()
+                        == Some(&id)
+                })?
+                .take()
+        });
+        if matched_b.is_some() {
+            debug!(
+                "Matched array items by {}: {}",
+                array_key.unwrap_or_default(),
+                value_a
+            );
+        }
+        rows.push((Some(value_a), matched_b));
+    }
+
+    rows.extend(
+        unmatched_b
+            .into_iter()
+            .flatten()
+            .map(|value_b| (None, Some(value_b))),
+    );
+    rows
+}
+
+/// Get the value of the `array_key` field, if the value is an object containing it.
+/// JSON is valid YAML, so this works for both formats.
+fn get_array_key_value(value: &str, array_key: &str) -> Option<Value> {
+    serde_yaml::from_str::<Value>(value)
+        .ok()?
+        .get(array_key)
+        .cloned()
+}
+
+/// Creates a working context object based on user configuration.
+/// Both file names have to be set in the configuration.
+pub fn create_working_context(config: &Config) -> Result<WorkingContext, DtfError> {
+    let file_a = config
+        .file_a
+        .clone()
+        .ok_or_else(|| DtfError::MissingArgument("the name of the first file".to_owned()))?;
+    let file_b = config
+        .file_b
+        .clone()
+        .ok_or_else(|| DtfError::MissingArgument("the name of the second file".to_owned()))?;
+
+    let lib_working_context = LibWorkingContext::new(
+        WorkingFile::new(file_a),
+        WorkingFile::new(file_b),
+        LibConfig::new(config.array_same_order),
+    );
+
+    Ok(WorkingContext::new(lib_working_context, config.clone()))
+}
+
+/// Merges the results of an unordered and an ordered check.
+/// Differences under the ordered paths are taken from the ordered check, everything else from the unordered one.
+pub fn merge_ordered_array_diffs(
+    unordered: DiffCollection,
+    ordered: DiffCollection,
+    ordered_paths: &[String],
+) -> DiffCollection {
+    let ordered_paths = PathMatcher::compile_all(ordered_paths);
+    let is_ordered = |key: &str| ordered_paths.iter().any(|matcher| matcher.matches(key));
+
+    (
+        merge_by_key(unordered.0, ordered.0, |kd| is_ordered(&kd.key)),
+        merge_by_key(unordered.1, ordered.1, |td| is_ordered(&td.key)),
+        merge_by_key(unordered.2, ordered.2, |vd| is_ordered(&vd.key)),
+        merge_by_key(unordered.3, ordered.3, |ad| is_ordered(&ad.key)),
+    )
+}
+
+/// Keeps the unordered differences not matching the predicate and the ordered ones matching it
+fn merge_by_key<T>(
+    unordered: Option<Vec<T>>,
+    ordered: Option<Vec<T>>,
+    is_ordered: impl Fn(&T) -> bool,
+) -> Option<Vec<T>> {
+    let unordered = unordered?;
+    let ordered = ordered.unwrap_or_default();
+
+    Some(
+        unordered
+            .into_iter()
+            .filter(|diff| !is_ordered(diff))
+            .chain(ordered.into_iter().filter(|diff| is_ordered(diff)))
+            .collect(),
+    )
+}
+
+/// Drops the differences below the configured minimum severity and sorts the rest starting with the most severe.
+/// The context tells which file has the keys of the key differences.
+pub fn apply_severities(
+    diffs: DiffCollection,
+    config: &Config,
+    context: &WorkingContext,
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    (
+        filter_and_sort_by_severity(key_diff, config.min_severity, |kd| {
+            key_diff_severity(kd, config, context)
+        }),
+        filter_and_sort_by_severity(type_diff, config.min_severity, |td| {
+            config.severity_of(DiffCategory::Type, &td.key)
+        }),
+        filter_and_sort_by_severity(value_diff, config.min_severity, |vd| {
+            config.severity_of(DiffCategory::Value, &vd.key)
+        }),
+        filter_and_sort_by_severity(array_diff, config.min_severity, |ad| {
+            array_diff_severity(ad, config)
+        }),
+    )
+}
+
+/// The severity of the most severe difference, for failing the run with `--fail-on-severity`
+pub fn highest_severity(
+    diffs: &DiffCollection,
+    config: &Config,
+    context: &WorkingContext,
+) -> Option<Severity> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let key_severities = key_diff
+        .iter()
+        .flatten()
+        .map(|kd| key_diff_severity(kd, config, context));
+    let type_severities = type_diff
+        .iter()
+        .flatten()
+        .map(|td| config.severity_of(DiffCategory::Type, &td.key));
+    let value_severities = value_diff
         .iter()
-        .filter(|ad| ad.descriptor == diff_desc)
-        .map(|ad| prettify_data(file_names, ad.value.as_str()))
+        .flatten()
+        .map(|vd| config.severity_of(DiffCategory::Value, &vd.key));
+    let array_severities = array_diff
+        .iter()
+        .flatten()
+        .map(|ad| array_diff_severity(ad, config));
+    key_severities
+        .chain(type_severities)
+        .chain(value_severities)
+        .chain(array_severities)
+        .max()
+}
+
+fn key_diff_severity(kd: &KeyDiff, config: &Config, context: &WorkingContext) -> Severity {
+    config.presence_severity_of(
+        DiffCategory::Key,
+        &kd.key,
+        config.baseline_has_key(context.key_side(kd)),
+    )
+}
+
+fn array_diff_severity(ad: &ArrayDiff, config: &Config) -> Severity {
+    config.presence_severity_of(
+        DiffCategory::Array,
+        &ad.key,
+        config.baseline_has_array_item(&ad.descriptor),
+    )
+}
+
+fn filter_and_sort_by_severity<T>(
+    diffs: Option<Vec<T>>,
+    min_severity: Severity,
+    severity_of: impl Fn(&T) -> Severity,
+) -> Option<Vec<T>> {
+    diffs.map(|diffs| {
+        let mut diffs: Vec<T> = diffs
+            .into_iter()
+            .filter(|diff| severity_of(diff) >= min_severity)
+            .collect();
+        diffs.sort_by_key(|diff| Reverse(severity_of(diff)));
+        diffs
+    })
+}
+
+/// Logs every difference found by the checks, before any of them are filtered
+pub fn log_diffs(diffs: &DiffCollection) {
+    if !log_enabled!(Level::Trace) {
+        return;
+    }
+
+    for kd in diffs.0.iter().flatten() {
+        trace!("Key difference at {}: only {} has it", kd.key, kd.has);
+    }
+    for td in diffs.1.iter().flatten() {
+        trace!(
+            "Type difference at {}: {} -> {}",
+            td.key,
+            td.type1,
+            td.type2
+        );
+    }
+    for vd in diffs.2.iter().flatten() {
+        trace!("Value difference at {}", vd.key);
+    }
+    for ad in diffs.3.iter().flatten() {
+        trace!("Array difference at {}: {}", ad.key, ad.value);
+    }
+}
+
+/// Lists the sorted, unique key paths of the differences of the rendered categories.
+/// With `tag_paths` each path is preceded by the tag of its category and a tab, a path differing in multiple categories is listed once for each.
+pub fn diff_paths(diffs: &DiffCollection, config: &Config) -> Vec<String> {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let mut paths = BTreeSet::new();
+    let mut add = |category: DiffCategory, rendered: bool, keys: Vec<&String>| {
+        if rendered {
+            let tag = config.tag_paths.then_some(category);
+            paths.extend(keys.into_iter().map(|key| (key, tag)));
+        }
+    };
+    add(
+        DiffCategory::Key,
+        config.render_key_diffs,
+        key_diff.iter().flatten().map(|kd| &kd.key).collect(),
+    );
+    add(
+        DiffCategory::Type,
+        config.render_type_diffs,
+        type_diff.iter().flatten().map(|td| &td.key).collect(),
+    );
+    add(
+        DiffCategory::Value,
+        config.render_value_diffs,
+        value_diff.iter().flatten().map(|vd| &vd.key).collect(),
+    );
+    add(
+        DiffCategory::Array,
+        config.render_array_diffs,
+        array_diff.iter().flatten().map(|ad| &ad.key).collect(),
+    );
+
+    paths
+        .into_iter()
+        .map(|(key, category)| match category {
+            Some(category) => format!("{}\t{}", category.tag(), key),
+            None => key.clone(),
+        })
         .collect()
 }
 
-/// Creates a working context object based on user configuration
-pub fn create_working_context(config: &Config) -> WorkingContext {
-    let file_a = WorkingFile::new(config.file_a.as_ref().unwrap().clone());
-    let file_b = WorkingFile::new(config.file_b.as_ref().unwrap().clone());
+/// The differences of a category to render a table for: the rendered categories having differences,
+/// or all of them with `show_empty_tables`
+pub fn shown_diffs<'d, T>(
+    diffs: &'d Option<Vec<T>>,
+    rendered: bool,
+    config: &Config,
+) -> Option<&'d [T]> {
+    let diffs = diffs.as_deref().unwrap_or_default();
+    (rendered && (config.show_empty_tables || !diffs.is_empty())).then_some(diffs)
+}
+
+/// Counts all the differences found
+pub fn count_diffs(diffs: &DiffCollection) -> usize {
+    DiffStats::from_diffs(diffs).total()
+}
+
+/// Builds a single line summary of the check for tools reading the output:
+/// `datadiff v{version} a={fileA} b={fileB} key={n} type={n} value={n} array={n}`
+pub fn render_header(diffs: &DiffCollection, config: &Config) -> String {
+    let stats = DiffStats::from_diffs(diffs);
+    format!(
+        "datadiff v{} a={} b={} key={} type={} value={} array={}",
+        env!("CARGO_PKG_VERSION"),
+        config.file_a.as_deref().unwrap_or_default(),
+        config.file_b.as_deref().unwrap_or_default(),
+        stats.key_diffs,
+        stats.type_diffs,
+        stats.value_diffs,
+        stats.array_diffs,
+    )
+}
+
+/// Returns an error if more differences were found than the configured maximum
+pub fn check_diff_limit(diffs: &DiffCollection, config: &Config) -> Result<(), DtfError> {
+    match config.max_diffs {
+        Some(max_diffs) if count_diffs(diffs) > max_diffs => {
+            Err(diff_limit_error(count_diffs(diffs), max_diffs))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn diff_limit_error(found: usize, max_diffs: usize) -> DtfError {
+    DtfError::LimitError(format!(
+        "{} differences found, the maximum is {}",
+        found, max_diffs
+    ))
+}
+
+/// Counts the differences while the categories are checked one after the other,
+/// so the check stops after the first category exceeding the maximum instead of checking the rest.
+/// Walks of this crate count each difference as they find it, so they stop as soon as the maximum is exceeded.
+/// A copy counts from the differences found so far, for a check whose differences are merged with others before counting.
+#[derive(Clone)]
+pub struct DiffLimit {
+    max_diffs: Option<usize>,
+    found: usize,
+}
+
+impl DiffLimit {
+    pub fn new(config: &Config) -> DiffLimit {
+        DiffLimit {
+            max_diffs: config.max_diffs,
+            found: 0,
+        }
+    }
+
+    /// Counts the differences of a category, returning an error if there are more than the maximum so far
+    pub fn add<T>(&mut self, diffs: Vec<T>) -> Result<Vec<T>, DtfError> {
+        self.found += diffs.len();
+        self.check()?;
+        Ok(diffs)
+    }
+
+    /// Counts the differences of a check, returning an error if there are more than the maximum so far
+    pub fn add_diffs(&mut self, diffs: &DiffCollection) -> Result<(), DtfError> {
+        self.found += count_diffs(diffs);
+        self.check()
+    }
+
+    /// Counts a single difference found by a walk, returning an error if there are more than the maximum so far
+    pub fn count(&mut self) -> Result<(), DtfError> {
+        self.found += 1;
+        self.check()
+    }
 
-    let lib_working_context =
-        LibWorkingContext::new(file_a, file_b, LibConfig::new(config.array_same_order));
+    fn check(&self) -> Result<(), DtfError> {
+        match self.max_diffs {
+            Some(max_diffs) if self.found > max_diffs => {
+                Err(diff_limit_error(self.found, max_diffs))
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
-    WorkingContext::new(lib_working_context, config.clone())
+/// Formats the two versions of a value like `prettify_data`, unless that would make them look the same,
+/// like strings encoding the same document with a different spacing. Those are displayed as they are.
+pub fn prettify_values(file_names: (&str, &str), value1: &str, value2: &str) -> (String, String) {
+    let pretty1 = prettify_data(file_names, value1);
+    let pretty2 = prettify_data(file_names, value2);
+    if pretty1 == pretty2 && value1 != value2 {
+        return (value1.to_owned(), value2.to_owned());
+    }
+    (pretty1, pretty2)
 }
 
-/// Formats data based on file type
+/// Formats a value by its content if it looks like a JSON or YAML document, otherwise by the format of the files.
+/// Documents encoded in a string are formatted too, marked as decoded so they don't look like the document itself.
 pub fn prettify_data(file_names: (&str, &str), data: &str) -> String {
-    // at this point we can be sure, both file names have the same file type, so we can just check the first one
-    let (file1, _) = file_names;
-    if is_yaml_file(file1) {
+    if let Some(document) = unquote(data) {
+        if let Some(pretty) = prettify_document(&document) {
+            return format!("{} (decoded from a string)", pretty.trim_end());
+        }
+    }
+    if let Some(pretty) = prettify_document(data) {
+        return pretty;
+    }
+
+    // files of different formats are compared as JSON
+    let (file1, file2) = file_names;
+    if is_yaml_file(file1) && is_yaml_file(file2) {
         return prettify_yaml_str(data);
     }
 
     prettify_json_str(data)
 }
 
+/// Formats a JSON or YAML document, if the content looks like one
+fn prettify_document(data: &str) -> Option<String> {
+    match sniff_format(data)? {
+        ContentFormat::Json => Some(prettify_json_str(data.trim())),
+        ContentFormat::Yaml => Some(prettify_yaml_str(data)),
+    }
+}
+
+/// The format of a value, judged by its content
+enum ContentFormat {
+    Json,
+    Yaml,
+}
+
+/// JSON objects and arrays are recognized by their brackets, YAML mappings and sequences only if they span
+/// multiple lines, so plain strings and numbers are left to the format of the files
+fn sniff_format(data: &str) -> Option<ContentFormat> {
+    let trimmed = data.trim();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<Value>(trimmed).is_ok()
+    {
+        return Some(ContentFormat::Json);
+    }
+    match serde_yaml::from_str::<Value>(trimmed) {
+        Ok(Value::Mapping(_) | Value::Sequence(_)) if trimmed.contains('\n') => {
+            Some(ContentFormat::Yaml)
+        }
+        _ => None,
+    }
+}
+
+/// The content of a quoted string value, like `"{\"a\": 1}"` in JSON or `'{"a": 1}'` in YAML
+fn unquote(data: &str) -> Option<String> {
+    let trimmed = data.trim();
+    if trimmed.starts_with('"') {
+        return serde_json::from_str::<String>(trimmed).ok();
+    }
+    if trimmed.starts_with('\'') {
+        return serde_yaml::from_str::<String>(trimmed).ok();
+    }
+    None
+}
+
 /// Formats JSON strings
 pub fn prettify_json_str(json_str: &str) -> String {
     match serde_json::from_str::<Value>(json_str) {
@@ -81,19 +633,61 @@ pub fn prettify_yaml_str(yaml_str: &str) -> String {
     }
 }
 
-/// Checks if a file is a YAML file
+/// Checks if a file is a YAML file, gzipped or not
 pub fn is_yaml_file(path: &str) -> bool {
+    let path = path.strip_suffix(".gz").unwrap_or(path);
     path.ends_with(".yaml") || path.ends_with(".yml")
 }
 
+/// Checks if a file is a JSON file, gzipped or not. JSONC and JSON5 files count as JSON files.
+pub fn is_json_file(path: &str) -> bool {
+    path.strip_suffix(".gz").unwrap_or(path).ends_with(".json") || is_jsonc_file(path)
+}
+
+/// Checks if a file is a TOML file, gzipped or not
+pub fn is_toml_file(path: &str) -> bool {
+    path.strip_suffix(".gz").unwrap_or(path).ends_with(".toml")
+}
+
+/// Checks if a file is a Parquet file
+pub fn is_parquet_file(path: &str) -> bool {
+    path.ends_with(".parquet")
+}
+
+/// Checks if a file is a JSONC or JSON5 file, gzipped or not, which may contain comments and trailing commas
+pub fn is_jsonc_file(path: &str) -> bool {
+    let path = path.strip_suffix(".gz").unwrap_or(path);
+    path.ends_with(".jsonc") || path.ends_with(".json5")
+}
+
+/// Renders with colors regardless of the terminal, so tests can check them.
+/// The override is global, the tests using it take turns.
+#[cfg(test)]
+pub(crate) fn with_colors<T>(render: impl FnOnce() -> T) -> T {
+    static COLORS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = COLORS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    colored::control::set_override(true);
+    let rendered = render();
+    colored::control::unset_override();
+    rendered
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::dtfterminal_types::ConfigBuilder;
+    use libdtf::core::diff_types::{KeyDiff, ValueDiff};
+
+    use crate::{
+        array_indexes::find_json_array_indexes,
+        dtfterminal_types::{Baseline, ConfigBuilder},
+        side::{KeySides, Side},
+    };
 
     use super::*;
 
     #[test]
-    fn test_get_display_values_by_column() {
+    fn test_get_aligned_display_values() {
         let context = WorkingContext::new(
             LibWorkingContext::new(
                 WorkingFile::new("file_a.txt".to_owned()),
@@ -109,22 +703,241 @@ mod tests {
             value: "value1".to_owned(),
         };
         let diff2 = ArrayDiff {
-            descriptor: ArrayDiffDesc::AHas,
-            key: "key2".to_owned(),
+            descriptor: ArrayDiffDesc::BHas,
+            key: "key1".to_owned(),
             value: "value2".to_owned(),
         };
         let diff3 = ArrayDiff {
             descriptor: ArrayDiffDesc::AHas,
-            key: "key3".to_owned(),
+            key: "key1".to_owned(),
             value: "value3".to_owned(),
         };
         let values = vec![&diff1, &diff2, &diff3];
 
-        let diff_desc = ArrayDiffDesc::AHas;
+        let display_values = get_aligned_display_values(&context, &values);
 
-        let display_values = get_display_values_by_column(&context, &values, diff_desc);
+        assert_eq!(
+            display_values,
+            vec![
+                ("value1".to_owned(), String::new()),
+                ("value3".to_owned(), String::new()),
+                (String::new(), "value2".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_aligned_display_values_collapse_ranges() {
+        let mut context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.json".to_owned()),
+                WorkingFile::new("file_b.json".to_owned()),
+                LibConfig::new(true),
+            ),
+            ConfigBuilder::new().collapse_ranges(true).build(),
+        );
+        let data1 = serde_json::from_str(r#"{"ids": [1, 2]}"#).unwrap();
+        let data2 = serde_json::from_str(r#"{"ids": [1, 2, 3, 4, 5, 6]}"#).unwrap();
+        let diffs: Vec<ArrayDiff> = ["3", "4", "5", "6"]
+            .into_iter()
+            .map(|value| ArrayDiff {
+                descriptor: ArrayDiffDesc::BHas,
+                key: "ids".to_owned(),
+                value: value.to_owned(),
+            })
+            .collect();
+        context.array_indexes = find_json_array_indexes(&diffs, &data1, &data2);
+        let values: Vec<&ArrayDiff> = diffs.iter().collect();
 
-        assert_eq!(display_values, vec!["value1", "value2", "value3"]);
+        let display_values = get_aligned_display_values(&context, &values);
+
+        assert_eq!(
+            display_values,
+            vec![(String::new(), "indices 2\u{2013}5 added in B".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_shown_diffs() {
+        let diffs = Some(vec![1, 2]);
+        let no_diffs: Option<Vec<i32>> = Some(vec![]);
+        let mut config = ConfigBuilder::new().build();
+
+        assert_eq!(shown_diffs(&diffs, true, &config), Some(&[1, 2][..]));
+        assert_eq!(shown_diffs(&diffs, false, &config), None);
+        assert_eq!(shown_diffs(&no_diffs, true, &config), None);
+        assert_eq!(shown_diffs(&None::<Vec<i32>>, true, &config), None);
+
+        config.show_empty_tables = true;
+        assert_eq!(shown_diffs(&no_diffs, true, &config), Some(&[][..]));
+        assert_eq!(shown_diffs(&None::<Vec<i32>>, true, &config), Some(&[][..]));
+        assert_eq!(shown_diffs(&no_diffs, false, &config), None);
+    }
+
+    #[test]
+    fn test_get_aligned_display_values_sorted() {
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.txt".to_owned()),
+                WorkingFile::new("file_b.txt".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().sort_array_values(true).build(),
+        );
+        let array_diff = |descriptor: ArrayDiffDesc, value: &str| ArrayDiff {
+            descriptor,
+            key: "key1".to_owned(),
+            value: value.to_owned(),
+        };
+        let diffs = [
+            array_diff(ArrayDiffDesc::AHas, "10"),
+            array_diff(ArrayDiffDesc::BHas, "pear"),
+            array_diff(ArrayDiffDesc::AHas, "9"),
+            array_diff(ArrayDiffDesc::BHas, "apple"),
+            array_diff(ArrayDiffDesc::AHas, "100"),
+        ];
+        let values: Vec<&ArrayDiff> = diffs.iter().collect();
+
+        let display_values = get_aligned_display_values(&context, &values);
+
+        assert_eq!(
+            display_values,
+            vec![
+                ("9".to_owned(), String::new()),
+                ("10".to_owned(), String::new()),
+                ("100".to_owned(), String::new()),
+                (String::new(), "apple".to_owned()),
+                (String::new(), "pear".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_paths() {
+        let array_diff = |value: &str| ArrayDiff {
+            descriptor: ArrayDiffDesc::AHas,
+            key: "tags".to_owned(),
+            value: value.to_owned(),
+        };
+        let diffs = (
+            Some(vec![libdtf::core::diff_types::KeyDiff {
+                key: "user.email".to_owned(),
+                has: "a.json".to_owned(),
+                misses: "b.json".to_owned(),
+            }]),
+            Some(vec![libdtf::core::diff_types::TypeDiff {
+                key: "age".to_owned(),
+                type1: "number".to_owned(),
+                type2: "string".to_owned(),
+            }]),
+            Some(vec![
+                ValueDiff {
+                    key: "user.name".to_owned(),
+                    value1: "\"John\"".to_owned(),
+                    value2: "\"Jane\"".to_owned(),
+                },
+                ValueDiff {
+                    key: "tags".to_owned(),
+                    value1: "1".to_owned(),
+                    value2: "2".to_owned(),
+                },
+            ]),
+            Some(vec![array_diff("a"), array_diff("b")]),
+        );
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_type_diffs(true)
+            .render_value_diffs(true)
+            .render_array_diffs(true)
+            .build();
+
+        assert_eq!(
+            diff_paths(&diffs, &config),
+            vec!["age", "tags", "user.email", "user.name"]
+        );
+
+        let config = ConfigBuilder::new()
+            .render_key_diffs(true)
+            .render_value_diffs(true)
+            .render_array_diffs(true)
+            .tag_paths(true)
+            .build();
+
+        assert_eq!(
+            diff_paths(&diffs, &config),
+            vec!["V\ttags", "A\ttags", "K\tuser.email", "V\tuser.name"]
+        );
+    }
+
+    #[test]
+    fn test_cap_array_rows() {
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.txt".to_owned()),
+                WorkingFile::new("file_b.txt".to_owned()),
+                LibConfig::new(true),
+            ),
+            ConfigBuilder::new().build(),
+        );
+        let array_diff = |descriptor: ArrayDiffDesc, index: usize| ArrayDiff {
+            descriptor,
+            key: "items".to_owned(),
+            value: format!("value{}", index),
+        };
+        let diffs: Vec<ArrayDiff> = (0..1000)
+            .map(|index| array_diff(ArrayDiffDesc::AHas, index))
+            .chain((1000..1010).map(|index| array_diff(ArrayDiffDesc::BHas, index)))
+            .collect();
+        let values: Vec<&ArrayDiff> = diffs.iter().collect();
+
+        let mut rows = get_aligned_display_values(&context, &values);
+        let summary = cap_array_rows(&mut rows, Some(5));
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0], ("value0".to_owned(), String::new()));
+        assert_eq!(summary, Some("+995 more in A, +10 more in B".to_owned()));
+
+        let mut rows = get_aligned_display_values(&context, &values);
+        assert_eq!(cap_array_rows(&mut rows, Some(1010)), None);
+        assert_eq!(cap_array_rows(&mut rows, None), None);
+        assert_eq!(rows.len(), 1010);
+    }
+
+    #[test]
+    fn test_align_array_values_pads_asymmetric_counts() {
+        let rows = align_array_values(vec!["1", "2", "3"], vec!["4"], None);
+
+        assert_eq!(
+            rows,
+            vec![
+                (Some("1"), None),
+                (Some("2"), None),
+                (Some("3"), None),
+                (None, Some("4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_array_values_by_array_key() {
+        let rows = align_array_values(
+            vec![r#"{"id":1,"name":"a"}"#, r#"{"id":2,"name":"b"}"#, "3"],
+            vec![r#"{"id":2,"name":"c"}"#, r#"{"id":4,"name":"d"}"#],
+            Some("id"),
+        );
+
+        assert_eq!(
+            rows,
+            vec![
+                (Some(r#"{"id":1,"name":"a"}"#), None),
+                (
+                    Some(r#"{"id":2,"name":"b"}"#),
+                    Some(r#"{"id":2,"name":"c"}"#)
+                ),
+                (Some("3"), None),
+                (None, Some(r#"{"id":4,"name":"d"}"#)),
+            ]
+        );
     }
 
     #[test]
@@ -135,7 +948,7 @@ mod tests {
             .array_same_order(true)
             .build();
 
-        let working_context = create_working_context(&config);
+        let working_context = create_working_context(&config).unwrap();
 
         let (file_a_in_context, file_b_in_context) = working_context.get_file_names();
         assert_eq!(file_a_in_context, "file_a.txt");
@@ -146,6 +959,212 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_working_context_without_file_names() {
+        let config = ConfigBuilder::new()
+            .file_a(Some("file_a.txt".to_owned()))
+            .build();
+
+        let result = create_working_context(&config);
+
+        assert!(matches!(result, Err(DtfError::MissingArgument(_))));
+    }
+
+    #[test]
+    fn test_apply_severities() {
+        let config = ConfigBuilder::new()
+            .severity_rules(vec![
+                "value=warning".parse().unwrap(),
+                "value:id=error".parse().unwrap(),
+                "array=info".parse().unwrap(),
+            ])
+            .min_severity(Severity::Warning)
+            .build();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        };
+        let diffs = (
+            None,
+            None,
+            Some(vec![value_diff("name"), value_diff("id")]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "tags".to_owned(),
+                value: "x".to_owned(),
+            }]),
+        );
+
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config.clone(),
+        );
+
+        let (key_diff, type_diff, value_diff, array_diff) =
+            apply_severities(diffs, &config, &context);
+
+        assert!(key_diff.is_none());
+        assert!(type_diff.is_none());
+        let value_keys: Vec<String> = value_diff.unwrap().into_iter().map(|vd| vd.key).collect();
+        assert_eq!(value_keys, vec!["id", "name"]);
+        assert!(array_diff.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_highest_severity() {
+        let config = ConfigBuilder::new()
+            .severity_rules(vec![
+                "value=warning".parse().unwrap(),
+                "array=info".parse().unwrap(),
+            ])
+            .build();
+        let context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("a.json".to_owned()),
+                WorkingFile::new("b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config.clone(),
+        );
+        let array_diffs = Some(vec![ArrayDiff {
+            descriptor: ArrayDiffDesc::AHas,
+            key: "tags".to_owned(),
+            value: "x".to_owned(),
+        }]);
+        let value_diffs = Some(vec![ValueDiff {
+            key: "name".to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        }]);
+
+        assert_eq!(
+            highest_severity(&(None, None, None, array_diffs.clone()), &config, &context),
+            Some(Severity::Info)
+        );
+        assert_eq!(
+            highest_severity(&(None, None, value_diffs, array_diffs), &config, &context),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            highest_severity(&(None, None, None, Some(vec![])), &config, &context),
+            None
+        );
+    }
+
+    #[test]
+    fn test_apply_severities_baseline_same_file_names() {
+        let config = ConfigBuilder::new().baseline(Some(Baseline::B)).build();
+        let mut context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("config.json".to_owned()),
+                WorkingFile::new("config.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            config.clone(),
+        );
+        context.key_sides = KeySides::from([
+            ("extra".to_owned(), Side::A),
+            ("missing".to_owned(), Side::B),
+        ]);
+        let key_diff = |key: &str| KeyDiff {
+            key: key.to_owned(),
+            has: "config.json".to_owned(),
+            misses: "config.json".to_owned(),
+        };
+        let diffs = (
+            Some(vec![key_diff("extra"), key_diff("missing")]),
+            None,
+            None,
+            None,
+        );
+
+        let (key_diff, ..) = apply_severities(diffs, &config, &context);
+
+        // Keys only the baseline has are missing from the other file, which is more severe
+        let keys: Vec<String> = key_diff.unwrap().into_iter().map(|kd| kd.key).collect();
+        assert_eq!(keys, vec!["missing", "extra"]);
+    }
+
+    #[test]
+    fn test_render_header() {
+        let config = ConfigBuilder::new()
+            .file_a(Some("a.json".to_owned()))
+            .file_b(Some("b.json".to_owned()))
+            .build();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: "1".to_owned(),
+            value2: "2".to_owned(),
+        };
+        let diffs = (
+            Some(vec![]),
+            None,
+            Some(vec![value_diff("name"), value_diff("id")]),
+            Some(vec![ArrayDiff {
+                descriptor: ArrayDiffDesc::AHas,
+                key: "tags".to_owned(),
+                value: "x".to_owned(),
+            }]),
+        );
+
+        assert_eq!(
+            render_header(&diffs, &config),
+            format!(
+                "datadiff v{} a=a.json b=b.json key=0 type=0 value=2 array=1",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_prettify_data_json_string_in_yaml() {
+        let yaml_files = ("a.yaml", "b.yaml");
+        let pretty = "{\n  \"name\": \"dtf\",\n  \"tags\": [\n    1\n  ]\n}";
+
+        assert_eq!(
+            prettify_data(yaml_files, r#"'{"name": "dtf", "tags": [1]}'"#),
+            format!("{} (decoded from a string)", pretty)
+        );
+        assert_eq!(
+            prettify_data(yaml_files, r#"{"name": "dtf", "tags": [1]}"#),
+            pretty
+        );
+        assert_eq!(prettify_data(yaml_files, "plain"), "plain\n");
+    }
+
+    #[test]
+    fn test_prettify_data_yaml_string_in_json() {
+        let json_files = ("a.json", "b.json");
+
+        assert_eq!(
+            prettify_data(json_files, r#""name: dtf\ntags:\n- 1\n""#),
+            "name: dtf\ntags:\n- 1 (decoded from a string)"
+        );
+        assert_eq!(prettify_data(json_files, r#""a: b""#), r#""a: b""#);
+    }
+
+    #[test]
+    fn test_prettify_values_encoded() {
+        let json_files = ("a.json", "b.json");
+
+        assert_eq!(
+            prettify_values(json_files, r#""{\"a\":1}""#, r#""{\"a\": 1}""#),
+            (r#""{\"a\":1}""#.to_owned(), r#""{\"a\": 1}""#.to_owned())
+        );
+        assert_eq!(
+            prettify_values(json_files, r#""[1]""#, "[1]"),
+            (
+                "[\n  1\n] (decoded from a string)".to_owned(),
+                "[\n  1\n]".to_owned()
+            )
+        );
+    }
+
     #[test]
     fn test_is_yaml_file() {
         let yaml_file = "file.yaml";
@@ -157,6 +1176,26 @@ mod tests {
         assert_eq!(is_yaml_file(yml_file), true);
         assert_eq!(is_yaml_file(txt_file), false);
         assert_eq!(is_yaml_file(json_file), false);
+        assert_eq!(is_yaml_file("file.yaml.gz"), true);
+        assert_eq!(is_yaml_file("file.json.gz"), false);
+    }
+
+    #[test]
+    fn test_is_json_file() {
+        assert_eq!(is_json_file("file.json"), true);
+        assert_eq!(is_json_file("file.json.gz"), true);
+        assert_eq!(is_json_file("file.yaml"), false);
+        assert_eq!(is_json_file("file.gz"), false);
+        assert_eq!(is_json_file("file.jsonc"), true);
+        assert_eq!(is_jsonc_file("file.json5.gz"), true);
+        assert_eq!(is_jsonc_file("file.json"), false);
+    }
+
+    #[test]
+    fn test_is_toml_file() {
+        assert_eq!(is_toml_file("Cargo.toml"), true);
+        assert_eq!(is_toml_file("config.toml.gz"), true);
+        assert_eq!(is_toml_file("config.json"), false);
     }
 
     #[test]
@@ -0,0 +1,458 @@
+use chrono::{DateTime, FixedOffset};
+use libdtf::core::diff_types::ValueDiff;
+use log::debug;
+
+use crate::{
+    comparator::are_equal_by_comparators,
+    dtfterminal_types::{Config, DiffCollection, NumberFormat},
+};
+
+/// Marks the values shortened by `truncate_value_diffs`
+const TRUNCATED_MARKER: &str = "…(truncated)";
+
+/// Strings longer than this many characters are not compared by their similarity,
+/// the distance takes time proportional to the product of the lengths
+const MAX_SIMILARITY_LEN: usize = 10_000;
+
+/// Drops the value differences that are considered equal by the enabled comparison options
+pub fn filter_value_diffs(diffs: DiffCollection, config: &Config) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let value_diff = value_diff.map(|value_diffs| {
+        value_diffs
+            .into_iter()
+            .filter(|vd| !is_equivalent(vd, config))
+            .collect()
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// Shortens the values of the value differences to `max_value_len` characters, followed by a marker.
+/// Applied after every check, so the values are always compared in full.
+/// The full values are built by the check, this only keeps them out of the output and the saved results.
+pub fn truncate_value_diffs(diffs: DiffCollection, config: &Config) -> DiffCollection {
+    let max_value_len = match config.max_value_len {
+        Some(max_value_len) => max_value_len,
+        None => return diffs,
+    };
+
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let value_diff = value_diff.map(|value_diffs| {
+        value_diffs
+            .into_iter()
+            .map(|vd| ValueDiff {
+                key: vd.key,
+                value1: truncate(vd.value1, max_value_len),
+                value2: truncate(vd.value2, max_value_len),
+            })
+            .collect()
+    });
+
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+fn truncate(value: String, max_len: usize) -> String {
+    match value.char_indices().nth(max_len) {
+        Some((index, _)) => format!("{}{}", &value[..index], TRUNCATED_MARKER),
+        None => value,
+    }
+}
+
+/// Checks if the two values of a difference are equal by any of the enabled comparison options
+fn is_equivalent(value_diff: &ValueDiff, config: &Config) -> bool {
+    if are_equal_by_comparators(value_diff, &config.value_comparators) {
+        return true;
+    }
+
+    let same_instant =
+        config.parse_dates && are_same_instant(&value_diff.value1, &value_diff.value2);
+    if same_instant {
+        debug!("Skipped {}: the dates are the same instant", value_diff.key);
+        return true;
+    }
+
+    let within_tolerance = epsilon_for(&value_diff.key, config).map_or(false, |epsilon| {
+        are_within_tolerance(&value_diff.value1, &value_diff.value2, epsilon)
+    });
+    if within_tolerance {
+        debug!(
+            "Skipped {}: the numbers are within the tolerance",
+            value_diff.key
+        );
+        return true;
+    }
+
+    let same_number = config.number_format.map_or(false, |number_format| {
+        are_same_number(&value_diff.value1, &value_diff.value2, number_format)
+    });
+    if same_number {
+        debug!(
+            "Skipped {}: the numbers are only formatted differently",
+            value_diff.key
+        );
+        return true;
+    }
+
+    config
+        .string_similarity_threshold
+        .map_or(false, |threshold| {
+            are_similar_strings(value_diff, threshold)
+        })
+}
+
+/// Checks if both values are strings more similar than the threshold
+fn are_similar_strings(value_diff: &ValueDiff, threshold: f64) -> bool {
+    let (value1, value2) = (value_diff.value1.trim(), value_diff.value2.trim());
+    if value1.parse::<f64>().is_ok() || value2.parse::<f64>().is_ok() {
+        return false;
+    }
+
+    let (value1, value2) = (unquote(value1), unquote(value2));
+    let (len1, len2) = (value1.chars().count(), value2.chars().count());
+    if len1.max(len2) > MAX_SIMILARITY_LEN {
+        debug!(
+            "Reported {}: the strings are too long to compare their similarity",
+            value_diff.key
+        );
+        return false;
+    }
+    if max_similarity(len1, len2) <= threshold {
+        debug!(
+            "Reported {}: the lengths of the strings differ too much for them to be similar",
+            value_diff.key
+        );
+        return false;
+    }
+
+    let similarity = string_similarity(value1, value2);
+    if similarity > threshold {
+        debug!(
+            "Skipped {}: the strings are {:.2} similar",
+            value_diff.key, similarity
+        );
+        true
+    } else {
+        debug!(
+            "Reported {}: the strings are only {:.2} similar",
+            value_diff.key, similarity
+        );
+        false
+    }
+}
+
+/// The highest similarity strings of these lengths can have,
+/// as it takes at least as many edits as the difference of the lengths
+fn max_similarity(len1: usize, len2: usize) -> f64 {
+    let max_len = len1.max(len2);
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - len1.abs_diff(len2) as f64 / max_len as f64
+}
+
+/// The Levenshtein distance of the strings normalized to a similarity between 0 and 1,
+/// where 1 means the strings are equal
+fn string_similarity(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
+    let max_len = chars1.len().max(chars2.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=chars2.len()).collect();
+    for (i, c1) in chars1.iter().enumerate() {
+        let mut current = vec![i + 1; chars2.len() + 1];
+        for (j, c2) in chars2.iter().enumerate() {
+            let substitution = previous[j] + usize::from(c1 != c2);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    1.0 - previous[chars2.len()] as f64 / max_len as f64
+}
+
+/// Finds the tolerance of the most specific path the key is under, falling back to the global one
+fn epsilon_for(key: &str, config: &Config) -> Option<f64> {
+    config
+        .path_epsilons
+        .iter()
+        .filter(|(matcher, _)| matcher.matches(key))
+        .max_by_key(|(matcher, _)| matcher.specificity())
+        .map(|(_, epsilon)| *epsilon)
+        .or(config.epsilon)
+}
+
+/// Checks if both values are numbers not further apart than the epsilon
+fn are_within_tolerance(value1: &str, value2: &str, epsilon: f64) -> bool {
+    match (value1.trim().parse::<f64>(), value2.trim().parse::<f64>()) {
+        (Ok(number1), Ok(number2)) => (number1 - number2).abs() <= epsilon,
+        _ => false,
+    }
+}
+
+/// Checks if both values are the same number, written plainly or with the separators of the format.
+/// Only the comparison uses the numbers, the values are still shown as they are written.
+fn are_same_number(value1: &str, value2: &str, number_format: NumberFormat) -> bool {
+    let parse = |value: &str| {
+        let value = unquote(value.trim());
+        value
+            .parse::<f64>()
+            .ok()
+            .or_else(|| number_format.parse(value))
+    };
+    match (parse(value1), parse(value2)) {
+        (Some(number1), Some(number2)) => number1 == number2,
+        _ => false,
+    }
+}
+
+/// Checks if both values are timestamps representing the same instant
+fn are_same_instant(value1: &str, value2: &str) -> bool {
+    match (parse_instant(value1), parse_instant(value2)) {
+        (Some(instant1), Some(instant2)) => instant1 == instant2,
+        _ => false,
+    }
+}
+
+/// Parses an RFC 3339 (ISO-8601) timestamp
+pub(crate) fn parse_instant(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(unquote(value.trim())).ok()
+}
+
+/// Removes the quotes serialized strings are wrapped in
+pub(crate) fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::dtfterminal_types::{ConfigBuilder, NumberFormat, Tolerance};
+
+    use super::*;
+
+    #[test]
+    fn test_are_same_instant() {
+        assert_eq!(
+            are_same_instant("2024-01-01T00:00:00Z", "2024-01-01T00:00:00+00:00"),
+            true
+        );
+        assert_eq!(
+            are_same_instant("\"2024-01-01T02:00:00+02:00\"", "\"2024-01-01T00:00:00Z\""),
+            true
+        );
+        assert_eq!(
+            are_same_instant("2024-01-01T00:00:00Z", "2024-01-01T00:00:01Z"),
+            false
+        );
+        assert_eq!(are_same_instant("2024-01-01T00:00:00Z", "tomorrow"), false);
+    }
+
+    #[test]
+    fn test_filter_value_diffs_parse_dates() {
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff(
+                    "created",
+                    "\"2024-01-01T00:00:00Z\"",
+                    "\"2024-01-01T00:00:00+00:00\"",
+                ),
+                value_diff(
+                    "updated",
+                    "\"2024-01-01T00:00:00Z\"",
+                    "\"2024-01-02T00:00:00Z\"",
+                ),
+            ]),
+            None,
+        );
+
+        let config = ConfigBuilder::new().parse_dates(true).build();
+        let value_diffs = filter_value_diffs(diffs.clone(), &config).2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "updated");
+        assert_eq!(value_diffs[0].value1, "\"2024-01-01T00:00:00Z\"");
+        assert_eq!(value_diffs[0].value2, "\"2024-01-02T00:00:00Z\"");
+
+        let config = ConfigBuilder::new().build();
+        let value_diffs = filter_value_diffs(diffs, &config).2.unwrap();
+        assert_eq!(value_diffs.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_value_diffs_path_epsilons() {
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff("price", "10.00", "10.004"),
+                value_diff("ratio", "0.5", "0.5002"),
+                value_diff("stats.ratio", "0.5", "0.50005"),
+                value_diff("count", "3", "3.4"),
+                value_diff("name", "\"1\"", "\"1.001\""),
+            ]),
+            None,
+        );
+        let (epsilon, path_epsilons) = Tolerance::split(vec![
+            "0.5".parse().unwrap(),
+            "price=0.01".parse().unwrap(),
+            "ratio=0.0001".parse().unwrap(),
+            "stats=0.0001".parse().unwrap(),
+        ]);
+        let config = ConfigBuilder::new()
+            .epsilon(epsilon)
+            .path_epsilons(path_epsilons)
+            .build();
+
+        let value_diffs = filter_value_diffs(diffs, &config).2.unwrap();
+
+        let keys: Vec<&str> = value_diffs.iter().map(|vd| vd.key.as_str()).collect();
+        assert_eq!(keys, vec!["ratio", "name"]);
+    }
+
+    #[test]
+    fn test_epsilon_for_most_specific_path() {
+        let config = ConfigBuilder::new()
+            .epsilon(Some(1.0))
+            .path_epsilons(HashMap::from([
+                ("items".to_owned(), 0.1),
+                ("items[0].price".to_owned(), 0.01),
+            ]))
+            .build();
+
+        assert_eq!(epsilon_for("items[0].price", &config), Some(0.01));
+        assert_eq!(epsilon_for("items[1].price", &config), Some(0.1));
+        assert_eq!(epsilon_for("total", &config), Some(1.0));
+        assert_eq!(epsilon_for("total", &ConfigBuilder::new().build()), None);
+    }
+
+    #[test]
+    fn test_filter_value_diffs_number_format() {
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff("price", "\"1,234.56\"", "\"1234.56\""),
+                value_diff("total", "\"1,234,567\"", "\"1234567\""),
+                value_diff("count", "\"1,234\"", "\"1235\""),
+                value_diff("name", "\"a,b\"", "\"ab\""),
+            ]),
+            None,
+        );
+
+        let config = ConfigBuilder::new()
+            .number_format(Some(",.".parse().unwrap()))
+            .build();
+        let value_diffs = filter_value_diffs(diffs.clone(), &config).2.unwrap();
+        let keys: Vec<&str> = value_diffs.iter().map(|vd| vd.key.as_str()).collect();
+        assert_eq!(keys, vec!["count", "name"]);
+        assert_eq!(value_diffs[0].value1, "\"1,234\"");
+
+        let value_diffs = filter_value_diffs(diffs, &ConfigBuilder::new().build())
+            .2
+            .unwrap();
+        assert_eq!(value_diffs.len(), 4);
+    }
+
+    #[test]
+    fn test_are_same_number() {
+        let european: NumberFormat = ".,".parse().unwrap();
+        assert_eq!(
+            are_same_number("\"1.234,56\"", "\"1234.56\"", european),
+            true
+        );
+        assert_eq!(are_same_number("\"1.234,56\"", "1234.56", european), true);
+        assert_eq!(
+            are_same_number("\"1.234,5\"", "\"1234.56\"", european),
+            false
+        );
+        assert!(",,".parse::<NumberFormat>().is_err());
+        assert!(",".parse::<NumberFormat>().is_err());
+    }
+
+    #[test]
+    fn test_filter_value_diffs_string_similarity() {
+        let diffs = (
+            None,
+            None,
+            Some(vec![
+                value_diff(
+                    "title",
+                    "\"The quick brown fox\"",
+                    "\"The quick brown fax\"",
+                ),
+                value_diff("status", "\"active\"", "\"deleted\""),
+                value_diff("count", "10", "11"),
+            ]),
+            None,
+        );
+
+        let config = ConfigBuilder::new()
+            .string_similarity_threshold(Some(0.9))
+            .build();
+        let value_diffs = filter_value_diffs(diffs, &config).2.unwrap();
+        let keys: Vec<&str> = value_diffs.iter().map(|vd| vd.key.as_str()).collect();
+        assert_eq!(keys, vec!["status", "count"]);
+    }
+
+    #[test]
+    fn test_string_similarity() {
+        assert_eq!(string_similarity("abc", "abc"), 1.0);
+        assert_eq!(string_similarity("", ""), 1.0);
+        assert_eq!(string_similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        assert_eq!(string_similarity("abc", "xyz"), 0.0);
+        assert_eq!(max_similarity(6, 7), 1.0 - 1.0 / 7.0);
+        assert_eq!(max_similarity(0, 0), 1.0);
+    }
+
+    #[test]
+    fn test_are_similar_strings_long_values() {
+        let long = format!("\"{}\"", "x".repeat(MAX_SIMILARITY_LEN + 1));
+        let long_typo = format!("\"{}y\"", "x".repeat(MAX_SIMILARITY_LEN));
+        assert!(!are_similar_strings(
+            &value_diff("text", &long, &long_typo),
+            0.5
+        ));
+
+        let short = format!("\"{}\"", "x".repeat(10));
+        let longer = format!("\"{}\"", "x".repeat(100));
+        assert!(!are_similar_strings(
+            &value_diff("text", &short, &longer),
+            0.5
+        ));
+    }
+
+    #[test]
+    fn test_truncate_value_diffs() {
+        let huge_value = "x".repeat(1_000_000);
+        let diffs = (
+            None,
+            None,
+            Some(vec![value_diff("blob", &huge_value, "short")]),
+            None,
+        );
+
+        let config = ConfigBuilder::new().max_value_len(Some(8)).build();
+        let value_diffs = truncate_value_diffs(diffs.clone(), &config).2.unwrap();
+        assert_eq!(value_diffs[0].value1, "xxxxxxxx…(truncated)");
+        assert_eq!(value_diffs[0].value2, "short");
+
+        let config = ConfigBuilder::new().build();
+        let value_diffs = truncate_value_diffs(diffs, &config).2.unwrap();
+        assert_eq!(value_diffs[0].value1.len(), 1_000_000);
+    }
+
+    fn value_diff(key: &str, value1: &str, value2: &str) -> ValueDiff {
+        ValueDiff {
+            key: key.to_owned(),
+            value1: value1.to_owned(),
+            value2: value2.to_owned(),
+        }
+    }
+}
@@ -1,11 +1,13 @@
+use colored::Colorize;
 use libdtf::core::diff_types::ValueDiff;
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
 };
 
-use crate::dtfterminal_types::{TableContext, TermTable, WorkingContext};
-use crate::utils::prettify_data;
+use crate::dtfterminal_types::{DiffCategory, TableContext, TermTable, WorkingContext};
+use crate::utils::prettify_values;
+use crate::value_types::annotate_type;
 
 /// Table to display value differences in the terminal
 pub struct ValueTable<'a> {
@@ -19,7 +21,11 @@ impl<'a> TermTable<ValueDiff> for ValueTable<'a> {
 
     fn create_table(&mut self, data: &[ValueDiff]) {
         self.add_header();
-        self.add_rows(data);
+        if data.is_empty() {
+            self.context.add_no_differences_row();
+        } else {
+            self.add_rows(data);
+        }
     }
 
     fn add_header(&mut self) {
@@ -39,16 +45,23 @@ impl<'a> TermTable<ValueDiff> for ValueTable<'a> {
 
     fn add_rows(&mut self, data: &[ValueDiff]) {
         for vd in data {
+            let (type_a, type_b) = self.value_types(&vd.key);
+            let (value_a, value_b) = self.format_values(&vd.key, &vd.value1, &vd.value2);
+            let value_a = annotate_type(value_a, type_a);
+            let value_b = format!(
+                "{} {}",
+                self.context.arrow(),
+                annotate_type(value_b, type_b)
+            );
+            let (value_a, value_b) = if self.context.working_context().config.side_by_side {
+                align_side_by_side(&value_a, &value_b, self.context.column_width())
+            } else {
+                (value_a, value_b)
+            };
             self.context.add_row(Row::new(vec![
-                TableCell::new(&vd.key),
-                TableCell::new(prettify_data(
-                    self.context.working_context().get_file_names(),
-                    &vd.value1,
-                )),
-                TableCell::new(prettify_data(
-                    self.context.working_context().get_file_names(),
-                    &vd.value2,
-                )),
+                TableCell::new(self.context.format_key(DiffCategory::Value, &vd.key)),
+                TableCell::new(value_a.red()),
+                TableCell::new(value_b.green()),
             ]));
         }
     }
@@ -62,4 +75,147 @@ impl<'a> ValueTable<'a> {
         table.create_table(data);
         table
     }
+
+    /// Prettifies the two versions of a value, except line differences of multi-line strings, which are displayed as is
+    fn format_values(&self, key: &str, value1: &str, value2: &str) -> (String, String) {
+        let working_context = self.context.working_context();
+        if working_context.is_line_diff(key) {
+            return (value1.to_owned(), value2.to_owned());
+        }
+
+        prettify_values(working_context.get_file_names(), value1, value2)
+    }
+
+    /// The types of the values in the two files, if they are to be shown
+    fn value_types(&self, key: &str) -> (Option<&str>, Option<&str>) {
+        let working_context = self.context.working_context();
+        if !working_context.config.show_types_in_values {
+            return (None, None);
+        }
+
+        match working_context.value_types_of(key) {
+            Some((type_a, type_b)) => (Some(type_a.as_str()), Some(type_b.as_str())),
+            None => (None, None),
+        }
+    }
+}
+
+/// Wraps the lines of both values to the width and pads the shorter one with empty lines,
+/// so the lines of the two versions are next to each other
+fn align_side_by_side(value_a: &str, value_b: &str, width: usize) -> (String, String) {
+    let mut lines_a = wrap_lines(value_a, width);
+    let mut lines_b = wrap_lines(value_b, width);
+    let line_count = lines_a.len().max(lines_b.len());
+    lines_a.resize(line_count, String::new());
+    lines_b.resize(line_count, String::new());
+    (lines_a.join("\n"), lines_b.join("\n"))
+}
+
+/// Splits every line of the value into lines of at most `width` characters
+fn wrap_lines(value: &str, width: usize) -> Vec<String> {
+    value
+        .lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                return vec![String::new()];
+            }
+            chars
+                .chunks(width.max(1))
+                .map(|chunk| chunk.iter().collect())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dtfterminal_types::{ConfigBuilder, LibConfig, LibWorkingContext};
+    use crate::utils::{with_colors, RIGHT_ARROW};
+    use libdtf::core::diff_types::WorkingFile;
+
+    use super::*;
+
+    #[test]
+    fn test_change_direction() {
+        let working_context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.json".to_owned()),
+                WorkingFile::new("file_b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().build(),
+        );
+        let value_diff = ValueDiff {
+            key: "config.port".to_owned(),
+            value1: "8080".to_owned(),
+            value2: "9090".to_owned(),
+        };
+
+        let rendered = with_colors(|| ValueTable::new(&[value_diff], &working_context).render());
+
+        assert!(rendered.contains("\u{1b}[31m8080\u{1b}[0m"));
+        assert!(rendered.contains(&format!("\u{1b}[32m{} 9090\u{1b}[0m", RIGHT_ARROW)));
+    }
+
+    #[test]
+    fn test_align_side_by_side() {
+        let (value_a, value_b) = align_side_by_side(
+            "first line\nsecond line is long\nthird",
+            "first line\nthird",
+            12,
+        );
+
+        assert_eq!(value_a, "first line\nsecond line \nis long\nthird");
+        assert_eq!(value_b, "first line\nthird\n\n");
+        assert_eq!(value_a.lines().count(), 4);
+        assert_eq!(value_b.split('\n').count(), 4);
+    }
+
+    #[test]
+    fn test_show_types_in_values() {
+        let mut working_context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.json".to_owned()),
+                WorkingFile::new("file_b.json".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().show_types_in_values(true).build(),
+        );
+        working_context
+            .value_types
+            .insert("id".to_owned(), ("string".to_owned(), "number".to_owned()));
+        let value_diff = ValueDiff {
+            key: "id".to_owned(),
+            value1: "1".to_owned(),
+            value2: "1".to_owned(),
+        };
+
+        let rendered = ValueTable::new(&[value_diff], &working_context).render();
+
+        assert!(rendered.contains("1 (string)"));
+        assert!(rendered.contains(&format!("{} 1 (number)", RIGHT_ARROW)));
+    }
+
+    #[test]
+    fn test_json_string_in_yaml() {
+        let working_context = WorkingContext::new(
+            LibWorkingContext::new(
+                WorkingFile::new("file_a.yaml".to_owned()),
+                WorkingFile::new("file_b.yaml".to_owned()),
+                LibConfig::new(false),
+            ),
+            ConfigBuilder::new().build(),
+        );
+        let value_diff = ValueDiff {
+            key: "settings".to_owned(),
+            value1: r#"'{"retries": 3}'"#.to_owned(),
+            value2: r#"'{"retries": 5}'"#.to_owned(),
+        };
+
+        let rendered = ValueTable::new(&[value_diff], &working_context).render();
+
+        assert!(rendered.contains(r#""retries": 3"#));
+        assert!(rendered.contains(r#""retries": 5"#));
+    }
 }
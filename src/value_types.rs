@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use libdtf::core::diff_types::{TypeDiff, ValueDiff};
+use serde_json::{Map, Value};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::{
+    dtfterminal_types::DiffCollection,
+    key_path::{find_json_path, find_yaml_path, follow_json, follow_yaml},
+};
+
+/// The types of the differing values by their keys, as they are in the two files
+pub type ValueTypes = HashMap<String, (String, String)>;
+
+/// Looks up the types of the differing values in the JSON data
+pub fn find_json_value_types(
+    value_diffs: &[ValueDiff],
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> ValueTypes {
+    value_diffs
+        .iter()
+        .map(|vd| {
+            let type_of = |data: &Map<String, Value>| {
+                lookup_json(data, &vd.key)
+                    .map_or("", json_type_name)
+                    .to_owned()
+            };
+            (vd.key.clone(), (type_of(data1), type_of(data2)))
+        })
+        .collect()
+}
+
+/// Looks up the types of the differing values in the YAML data
+pub fn find_yaml_value_types(
+    value_diffs: &[ValueDiff],
+    data1: &Mapping,
+    data2: &Mapping,
+) -> ValueTypes {
+    value_diffs
+        .iter()
+        .map(|vd| {
+            let type_of = |data: &Mapping| {
+                lookup_yaml(data, &vd.key)
+                    .map_or("", yaml_type_name)
+                    .to_owned()
+            };
+            (vd.key.clone(), (type_of(data1), type_of(data2)))
+        })
+        .collect()
+}
+
+/// Renames the types of the type differences to the names of JSON Schema, by the values in the JSON data
+pub fn name_json_schema_types(
+    diffs: DiffCollection,
+    data1: &Map<String, Value>,
+    data2: &Map<String, Value>,
+) -> DiffCollection {
+    name_schema_types(diffs, |key| {
+        (
+            lookup_json(data1, key).map(json_schema_type_name),
+            lookup_json(data2, key).map(json_schema_type_name),
+        )
+    })
+}
+
+/// Renames the types of the type differences to the names of JSON Schema, by the values in the YAML data
+pub fn name_yaml_schema_types(
+    diffs: DiffCollection,
+    data1: &Mapping,
+    data2: &Mapping,
+) -> DiffCollection {
+    name_schema_types(diffs, |key| {
+        (
+            lookup_yaml(data1, key).map(yaml_schema_type_name),
+            lookup_yaml(data2, key).map(yaml_schema_type_name),
+        )
+    })
+}
+
+fn name_schema_types(
+    diffs: DiffCollection,
+    type_names: impl Fn(&str) -> (Option<&'static str>, Option<&'static str>),
+) -> DiffCollection {
+    let (key_diff, type_diff, value_diff, array_diff) = diffs;
+    let type_diff = type_diff.map(|diffs| {
+        diffs
+            .into_iter()
+            .map(|td| {
+                let (type1, type2) = type_names(&td.key);
+                TypeDiff {
+                    type1: type1.map_or(td.type1, str::to_owned),
+                    type2: type2.map_or(td.type2, str::to_owned),
+                    key: td.key,
+                }
+            })
+            .collect()
+    });
+    (key_diff, type_diff, value_diff, array_diff)
+}
+
+/// Appends the type to a displayed value, if it's known
+pub fn annotate_type(value: String, value_type: Option<&str>) -> String {
+    match value_type {
+        Some(value_type) if !value_type.is_empty() => format!("{} ({})", value, value_type),
+        _ => value,
+    }
+}
+
+/// Get the value at the key of a difference in the JSON data, following the keys the data has
+pub(crate) fn lookup_json<'a>(data: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    follow_json(data, &find_json_path(data, key)?)
+}
+
+/// Get the value at the key of a difference in the YAML data, following the keys the data has
+pub(crate) fn lookup_yaml<'a>(data: &'a Mapping, key: &str) -> Option<&'a YamlValue> {
+    follow_yaml(data, &find_yaml_path(data, key)?)
+}
+
+pub(crate) fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The name of the type of a value, as used by JSON Schema
+pub(crate) fn json_schema_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        other => json_type_name(other),
+    }
+}
+
+fn yaml_schema_type_name(value: &YamlValue) -> &'static str {
+    match value {
+        YamlValue::Number(n) if n.is_f64() => "number",
+        YamlValue::Number(_) => "integer",
+        YamlValue::Tagged(tagged) => yaml_schema_type_name(&tagged.value),
+        other => yaml_type_name(other),
+    }
+}
+
+fn yaml_type_name(value: &YamlValue) -> &'static str {
+    match value {
+        YamlValue::Null => "null",
+        YamlValue::Bool(_) => "boolean",
+        YamlValue::Number(_) => "number",
+        YamlValue::String(_) => "string",
+        YamlValue::Sequence(_) => "array",
+        YamlValue::Mapping(_) => "object",
+        YamlValue::Tagged(tagged) => yaml_type_name(&tagged.value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_json_value_types() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"id": "1", "tags": [{"name": true}]}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"id": 1, "tags": [{"name": null}]}"#).unwrap();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: String::new(),
+            value2: String::new(),
+        };
+
+        let value_types = find_json_value_types(
+            &[value_diff("id"), value_diff("tags[0].name")],
+            &data1,
+            &data2,
+        );
+
+        assert_eq!(
+            value_types.get("id"),
+            Some(&("string".to_owned(), "number".to_owned()))
+        );
+        assert_eq!(
+            value_types.get("tags[0].name"),
+            Some(&("boolean".to_owned(), "null".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_find_json_value_types_of_keys_with_dots() {
+        let data1: Map<String, Value> =
+            serde_json::from_str(r#"{"app.port": "80", "hosts[eu]": [1]}"#).unwrap();
+        let data2: Map<String, Value> =
+            serde_json::from_str(r#"{"app.port": 80, "hosts[eu]": [true]}"#).unwrap();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: String::new(),
+            value2: String::new(),
+        };
+
+        let value_types = find_json_value_types(
+            &[value_diff("app.port"), value_diff("hosts[eu][0]")],
+            &data1,
+            &data2,
+        );
+
+        assert_eq!(
+            value_types.get("app.port"),
+            Some(&("string".to_owned(), "number".to_owned()))
+        );
+        assert_eq!(
+            value_types.get("hosts[eu][0]"),
+            Some(&("number".to_owned(), "boolean".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_find_yaml_value_types() {
+        let data1: Mapping = serde_yaml::from_str("id: '1'\nnested:\n  list: [1]\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("id: 1\nnested:\n  list: [a]\n").unwrap();
+        let value_diff = |key: &str| ValueDiff {
+            key: key.to_owned(),
+            value1: String::new(),
+            value2: String::new(),
+        };
+
+        let value_types = find_yaml_value_types(
+            &[value_diff("id"), value_diff("nested.list[0]")],
+            &data1,
+            &data2,
+        );
+
+        assert_eq!(
+            value_types.get("id"),
+            Some(&("string".to_owned(), "number".to_owned()))
+        );
+        assert_eq!(
+            value_types.get("nested.list[0]"),
+            Some(&("number".to_owned(), "string".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_annotate_type() {
+        assert_eq!(annotate_type("1".to_owned(), Some("number")), "1 (number)");
+        assert_eq!(annotate_type("1".to_owned(), Some("")), "1");
+        assert_eq!(annotate_type("1".to_owned(), None), "1");
+    }
+}
@@ -1,18 +1,47 @@
+use std::rc::Rc;
+
+#[cfg(feature = "cli")]
+use crate::key_order::{find_yaml_key_order_diffs, KeyOrderDiff};
+use crate::{
+    array_indexes::{find_yaml_array_indexes, ArrayIndexes},
+    block_scalars::ignore_chomping,
+    canonical::canonicalize_yaml,
+    checked_data::{check_in_chunks, process_diffs},
+    common::common_yaml,
+    depth::check_yaml_depth,
+    dtfterminal_types::{Config, DiffCollection, DtfError, LibWorkingContext, WorkingContext},
+    envelope::unwrap_yaml,
+    flatten::flatten_yaml,
+    key_walk::find_yaml_key_diffs,
+    line_diff::{find_yaml_line_diff_keys, LineDiffKeys},
+    matrix::compare_yaml_matrices,
+    redact::redact_yaml,
+    sample::{sample_yaml_data, SampledArray, SampledData},
+    side::{find_yaml_key_sides, KeySides},
+    unicode::{normalize_yaml, restore_originals},
+    utils::{check_diff_limit, log_diffs, merge_ordered_array_diffs, DiffLimit},
+    value_types::{find_yaml_value_types, ValueTypes},
+    yaml_tags::{has_yaml_tags, resolve_yaml_tags},
+};
+#[cfg(feature = "cli")]
 use crate::{
-    dtfterminal_types::{DiffCollection, WorkingContext},
-    file_handler::FileHandler,
+    defaults::apply_yaml_defaults, document_cache::DocumentCache, file_handler::FileHandler,
+    first_diff::find_first_diff,
 };
 
 use libdtf::{
     core::diff_types::{ArrayDiff, Checker, KeyDiff, TypeDiff, ValueDiff},
     yaml::diff_types::CheckingData,
 };
-use serde_yaml::Mapping;
+use log::debug;
+use serde_yaml::{Mapping, Value as YamlValue};
 
 /// Responsible for the main functionality of the app. Makes sure everything runs in the correct order.
 pub struct YamlApp {
-    data1: Mapping,
-    data2: Mapping,
+    data1: Rc<Mapping>,
+    data2: Rc<Mapping>,
+    /// The data with the long arrays sampled, if only a sample of them is compared
+    sampled: Option<SampledData<Mapping>>,
     context: WorkingContext,
 }
 
@@ -20,63 +49,379 @@ impl YamlApp {
     /// Creates a new App instance
     /// 1. Parses the command line arguments
     /// 2. Checks for differences and stores them
-    pub fn new(path1: String, path2: String, context: WorkingContext) -> YamlApp {
-        let data1 = FileHandler::read_yaml_file(&path1).expect("Could not read YAML file");
-        let data2 = FileHandler::read_yaml_file(&path2).expect("Could not read YAML file");
+    /// If a defaults file is configured, its values fill in the keys missing from both files
+    #[cfg(feature = "cli")]
+    pub fn new(path1: String, path2: String, context: WorkingContext) -> Result<YamlApp, DtfError> {
+        YamlApp::new_cached(path1, path2, context, &mut DocumentCache::default())
+    }
+
+    /// Creates a new App instance, reading the files through the cache
+    #[cfg(feature = "cli")]
+    pub fn new_cached(
+        path1: String,
+        path2: String,
+        context: WorkingContext,
+        cache: &mut DocumentCache,
+    ) -> Result<YamlApp, DtfError> {
+        let max_size = context.config.max_size;
+        let (mut data1, mut data2) = prepare_data(
+            cache.read_yaml_file(&path1, max_size)?,
+            cache.read_yaml_file(&path2, max_size)?,
+            &context,
+        )?;
+        if let Some(defaults_path) = &context.config.defaults {
+            let defaults = FileHandler::read_yaml_file(defaults_path, max_size)?;
+            apply_yaml_defaults(Rc::make_mut(&mut data1), &defaults);
+            apply_yaml_defaults(Rc::make_mut(&mut data2), &defaults);
+        }
+        Ok(YamlApp::from_shared(data1, data2, context))
+    }
+
+    /// Creates a new App instance from already parsed data
+    pub fn from_data(data1: Mapping, data2: Mapping, context: WorkingContext) -> YamlApp {
+        YamlApp::from_shared(Rc::new(data1), Rc::new(data2), context)
+    }
+
+    /// Creates a new App instance from parsed data shared with others, like the cached baseline file
+    pub fn from_shared(data1: Rc<Mapping>, data2: Rc<Mapping>, context: WorkingContext) -> YamlApp {
+        let sampled = context
+            .config
+            .sample
+            .map(|size| sample_yaml_data(&data1, &data2, size, context.config.sample_seed));
         YamlApp {
             data1,
             data2,
+            sampled,
             context,
         }
     }
 
+    /// Finds the objects having the same keys in a different order in the two files
+    #[cfg(feature = "cli")]
+    pub fn find_key_order_diffs(&self) -> Vec<KeyOrderDiff> {
+        find_yaml_key_order_diffs(&self.data1, &self.data2)
+    }
+
+    /// Looks up where the items of the array differences are in the two files
+    pub fn find_array_indexes(&self, array_diffs: &[ArrayDiff]) -> ArrayIndexes {
+        find_yaml_array_indexes(array_diffs, &self.data1, &self.data2)
+    }
+
+    /// Adds which file has the key of each key difference to the differences of the two files.
+    /// The sides are looked up in the data the differences were found in, so files with the same name are told apart.
+    pub fn with_key_sides(&self, diffs: DiffCollection) -> (DiffCollection, KeySides) {
+        let key_diffs = diffs.0.as_deref().unwrap_or_default();
+        let key_sides = find_yaml_key_sides(key_diffs, &self.data1, &self.data2);
+        (diffs, key_sides)
+    }
+
+    /// Looks up which value differences are of multi-line strings
+    pub fn find_line_diff_keys(&self, value_diffs: &[ValueDiff]) -> LineDiffKeys {
+        find_yaml_line_diff_keys(value_diffs, &self.data1, &self.data2)
+    }
+
+    /// Looks up the types of the differing values in the two files
+    pub fn find_value_types(&self, value_diffs: &[ValueDiff]) -> ValueTypes {
+        find_yaml_value_types(value_diffs, &self.data1, &self.data2)
+    }
+
+    /// Finds only the first difference, without comparing the rest of the files.
+    /// The files are checked key by key like in `perform_new_check`, stopping at the first key with a difference.
+    #[cfg(feature = "cli")]
+    pub fn find_first_diff(&self) -> Result<DiffCollection, DtfError> {
+        check_yaml_depth(&self.data1)?;
+        check_yaml_depth(&self.data2)?;
+        let (data1, data2) = self.compared_data();
+        let mut limit = DiffLimit::new(&self.context.config);
+        find_first_diff(data1, data2, &self.context.config, |chunk1, chunk2| {
+            let diffs = self.check_compared_data(&chunk1, &chunk2, &mut limit)?;
+            Ok(process_diffs(
+                diffs,
+                self.data1.as_ref(),
+                self.data2.as_ref(),
+                &self.context,
+            ))
+        })
+    }
+
+    /// The arrays of which only a sample of the items was compared
+    pub fn sampled_arrays(&self) -> Vec<SampledArray> {
+        self.sampled
+            .as_ref()
+            .map(SampledData::sampled_arrays)
+            .unwrap_or_default()
+    }
+
+    /// Serializes the structure the two files agree on, redacted like the differences
+    pub fn common(&self) -> Result<String, DtfError> {
+        let common = YamlValue::Mapping(common_yaml(&self.data1, &self.data2));
+        let common = redact_yaml(common, &self.context.config.redact_patterns);
+        serde_yaml::to_string(&common).map_err(|e| DtfError::DiffError(e.to_string()))
+    }
+
+    /// Checks for differences between the two files, with the file having the key of each key difference
+    pub fn perform_sided_check(&self) -> Result<(DiffCollection, KeySides), DtfError> {
+        Ok(self.with_key_sides(self.perform_new_check()?))
+    }
+
     /// Checks for differences between the two files
-    pub fn perform_new_check(&self) -> DiffCollection {
-        self.check_for_diffs(&self.data1, &self.data2)
+    pub fn perform_new_check(&self) -> Result<DiffCollection, DtfError> {
+        check_yaml_depth(&self.data1)?;
+        check_yaml_depth(&self.data2)?;
+        let (data1, data2) = self.compared_data();
+        let mut limit = DiffLimit::new(&self.context.config);
+        // With a maximum the files are checked key by key, so the check stops at the key exceeding it
+        let diffs = if self.context.config.max_diffs.is_some() {
+            check_in_chunks(data1, data2, |chunk1, chunk2| {
+                self.check_compared_data(&chunk1, &chunk2, &mut limit)
+            })?
+        } else {
+            self.check_compared_data(data1, data2, &mut limit)?
+        };
+        Ok(process_diffs(
+            diffs,
+            self.data1.as_ref(),
+            self.data2.as_ref(),
+            &self.context,
+        ))
+    }
+
+    /// The data the check compares: the files, or their samples if only a sample of the long arrays is compared
+    fn compared_data(&self) -> (&Mapping, &Mapping) {
+        match &self.sampled {
+            Some(sampled) => (&sampled.data1, &sampled.data2),
+            None => (self.data1.as_ref(), self.data2.as_ref()),
+        }
+    }
+
+    /// Checks the compared data, or a chunk of it, for differences.
+    /// The keys of the differences are of the files, with the original strings and array indexes.
+    /// The differences are counted by the limit shared by the chunks.
+    fn check_compared_data(
+        &self,
+        data1: &Mapping,
+        data2: &Mapping,
+        limit: &mut DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = if self.context.config.normalize_unicode {
+            let (data1, originals1) = normalize_yaml(data1);
+            let (data2, originals2) = normalize_yaml(data2);
+            let diffs = self.check_with_matrices(&data1, &data2, limit)?;
+            restore_originals(diffs, &originals1, &originals2)
+        } else {
+            self.check_with_matrices(data1, data2, limit)?
+        };
+        let diffs = match &self.sampled {
+            Some(sampled) => sampled.restore_indexes(diffs),
+            None => diffs,
+        };
+        limit.add_diffs(&diffs)?;
+        Ok(diffs)
+    }
+
+    /// Checks for differences between the two files, comparing the matrices cell by cell if asked to.
+    /// The matrices are looked up in the compared data, so their cells are normalized and sampled like the rest.
+    fn check_with_matrices(
+        &self,
+        data1: &Mapping,
+        data2: &Mapping,
+        limit: &DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = self.check_with_ordered_arrays(data1, data2, limit)?;
+        if self.context.config.matrices {
+            Ok(compare_yaml_matrices(
+                diffs,
+                data1,
+                data2,
+                self.context.config.intersection,
+            ))
+        } else {
+            Ok(diffs)
+        }
+    }
+
+    /// Checks for differences between the two files, checking the arrays under the ordered paths as ordered.
+    /// Both checks count their differences on top of those found so far, the merged ones are counted by the caller.
+    fn check_with_ordered_arrays(
+        &self,
+        data1: &Mapping,
+        data2: &Mapping,
+        limit: &DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let diffs = self.check_for_diffs(
+            data1,
+            data2,
+            &self.context.lib_working_context,
+            &mut limit.clone(),
+        )?;
+        log_diffs(&diffs);
+        if self.context.has_ordered_array_paths() {
+            let ordered_diffs = self.check_for_diffs(
+                data1,
+                data2,
+                &self.context.ordered_lib_working_context(),
+                &mut limit.clone(),
+            )?;
+            debug!(
+                "Checked the arrays under {:?} as ordered",
+                self.context.config.ordered_array_paths
+            );
+            Ok(merge_ordered_array_diffs(
+                diffs,
+                ordered_diffs,
+                &self.context.config.ordered_array_paths,
+            ))
+        } else {
+            Ok(diffs)
+        }
     }
 
     /// Checks for differences between the two files
-    fn check_for_diffs(&self, data1: &Mapping, data2: &Mapping) -> DiffCollection {
-        let key_diff = if self.context.config.check_for_key_diffs {
+    /// In intersection mode key differences are not checked, they are caused by presence only.
+    /// Stops with an error as soon as a category exceeds the maximum number of differences.
+    fn check_for_diffs(
+        &self,
+        data1: &Mapping,
+        data2: &Mapping,
+        lib_working_context: &LibWorkingContext,
+        limit: &mut DiffLimit,
+    ) -> Result<DiffCollection, DtfError> {
+        let config = &self.context.config;
+        let key_diff = if config.check_for_key_diffs && config.intersection {
+            None
+        } else if config.check_for_key_diffs && config.compare_keys_only {
+            let file_names = (
+                lib_working_context.file_a.name.as_str(),
+                lib_working_context.file_b.name.as_str(),
+            );
+            Some(find_yaml_key_diffs(data1, data2, file_names, limit)?)
+        } else if config.check_for_key_diffs {
             let mut checking_data: CheckingData<KeyDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let type_diff = if self.context.config.check_for_type_diffs {
             let mut checking_data: CheckingData<TypeDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let value_diff = if self.context.config.check_for_value_diffs {
             let mut checking_data: CheckingData<ValueDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
         let array_diff = if self.context.config.check_for_array_diffs {
+            let lib_working_context = self
+                .context
+                .array_diff_lib_working_context(lib_working_context);
             let mut checking_data: CheckingData<ArrayDiff> =
-                CheckingData::new("", data1, data2, &self.context.lib_working_context);
+                CheckingData::new("", data1, data2, &lib_working_context);
             checking_data.check();
-            Some(checking_data.diffs()).cloned()
+            Some(limit.add(checking_data.diffs().clone())?)
         } else {
             None
         };
 
-        (key_diff, type_diff, value_diff, array_diff)
+        Ok((key_diff, type_diff, value_diff, array_diff))
+    }
+}
+
+/// Checks two YAML strings for differences without touching the filesystem
+pub fn compare_yaml_str(
+    a: &str,
+    b: &str,
+    context: &WorkingContext,
+) -> Result<DiffCollection, DtfError> {
+    compare_yaml_str_sided(a, b, context).map(|(diffs, _)| diffs)
+}
+
+/// Checks two YAML strings for differences, with the file having the key of each key difference
+pub(crate) fn compare_yaml_str_sided(
+    a: &str,
+    b: &str,
+    context: &WorkingContext,
+) -> Result<(DiffCollection, KeySides), DtfError> {
+    let (data1, data2) = prepare_data(
+        Rc::new(parse_yaml_str(a)?),
+        Rc::new(parse_yaml_str(b)?),
+        context,
+    )?;
+    let (diffs, key_sides) =
+        YamlApp::from_shared(data1, data2, context.clone()).perform_sided_check()?;
+    check_diff_limit(&diffs, &context.config)?;
+    Ok((diffs, key_sides))
+}
+
+/// Navigates both files to the objects to compare, resolves their tags and rewrites them into their canonical form, if it's configured
+fn prepare_data(
+    data1: Rc<Mapping>,
+    data2: Rc<Mapping>,
+    context: &WorkingContext,
+) -> Result<(Rc<Mapping>, Rc<Mapping>), DtfError> {
+    let config = &context.config;
+    let (file_name_a, file_name_b) = context.get_file_names();
+    Ok((
+        prepare_file(data1, config.unwrap_a.as_deref(), file_name_a, config)?,
+        prepare_file(data2, config.unwrap_b.as_deref(), file_name_b, config)?,
+    ))
+}
+
+/// Navigates a file to the mapping to compare and rewrites it, if it has tags or it's configured.
+/// The data is only copied if it's rewritten, so a cached file stays shared otherwise.
+fn prepare_file(
+    data: Rc<Mapping>,
+    unwrap: Option<&str>,
+    file_name: &str,
+    config: &Config,
+) -> Result<Rc<Mapping>, DtfError> {
+    check_yaml_depth(&data)?;
+    let unwraps = unwrap.is_some_and(|path| !path.is_empty());
+    if !unwraps
+        && !has_yaml_tags(&data)
+        && !config.ignore_chomping
+        && !config.canonicalize
+        && !config.flatten
+    {
+        return Ok(data);
     }
+    let data = unwrap_yaml(Rc::unwrap_or_clone(data), unwrap, file_name)?;
+    let data = resolve_yaml_tags(data, config.ignore_yaml_tags);
+    let data = if config.ignore_chomping {
+        ignore_chomping(data)
+    } else {
+        data
+    };
+    let data = if config.canonicalize {
+        canonicalize_yaml(data, config.trim_strings)
+    } else {
+        data
+    };
+    let data = if config.flatten {
+        flatten_yaml(data, config.flatten_arrays)
+    } else {
+        data
+    };
+    Ok(Rc::new(data))
+}
+
+/// Parses a YAML string into a mapping of the data
+fn parse_yaml_str(yaml_str: &str) -> Result<Mapping, DtfError> {
+    serde_yaml::from_str(yaml_str)
+        .map_err(|e| DtfError::DiffError(format!("Could not parse YAML: {}", e)))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::dtfterminal_types::ConfigBuilder;
+    use crate::{depth::MAX_DEPTH, dtfterminal_types::ConfigBuilder, utils::count_diffs};
 
     use super::*;
 
@@ -87,8 +432,9 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_some(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -102,8 +448,9 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_some(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -117,8 +464,9 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_some(), true);
@@ -132,8 +480,9 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
@@ -147,8 +496,9 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_some(), true);
         assert_eq!(diffs.1.is_some(), true);
         assert_eq!(diffs.2.is_some(), true);
@@ -163,14 +513,269 @@ mod tests {
             "test_data/yaml/person3.yml".to_string(),
             "test_data/yaml/person4.yml".to_string(),
             working_context,
-        );
-        let diffs = json_app.perform_new_check();
+        )
+        .unwrap();
+        let diffs = json_app.perform_new_check().unwrap();
         assert_eq!(diffs.0.is_none(), true);
         assert_eq!(diffs.1.is_none(), true);
         assert_eq!(diffs.2.is_none(), true);
         assert_eq!(diffs.3.is_none(), true);
     }
 
+    #[test]
+    fn test_empty_file() {
+        let working_context = get_working_context(true, true, true, true);
+        let result = YamlApp::new(
+            "test_data/yaml/empty.yaml".to_string(),
+            "test_data/yaml/person1.yaml".to_string(),
+            working_context,
+        );
+        assert!(matches!(result, Err(DtfError::DiffError(msg)) if msg.contains("empty")));
+    }
+
+    #[test]
+    fn test_empty_object_against_non_empty_object() {
+        let working_context = get_working_context(true, false, false, false);
+        let yaml_app = YamlApp::new(
+            "test_data/yaml/empty_object.yaml".to_string(),
+            "test_data/yaml/person1.yaml".to_string(),
+            working_context,
+        )
+        .unwrap();
+        let diffs = yaml_app.perform_new_check().unwrap();
+        assert_eq!(diffs.0.unwrap().is_empty(), false);
+    }
+
+    #[test]
+    fn test_empty_objects_are_identical() {
+        let working_context = get_working_context(true, true, true, true);
+        let yaml_app = YamlApp::new(
+            "test_data/yaml/empty_object.yaml".to_string(),
+            "test_data/yaml/empty_object.yaml".to_string(),
+            working_context,
+        )
+        .unwrap();
+        let diffs = yaml_app.perform_new_check().unwrap();
+        assert!(diffs.0.unwrap().is_empty());
+        assert!(diffs.1.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+        assert!(diffs.3.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compare_yaml_str() {
+        let working_context = get_working_context(true, false, true, false);
+        let diffs = compare_yaml_str(
+            "name: John\nage: 30\n",
+            "name: Jane\ncity: Budapest\n",
+            &working_context,
+        )
+        .unwrap();
+
+        let key_diffs = diffs.0.unwrap();
+        assert_eq!(key_diffs.len(), 2);
+        assert!(key_diffs
+            .iter()
+            .any(|kd| kd.key == "age" && kd.has == "FileA.yaml"));
+        assert!(key_diffs
+            .iter()
+            .any(|kd| kd.key == "city" && kd.has == "FileB.yaml"));
+
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "name");
+    }
+
+    #[test]
+    fn test_ordered_array_paths() {
+        let mut working_context = get_working_context(false, false, true, true);
+        working_context.config.ordered_array_paths = vec!["steps".to_owned()];
+        let diffs = compare_yaml_str(
+            "steps: [build, test]\ntags: [x, y]\n",
+            "steps: [test, build]\ntags: [y, z]\n",
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert!(value_diffs.iter().any(|vd| vd.key.starts_with("steps")));
+
+        let array_diffs = diffs.3.unwrap();
+        assert_eq!(array_diffs.is_empty(), false);
+        assert!(array_diffs.iter().all(|ad| ad.key == "tags"));
+    }
+
+    #[test]
+    fn test_compare_yaml_str_max_diffs() {
+        let mut working_context = get_working_context(true, false, true, false);
+        working_context.config.max_diffs = Some(2);
+        let result = compare_yaml_str(
+            "name: John\nage: 30\n",
+            "name: Jane\ncity: Budapest\n",
+            &working_context,
+        );
+        assert!(matches!(result, Err(DtfError::LimitError(_))));
+
+        working_context.config.max_diffs = Some(3);
+        let result = compare_yaml_str(
+            "name: John\nage: 30\n",
+            "name: Jane\ncity: Budapest\n",
+            &working_context,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_compare_yaml_too_deep() {
+        let working_context = get_working_context(true, true, true, true);
+
+        // Parsed data is limited by the parser
+        let deep = format!("a: {}1{}\n", "[".repeat(1000), "]".repeat(1000));
+        assert!(compare_yaml_str(&deep, "a: 1\n", &working_context).is_err());
+
+        // Data built in code is checked before it's compared
+        let mut value = YamlValue::from(1);
+        for _ in 0..MAX_DEPTH + 10 {
+            value = YamlValue::Sequence(vec![value]);
+        }
+        let mut data1 = Mapping::new();
+        data1.insert(YamlValue::from("a"), value);
+        let yaml_app = YamlApp::from_data(data1, Mapping::new(), working_context);
+        assert!(matches!(
+            yaml_app.perform_new_check(),
+            Err(DtfError::LimitError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_yaml_str_cyclic_alias() {
+        let working_context = get_working_context(true, true, true, true);
+
+        // An anchor can't be referred to inside itself, so the data can't be cyclic
+        let result = compare_yaml_str("a: &node\n  b: *node\n", "a: 1\n", &working_context);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_compare_yaml_str_parse_dates() {
+        let mut working_context = get_working_context(false, false, true, false);
+        working_context.config.parse_dates = true;
+        let diffs = compare_yaml_str(
+            "created: \"2024-01-01T00:00:00Z\"\nupdated: \"2024-01-01T00:00:00Z\"\n",
+            "created: \"2024-01-01T00:00:00+00:00\"\nupdated: \"2024-01-02T00:00:00Z\"\n",
+            &working_context,
+        )
+        .unwrap();
+
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "updated");
+    }
+
+    #[test]
+    fn test_compare_yaml_str_intersection() {
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.intersection = true;
+        let diffs = compare_yaml_str(
+            "name: John\nage: 30\ntags:\n  - a\n",
+            "name: Jane\nage: 30\ncity: Budapest\ntags:\n  - b\n  - c\n",
+            &working_context,
+        )
+        .unwrap();
+
+        assert!(diffs.0.is_none());
+        assert!(diffs.1.unwrap().is_empty());
+        let value_diffs = diffs.2.unwrap();
+        assert_eq!(value_diffs.len(), 1);
+        assert_eq!(value_diffs[0].key, "name");
+        // "c" is only added, but "a" and "b" are at the same index
+        let array_values: Vec<String> = diffs.3.unwrap().into_iter().map(|ad| ad.value).collect();
+        assert_eq!(array_values, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_compare_yaml_str_tags() {
+        let mut working_context = get_working_context(false, true, true, false);
+        let tagged = "color: !Color red\nsize: !Meters 5\nid: !!str 123\n";
+        let untagged = "color: red\nsize: 5\nid: 123\n";
+
+        let diffs = compare_yaml_str(tagged, untagged, &working_context).unwrap();
+        let type_keys: Vec<&str> = diffs.1.iter().flatten().map(|d| d.key.as_str()).collect();
+        let value_keys: Vec<&str> = diffs.2.iter().flatten().map(|d| d.key.as_str()).collect();
+        assert!(type_keys.contains(&"size"));
+        assert!(type_keys.contains(&"id"));
+        assert!(value_keys.contains(&"color"));
+
+        let diffs = compare_yaml_str(tagged, tagged, &working_context).unwrap();
+        assert!(diffs.1.unwrap().is_empty());
+        assert!(diffs.2.unwrap().is_empty());
+
+        working_context.config.ignore_yaml_tags = true;
+        let diffs = compare_yaml_str(tagged, untagged, &working_context).unwrap();
+        let type_keys: Vec<&str> = diffs.1.iter().flatten().map(|d| d.key.as_str()).collect();
+        assert_eq!(type_keys, vec!["id"]);
+        assert!(diffs.2.unwrap().iter().all(|d| d.key == "id"));
+    }
+
+    #[test]
+    fn test_compare_yaml_str_ignore_chomping() {
+        let mut working_context = get_working_context(false, false, true, false);
+        let clip = "script: |\n  make\n  make test\n";
+        let strip = "script: |-\n  make\n  make test\n";
+        let keep = "script: |+\n  make\n  make test\n\nnext: 1\n";
+        let other = "script: |-\n  make\n  make install\n";
+
+        let diffs = compare_yaml_str(clip, strip, &working_context).unwrap();
+        assert_eq!(diffs.2.unwrap().len(), 1);
+
+        working_context.config.ignore_chomping = true;
+        let diffs = compare_yaml_str(clip, strip, &working_context).unwrap();
+        assert!(diffs.2.unwrap().is_empty());
+        let diffs = compare_yaml_str(clip, keep, &working_context).unwrap();
+        assert!(diffs.2.unwrap().is_empty());
+        let diffs = compare_yaml_str(clip, other, &working_context).unwrap();
+        assert_eq!(diffs.2.unwrap()[0].key, "script");
+    }
+
+    #[test]
+    fn test_compare_yaml_str_invalid_input() {
+        let working_context = get_working_context(true, false, false, false);
+        let result = compare_yaml_str("- not a mapping", "{}", &working_context);
+        assert!(matches!(result, Err(DtfError::DiffError(_))));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_find_first_diff_agrees_with_the_full_check() {
+        let mut working_context = get_working_context(true, true, true, true);
+        working_context.config.empty_equivalence = true;
+        working_context.config.coerce_numeric_strings = true;
+        let data1: Mapping =
+            serde_yaml::from_str("server:\n  port: '8080'\n  note: ~\nref: !Ref x\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("server:\n  port: 8080\nref: !Ref x\n").unwrap();
+        let yaml_app = YamlApp::from_data(data1, data2, working_context);
+
+        // The files are the same after the normalization, so neither of the checks fails
+        assert_eq!(count_diffs(&yaml_app.perform_new_check().unwrap()), 0);
+        assert_eq!(count_diffs(&yaml_app.find_first_diff().unwrap()), 0);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_find_first_diff_reports_like_the_full_check() {
+        let working_context = get_working_context(false, true, false, false);
+        let data1: Mapping = serde_yaml::from_str("server:\n  enabled: true\n").unwrap();
+        let data2: Mapping = serde_yaml::from_str("server:\n  enabled: 'yes'\n").unwrap();
+        let yaml_app = YamlApp::from_data(data1, data2, working_context);
+
+        // The type names are those of the full check
+        assert_eq!(
+            yaml_app.find_first_diff().unwrap(),
+            yaml_app.perform_new_check().unwrap()
+        );
+    }
+
     fn get_working_context(
         key_diffs: bool,
         type_diffs: bool,
@@ -0,0 +1,106 @@
+//! Makes the tags of YAML scalars part of their values, so `!Color red` and `red` are different values.
+//!
+//! A tagged scalar is compared as the string of its tag and value, e.g. `"!Color red"`. Against an untagged
+//! string it's a value difference, against an untagged number, boolean or null a type difference.
+//! The core tags like `!!str 123` are resolved while parsing, so they already change the type of the value.
+//! The tags of mappings and sequences are dropped, their contents are compared like untagged ones.
+
+use serde_yaml::{value::TaggedValue, Mapping, Value as YamlValue};
+
+/// Rewrites the tagged values of the YAML data, either keeping their tags in their values or dropping them
+pub fn resolve_yaml_tags(data: Mapping, ignore_tags: bool) -> Mapping {
+    data.into_iter()
+        .map(|(key, value)| (untag(key), resolve_value(value, ignore_tags)))
+        .collect()
+}
+
+/// Whether any key or value of the YAML data is tagged, so it has to be rewritten
+pub fn has_yaml_tags(data: &Mapping) -> bool {
+    data.iter()
+        .any(|(key, value)| is_tagged(key) || is_tagged(value))
+}
+
+fn is_tagged(value: &YamlValue) -> bool {
+    match value {
+        YamlValue::Tagged(_) => true,
+        YamlValue::Sequence(sequence) => sequence.iter().any(is_tagged),
+        YamlValue::Mapping(mapping) => has_yaml_tags(mapping),
+        _ => false,
+    }
+}
+
+fn resolve_value(value: YamlValue, ignore_tags: bool) -> YamlValue {
+    match value {
+        YamlValue::Tagged(tagged) if ignore_tags => resolve_value(tagged.value, ignore_tags),
+        YamlValue::Tagged(tagged) => match tagged_scalar(&tagged) {
+            Some(scalar) => YamlValue::String(scalar),
+            None => resolve_value(tagged.value, ignore_tags),
+        },
+        YamlValue::Sequence(sequence) => YamlValue::Sequence(
+            sequence
+                .into_iter()
+                .map(|item| resolve_value(item, ignore_tags))
+                .collect(),
+        ),
+        YamlValue::Mapping(mapping) => YamlValue::Mapping(resolve_yaml_tags(mapping, ignore_tags)),
+        other => other,
+    }
+}
+
+/// The tag and the value of a tagged scalar, e.g. `!Color red`. None for tagged collections.
+fn tagged_scalar(tagged: &TaggedValue) -> Option<String> {
+    let value = match &tagged.value {
+        YamlValue::Null => "null".to_owned(),
+        YamlValue::Bool(b) => b.to_string(),
+        YamlValue::Number(n) => n.to_string(),
+        YamlValue::String(s) => s.clone(),
+        YamlValue::Tagged(inner) => tagged_scalar(inner)?,
+        YamlValue::Sequence(_) | YamlValue::Mapping(_) => return None,
+    };
+    Some(format!("{} {}", tagged.tag, value))
+}
+
+/// The tags of keys are always dropped, a key is only a name
+fn untag(key: YamlValue) -> YamlValue {
+    match key {
+        YamlValue::Tagged(tagged) => untag(tagged.value),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_yaml_tags() {
+        let data = parse("color: !Color red\nsize: !Meters 5\nplain: red\npoint: !Point {x: 1}\nitems: [!Id 7, 8]\n");
+
+        let resolved = resolve_yaml_tags(data, false);
+
+        assert_eq!(
+            resolved,
+            parse("color: \"!Color red\"\nsize: \"!Meters 5\"\nplain: red\npoint: {x: 1}\nitems: [\"!Id 7\", 8]\n")
+        );
+    }
+
+    #[test]
+    fn test_resolve_yaml_tags_ignored() {
+        let data = parse("color: !Color red\nsize: !Meters 5\n!Name key: value\n");
+
+        let resolved = resolve_yaml_tags(data, true);
+
+        assert_eq!(resolved, parse("color: red\nsize: 5\nkey: value\n"));
+    }
+
+    #[test]
+    fn test_has_yaml_tags() {
+        assert!(!has_yaml_tags(&parse("color: red\nitems: [1, {a: 2}]\n")));
+        assert!(has_yaml_tags(&parse("items: [1, {a: !Id 2}]\n")));
+        assert!(has_yaml_tags(&parse("!Name key: value\n")));
+    }
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn dtfterminal(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dtfterminal"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_fail_fast_writes_one_json_document() {
+    let output = dtfterminal(&[
+        "-c",
+        "test_data/json/person1.json",
+        "test_data/json/person2.json",
+        "-k",
+        "-v",
+        "--format",
+        "json",
+        "--fail-fast",
+    ]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let documents: Vec<Value> = serde_json::Deserializer::from_str(&stdout)
+        .into_iter::<Value>()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(documents.len(), 1);
+    assert!(documents[0].get("error").is_none());
+}
+
+#[test]
+fn test_error_is_written_as_json() {
+    let output = dtfterminal(&[
+        "-c",
+        "test_data/json/missing.json",
+        "test_data/json/person2.json",
+        "-k",
+        "--format",
+        "json",
+    ]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let error: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(error.get("error").is_some());
+}